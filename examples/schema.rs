@@ -1,22 +1,44 @@
 use std::env::current_dir;
 use std::fs::create_dir_all;
 
-use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
+use cosmwasm_schema::{
+    export_schema, export_schema_with_title, schema_for, write_api, QueryResponses,
+};
 
-use poll::msg::{CustomResponse, ExecuteMsg, InstantiateMsg, QueryMsg};
-use poll::state::{Ballot, Config, Poll};
+use poll::msg::{
+    Cw3QueryMsg, DaoQueryMsg, ExecuteMsg, InstantiateMsg, MigrateMsg, PollAck, PollPacket,
+    QueryMsg, SudoMsg,
+};
 
 fn main() {
+    write_api! {
+        instantiate: InstantiateMsg,
+        execute: ExecuteMsg,
+        query: QueryMsg,
+        migrate: MigrateMsg,
+        sudo: SudoMsg,
+    }
+
+    // The cw3 queries reuse the `vote` and `list_votes` names, so they get
+    // their own directory rather than being merged into the main API.
     let mut out_dir = current_dir().unwrap();
     out_dir.push("schema");
+    out_dir.push("cw3");
+    create_dir_all(&out_dir).unwrap();
+    export_schema(&schema_for!(Cw3QueryMsg), &out_dir);
+    for (query, schema) in Cw3QueryMsg::response_schemas().unwrap() {
+        export_schema_with_title(&schema, &out_dir, &format!("response_to_{}", query));
+    }
+
+    out_dir.set_file_name("dao");
     create_dir_all(&out_dir).unwrap();
-    remove_schemas(&out_dir).unwrap();
+    export_schema(&schema_for!(DaoQueryMsg), &out_dir);
+    for (query, schema) in DaoQueryMsg::response_schemas().unwrap() {
+        export_schema_with_title(&schema, &out_dir, &format!("response_to_{}", query));
+    }
 
-    export_schema(&schema_for!(InstantiateMsg), &out_dir);
-    export_schema(&schema_for!(ExecuteMsg), &out_dir);
-    export_schema(&schema_for!(QueryMsg), &out_dir);
-    export_schema(&schema_for!(Config), &out_dir);
-    export_schema(&schema_for!(Poll), &out_dir);
-    export_schema(&schema_for!(Ballot), &out_dir);
-    export_schema(&schema_for!(CustomResponse), &out_dir);
+    out_dir.set_file_name("ibc");
+    create_dir_all(&out_dir).unwrap();
+    export_schema(&schema_for!(PollPacket), &out_dir);
+    export_schema(&schema_for!(PollAck), &out_dir);
 }
@@ -0,0 +1,200 @@
+//! Typed helpers for contracts that embed polling in their own storage
+//! instead of instantiating this contract separately.
+//!
+//! Responses may carry reward payout submessages; their replies must be
+//! forwarded to [`crate::contract::reply`].
+
+use cosmwasm_std::{Addr, Deps, DepsMut, Env, MessageInfo, Response, StdResult};
+
+use crate::contract::{
+    execute_close_poll, execute_create_poll, execute_finalize_poll, execute_vote, poll_ballots,
+    poll_options, DEFAULT_MAX_DELEGATION_DEPTH,
+};
+use crate::msg::NewPoll;
+use crate::state::{Config, Poll, PollOption, PollResult, CONFIG, POLL, RESULTS};
+use crate::strategy::{Tally, TallyStrategy};
+use crate::ContractError;
+
+fn info(sender: Addr) -> MessageInfo {
+    MessageInfo {
+        sender,
+        funds: vec![],
+    }
+}
+
+/// Stores the poll configuration. Unlike `instantiate`, this leaves the
+/// host's cw2 contract version alone.
+pub fn setup(deps: DepsMut, admin: Addr, min_voting_period: u64) -> StdResult<Config> {
+    let config = Config {
+        admins: vec![admin],
+        moderators: vec![],
+        min_voting_period,
+        max_voting_period: None,
+        max_delegation_depth: DEFAULT_MAX_DELEGATION_DEPTH,
+        creation_deposit: None,
+        treasury: None,
+        flag_threshold: 0,
+        veto_period: 0,
+        notify_contract: None,
+        sponsors: vec![],
+        required_sponsors: 0,
+        cw20_creation_deposit: None,
+        voting_token: None,
+        randomness_proxy: None,
+        receipt_nft: None,
+        restricted_creation: false,
+        import_mode: false,
+    };
+    CONFIG.save(deps.storage, &config)?;
+    Ok(config)
+}
+
+pub fn create_poll(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    poll: NewPoll,
+) -> Result<Response, ContractError> {
+    execute_create_poll(deps, env, info(sender), poll)
+}
+
+/// Casts a ballot that carries no funds, so it suits polls in the
+/// standard and snapshot modes.
+pub fn vote(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    poll_id: String,
+    option: u8,
+) -> Result<Response, ContractError> {
+    execute_vote(deps, env, info(sender), poll_id, option, None, None)
+}
+
+pub fn close_poll(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    poll_id: String,
+) -> Result<Response, ContractError> {
+    execute_close_poll(deps, env, info(sender), poll_id)
+}
+
+pub fn finalize_poll(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    poll_id: String,
+) -> Result<Response, ContractError> {
+    execute_finalize_poll(deps, env, info(sender), poll_id)
+}
+
+pub fn poll(deps: Deps, poll_id: &str) -> StdResult<Option<Poll>> {
+    POLL.may_load(deps.storage, poll_id.to_string())
+}
+
+/// Current votes per option, in the order the options were listed.
+pub fn tally(deps: Deps, poll_id: &str) -> Result<Vec<PollOption>, ContractError> {
+    let poll = poll(deps, poll_id)?.ok_or(ContractError::PollNotFound {})?;
+    Ok(poll_options(deps.storage, poll_id, &poll)?)
+}
+
+/// Counts the poll's current ballots with `strategy` instead of the poll's
+/// own mode. Ranked ballots keep their ranking; every other ballot names
+/// each option it gives weight to on its own.
+pub fn custom_tally(
+    deps: Deps,
+    poll_id: &str,
+    strategy: &dyn TallyStrategy,
+) -> Result<Tally, ContractError> {
+    let poll = poll(deps, poll_id)?.ok_or(ContractError::PollNotFound {})?;
+    let options: Vec<u8> = poll_options(deps.storage, poll_id, &poll)?
+        .iter()
+        .map(|option| option.id)
+        .collect();
+    let ballots: Vec<(Vec<u8>, u64)> = poll_ballots(deps.storage, poll_id)?
+        .into_iter()
+        .flat_map(|(_, ballot)| {
+            if ballot.ranking.is_empty() {
+                ballot
+                    .shares()
+                    .into_iter()
+                    .map(|(id, weight)| (vec![id], weight))
+                    .collect()
+            } else {
+                vec![(ballot.ranking, ballot.weight)]
+            }
+        })
+        .collect();
+    Ok(strategy.tally(&options, &ballots))
+}
+
+pub fn final_result(deps: Deps, poll_id: &str) -> StdResult<Option<PollResult>> {
+    RESULTS.may_load(deps.storage, poll_id.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::Plurality;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env};
+
+    #[test]
+    fn test_embedded_poll() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let host = Addr::unchecked("host");
+        setup(deps.as_mut(), host.clone(), 0).unwrap();
+
+        let new_poll = NewPoll {
+            poll_id: "1".to_string(),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            ..Default::default()
+        };
+        create_poll(deps.as_mut(), env.clone(), host.clone(), new_poll).unwrap();
+        for (voter, option) in [("voter1", 1), ("voter2", 1), ("voter3", 0)] {
+            let voter = Addr::unchecked(voter);
+            vote(deps.as_mut(), env.clone(), voter, "1".to_string(), option).unwrap();
+        }
+        let tallies: Vec<u64> = tally(deps.as_ref(), "1")
+            .unwrap()
+            .iter()
+            .map(|option| option.votes)
+            .collect();
+        assert_eq!(tallies, vec![1, 2]);
+        assert!(matches!(
+            tally(deps.as_ref(), "2").unwrap_err(),
+            ContractError::PollNotFound {}
+        ));
+
+        close_poll(deps.as_mut(), env.clone(), host.clone(), "1".to_string()).unwrap();
+        finalize_poll(deps.as_mut(), env, host, "1".to_string()).unwrap();
+        let counted = custom_tally(deps.as_ref(), "1", &Plurality).unwrap();
+        assert_eq!(counted.scores, vec![(0, 1), (1, 2)]);
+
+        let result = final_result(deps.as_ref(), "1").unwrap().unwrap();
+        assert_eq!(result.winner, Some(1));
+    }
+
+    #[test]
+    fn test_paged_tally() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let host = Addr::unchecked("host");
+        setup(deps.as_mut(), host.clone(), 0).unwrap();
+
+        let new_poll = NewPoll {
+            poll_id: "1".to_string(),
+            question: "Pick a colour".to_string(),
+            options: (0..12).map(|i| format!("Colour {i}")).collect(),
+            ..Default::default()
+        };
+        create_poll(deps.as_mut(), env.clone(), host, new_poll).unwrap();
+        let voter = Addr::unchecked("voter");
+        vote(deps.as_mut(), env, voter, "1".to_string(), 11).unwrap();
+
+        let options = tally(deps.as_ref(), "1").unwrap();
+        assert_eq!(options.len(), 12);
+        assert_eq!((options[11].id, options[11].votes), (11, 1));
+    }
+}
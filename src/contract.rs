@@ -1,23 +1,90 @@
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Order, Response, StdResult,
+    coins, from_binary, to_binary, to_vec, Addr, BankMsg, Binary, BlockInfo, Coin, CosmosMsg,
+    Decimal, Deps, DepsMut, Env, Event, HexBinary, MessageInfo, Order, Reply, Response, StdError,
+    StdResult, Storage, SubMsg, Timestamp, Uint128, WasmMsg,
 };
-use cw2::set_contract_version;
+use cw2::{get_contract_version, set_contract_version};
+use cw20::{BalanceResponse, Cw20CoinVerified, Cw20ExecuteMsg, Cw20QueryMsg, Cw20ReceiveMsg};
+use cw_storage_plus::{Bound, Map};
+use cw_utils::{must_pay, nonpayable, one_coin, Expiration};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
 
 use crate::error::ContractError;
+use crate::migrations;
 use crate::msg::{
-    AllPollResponse, ExecuteMsg, InstantiateMsg, PollResponse, QueryMsg, VoteResponse,
+    AdminLogResponse, AllPollResponse, AllocationResponse, BallotDetail, BallotExport,
+    BallotLookup, BallotResponse, BallotShare, BannedVotersResponse, CategoryAdminsResponse,
+    ChainTalliesResponse, ClaimableRewardResponse, CommentsResponse, ConfigResponse,
+    ContractQueryMsg, CreatorDashboardResponse, CreatorsResponse, Cw3ProposalListResponse,
+    Cw3ProposalResponse, Cw3QueryMsg, Cw3Status, Cw3ThresholdResponse, Cw3Vote, Cw3VoteInfo,
+    Cw3VoteListResponse, Cw3VoteResponse, Cw3VoterResponse, DaoQueryMsg, ExecuteMsg,
+    ExportBallotsResponse, ExportEntry, ExportSection, ExportStateResponse, FinalResultResponse,
+    FinalizedPoll, HeldDeposit, InfoResponse, InstantiateMsg, KeyRegistration, ListVotesResponse,
+    MerkleProof, MigrateMsg, NewPoll, NoisCallback, NoisProxyMsg, NotifyMsg, OptionMetadata,
+    OptionTally, OptionVoter, OptionsResponse, OwnerProof, PendingAction, PollEntry,
+    PollHistoryResponse, PollImport, PollRecord, PollResponse, PollSummariesResponse, PollSummary,
+    QueryMsg, ReceiptMetadata, ReceiptMintMsg, ReceiveMsg, RecordExport, ResolvedWeightResponse,
+    RewardPoolResponse, SignedVote, StakeResponse, StakedBalanceAtHeightResponse, StakingQueryMsg,
+    SudoMsg, SuggestionsResponse, TallyAtHeightResponse, TallyDelta, TopVoter, TopVotersResponse,
+    TotalPowerAtHeightResponse, TotalStakedAtHeightResponse, TurnoutStatsResponse,
+    VerifyContentResponse, VestingQueryMsg, VoteEntry, VotePayload, VoteResponse,
+    VoterInfoResponse, VotersForOptionResponse, VotingPowerAtHeightResponse,
+    WeightOverridesResponse, EXPORT_FORMAT,
+};
+use crate::state::{
+    voter_stats, AdminAction, Ballot, ChangeRecord, Comment, Config, Poll, PollChange, PollOption,
+    PollResult, PollStatus, Quorum, RankedMethod, RewardAsset, RewardDistribution, RewardPool,
+    Suggestion, SuggestionStatus, TieBreakPolicy, TieResolution, VoteBudget, VoteMode, WeightDecay,
+    WeightFormula, ADMIN_LOG, ALLOWED_VOTERS, BALLOT, BANNED, CATEGORY_ADMINS, COMMENTS, CONFIG,
+    CRANK_CURSOR, CREATORS, CREATOR_POLLS, DELEGATIONS, DELEGATORS, FLAGS, LOCKED_SINCE, OPTIONS,
+    OPTION_VOTERS, POLL, POLL_ATTRIBUTES, POLL_ENDS, POLL_HISTORY, POLL_SALTS, POLL_VOTERS,
+    PROPOSALS, PROPOSAL_SEQ, RAFFLE_COUNTS, RAFFLE_ENTRIES, RAFFLE_SLOTS, REPRESENTED, RESULTS,
+    REWARD_CLAIMS, REWARD_POOLS, SALT_SEED, SATELLITE_TALLIES, SEEN_VOTERS, SPONSORSHIPS, STAKES,
+    STAKE_TOTALS, STATS, SUGGESTIONS, TALLIES, VOTE_NONCES, VOTING_KEYS, WEIGHT_OVERRIDES,
 };
-use crate::state::{Ballot, Config, Poll, BALLOT, CONFIG, POLL};
+use crate::tally;
 
 const CONTRACT_NAME: &str = "crates.io:poll";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+const MAX_MEMO_LENGTH: usize = 280;
+const MAX_COMMENT_LENGTH: usize = 1000;
+const MAX_SUGGESTION_LENGTH: usize = 100;
+const MAX_REASON_LENGTH: usize = 280;
+const MAX_OPTION_DESCRIPTION_LENGTH: usize = 500;
+const MAX_OPTION_URI_LENGTH: usize = 256;
+/// Reply id for vote notifications.
+const NOTIFY_REPLY_ID: u64 = 0;
+/// Reply id for receipt mints.
+const RECEIPT_REPLY_ID: u64 = u64::MAX;
+const MAX_CONTENT_HASH_LENGTH: usize = 64;
+/// Polls with more options than this keep them in `OPTIONS`.
+const MAX_INLINE_OPTIONS: usize = 10;
+/// Option ids are `u8`, so no poll can hold more.
+const MAX_OPTIONS: usize = 255;
+/// Voters a ranked, conviction or decaying poll takes. Those are counted
+/// from every ballot when they close, which has to fit in one transaction.
+const MAX_RECOUNTED_VOTERS: u32 = 1000;
+const MAX_ATTRIBUTES: usize = 10;
+const MAX_ATTRIBUTE_LENGTH: usize = 100;
+/// Draws a raffle makes before giving up on finding a ranked ballot that
+/// backs the winner in the final round.
+const MAX_RAFFLE_DRAWS: u32 = 10;
+pub(crate) const DEFAULT_MAX_DELEGATION_DEPTH: u64 = 3;
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
-    deps: DepsMut,
-    _env: Env,
+    mut deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
@@ -25,10 +92,75 @@ pub fn instantiate(
     let admin = msg.admin.unwrap_or(info.sender.to_string());
     let validated_admin = deps.api.addr_validate(&admin)?;
     let config = Config {
-        admin: validated_admin.clone(),
+        admins: vec![validated_admin.clone()],
+        moderators: vec![],
+        min_voting_period: msg.min_voting_period.unwrap_or_default(),
+        max_voting_period: msg.max_voting_period.filter(|max| *max > 0),
+        max_delegation_depth: msg
+            .max_delegation_depth
+            .unwrap_or(DEFAULT_MAX_DELEGATION_DEPTH),
+        creation_deposit: msg.creation_deposit,
+        treasury: msg
+            .treasury
+            .map(|treasury| deps.api.addr_validate(&treasury))
+            .transpose()?,
+        flag_threshold: msg.flag_threshold.unwrap_or_default(),
+        veto_period: msg.veto_period.unwrap_or_default(),
+        notify_contract: msg
+            .notify_contract
+            .map(|contract| deps.api.addr_validate(&contract))
+            .transpose()?,
+        sponsors: msg
+            .sponsors
+            .unwrap_or_default()
+            .iter()
+            .map(|sponsor| deps.api.addr_validate(sponsor))
+            .collect::<StdResult<_>>()?,
+        required_sponsors: msg.required_sponsors.unwrap_or_default(),
+        voting_token: msg
+            .voting_token
+            .map(|token| deps.api.addr_validate(&token))
+            .transpose()?,
+        randomness_proxy: msg
+            .randomness_proxy
+            .map(|proxy| deps.api.addr_validate(&proxy))
+            .transpose()?,
+        receipt_nft: msg
+            .receipt_nft
+            .map(|collection| deps.api.addr_validate(&collection))
+            .transpose()?,
+        restricted_creation: msg.restricted_creation,
+        import_mode: false,
+        cw20_creation_deposit: msg
+            .cw20_creation_deposit
+            .map(|deposit| -> StdResult<_> {
+                Ok(Cw20CoinVerified {
+                    address: deps.api.addr_validate(&deposit.address)?,
+                    amount: deposit.amount,
+                })
+            })
+            .transpose()?,
     };
+    if config
+        .max_voting_period
+        .is_some_and(|max| max < config.min_voting_period)
+    {
+        return Err(ContractError::InvalidVotingPeriod {});
+    }
     CONFIG.save(deps.storage, &config)?;
-    Ok(Response::new()
+    for creator in msg.creators.unwrap_or_default() {
+        CREATORS.save(deps.storage, deps.api.addr_validate(&creator)?, &())?;
+    }
+    let initial_polls = msg.initial_polls.unwrap_or_default();
+    let mut response = Response::new();
+    for new_poll in initial_polls.iter().cloned() {
+        let refunds = create_poll(deps.branch(), &env, validated_admin.clone(), new_poll, None)?;
+        response = response.add_messages(refunds);
+    }
+    if !initial_polls.is_empty() {
+        response = response.add_attribute("initial_polls", initial_polls.len().to_string());
+    }
+    Ok(response
         .add_attribute("action", "instantiate")
         .add_attribute(admin, validated_admin.to_string()))
 }
@@ -40,245 +172,13772 @@ pub fn execute(
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
+    // Funds sent with any other message would be stuck in the contract.
+    let payable = matches!(
+        msg,
+        ExecuteMsg::CreatePoll(_)
+            | ExecuteMsg::ClonePoll { .. }
+            | ExecuteMsg::Vote { .. }
+            | ExecuteMsg::VoteByIndex { .. }
+            | ExecuteMsg::FinalizePoll { .. }
+            | ExecuteMsg::FundRewards { .. }
+    );
+    if !payable {
+        nonpayable(&info)?;
+    }
     match msg {
-        ExecuteMsg::CreatePoll {
+        ExecuteMsg::CreatePoll(new_poll) => execute_create_poll(deps, env, info, new_poll),
+        ExecuteMsg::ClonePoll {
+            source_poll_id,
+            new_poll_id,
+        } => execute_clone_poll(deps, env, info, source_poll_id, new_poll_id),
+        ExecuteMsg::Vote {
+            poll_id,
+            vote,
+            memo,
+            proof,
+        } => execute_vote(deps, env, info, poll_id, vote, memo, proof),
+        ExecuteMsg::VoteByIndex {
+            poll_id,
+            option_index,
+            memo,
+            proof,
+        } => {
+            let mut poll = POLL
+                .may_load(deps.storage, poll_id.clone())?
+                .ok_or(ContractError::PollNotFound {})?;
+            // the index must point where queries list the option this block
+            if poll.shuffle_options {
+                return Err(ContractError::ShuffledOptions {});
+            }
+            arrange_options(&mut poll, &poll_id, &env.block);
+            let vote = match poll.option_count {
+                0 => poll
+                    .options
+                    .get(option_index as usize)
+                    .map(|option| option.id),
+                _ => OPTIONS
+                    .prefix(poll_id.clone())
+                    .keys(deps.storage, None, None, Order::Ascending)
+                    .nth(option_index as usize)
+                    .transpose()?,
+            }
+            .ok_or(ContractError::OptionNotFound {})?;
+            execute_vote(deps, env, info, poll_id, vote, memo, proof)
+        }
+        ExecuteMsg::RankVotes {
+            poll_id,
+            ranking,
+            memo,
+            proof,
+        } => execute_rank_votes(deps, env, info, poll_id, ranking, memo, proof),
+        ExecuteMsg::AllocateVotes {
+            poll_id,
+            allocations,
+            memo,
+            proof,
+        } => execute_allocate_votes(deps, env, info, poll_id, allocations, memo, proof),
+        ExecuteMsg::Comment {
             poll_id,
-            question,
-            options,
-        } => execute_create_poll(deps, env, info, poll_id, question, options),
-        ExecuteMsg::Vote { poll_id, vote } => execute_vote(deps, env, info, poll_id, vote),
+            body,
+            reply_to,
+        } => execute_comment(deps, env, info, poll_id, body, reply_to),
+        ExecuteMsg::SuggestOption { poll_id, option } => {
+            execute_suggest_option(deps, env, info, poll_id, option)
+        }
+        ExecuteMsg::ReviewSuggestion {
+            poll_id,
+            suggestion_id,
+            approve,
+        } => execute_review_suggestion(deps, env, info, poll_id, suggestion_id, approve),
+        ExecuteMsg::ClosePoll { poll_id } => execute_close_poll(deps, env, info, poll_id),
+        ExecuteMsg::FreezePoll { poll_id } => execute_freeze_poll(deps, env, info, poll_id),
+        ExecuteMsg::UnfreezePoll { poll_id } => execute_unfreeze_poll(deps, env, info, poll_id),
+        ExecuteMsg::ReopenPoll {
+            poll_id,
+            new_end,
+            reason,
+        } => execute_reopen_poll(deps, env, info, poll_id, new_end, reason),
+        ExecuteMsg::CancelPoll { poll_id, reason } => {
+            execute_cancel_poll(deps, env, info, poll_id, reason)
+        }
+        ExecuteMsg::FlagPoll { poll_id } => execute_flag_poll(deps, env, info, poll_id),
+        ExecuteMsg::Veto { poll_id, reason } => execute_veto(deps, env, info, poll_id, reason),
+        ExecuteMsg::SponsorPoll { poll_id } => execute_sponsor_poll(deps, env, info, poll_id),
+        ExecuteMsg::ExtendDeadline { poll_id, new_end } => {
+            execute_extend_deadline(deps, env, info, poll_id, new_end)
+        }
+        ExecuteMsg::SetDisplayOrder {
+            poll_id,
+            order,
+            shuffle,
+        } => execute_set_display_order(deps, env, info, poll_id, order, shuffle),
+        ExecuteMsg::ArchivePoll { poll_id } => execute_archive_poll(deps, env, info, poll_id),
+        ExecuteMsg::FinalizePoll { poll_id } => execute_finalize_poll(deps, env, info, poll_id),
+        ExecuteMsg::Crank { limit } => execute_crank(deps, env, info, limit),
+        ExecuteMsg::FinalizeExpired { limit } => execute_finalize_expired(deps, env, info, limit),
+        ExecuteMsg::PruneBallots { poll_id, limit } => {
+            execute_prune_ballots(deps, env, info, poll_id, limit)
+        }
+        ExecuteMsg::ResolveTie { poll_id, option } => {
+            execute_resolve_tie(deps, env, info, poll_id, option)
+        }
+        ExecuteMsg::NoisReceive { callback } => execute_nois_receive(deps, info, callback),
+        ExecuteMsg::TryEarlyClose { poll_id } => execute_try_early_close(deps, env, info, poll_id),
+        ExecuteMsg::UpdateConfig {
+            min_voting_period,
+            max_voting_period,
+            max_delegation_depth,
+            veto_period,
+            restricted_creation,
+        } => execute_update_config(
+            deps,
+            env,
+            info,
+            min_voting_period,
+            max_voting_period,
+            max_delegation_depth,
+            veto_period,
+            restricted_creation,
+        ),
+        ExecuteMsg::Delegate { to } => execute_delegate(deps, env, info, to),
+        ExecuteMsg::Undelegate {} => execute_undelegate(deps, env, info),
+        ExecuteMsg::Unlock { poll_id } => execute_unlock(deps, env, info, poll_id),
+        ExecuteMsg::ClaimDeposit { poll_id } => execute_claim_deposit(deps, env, info, poll_id),
+        ExecuteMsg::RegisterVotingKey { pubkey, signature } => {
+            execute_register_voting_key(deps, env, info, pubkey, signature)
+        }
+        ExecuteMsg::SubmitSignedVotes { votes } => {
+            execute_submit_signed_votes(deps, env, info, votes)
+        }
+        ExecuteMsg::AddAdmin { address } => execute_add_admin(deps, env, info, address),
+        ExecuteMsg::RemoveAdmin { address } => execute_remove_admin(deps, env, info, address),
+        ExecuteMsg::AddModerator { address } => execute_add_moderator(deps, env, info, address),
+        ExecuteMsg::RemoveModerator { address } => {
+            execute_remove_moderator(deps, env, info, address)
+        }
+        ExecuteMsg::AddCategoryAdmin { category, address } => {
+            execute_add_category_admin(deps, env, info, category, address)
+        }
+        ExecuteMsg::RemoveCategoryAdmin { category, address } => {
+            execute_remove_category_admin(deps, env, info, category, address)
+        }
+        ExecuteMsg::BanVoter { address } => execute_ban_voter(deps, env, info, address),
+        ExecuteMsg::UnbanVoter { address } => execute_unban_voter(deps, env, info, address),
+        ExecuteMsg::AddCreator { address } => execute_add_creator(deps, env, info, address),
+        ExecuteMsg::RemoveCreator { address } => execute_remove_creator(deps, env, info, address),
+        ExecuteMsg::SetImportMode { enabled } => execute_set_import_mode(deps, env, info, enabled),
+        ExecuteMsg::ImportPolls {
+            polls,
+            ballots,
+            records: vec![],
+            records,
+        } => execute_import_polls(deps, env, info, polls, ballots, records),
+        ExecuteMsg::Receive(wrapper) => execute_receive(deps, env, info, wrapper),
+        ExecuteMsg::FundRewards { poll_id } => execute_fund_rewards(deps, env, info, poll_id),
+        ExecuteMsg::ClaimReward { poll_id } => execute_claim_reward(deps, env, info, poll_id),
+        ExecuteMsg::Unstake { token, amount } => execute_unstake(deps, env, info, token, amount),
+    }
+}
+
+/// Only contract admins may change configuration.
+fn ensure_admin(config: &Config, sender: &Addr) -> Result<(), ContractError> {
+    if !config.is_admin(sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(())
+}
+
+/// A poll may be moderated by its creator, a contract admin, a moderator or
+/// an admin of its category.
+fn ensure_can_moderate(
+    storage: &dyn Storage,
+    config: &Config,
+    poll: &Poll,
+    sender: &Addr,
+) -> Result<(), ContractError> {
+    let category_admin = poll
+        .category
+        .as_ref()
+        .is_some_and(|category| CATEGORY_ADMINS.has(storage, (category.clone(), sender.clone())));
+    if poll.admin != *sender
+        && !config.is_admin(sender)
+        && !config.is_moderator(sender)
+        && !category_admin
+    {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(())
+}
+
+fn ensure_not_banned(deps: Deps, sender: &Addr) -> Result<(), ContractError> {
+    if BANNED.has(deps.storage, sender.clone()) {
+        return Err(ContractError::VoterBanned {});
     }
+    Ok(())
 }
 
-fn execute_create_poll(
+/// Balance of `asset` held by `holder`: a cw20 balance when `asset` is a
+/// contract, otherwise a bank balance of that denom.
+fn balance_of(deps: Deps, asset: &str, holder: &Addr) -> StdResult<Uint128> {
+    let is_contract = deps.api.addr_validate(asset).is_ok()
+        && deps.querier.query_wasm_contract_info(asset).is_ok();
+    if is_contract {
+        let resp: BalanceResponse = deps.querier.query_wasm_smart(
+            asset,
+            &Cw20QueryMsg::Balance {
+                address: holder.to_string(),
+            },
+        )?;
+        Ok(resp.balance)
+    } else {
+        Ok(deps.querier.query_balance(holder, asset)?.amount)
+    }
+}
+
+fn verify_merkle_proof(root: &str, voter: &Addr, proof: &MerkleProof) -> Result<(), ContractError> {
+    let leaf = format!("{}{}", voter, proof.weight);
+    let mut hash: [u8; 32] = Sha256::digest(leaf.as_bytes()).into();
+    for sibling in &proof.proof {
+        let mut sibling_hash = [0u8; 32];
+        hex::decode_to_slice(sibling, &mut sibling_hash)
+            .map_err(|_| ContractError::InvalidMerkleProof {})?;
+        let (first, second) = if hash < sibling_hash {
+            (hash, sibling_hash)
+        } else {
+            (sibling_hash, hash)
+        };
+        hash = Sha256::digest([first, second].concat()).into();
+    }
+    if hex::encode(hash) != root {
+        return Err(ContractError::InvalidMerkleProof {});
+    }
+    Ok(())
+}
+
+/// The voter's weight under the poll's Merkle root, if it has one.
+fn proven_weight(
+    poll: &Poll,
+    voter: &Addr,
+    proof: Option<MerkleProof>,
+) -> Result<Option<Uint128>, ContractError> {
+    match &poll.merkle_root {
+        Some(root) => {
+            let proof = proof.ok_or(ContractError::MerkleProofRequired {})?;
+            verify_merkle_proof(root, voter, &proof)?;
+            Ok(Some(proof.weight))
+        }
+        None => Ok(None),
+    }
+}
+
+fn ensure_min_balance(deps: Deps, poll: &Poll, voter: &Addr) -> Result<(), ContractError> {
+    if let Some((asset, min)) = &poll.min_balance {
+        if balance_of(deps, asset, voter)? < *min {
+            return Err(ContractError::BalanceTooLow {
+                min: *min,
+                asset: asset.clone(),
+            });
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn execute_create_poll(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
-    poll_id: String,
-    question: String,
-    options: Vec<String>,
+    new_poll: NewPoll,
 ) -> Result<Response, ContractError> {
-    if options.len() > 10 {
+    ensure_not_banned(deps.as_ref(), &info.sender)?;
+    let config = CONFIG.load(deps.storage)?;
+    let mut response = Response::new().add_attribute("action", "create poll");
+    if let Some(deposit) = &config.creation_deposit {
+        let required = || ContractError::CreationDepositRequired {
+            deposit: deposit.clone(),
+        };
+        let paid = must_pay(&info, &deposit.denom).map_err(|_| required())?;
+        let excess = paid.checked_sub(deposit.amount).map_err(|_| required())?;
+        if !excess.is_zero() {
+            let asset = RewardAsset::Native(deposit.denom.clone());
+            response = response
+                .add_message(transfer_msg(&asset, &info.sender, excess)?)
+                .add_attribute("refunded", excess);
+        }
+    } else if let Some(deposit) = &config.cw20_creation_deposit {
+        return Err(ContractError::Cw20CreationDepositRequired {
+            token: deposit.address.to_string(),
+            amount: deposit.amount,
+        });
+    } else {
+        nonpayable(&info)?;
+    }
+    let refunds = create_poll(deps, &env, info.sender, new_poll, config.creation_deposit)?;
+    Ok(response.add_messages(refunds))
+}
+
+/// Validates and stores a poll for `admin`, holding `creation_deposit` until
+/// it closes. Returns the refunds of a superseded poll it had to close.
+fn create_poll(
+    deps: DepsMut,
+    env: &Env,
+    admin: Addr,
+    new_poll: NewPoll,
+    creation_deposit: Option<Coin>,
+) -> Result<Vec<CosmosMsg>, ContractError> {
+    // a poll's content, ballots and deposits are never replaced
+    if POLL.has(deps.storage, new_poll.poll_id.clone()) {
+        return Err(ContractError::PollIdTaken {});
+    }
+    let config = CONFIG.load(deps.storage)?;
+    if config.import_mode {
+        return Err(ContractError::ImportInProgress {});
+    }
+    if config.restricted_creation
+        && !config.is_admin(&admin)
+        && !CREATORS.has(deps.storage, admin.clone())
+    {
+        return Err(ContractError::CreationRestricted {});
+    }
+    if new_poll.options.len() > MAX_OPTIONS {
         return Err(ContractError::TooManyPollOptions {});
     }
+    if let Some(hash) = &new_poll.content_hash {
+        if hash.is_empty() || hash.len() > MAX_CONTENT_HASH_LENGTH {
+            return Err(ContractError::InvalidContentHash {
+                max: MAX_CONTENT_HASH_LENGTH,
+            });
+        }
+    }
+    if let Some(end_time) = new_poll.end_time {
+        let earliest_end = env.block.time.plus_seconds(config.min_voting_period);
+        if end_time <= env.block.time || end_time < earliest_end {
+            return Err(ContractError::VotingPeriodTooShort {
+                min: config.min_voting_period,
+            });
+        }
+    }
+    if let Some(max) = config.max_voting_period {
+        let latest_end = env.block.time.plus_seconds(max);
+        if new_poll
+            .end_time
+            .is_none_or(|end_time| end_time > latest_end)
+        {
+            return Err(ContractError::VotingPeriodTooLong { max });
+        }
+    }
 
-    let mut opts: Vec<(String, u64)> = vec![];
-    for option in options {
-        opts.push((option, 0))
+    let translations = new_poll.translations.unwrap_or_default();
+    for (locale, text) in &translations {
+        if new_poll.default_locale.is_none() || text.options.len() != new_poll.options.len() {
+            return Err(ContractError::InvalidTranslation {
+                locale: locale.clone(),
+            });
+        }
     }
+    let metadata = new_poll
+        .option_metadata
+        .unwrap_or_else(|| vec![OptionMetadata::default(); new_poll.options.len()]);
+    let too_long = |text: &Option<String>, max: usize| {
+        text.as_ref().is_some_and(|text| text.chars().count() > max)
+    };
+    if metadata.len() != new_poll.options.len()
+        || metadata.iter().any(|meta| {
+            too_long(&meta.description, MAX_OPTION_DESCRIPTION_LENGTH)
+                || too_long(&meta.image_uri, MAX_OPTION_URI_LENGTH)
+        })
+    {
+        return Err(ContractError::InvalidOptionMetadata {
+            max_description: MAX_OPTION_DESCRIPTION_LENGTH,
+            max_uri: MAX_OPTION_URI_LENGTH,
+        });
+    }
+    let attributes = &new_poll.attributes;
+    if attributes.len() > MAX_ATTRIBUTES
+        || attributes.iter().enumerate().any(|(i, (key, value))| {
+            key.is_empty()
+                || key.chars().count() > MAX_ATTRIBUTE_LENGTH
+                || value.chars().count() > MAX_ATTRIBUTE_LENGTH
+                || attributes[..i].iter().any(|(other, _)| other == key)
+        })
+    {
+        return Err(ContractError::InvalidAttributes {
+            max_count: MAX_ATTRIBUTES,
+            max_length: MAX_ATTRIBUTE_LENGTH,
+        });
+    }
+    let opts: Vec<PollOption> = new_poll
+        .options
+        .into_iter()
+        .zip(metadata)
+        .enumerate()
+        .map(|(id, (label, meta))| PollOption {
+            id: id as u8,
+            label,
+            votes: 0,
+            description: meta.description,
+            image_uri: meta.image_uri,
+        })
+        .collect();
 
-    let poll = Poll {
-        admin: info.sender,
-        question,
+    let mode = match new_poll.mode.unwrap_or_default() {
+        VoteMode::Cw20Deposit { token } => VoteMode::Cw20Deposit {
+            token: deps.api.addr_validate(token.as_str())?,
+        },
+        VoteMode::Snapshot { token } => VoteMode::Snapshot {
+            token: deps.api.addr_validate(token.as_str())?,
+        },
+        VoteMode::Vesting { contract } => VoteMode::Vesting {
+            contract: deps.api.addr_validate(contract.as_str())?,
+        },
+        VoteMode::Staking { contract } => VoteMode::Staking {
+            contract: deps.api.addr_validate(contract.as_str())?,
+        },
+        VoteMode::Conviction {
+            token,
+            max_multiplier,
+            ramp_seconds,
+        } => {
+            if max_multiplier == 0 || ramp_seconds == 0 {
+                return Err(ContractError::InvalidConviction {});
+            }
+            VoteMode::Conviction {
+                token: deps.api.addr_validate(token.as_str())?,
+                max_multiplier,
+                ramp_seconds,
+            }
+        }
+        VoteMode::Cumulative {
+            budget: VoteBudget::Stake { token },
+        } => VoteMode::Cumulative {
+            budget: VoteBudget::Stake {
+                token: deps.api.addr_validate(token.as_str())?,
+            },
+        },
+        mode => mode,
+    };
+    if matches!(
+        new_poll.decay,
+        Some(WeightDecay::Linear { period: 0 } | WeightDecay::HalfLife { half_life: 0 })
+    ) {
+        return Err(ContractError::InvalidDecay {});
+    }
+    if new_poll.merkle_root.is_none()
+        && matches!(
+            mode,
+            VoteMode::Cumulative {
+                budget: VoteBudget::Snapshot {}
+            }
+        )
+    {
+        return Err(ContractError::SnapshotRootRequired {});
+    }
+    if new_poll.merkle_total_weight.is_some() && new_poll.merkle_root.is_none() {
+        return Err(ContractError::InvalidMerkleRoot {});
+    }
+    let merkle_root = match new_poll.merkle_root {
+        Some(root) => {
+            let mut hash = [0u8; 32];
+            hex::decode_to_slice(&root, &mut hash)
+                .map_err(|_| ContractError::InvalidMerkleRoot {})?;
+            Some(root.to_lowercase())
+        }
+        None => None,
+    };
+    let valid_quorum = match &new_poll.quorum {
+        None | Some(Quorum::AbsoluteWeight(_)) => true,
+        Some(Quorum::Percentage(share)) => *share <= Decimal::one(),
+        Some(Quorum::Dynamic { curve }) => {
+            !curve.is_empty()
+                && curve.windows(2).all(|steps| steps[0].0 < steps[1].0)
+                && curve.iter().all(|(turnout, threshold)| {
+                    *turnout <= Decimal::one() && *threshold <= Decimal::one()
+                })
+        }
+    };
+    if !valid_quorum {
+        return Err(ContractError::InvalidQuorum {});
+    }
+    if new_poll.results_after_quorum && new_poll.quorum.is_none() {
+        return Err(ContractError::QuorumRequired {});
+    }
+    if new_poll
+        .win_threshold
+        .is_some_and(|threshold| threshold.is_zero() || threshold > Decimal::one())
+    {
+        return Err(ContractError::InvalidWinThreshold {});
+    }
+    let superseded = match new_poll.supersedes {
+        Some(old_id) => {
+            let old = POLL
+                .may_load(deps.storage, old_id.clone())?
+                .ok_or(ContractError::PollNotFound {})?;
+            if old.admin != admin {
+                return Err(ContractError::Unauthorized {});
+            }
+            if old.superseded_by.is_some() {
+                return Err(ContractError::InvalidSupersede {});
+            }
+            Some((old_id, old))
+        }
+        None => None,
+    };
+    let recounted = matches!(mode, VoteMode::Ranked { .. } | VoteMode::Conviction { .. })
+        || new_poll.decay.is_some();
+    let max_voters = match new_poll.max_voters {
+        Some(max) if recounted && max > MAX_RECOUNTED_VOTERS => {
+            return Err(ContractError::TooManyVoters {
+                max: MAX_RECOUNTED_VOTERS,
+            })
+        }
+        None if recounted => Some(MAX_RECOUNTED_VOTERS),
+        max_voters => max_voters,
+    };
+    if let Some(min_voters) = new_poll.min_voters {
+        let reachable = max_voters.is_none_or(|max| min_voters <= max)
+            && new_poll
+                .allowed_voters
+                .as_ref()
+                .is_none_or(|voters| min_voters as usize <= voters.len());
+        if min_voters == 0 || !reachable {
+            return Err(ContractError::InvalidMinVoters {});
+        }
+    }
+    if new_poll.pseudonymous
+        && matches!(
+            mode,
+            VoteMode::Cw20Deposit { .. } | VoteMode::Conviction { .. }
+        )
+    {
+        return Err(ContractError::PseudonymousPayout {});
+    }
+    let weight_overrides = new_poll
+        .weight_overrides
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(voter, weight)| Ok((deps.api.addr_validate(&voter)?, weight)))
+        .collect::<StdResult<BTreeMap<_, _>>>()?;
+    if !weight_overrides.is_empty()
+        && (weight_overrides.iter().any(|(_, weight)| *weight == 0)
+            || matches!(
+                mode,
+                VoteMode::Burn { .. }
+                    | VoteMode::Cw20Deposit { .. }
+                    | VoteMode::Conviction { .. }
+                    | VoteMode::Cumulative { .. }
+            ))
+    {
+        return Err(ContractError::InvalidWeightOverrides {});
+    }
+    // the weight the allowlist can cast, each voter counting its override
+    let electorate = match new_poll.allowed_voters {
+        Some(voters) => {
+            for voter in voters {
+                let voter = deps.api.addr_validate(&voter)?;
+                ALLOWED_VOTERS.save(deps.storage, (new_poll.poll_id.clone(), voter), &())?;
+            }
+            let mut total: u64 = 0;
+            for voter in ALLOWED_VOTERS.prefix(new_poll.poll_id.clone()).keys(
+                deps.storage,
+                None,
+                None,
+                Order::Ascending,
+            ) {
+                let weight = weight_overrides.get(&voter?).copied().unwrap_or(1);
+                total = total
+                    .checked_add(weight)
+                    .ok_or(ContractError::WeightOverflow {})?;
+            }
+            Some(total)
+        }
+        None => None,
+    };
+    let weight_fn = new_poll.weight_fn.unwrap_or_default();
+    if weight_fn != WeightFormula::Linear
+        && matches!(
+            mode,
+            VoteMode::Burn { .. } | VoteMode::Conviction { .. } | VoteMode::Cumulative { .. }
+        )
+    {
+        return Err(ContractError::UnsupportedWeightFormula {});
+    }
+    if new_poll.max_weight_per_voter.is_some()
+        && matches!(
+            mode,
+            VoteMode::Cumulative { .. } | VoteMode::Conviction { .. }
+        )
+    {
+        return Err(ContractError::UnsupportedWeightCap {});
+    }
+    // staked supply is only comparable to tallies it was not reshaped from
+    let eligible_weight = match (&mode, new_poll.merkle_total_weight) {
+        (_, Some(total)) => Some(to_weight(total)?),
+        (VoteMode::Snapshot { token }, None) if weight_fn == WeightFormula::Linear => {
+            let total = total_stake_at(deps.storage, token.clone(), env.block.height)?;
+            Some(to_weight(total)?)
+        }
+        (VoteMode::Staking { contract }, None) if weight_fn == WeightFormula::Linear => {
+            let total = total_staked(deps.as_ref(), contract, env.block.height)?;
+            Some(to_weight(total)?)
+        }
+        (VoteMode::Standard | VoteMode::Ranked { .. }, None) => electorate,
+        _ => None,
+    };
+    if matches!(
+        new_poll.quorum,
+        Some(Quorum::Percentage(_) | Quorum::Dynamic { .. })
+    ) && eligible_weight.is_none()
+    {
+        return Err(ContractError::InvalidQuorum {});
+    }
+    let tie_break = new_poll.tie_break.unwrap_or_default();
+    if (tie_break == TieBreakPolicy::Random || new_poll.raffle) && config.randomness_proxy.is_none()
+    {
+        return Err(ContractError::RandomnessUnavailable {});
+    }
+    if new_poll.raffle && new_poll.pseudonymous {
+        return Err(ContractError::PseudonymousPayout {});
+    }
+    let paged = opts.len() > MAX_INLINE_OPTIONS;
+    if paged
+        && (matches!(mode, VoteMode::Ranked { .. } | VoteMode::Conviction { .. })
+            || new_poll.decay.is_some()
+            || new_poll.open_options
+            || new_poll.results_after_quorum
+            || !new_poll.satellite_channels.is_empty()
+            || !translations.is_empty())
+    {
+        return Err(ContractError::PagedOptionsUnsupported {
+            max: MAX_INLINE_OPTIONS,
+        });
+    }
+    let mut poll = Poll {
+        admin,
+        question: new_poll.question,
         options: opts,
+        status: if config.required_sponsors > 0 {
+            PollStatus::Draft
+        } else {
+            PollStatus::Open
+        },
+        start_time: env.block.time,
+        end_time: new_poll.end_time,
+        mode,
+        reward_distribution: new_poll.reward_distribution.unwrap_or_default(),
+        snapshot_height: env.block.height,
+        tie_break,
+        voter_count: 0,
+        electorate,
+        eligible_weight,
+        cancel_reason: None,
+        min_balance: new_poll.min_balance,
+        merkle_root,
+        decay: new_poll.decay,
+        category: new_poll.category,
+        creation_deposit,
+        cw20_creation_deposit: None,
+        flags: 0,
+        closed_at: None,
+        content_hash: new_poll.content_hash,
+        max_voters,
+        min_voters: new_poll.min_voters,
+        quorum: new_poll.quorum,
+        pseudonymous: new_poll.pseudonymous,
+        default_locale: new_poll.default_locale,
+        translations,
+        voters_only_results: new_poll.voters_only_results,
+        results_after_quorum: new_poll.results_after_quorum,
+        revote_window: new_poll.revote_window,
+        open_options: new_poll.open_options,
+        satellite_channels: new_poll.satellite_channels,
+        weight_fn,
+        max_weight_per_voter: new_poll.max_weight_per_voter,
+        raffle: new_poll.raffle,
+        win_threshold: new_poll.win_threshold,
+        attributes: new_poll.attributes,
+        display_order: vec![],
+        shuffle_options: false,
+        supersedes: superseded.as_ref().map(|(old_id, _)| old_id.clone()),
+        superseded_by: None,
+        option_count: 0,
+        pending_suggestions: 0,
+        ballot_weight: 0,
+        pruned_ballots: 0,
     };
-    POLL.save(deps.storage, poll_id, &poll)?;
-    Ok(Response::new().add_attribute("action", "create poll"))
+    let proposal_id = PROPOSAL_SEQ.may_load(deps.storage)?.unwrap_or_default() + 1;
+    PROPOSAL_SEQ.save(deps.storage, &proposal_id)?;
+    PROPOSALS.save(deps.storage, proposal_id, &new_poll.poll_id)?;
+    page_options(deps.storage, &new_poll.poll_id, &mut poll)?;
+    POLL.save(deps.storage, new_poll.poll_id.clone(), &poll)?;
+    if poll.pseudonymous {
+        new_salt(deps.storage, env, &new_poll.poll_id, &poll.admin)?;
+    }
+    index_end(deps.storage, &new_poll.poll_id, &poll)?;
+    index_attributes(deps.storage, &new_poll.poll_id, &poll)?;
+    for (voter, weight) in weight_overrides {
+        WEIGHT_OVERRIDES.save(deps.storage, (new_poll.poll_id.clone(), voter), &weight)?;
+    }
+    CREATOR_POLLS.save(
+        deps.storage,
+        (poll.admin.clone(), new_poll.poll_id.clone()),
+        &(),
+    )?;
+    count_created(deps.storage, &poll.admin)?;
+    record_change(
+        deps.storage,
+        &env.block,
+        &new_poll.poll_id,
+        &poll.admin,
+        PollChange::Created,
+    )?;
+
+    let mut refunds = vec![];
+    if let Some((old_id, mut old)) = superseded {
+        if old.status == PollStatus::Open {
+            refunds = close_poll(deps.storage, &env.block, &old_id, &mut old, &poll.admin)?;
+        }
+        old.superseded_by = Some(new_poll.poll_id);
+        POLL.save(deps.storage, old_id, &old)?;
+    }
+    Ok(refunds)
 }
 
-fn execute_vote(
+/// Recreates a poll from another's settings. A source with a deadline gives
+/// the clone the same voting period, counted from now. Only the source's
+/// creator or a contract admin may clone it.
+fn execute_clone_poll(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
+    info: MessageInfo,
+    source_poll_id: String,
+    new_poll_id: String,
+) -> Result<Response, ContractError> {
+    let mut source = POLL
+        .may_load(deps.storage, source_poll_id.clone())?
+        .ok_or(ContractError::PollNotFound {})?;
+    if source.admin != info.sender {
+        ensure_admin(&CONFIG.load(deps.storage)?, &info.sender)?;
+    }
+    let allowed_voters = match source.electorate {
+        Some(_) => Some(
+            ALLOWED_VOTERS
+                .prefix(source_poll_id.clone())
+                .keys(deps.storage, None, None, Order::Ascending)
+                .map(|voter| voter.map(String::from))
+                .collect::<StdResult<Vec<_>>>()?,
+        ),
+        None => None,
+    };
+    let weight_overrides = WEIGHT_OVERRIDES
+        .prefix(source_poll_id.clone())
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(voter, weight)| (voter.into_string(), weight)))
+        .collect::<StdResult<Vec<_>>>()?;
+    source.options = poll_options(deps.storage, &source_poll_id, &source)?;
+    let merkle_total_weight = source
+        .merkle_root
+        .as_ref()
+        .and(source.eligible_weight)
+        .map(Uint128::from);
+    let voting_period = source
+        .end_time
+        .map(|end| end.seconds() - source.start_time.seconds());
+    let new_poll = NewPoll {
+        poll_id: new_poll_id,
+        question: source.question,
+        options: source
+            .options
+            .iter()
+            .map(|option| option.label.clone())
+            .collect(),
+        option_metadata: Some(
+            source
+                .options
+                .into_iter()
+                .map(|option| OptionMetadata {
+                    description: option.description,
+                    image_uri: option.image_uri,
+                })
+                .collect(),
+        ),
+        end_time: voting_period.map(|period| env.block.time.plus_seconds(period)),
+        mode: Some(source.mode),
+        reward_distribution: Some(source.reward_distribution),
+        tie_break: Some(source.tie_break),
+        allowed_voters,
+        weight_overrides: (!weight_overrides.is_empty()).then_some(weight_overrides),
+        min_balance: source.min_balance,
+        merkle_root: source.merkle_root,
+        merkle_total_weight,
+        decay: source.decay,
+        weight_fn: Some(source.weight_fn),
+        max_weight_per_voter: source.max_weight_per_voter,
+        raffle: source.raffle,
+        win_threshold: source.win_threshold,
+        attributes: source.attributes,
+        category: source.category,
+        content_hash: source.content_hash,
+        max_voters: source.max_voters,
+        min_voters: source.min_voters,
+        quorum: source.quorum,
+        pseudonymous: source.pseudonymous,
+        default_locale: source.default_locale,
+        translations: Some(source.translations),
+        voters_only_results: source.voters_only_results,
+        results_after_quorum: source.results_after_quorum,
+        revote_window: source.revote_window,
+        open_options: source.open_options,
+        satellite_channels: source.satellite_channels,
+        supersedes: None,
+    };
+    let response = execute_create_poll(deps, env, info, new_poll)?;
+    Ok(response.add_attribute("cloned_from", source_poll_id))
+}
+
+pub(crate) fn execute_vote(
+    deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     poll_id: String,
-    vote: String,
+    vote: u8,
+    memo: Option<String>,
+    proof: Option<MerkleProof>,
 ) -> Result<Response, ContractError> {
-    let poll = POLL.may_load(deps.storage, poll_id.clone())?;
+    ensure_not_banned(deps.as_ref(), &info.sender)?;
+    validate_memo(&memo)?;
+    let mut poll = POLL
+        .may_load(deps.storage, poll_id.clone())?
+        .ok_or(ContractError::PollNotFound {})?;
+    ensure_min_balance(deps.as_ref(), &poll, &info.sender)?;
+    let fixed_weight =
+        WEIGHT_OVERRIDES.may_load(deps.storage, (poll_id.clone(), info.sender.clone()))?;
+    let proven_weight = match fixed_weight {
+        Some(weight) => Some(weight),
+        None => proven_weight(&poll, &info.sender, proof)?
+            .map(|amount| token_weight(&poll, amount))
+            .transpose()?,
+    };
+    if !matches!(poll.mode, VoteMode::Burn { .. }) {
+        nonpayable(&info)?;
+    }
 
-    if let Some(mut poll) = poll {
-        BALLOT.update(
-            deps.storage,
-            (info.sender, poll_id.clone()),
-            |ballot| -> StdResult<Ballot> {
-                match ballot {
-                    Some(ballot) => {
-                        let position_of_old_vote = poll
-                            .options
-                            .iter()
-                            .position(|option| option.0 == ballot.option)
-                            .unwrap();
-                        poll.options[position_of_old_vote].1 -= 1;
-                        Ok(Ballot {
-                            option: vote.clone(),
-                        })
-                    }
-                    None => Ok(Ballot {
-                        option: vote.clone(),
-                    }),
+    let mut response = Response::new().add_attribute("action", "vote in poll");
+    let ballot = match &poll.mode {
+        VoteMode::Standard => {
+            let weight = standard_weight(
+                deps.storage,
+                &env.block,
+                &poll_id,
+                &mut poll,
+                &info.sender,
+                proven_weight,
+            )?;
+            Ballot {
+                option: vote,
+                weight,
+                deposit: Uint128::zero(),
+                memo,
+                allocations: vec![],
+                voted_at: env.block.time,
+                ranking: vec![],
+                raw_weight: None,
+            }
+        }
+        VoteMode::Snapshot { .. } | VoteMode::Vesting { .. } | VoteMode::Staking { .. }
+            if fixed_weight.is_some() =>
+        {
+            Ballot {
+                option: vote,
+                weight: fixed_weight.unwrap_or_default(),
+                deposit: Uint128::zero(),
+                memo,
+                allocations: vec![],
+                voted_at: env.block.time,
+                ranking: vec![],
+                raw_weight: None,
+            }
+        }
+        VoteMode::Cw20Deposit { .. } | VoteMode::Conviction { .. } => {
+            return Err(ContractError::DepositRequired {})
+        }
+        VoteMode::Burn { denom } => {
+            let amount = must_pay(&info, denom).map_err(|_| ContractError::BurnRequired {
+                denom: denom.clone(),
+            })?;
+            response = response
+                .add_message(BankMsg::Burn {
+                    amount: info.funds.clone(),
+                })
+                .add_attribute("burned", amount);
+            let (key, _) = ballot_key(deps.storage, &poll_id, &poll, &info.sender)?;
+            let mut ballot = top_up_ballot(deps.storage, &key, &poll_id, vote, amount, false)?;
+            ballot.memo = memo;
+            ballot.voted_at = env.block.time;
+            ballot
+        }
+        VoteMode::Snapshot { token } => {
+            let stake = staked_at(deps.storage, token, &info.sender, poll.snapshot_height)?;
+            Ballot {
+                option: vote,
+                weight: token_weight(&poll, stake)?,
+                deposit: Uint128::zero(),
+                memo,
+                allocations: vec![],
+                voted_at: env.block.time,
+                ranking: vec![],
+                raw_weight: None,
+            }
+        }
+        VoteMode::Vesting { contract } => {
+            let grant = vesting_grant(deps.as_ref(), contract, &info.sender)?;
+            Ballot {
+                option: vote,
+                weight: token_weight(&poll, grant)?,
+                deposit: Uint128::zero(),
+                memo,
+                allocations: vec![],
+                voted_at: env.block.time,
+                ranking: vec![],
+                raw_weight: None,
+            }
+        }
+        VoteMode::Staking { contract } => {
+            let stake =
+                staked_balance(deps.as_ref(), contract, &info.sender, poll.snapshot_height)?;
+            Ballot {
+                option: vote,
+                weight: token_weight(&poll, stake)?,
+                deposit: Uint128::zero(),
+                memo,
+                allocations: vec![],
+                voted_at: env.block.time,
+                ranking: vec![],
+                raw_weight: None,
+            }
+        }
+        VoteMode::Cumulative { .. } => return Err(ContractError::AllocationsRequired {}),
+        VoteMode::Ranked { .. } => return Err(ContractError::RankingRequired {}),
+    };
+    let saved = save_ballot(
+        deps.storage,
+        &env.block,
+        poll_id,
+        &mut poll,
+        info.sender,
+        ballot,
+    )?;
+    let mut response = response
+        .add_submessages(saved.messages)
+        .add_events(saved.events);
+    response.data = saved.data;
+    Ok(response)
+}
+
+fn execute_receive(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    wrapper: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    let sender = deps.api.addr_validate(&wrapper.sender)?;
+    match from_binary(&wrapper.msg)? {
+        ReceiveMsg::Vote {
+            poll_id,
+            option,
+            memo,
+        } => {
+            validate_memo(&memo)?;
+            let mut ballot = top_up_ballot(
+                deps.storage,
+                &sender,
+                &poll_id,
+                option,
+                wrapper.amount,
+                true,
+            )?;
+            ballot.memo = memo;
+            ballot.voted_at = env.block.time;
+            receive_vote(deps, env, info.sender, sender, poll_id, ballot)
+        }
+        ReceiveMsg::FundRewards { poll_id } => {
+            let asset = RewardAsset::Cw20(info.sender);
+            fund_rewards(deps, sender, poll_id, asset, wrapper.amount)
+        }
+        ReceiveMsg::CreatePoll(new_poll) => {
+            ensure_not_banned(deps.as_ref(), &sender)?;
+            let config = CONFIG.load(deps.storage)?;
+            let deposit = match config.cw20_creation_deposit {
+                Some(required)
+                    if required.address == info.sender && wrapper.amount >= required.amount =>
+                {
+                    required
                 }
-            },
-        )?;
-        let position = poll
-            .options
-            .iter()
-            .position(|option| option.0 == vote)
-            .unwrap();
-        poll.options[position].1 += 1;
-        POLL.save(deps.storage, poll_id, &poll)?;
-        Ok(Response::new().add_attribute("action", "vote in poll"))
-    } else {
-        Err(ContractError::CustomError {
-            val: "Poll not found".to_string(),
-        })
+                Some(required) => {
+                    return Err(ContractError::Cw20CreationDepositRequired {
+                        token: required.address.to_string(),
+                        amount: required.amount,
+                    })
+                }
+                None => return Err(ContractError::InvalidDepositToken {}),
+            };
+            let poll_id = new_poll.poll_id.clone();
+            let refunds = create_poll(deps.branch(), &env, sender.clone(), new_poll, None)?;
+            let excess = wrapper.amount - deposit.amount;
+            let mut response = Response::new()
+                .add_messages(refunds)
+                .add_attribute("action", "create poll");
+            if !excess.is_zero() {
+                let asset = RewardAsset::Cw20(deposit.address.clone());
+                response = response
+                    .add_message(transfer_msg(&asset, &sender, excess)?)
+                    .add_attribute("refunded", excess);
+            }
+            POLL.update(deps.storage, poll_id, |poll| -> StdResult<_> {
+                let mut poll = poll.ok_or_else(|| StdError::not_found("poll"))?;
+                poll.cw20_creation_deposit = Some(deposit);
+                Ok(poll)
+            })?;
+            Ok(response)
+        }
+        ReceiveMsg::Lock { poll_id, option } => lock_conviction(
+            deps,
+            env,
+            info.sender,
+            sender,
+            poll_id,
+            option,
+            wrapper.amount,
+        ),
+        ReceiveMsg::Stake {} => {
+            let stake = STAKES.update(
+                deps.storage,
+                (info.sender.clone(), sender.clone()),
+                env.block.height,
+                |stake| -> StdResult<_> { Ok(stake.unwrap_or_default() + wrapper.amount) },
+            )?;
+            STAKE_TOTALS.update(
+                deps.storage,
+                info.sender.clone(),
+                env.block.height,
+                |total| -> StdResult<_> { Ok(total.unwrap_or_default() + wrapper.amount) },
+            )?;
+            Ok(Response::new()
+                .add_attribute("action", "stake")
+                .add_attribute("token", info.sender)
+                .add_attribute("staker", sender)
+                .add_attribute("amount", wrapper.amount)
+                .add_attribute("stake", stake))
+        }
     }
 }
 
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    match msg {
-        QueryMsg::AllPoll {} => query_all_poll(deps, env),
-        QueryMsg::Poll { poll_id } => query_poll(deps, env, poll_id),
-        QueryMsg::Vote { poll_id, address } => query_vote(deps, env, poll_id, address),
+fn execute_unstake(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    token: String,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    let token = deps.api.addr_validate(&token)?;
+    let stake = STAKES.update(
+        deps.storage,
+        (token.clone(), info.sender.clone()),
+        env.block.height,
+        |stake| {
+            stake
+                .unwrap_or_default()
+                .checked_sub(amount)
+                .map_err(|_| ContractError::InsufficientStake {})
+        },
+    )?;
+    STAKE_TOTALS.update(
+        deps.storage,
+        token.clone(),
+        env.block.height,
+        |total| -> StdResult<_> { Ok(total.unwrap_or_default().checked_sub(amount)?) },
+    )?;
+
+    let transfer = transfer_msg(&RewardAsset::Cw20(token.clone()), &info.sender, amount)?;
+    Ok(Response::new()
+        .add_message(transfer)
+        .add_attribute("action", "unstake")
+        .add_attribute("token", token)
+        .add_attribute("staker", info.sender)
+        .add_attribute("amount", amount)
+        .add_attribute("stake", stake))
+}
+
+/// The voter plus every non-voting delegator within `max_delegation_depth`
+/// hops of them.
+fn delegation_units(
+    storage: &dyn Storage,
+    poll_id: &str,
+    poll: &Poll,
+    voter: &Addr,
+) -> StdResult<Vec<Addr>> {
+    let config = CONFIG.load(storage)?;
+    let mut units = vec![voter.clone()];
+    let mut frontier = vec![voter.clone()];
+    for _ in 0..config.max_delegation_depth {
+        let mut next = vec![];
+        for delegate in frontier {
+            for delegator in DELEGATORS
+                .prefix(delegate)
+                .keys(storage, None, None, Order::Ascending)
+            {
+                let delegator = delegator?;
+                let key = (poll_id.to_string(), delegator.clone());
+                if BALLOT.has(storage, (delegator.clone(), poll_id.to_string()))
+                    || BANNED.has(storage, delegator.clone())
+                    || (poll.electorate.is_some() && !ALLOWED_VOTERS.has(storage, key))
+                {
+                    continue;
+                }
+                next.push(delegator);
+            }
+        }
+        units.extend(next.iter().cloned());
+        frontier = next;
     }
+    Ok(units)
 }
 
-fn query_all_poll(deps: Deps, _env: Env) -> StdResult<Binary> {
-    let polls = POLL
-        .range(deps.storage, None, None, Order::Ascending)
-        .map(|p| Ok(p?.1))
-        .collect::<StdResult<Vec<_>>>()?;
-    to_binary(&AllPollResponse { polls })
+/// Weight for a first ballot in a standard poll: the voter plus every
+/// non-voting delegator within `max_delegation_depth` hops. Delegators move
+/// over from a more distant representative that had been carrying them.
+fn claim_delegated_weight(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    poll_id: &str,
+    poll: &mut Poll,
+    voter: &Addr,
+) -> Result<u64, ContractError> {
+    let units = delegation_units(storage, poll_id, poll, voter)?;
+    for unit in &units {
+        let key = (poll_id.to_string(), unit.clone());
+        if let Some(representative) = REPRESENTED.may_load(storage, key.clone())? {
+            let mut ballot = BALLOT.load(storage, (representative.clone(), poll_id.to_string()))?;
+            let old = ballot.shares();
+            let counted = ballot.weight;
+            ballot.set_weight(ballot.uncapped_weight() - 1, poll.weight_cap());
+            let dropped = counted - ballot.weight;
+            poll.ballot_weight = poll.ballot_weight.saturating_sub(dropped);
+            update_option(storage, block, poll_id, poll, ballot.option, |option| {
+                option.votes -= dropped
+            })?;
+            index_shares(storage, poll_id, &representative, &old, &ballot.shares())?;
+            BALLOT.save(storage, (representative, poll_id.to_string()), &ballot)?;
+        }
+        if unit == voter {
+            REPRESENTED.remove(storage, key);
+        } else {
+            REPRESENTED.save(storage, key, voter)?;
+        }
+    }
+    Ok(units.len() as u64)
 }
 
-fn query_poll(deps: Deps, _env: Env, poll_id: String) -> StdResult<Binary> {
-    let poll = POLL.may_load(deps.storage, poll_id)?;
-    to_binary(&PollResponse { poll })
+/// `Poll::ballot_key` with the poll's salt.
+fn ballot_key(
+    storage: &dyn Storage,
+    poll_id: &str,
+    poll: &Poll,
+    voter: &Addr,
+) -> StdResult<(Addr, String)> {
+    let salt = match poll.pseudonymous {
+        true => POLL_SALTS.load(storage, poll_id.to_string())?,
+        false => Binary::default(),
+    };
+    Ok(poll.ballot_key(poll_id, voter, &salt))
 }
 
-fn query_vote(deps: Deps, _env: Env, address: String, poll_id: String) -> StdResult<Binary> {
-    let validated_address = deps.api.addr_validate(&address)?;
-    let vote = BALLOT.may_load(deps.storage, (validated_address, poll_id))?;
-    to_binary(&VoteResponse { vote })
+/// Draws the salt of a new pseudonymous poll, chained onto the previous
+/// one so it can't be worked out from the poll's own fields.
+fn new_salt(storage: &mut dyn Storage, env: &Env, poll_id: &str, creator: &Addr) -> StdResult<()> {
+    let seed = SALT_SEED.may_load(storage)?.unwrap_or_default();
+    let tx_index = env.transaction.as_ref().map_or(0, |tx| tx.index);
+    let salt = Sha256::new()
+        .chain_update(seed.as_slice())
+        .chain_update(poll_id)
+        .chain_update(creator.as_bytes())
+        .chain_update(env.block.height.to_be_bytes())
+        .chain_update(env.block.time.nanos().to_be_bytes())
+        .chain_update(tx_index.to_be_bytes())
+        .finalize();
+    let salt = Binary::from(salt.to_vec());
+    SALT_SEED.save(storage, &salt)?;
+    POLL_SALTS.save(storage, poll_id.to_string(), &salt)
 }
 
-#[cfg(test)]
-mod tests {
-    use crate::contract::{execute, execute_create_poll, instantiate, query};
-    use crate::msg::{ExecuteMsg, InstantiateMsg, PollResponse, QueryMsg};
-    use crate::state::Poll;
-    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-    use cosmwasm_std::{attr, from_binary, Addr};
+/// One vote per address, plus any weight delegated to it, kept across revotes.
+fn standard_weight(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    poll_id: &str,
+    poll: &mut Poll,
+    voter: &Addr,
+    proven_weight: Option<u64>,
+) -> Result<u64, ContractError> {
+    let previous = BALLOT.may_load(storage, ballot_key(storage, poll_id, poll, voter)?)?;
+    match (previous, proven_weight) {
+        (Some(previous), _) => Ok(previous.uncapped_weight()),
+        (None, Some(weight)) => Ok(weight),
+        // tracking representatives would reveal who voted
+        (None, None) if poll.pseudonymous => Ok(1),
+        (None, None) => claim_delegated_weight(storage, block, poll_id, poll, voter),
+    }
+}
 
-    #[test]
-    fn test_instantiate() {
-        let mut deps = mock_dependencies();
-        let env = mock_env();
-        let info = mock_info("addr1", &[]);
-        let msg = InstantiateMsg {
-            admin: Some("addr1".to_string()),
+fn execute_rank_votes(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    poll_id: String,
+    ranking: Vec<u8>,
+    memo: Option<String>,
+    proof: Option<MerkleProof>,
+) -> Result<Response, ContractError> {
+    ensure_not_banned(deps.as_ref(), &info.sender)?;
+    validate_memo(&memo)?;
+    let mut poll = POLL
+        .may_load(deps.storage, poll_id.clone())?
+        .ok_or(ContractError::PollNotFound {})?;
+    if !matches!(poll.mode, VoteMode::Ranked { .. }) {
+        return Err(ContractError::NotRanked {});
+    }
+    ensure_min_balance(deps.as_ref(), &poll, &info.sender)?;
+    for (i, id) in ranking.iter().enumerate() {
+        if ranking[..i].contains(id) {
+            return Err(ContractError::DuplicateOption {});
+        }
+        if !has_option(deps.storage, &poll_id, &poll, *id)? {
+            return Err(ContractError::OptionNotFound {});
+        }
+    }
+    let first = *ranking.first().ok_or(ContractError::RankingRequired {})?;
+    let proven_weight =
+        match WEIGHT_OVERRIDES.may_load(deps.storage, (poll_id.clone(), info.sender.clone()))? {
+            Some(weight) => Some(weight),
+            None => proven_weight(&poll, &info.sender, proof)?
+                .map(|amount| token_weight(&poll, amount))
+                .transpose()?,
         };
-        let resp = instantiate(deps.as_mut(), env, info, msg).unwrap();
-        assert_eq!(
-            resp.attributes,
-            vec![
-                attr("action", "instantiate"),
-                attr("addr1".to_string(), "addr1".to_string())
-            ]
-        );
+    let weight = standard_weight(
+        deps.storage,
+        &env.block,
+        &poll_id,
+        &mut poll,
+        &info.sender,
+        proven_weight,
+    )?;
+
+    let ballot = Ballot {
+        option: first,
+        weight,
+        deposit: Uint128::zero(),
+        memo,
+        allocations: vec![],
+        voted_at: env.block.time,
+        ranking,
+        raw_weight: None,
+    };
+    let response = save_ballot(
+        deps.storage,
+        &env.block,
+        poll_id,
+        &mut poll,
+        info.sender,
+        ballot,
+    )?;
+    Ok(response.add_attribute("action", "rank votes"))
+}
+
+fn execute_allocate_votes(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    poll_id: String,
+    allocations: Vec<(u8, Uint128)>,
+    memo: Option<String>,
+    proof: Option<MerkleProof>,
+) -> Result<Response, ContractError> {
+    ensure_not_banned(deps.as_ref(), &info.sender)?;
+    validate_memo(&memo)?;
+    let mut poll = POLL
+        .may_load(deps.storage, poll_id.clone())?
+        .ok_or(ContractError::PollNotFound {})?;
+    ensure_min_balance(deps.as_ref(), &poll, &info.sender)?;
+    let proven = proven_weight(&poll, &info.sender, proof)?;
+    let budget = match &poll.mode {
+        VoteMode::Cumulative {
+            budget: VoteBudget::Points(points),
+        } => *points,
+        VoteMode::Cumulative {
+            budget: VoteBudget::Snapshot {},
+        } => proven.ok_or(ContractError::MerkleProofRequired {})?,
+        VoteMode::Cumulative {
+            budget: VoteBudget::Stake { token },
+        } => staked_at(deps.storage, token, &info.sender, poll.snapshot_height)?,
+        _ => return Err(ContractError::NotCumulative {}),
+    };
+
+    let mut total = Uint128::zero();
+    let mut split: Vec<(u8, u64)> = vec![];
+    for (option, amount) in allocations {
+        if split.iter().any(|(listed, _)| *listed == option) {
+            return Err(ContractError::DuplicateOption {});
+        }
+        total = total
+            .checked_add(amount)
+            .map_err(|_| ContractError::AllocationMismatch { budget })?;
+        split.push((option, to_weight(amount)?));
+    }
+    if total != budget {
+        return Err(ContractError::AllocationMismatch { budget });
     }
 
-    #[test]
-    fn test_execute_create_poll() {
-        let mut deps = mock_dependencies();
-        let env = mock_env();
-        let info = mock_info("addr1", &[]);
-        let msg = InstantiateMsg {
-            admin: Some("addr1".to_string()),
-        };
-        let resp = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
-        assert_eq!(
-            resp.attributes,
-            vec![
-                attr("action", "instantiate"),
-                attr("addr1".to_string(), "addr1".to_string())
-            ]
-        );
-        let poll_id = "1".to_string();
-        let question = "Should We Have a Meeting Today".to_string();
-        let options = vec![String::from("Yes"), String::from("No")];
-        let resp =
-            execute_create_poll(deps.as_mut(), env, info, poll_id, question, options).unwrap();
-        assert_eq!(resp.attributes, vec![attr("action", "create poll")])
+    let ballot = Ballot {
+        option: 0,
+        weight: to_weight(budget)?,
+        deposit: Uint128::zero(),
+        memo,
+        allocations: split,
+        voted_at: env.block.time,
+        ranking: vec![],
+        raw_weight: None,
+    };
+    let response = save_ballot(
+        deps.storage,
+        &env.block,
+        poll_id,
+        &mut poll,
+        info.sender,
+        ballot,
+    )?;
+    Ok(response.add_attribute("action", "allocate votes"))
+}
+
+/// The voter's stake of `token` at `height`; voters without one cannot vote.
+fn staked_at(
+    storage: &dyn Storage,
+    token: &Addr,
+    voter: &Addr,
+    height: u64,
+) -> Result<Uint128, ContractError> {
+    let stake = STAKES
+        .may_load_at_height(storage, (token.clone(), voter.clone()), height)?
+        .unwrap_or_default();
+    if stake.is_zero() {
+        return Err(ContractError::NoVotingPower {});
     }
+    Ok(stake)
+}
 
-    #[test]
-    fn test_execute_vote() {
-        let mut deps = mock_dependencies();
-        let info = mock_info("addr1", &[]);
-        let env = mock_env();
-        let msg = InstantiateMsg {
-            admin: Some("addr1".to_string()),
-        };
-        let resp = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
-        assert_eq!(
-            resp.attributes,
-            vec![
-                attr("action", "instantiate"),
-                attr("addr1".to_string(), "addr1".to_string())
-            ]
-        );
+/// The voter's vested and unvested tokens in `contract`; voters without a
+/// grant cannot vote.
+fn vesting_grant(deps: Deps, contract: &Addr, voter: &Addr) -> Result<Uint128, ContractError> {
+    let allocation: AllocationResponse = deps.querier.query_wasm_smart(
+        contract,
+        &VestingQueryMsg::Allocation {
+            address: voter.to_string(),
+        },
+    )?;
+    let grant = allocation
+        .vested
+        .checked_add(allocation.unvested)
+        .map_err(|_| ContractError::WeightOverflow {})?;
+    if grant.is_zero() {
+        return Err(ContractError::NoVotingPower {});
+    }
+    Ok(grant)
+}
 
-        let poll_id = "1".to_string();
-        let question = "Should We Have a Meeting Today".to_string();
-        let options = vec![String::from("Yes"), String::from("No")];
-        let resp = execute_create_poll(
-            deps.as_mut(),
+/// The voter's stake in a cw20-stake contract at `height`; voters without
+/// one cannot vote.
+fn staked_balance(
+    deps: Deps,
+    contract: &Addr,
+    voter: &Addr,
+    height: u64,
+) -> Result<Uint128, ContractError> {
+    let staked: StakedBalanceAtHeightResponse = deps.querier.query_wasm_smart(
+        contract,
+        &StakingQueryMsg::StakedBalanceAtHeight {
+            address: voter.to_string(),
+            height: Some(height),
+        },
+    )?;
+    if staked.balance.is_zero() {
+        return Err(ContractError::NoVotingPower {});
+    }
+    Ok(staked.balance)
+}
+
+fn total_staked(deps: Deps, contract: &Addr, height: u64) -> Result<Uint128, ContractError> {
+    let staked: TotalStakedAtHeightResponse = deps.querier.query_wasm_smart(
+        contract,
+        &StakingQueryMsg::TotalStakedAtHeight {
+            height: Some(height),
+        },
+    )?;
+    Ok(staked.total)
+}
+
+fn to_weight(amount: Uint128) -> Result<u64, ContractError> {
+    u64::try_from(amount.u128()).map_err(|_| ContractError::WeightOverflow {})
+}
+
+/// Ballot weight for a token amount under the poll's weight formula.
+fn token_weight(poll: &Poll, amount: Uint128) -> Result<u64, ContractError> {
+    to_weight(poll.weight_fn.apply(amount))
+}
+
+fn receive_vote(
+    deps: DepsMut,
+    env: Env,
+    token: Addr,
+    voter: Addr,
+    poll_id: String,
+    mut ballot: Ballot,
+) -> Result<Response, ContractError> {
+    ensure_not_banned(deps.as_ref(), &voter)?;
+    let mut poll = POLL
+        .may_load(deps.storage, poll_id.clone())?
+        .ok_or(ContractError::PollNotFound {})?;
+    if poll.mode != (VoteMode::Cw20Deposit { token }) {
+        return Err(ContractError::InvalidDepositToken {});
+    }
+    if poll.weight_fn != WeightFormula::Linear {
+        // the formula applies to the whole deposit, not to each top-up
+        ballot.weight = token_weight(&poll, ballot.deposit)?;
+    }
+    if poll.merkle_root.is_some() {
+        return Err(ContractError::MerkleProofRequired {});
+    }
+    ensure_min_balance(deps.as_ref(), &poll, &voter)?;
+
+    let deposit = ballot.deposit;
+    let response = save_ballot(deps.storage, &env.block, poll_id, &mut poll, voter, ballot)?;
+    Ok(response
+        .add_attribute("action", "vote in poll")
+        .add_attribute("deposit", deposit))
+}
+
+fn lock_conviction(
+    deps: DepsMut,
+    env: Env,
+    token: Addr,
+    voter: Addr,
+    poll_id: String,
+    option: u8,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    ensure_not_banned(deps.as_ref(), &voter)?;
+    let mut poll = POLL
+        .may_load(deps.storage, poll_id.clone())?
+        .ok_or(ContractError::PollNotFound {})?;
+    match &poll.mode {
+        VoteMode::Conviction {
+            token: expected, ..
+        } if *expected == token => {}
+        VoteMode::Conviction { .. } => return Err(ContractError::InvalidDepositToken {}),
+        _ => return Err(ContractError::NotConviction {}),
+    }
+    if poll.merkle_root.is_some() {
+        return Err(ContractError::MerkleProofRequired {});
+    }
+    ensure_min_balance(deps.as_ref(), &poll, &voter)?;
+
+    let mut ballot = top_up_ballot(deps.storage, &voter, &poll_id, option, amount, true)?;
+    ballot.voted_at = env.block.time;
+    let key = (poll_id.clone(), voter.clone());
+    let since = match LOCKED_SINCE.may_load(deps.storage, key.clone())? {
+        Some(since) => {
+            let waited = env.block.time.seconds() - since.seconds();
+            let shift = Uint128::from(waited).multiply_ratio(amount, ballot.deposit);
+            since.plus_seconds(shift.u128() as u64)
+        }
+        None => env.block.time,
+    };
+    LOCKED_SINCE.save(deps.storage, key, &since)?;
+    let locked = ballot.deposit;
+    let response = save_ballot(
+        deps.storage,
+        &env.block,
+        poll_id.clone(),
+        &mut poll,
+        voter,
+        ballot,
+    )?;
+    Ok(response
+        .add_attribute("action", "lock")
+        .add_attribute("poll_id", poll_id)
+        .add_attribute("locked", locked))
+}
+
+fn execute_unlock(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    poll_id: String,
+) -> Result<Response, ContractError> {
+    let mut poll = POLL
+        .may_load(deps.storage, poll_id.clone())?
+        .ok_or(ContractError::PollNotFound {})?;
+    let token = match &poll.mode {
+        VoteMode::Conviction { token, .. } => token.clone(),
+        _ => return Err(ContractError::NotConviction {}),
+    };
+    // Locks in a closed poll have already been returned.
+    if poll.status != PollStatus::Open {
+        return Err(ContractError::PollNotOpen {});
+    }
+    let key = (info.sender.clone(), poll_id.clone());
+    let ballot = BALLOT
+        .may_load(deps.storage, key.clone())?
+        .ok_or(ContractError::NothingLocked {})?;
+
+    for (id, weight) in ballot.shares() {
+        update_option(
+            deps.storage,
+            &env.block,
+            &poll_id,
+            &mut poll,
+            id,
+            |option| option.votes -= weight,
+        )?;
+    }
+    poll.voter_count -= 1;
+    poll.ballot_weight = poll.ballot_weight.saturating_sub(ballot.weight);
+    index_shares(deps.storage, &poll_id, &info.sender, &ballot.shares(), &[])?;
+    enter_raffle(
+        deps.storage,
+        &poll_id,
+        &poll,
+        &info.sender,
+        &ballot.raffle_options(),
+        &[],
+    )?;
+    POLL_VOTERS.remove(deps.storage, (poll_id.clone(), info.sender.clone()));
+    BALLOT.remove(deps.storage, key);
+    LOCKED_SINCE.remove(deps.storage, (poll_id.clone(), info.sender.clone()));
+    POLL.save(deps.storage, poll_id.clone(), &poll)?;
+    Ok(Response::new()
+        .add_message(transfer_msg(
+            &RewardAsset::Cw20(token),
+            &info.sender,
+            ballot.deposit,
+        )?)
+        .add_attribute("action", "unlock")
+        .add_attribute("poll_id", poll_id)
+        .add_attribute("amount", ballot.deposit))
+}
+
+/// Returns the sender's deposit from a poll no longer taking votes. The
+/// ballot stays, with nothing left in escrow.
+fn execute_claim_deposit(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    poll_id: String,
+) -> Result<Response, ContractError> {
+    let poll = POLL
+        .may_load(deps.storage, poll_id.clone())?
+        .ok_or(ContractError::PollNotFound {})?;
+    let token = match &poll.mode {
+        VoteMode::Cw20Deposit { token } | VoteMode::Conviction { token, .. } => token.clone(),
+        _ => return Err(ContractError::NoDepositToClaim {}),
+    };
+    if matches!(poll.status, PollStatus::Open | PollStatus::Frozen) {
+        return Err(ContractError::DepositLocked {});
+    }
+    let key = (info.sender.clone(), poll_id.clone());
+    let mut ballot = BALLOT
+        .may_load(deps.storage, key.clone())?
+        .filter(|ballot| !ballot.deposit.is_zero())
+        .ok_or(ContractError::NoDepositToClaim {})?;
+    let amount = ballot.deposit;
+    ballot.deposit = Uint128::zero();
+    BALLOT.save(deps.storage, key, &ballot)?;
+
+    Ok(Response::new()
+        .add_message(transfer_msg(
+            &RewardAsset::Cw20(token),
+            &info.sender,
+            amount,
+        )?)
+        .add_attribute("action", "claim deposit")
+        .add_attribute("poll_id", poll_id)
+        .add_attribute("amount", amount))
+}
+
+/// Recomputes the tallies at `at` for polls whose ballot weight changes over
+/// time: conviction locks grow and decaying ballots shrink. Without `decayed`
+/// ballots count at full weight again, as while the poll is open. Such polls
+/// always keep their options inline.
+fn settle_tallies(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    poll_id: &str,
+    poll: &mut Poll,
+    decayed: bool,
+) -> Result<(), ContractError> {
+    let conviction = match poll.mode {
+        VoteMode::Conviction {
+            max_multiplier,
+            ramp_seconds,
+            ..
+        } => Some((max_multiplier, ramp_seconds)),
+        _ => None,
+    };
+    if conviction.is_none() && poll.decay.is_none() {
+        return Ok(());
+    }
+    let at = poll.end_time.map_or(block.time, |end| end.min(block.time));
+    for option in poll.options.iter_mut() {
+        option.votes = 0;
+    }
+    for (voter, ballot) in poll_ballots(storage, poll_id)? {
+        let mut shares = ballot.shares();
+        if let Some((max_multiplier, ramp_seconds)) = conviction {
+            let since = LOCKED_SINCE.load(storage, (poll_id.to_string(), voter))?;
+            let elapsed = at
+                .seconds()
+                .saturating_sub(since.seconds())
+                .min(ramp_seconds);
+            let bonus = ballot
+                .deposit
+                .multiply_ratio(elapsed as u128 * (max_multiplier as u128 - 1), ramp_seconds);
+            shares = vec![(
+                ballot.option,
+                to_weight(ballot.deposit.saturating_add(bonus))?,
+            )];
+        }
+        if let Some(decay) = poll.decay.filter(|_| decayed) {
+            let elapsed = at.seconds().saturating_sub(ballot.voted_at.seconds());
+            for (_, weight) in shares.iter_mut() {
+                *weight = decay.apply(*weight, elapsed);
+            }
+        }
+        for (id, weight) in shares {
+            if let Some(option) = poll.option_mut(id) {
+                option.votes = option
+                    .votes
+                    .checked_add(weight)
+                    .ok_or(ContractError::WeightOverflow {})?;
+            }
+        }
+    }
+    // votes pushed from satellite chains have no ballots here
+    let satellite_tallies = SATELLITE_TALLIES
+        .prefix(poll_id.to_string())
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (_, tally) in satellite_tallies {
+        for (id, votes) in tally.tallies {
+            if let Some(option) = poll.option_mut(id) {
+                option.votes = option
+                    .votes
+                    .checked_add(votes)
+                    .ok_or(ContractError::WeightOverflow {})?;
+            }
+        }
+    }
+    for option in &poll.options {
+        TALLIES.save(
+            storage,
+            (poll_id.to_string(), option.id),
+            &option.votes,
+            block.height,
+        )?;
+    }
+    Ok(())
+}
+
+fn execute_fund_rewards(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    poll_id: String,
+) -> Result<Response, ContractError> {
+    let coin = one_coin(&info).map_err(|_| ContractError::InvalidFunds {})?;
+    fund_rewards(
+        deps,
+        info.sender,
+        poll_id,
+        RewardAsset::Native(coin.denom),
+        coin.amount,
+    )
+}
+
+fn fund_rewards(
+    deps: DepsMut,
+    sender: Addr,
+    poll_id: String,
+    asset: RewardAsset,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    let poll = POLL
+        .may_load(deps.storage, poll_id.clone())?
+        .ok_or(ContractError::PollNotFound {})?;
+    if poll.admin != sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    if poll.status != PollStatus::Open {
+        return Err(ContractError::PollNotOpen {});
+    }
+    if poll.pseudonymous {
+        return Err(ContractError::PseudonymousPayout {});
+    }
+
+    let pool = match REWARD_POOLS.may_load(deps.storage, poll_id.clone())? {
+        Some(pool) if pool.asset != asset => return Err(ContractError::RewardAssetMismatch {}),
+        Some(pool) => RewardPool {
+            amount: pool.amount + amount,
+            ..pool
+        },
+        None => RewardPool {
+            asset,
+            amount,
+            distributed: false,
+            shares: 0,
+            claimed_shares: 0,
+            claimed: Uint128::zero(),
+        },
+    };
+    REWARD_POOLS.save(deps.storage, poll_id.clone(), &pool)?;
+    Ok(Response::new()
+        .add_attribute("action", "fund rewards")
+        .add_attribute("poll_id", poll_id)
+        .add_attribute("amount", amount)
+        .add_attribute("pool", pool.amount))
+}
+
+fn execute_claim_reward(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    poll_id: String,
+) -> Result<Response, ContractError> {
+    let mut pool = REWARD_POOLS
+        .may_load(deps.storage, poll_id.clone())?
+        .ok_or(ContractError::NoRewardToClaim {})?;
+    let amount = claim_share(deps.storage, &poll_id, &info.sender, &mut pool)?;
+    if amount.is_zero() {
+        return Err(ContractError::NoRewardToClaim {});
+    }
+    REWARD_CLAIMS.save(
+        deps.storage,
+        (poll_id.clone(), info.sender.clone()),
+        &Uint128::zero(),
+    )?;
+    REWARD_POOLS.save(deps.storage, poll_id.clone(), &pool)?;
+
+    let transfer = transfer_msg(&pool.asset, &info.sender, amount)?;
+    Ok(Response::new()
+        .add_message(transfer)
+        .add_attribute("action", "claim reward")
+        .add_attribute("poll_id", poll_id)
+        .add_attribute("amount", amount))
+}
+
+/// What `voter` can still claim from `pool`: an amount kept in
+/// `REWARD_CLAIMS`, or else their ballot's share, which is counted as claimed.
+fn claim_share(
+    storage: &dyn Storage,
+    poll_id: &str,
+    voter: &Addr,
+    pool: &mut RewardPool,
+) -> StdResult<Uint128> {
+    if let Some(amount) = REWARD_CLAIMS.may_load(storage, (poll_id.to_string(), voter.clone()))? {
+        return Ok(amount);
+    }
+    match BALLOT.may_load(storage, (voter.clone(), poll_id.to_string()))? {
+        Some(ballot) if pool.distributed => {
+            let poll = POLL.load(storage, poll_id.to_string())?;
+            Ok(take_reward_share(pool, poll.reward_distribution, &ballot))
+        }
+        _ => Ok(Uint128::zero()),
+    }
+}
+
+/// Counts the ballot's share of a distributed pool as claimed and returns it.
+/// The last share claimed also takes the rounding dust.
+fn take_reward_share(
+    pool: &mut RewardPool,
+    distribution: RewardDistribution,
+    ballot: &Ballot,
+) -> Uint128 {
+    let units = match distribution {
+        RewardDistribution::ProRata => ballot.weight,
+        RewardDistribution::Equal => 1,
+    };
+    if pool.shares == 0 || units == 0 {
+        return Uint128::zero();
+    }
+    pool.claimed_shares += units;
+    let share = if pool.claimed_shares >= pool.shares {
+        pool.amount - pool.claimed
+    } else {
+        pool.amount.multiply_ratio(units, pool.shares)
+    };
+    pool.claimed += share;
+    share
+}
+
+fn transfer_msg(asset: &RewardAsset, recipient: &Addr, amount: Uint128) -> StdResult<CosmosMsg> {
+    Ok(match asset {
+        RewardAsset::Native(denom) => BankMsg::Send {
+            to_address: recipient.to_string(),
+            amount: coins(amount.u128(), denom),
+        }
+        .into(),
+        RewardAsset::Cw20(token) => WasmMsg::Execute {
+            contract_addr: token.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: recipient.to_string(),
+                amount,
+            })?,
+            funds: vec![],
+        }
+        .into(),
+    })
+}
+
+/// Builds a token-weighted ballot. Further tokens top up the voter's existing
+/// ballot rather than replacing it; `escrowed` tokens are tracked for refund.
+fn top_up_ballot(
+    storage: &dyn Storage,
+    voter: &Addr,
+    poll_id: &str,
+    option: u8,
+    amount: Uint128,
+    escrowed: bool,
+) -> Result<Ballot, ContractError> {
+    let previous = BALLOT.may_load(storage, (voter.clone(), poll_id.to_string()))?;
+    let (weight, deposit) = previous
+        .map(|ballot| (ballot.uncapped_weight(), ballot.deposit))
+        .unwrap_or_default();
+    Ok(Ballot {
+        option,
+        weight: weight
+            .checked_add(to_weight(amount)?)
+            .ok_or(ContractError::WeightOverflow {})?,
+        deposit: if escrowed { deposit + amount } else { deposit },
+        memo: None,
+        allocations: vec![],
+        voted_at: Timestamp::default(),
+        ranking: vec![],
+        raw_weight: None,
+    })
+}
+
+fn validate_memo(memo: &Option<String>) -> Result<(), ContractError> {
+    match memo {
+        Some(memo) if memo.chars().count() > MAX_MEMO_LENGTH => Err(ContractError::MemoTooLong {
+            max: MAX_MEMO_LENGTH,
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Records a voter's ballot, moving any previous ballot's weight to the new option.
+fn save_ballot(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    poll_id: String,
+    poll: &mut Poll,
+    voter: Addr,
+    mut ballot: Ballot,
+) -> Result<Response, ContractError> {
+    if poll.status == PollStatus::Frozen {
+        return Err(ContractError::PollFrozen {});
+    }
+    if poll.status != PollStatus::Open {
+        return Err(ContractError::PollNotOpen {});
+    }
+    if poll.is_expired(block) {
+        return Err(ContractError::PollExpired {});
+    }
+    if ballot.allocations.is_empty() {
+        ballot.set_weight(ballot.weight, poll.weight_cap());
+    }
+    let shares = ballot.shares();
+    for (id, _) in &shares {
+        if !has_option(storage, &poll_id, poll, *id)? {
+            return Err(ContractError::OptionNotFound {});
+        }
+    }
+
+    if poll.electorate.is_some() && !ALLOWED_VOTERS.has(storage, (poll_id.clone(), voter.clone())) {
+        return Err(ContractError::NotAllowedToVote {});
+    }
+
+    // per-voter records would name who voted in a pseudonymous poll
+    if !poll.pseudonymous && !SEEN_VOTERS.has(storage, voter.clone()) {
+        SEEN_VOTERS.save(storage, voter.clone(), &())?;
+        let mut stats = STATS.may_load(storage)?.unwrap_or_default();
+        stats.unique_voters += 1;
+        STATS.save(storage, &stats)?;
+    }
+
+    let key = ballot_key(storage, &poll_id, poll, &voter)?;
+    let previous = BALLOT.may_load(storage, key.clone())?;
+    let old_shares = previous.as_ref().map(Ballot::shares).unwrap_or_default();
+    let old_entries = previous
+        .as_ref()
+        .map(Ballot::raffle_options)
+        .unwrap_or_default();
+    let first_vote = previous.is_none();
+    let old_weight = previous.as_ref().map_or(0, |ballot| ballot.weight);
+    // (removed, added, new count) by option
+    let mut changes = BTreeMap::<u8, (u64, u64, u64)>::new();
+    match previous {
+        Some(old) => {
+            if let Some(window) = poll.revote_window {
+                let voted_for = |ballot: &Ballot| {
+                    ballot
+                        .shares()
+                        .into_iter()
+                        .filter(|(_, weight)| *weight > 0)
+                        .map(|(id, _)| id)
+                        .collect::<Vec<_>>()
+                };
+                if voted_for(&old) != voted_for(&ballot)
+                    && !window.is_open(old.voted_at, poll.end_time, block.time)
+                {
+                    return Err(ContractError::RevoteWindowClosed {});
+                }
+                // the window runs from the first vote, not the latest change
+                ballot.voted_at = old.voted_at;
+            }
+            for (id, weight) in old.shares() {
+                let votes = update_option(storage, block, &poll_id, poll, id, |option| {
+                    option.votes -= weight
+                })?;
+                if let Some(votes) = votes {
+                    let change = changes.entry(id).or_default();
+                    change.0 += weight;
+                    change.2 = votes;
+                }
+            }
+        }
+        None => {
+            if let Some(max_voters) = poll.max_voters {
+                if poll.voter_count >= u64::from(max_voters) {
+                    return Err(ContractError::PollFull { max_voters });
+                }
+            }
+            poll.voter_count += 1;
+            if !poll.pseudonymous {
+                voter_stats().update(storage, voter, |stats| -> StdResult<_> {
+                    let mut stats = stats.unwrap_or_default();
+                    stats.votes_cast += 1;
+                    stats.first_vote.get_or_insert(block.time);
+                    Ok(stats)
+                })?;
+            }
+        }
+    }
+    for (id, weight) in shares {
+        let votes = update_option(storage, block, &poll_id, poll, id, |option| {
+            option.votes += weight
+        })?;
+        if let Some(votes) = votes {
+            let change = changes.entry(id).or_default();
+            change.1 += weight;
+            change.2 = votes;
+        }
+    }
+
+    let mut event = Event::new("vote_tally").add_attribute("poll_id", &poll_id);
+    let mut delta = TallyDelta {
+        poll_id: poll_id.clone(),
+        decremented: vec![],
+        incremented: vec![],
+    };
+    // hidden tallies must not leak through events and data either, not
+    // even which options moved
+    let hidden = poll.hides_results();
+    for (option, (removed, added, votes)) in changes.into_iter().filter(|_| !hidden) {
+        let (kind, tallies) = match removed.cmp(&added) {
+            Ordering::Greater => ("decremented", &mut delta.decremented),
+            Ordering::Less => ("incremented", &mut delta.incremented),
+            Ordering::Equal => continue,
+        };
+        event = event
+            .add_attribute(kind, option.to_string())
+            .add_attribute(format!("{kind}_votes"), votes.to_string());
+        tallies.push(OptionTally { option, votes });
+    }
+
+    let mut msgs: Vec<SubMsg> = vote_notification(storage, &poll_id, &key.0, &ballot, hidden)?
+        .into_iter()
+        .collect();
+    if first_vote && !poll.pseudonymous {
+        msgs.extend(vote_receipt(storage, &poll_id, &key.0, &ballot, hidden)?);
+    }
+    index_shares(storage, &poll_id, &key.0, &old_shares, &ballot.shares())?;
+    enter_raffle(
+        storage,
+        &poll_id,
+        poll,
+        &key.0,
+        &old_entries,
+        &ballot.raffle_options(),
+    )?;
+    poll.ballot_weight = poll.ballot_weight.saturating_sub(old_weight) + ballot.weight;
+    POLL_VOTERS.save(storage, (poll_id.clone(), key.0.clone()), &())?;
+    BALLOT.save(storage, key, &ballot)?;
+    POLL.save(storage, poll_id, poll)?;
+    Ok(Response::new()
+        .add_submessages(msgs)
+        .add_event(event)
+        .set_data(to_binary(&delta)?))
+}
+
+/// Moves the options of a poll with more than `MAX_INLINE_OPTIONS` into
+/// `OPTIONS`.
+fn page_options(storage: &mut dyn Storage, poll_id: &str, poll: &mut Poll) -> StdResult<()> {
+    poll.option_count = 0;
+    if poll.options.len() > MAX_INLINE_OPTIONS {
+        poll.option_count = poll.options.len() as u32;
+        for option in std::mem::take(&mut poll.options) {
+            OPTIONS.save(storage, (poll_id.to_string(), option.id), &option)?;
+        }
+    }
+    Ok(())
+}
+
+/// All of the poll's options, wherever they are stored.
+pub(crate) fn poll_options(
+    storage: &dyn Storage,
+    poll_id: &str,
+    poll: &Poll,
+) -> StdResult<Vec<PollOption>> {
+    if poll.option_count == 0 {
+        return Ok(poll.options.clone());
+    }
+    OPTIONS
+        .prefix(poll_id.to_string())
+        .range(storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, option)| option))
+        .collect()
+}
+
+pub(crate) fn has_option(
+    storage: &dyn Storage,
+    poll_id: &str,
+    poll: &Poll,
+    id: u8,
+) -> StdResult<bool> {
+    Ok(match poll.option_count {
+        0 => poll.options.iter().any(|option| option.id == id),
+        _ => OPTIONS.has(storage, (poll_id.to_string(), id)),
+    })
+}
+
+/// Applies `update` to one option, touching only that option's key when the
+/// poll's options are stored separately.
+pub(crate) fn update_option(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    poll_id: &str,
+    poll: &mut Poll,
+    id: u8,
+    update: impl FnOnce(&mut PollOption),
+) -> StdResult<Option<u64>> {
+    let key = (poll_id.to_string(), id);
+    let votes = if poll.option_count == 0 {
+        let Some(option) = poll.option_mut(id) else {
+            return Ok(None);
+        };
+        update(option);
+        option.votes
+    } else {
+        let Some(mut option) = OPTIONS.may_load(storage, key.clone())? else {
+            return Ok(None);
+        };
+        update(&mut option);
+        OPTIONS.save(storage, key.clone(), &option)?;
+        option.votes
+    };
+    TALLIES.save(storage, key, &votes, block.height)?;
+    Ok(Some(votes))
+}
+
+/// Moves a ballot's `OPTION_VOTERS` entries from its `old` shares to its `new` ones.
+fn index_shares(
+    storage: &mut dyn Storage,
+    poll_id: &str,
+    voter: &Addr,
+    old: &[(u8, u64)],
+    new: &[(u8, u64)],
+) -> StdResult<()> {
+    for (id, _) in old {
+        OPTION_VOTERS.remove(storage, (poll_id.to_string(), *id, voter.clone()));
+    }
+    for (id, weight) in new.iter().filter(|(_, weight)| *weight > 0) {
+        OPTION_VOTERS.save(storage, (poll_id.to_string(), *id, voter.clone()), weight)?;
+    }
+    Ok(())
+}
+
+/// Moves a raffle poll voter's `RAFFLE_ENTRIES` from the `old` options to
+/// the `new` ones, filling a vacated number with the option's last entrant.
+fn enter_raffle(
+    storage: &mut dyn Storage,
+    poll_id: &str,
+    poll: &Poll,
+    voter: &Addr,
+    old: &[u8],
+    new: &[u8],
+) -> StdResult<()> {
+    if !poll.raffle {
+        return Ok(());
+    }
+    for id in old.iter().filter(|id| !new.contains(id)) {
+        let Some(slot) =
+            RAFFLE_SLOTS.may_load(storage, (poll_id.to_string(), *id, voter.clone()))?
+        else {
+            continue;
+        };
+        let count_key = (poll_id.to_string(), *id);
+        let last = RAFFLE_COUNTS.load(storage, count_key.clone())? - 1;
+        if slot != last {
+            let moved = RAFFLE_ENTRIES.load(storage, (poll_id.to_string(), *id, last))?;
+            RAFFLE_ENTRIES.save(storage, (poll_id.to_string(), *id, slot), &moved)?;
+            RAFFLE_SLOTS.save(storage, (poll_id.to_string(), *id, moved), &slot)?;
+        }
+        RAFFLE_ENTRIES.remove(storage, (poll_id.to_string(), *id, last));
+        RAFFLE_SLOTS.remove(storage, (poll_id.to_string(), *id, voter.clone()));
+        RAFFLE_COUNTS.save(storage, count_key, &last)?;
+    }
+    for id in new.iter().filter(|id| !old.contains(id)) {
+        let count_key = (poll_id.to_string(), *id);
+        let count = RAFFLE_COUNTS
+            .may_load(storage, count_key.clone())?
+            .unwrap_or_default();
+        RAFFLE_ENTRIES.save(storage, (poll_id.to_string(), *id, count), voter)?;
+        RAFFLE_SLOTS.save(storage, (poll_id.to_string(), *id, voter.clone()), &count)?;
+        RAFFLE_COUNTS.save(storage, count_key, &(count + 1))?;
+    }
+    Ok(())
+}
+
+/// Fire-and-forget `VoteCast` message for the configured notify contract,
+/// without the shares while the poll's results are `hidden`.
+fn vote_notification(
+    storage: &dyn Storage,
+    poll_id: &str,
+    voter: &Addr,
+    ballot: &Ballot,
+    hidden: bool,
+) -> StdResult<Option<SubMsg>> {
+    let Some(contract) = CONFIG.load(storage)?.notify_contract else {
+        return Ok(None);
+    };
+    let msg = WasmMsg::Execute {
+        contract_addr: contract.to_string(),
+        msg: to_binary(&NotifyMsg::VoteCast {
+            poll_id: poll_id.to_string(),
+            voter: voter.to_string(),
+            shares: (!hidden).then(|| ballot.shares()),
+            memo: ballot.memo.clone(),
+        })?,
+        funds: vec![],
+    };
+    Ok(Some(SubMsg::reply_on_error(msg, NOTIFY_REPLY_ID)))
+}
+
+/// Mints the configured receipt collection's badge for a voter's first
+/// ballot in a poll, naming its options unless results are `hidden`. A
+/// failed mint is ignored.
+fn vote_receipt(
+    storage: &dyn Storage,
+    poll_id: &str,
+    voter: &Addr,
+    ballot: &Ballot,
+    hidden: bool,
+) -> StdResult<Option<SubMsg>> {
+    let Some(collection) = CONFIG.load(storage)?.receipt_nft else {
+        return Ok(None);
+    };
+    let msg = WasmMsg::Execute {
+        contract_addr: collection.to_string(),
+        msg: to_binary(&ReceiptMintMsg::Mint {
+            token_id: format!("{}/{}", poll_id, voter),
+            owner: voter.to_string(),
+            token_uri: None,
+            extension: ReceiptMetadata {
+                poll_id: poll_id.to_string(),
+                options: match hidden {
+                    true => vec![],
+                    false => ballot.shares().into_iter().map(|(id, _)| id).collect(),
+                },
+            },
+        })?,
+        funds: vec![],
+    };
+    Ok(Some(SubMsg::reply_on_error(msg, RECEIPT_REPLY_ID)))
+}
+
+pub(crate) fn poll_ballots(storage: &dyn Storage, poll_id: &str) -> StdResult<Vec<(Addr, Ballot)>> {
+    ballots_page(storage, poll_id, None, usize::MAX)
+}
+
+/// Up to `limit` of the poll's ballots, by voter after `start_after`.
+fn ballots_page(
+    storage: &dyn Storage,
+    poll_id: &str,
+    start_after: Option<Addr>,
+    limit: usize,
+) -> StdResult<Vec<(Addr, Ballot)>> {
+    POLL_VOTERS
+        .prefix(poll_id.to_string())
+        .keys(
+            storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|voter| {
+            let voter = voter?;
+            let ballot = BALLOT.load(storage, (voter.clone(), poll_id.to_string()))?;
+            Ok((voter, ballot))
+        })
+        .collect()
+}
+
+/// Fixes the shares the poll's reward pool is split into; voters then claim
+/// theirs. The whole pool goes back to the poll admin if nobody voted.
+fn settle_rewards(
+    storage: &mut dyn Storage,
+    poll_id: &str,
+    poll: &Poll,
+) -> StdResult<Vec<CosmosMsg>> {
+    let mut pool = match REWARD_POOLS.may_load(storage, poll_id.to_string())? {
+        Some(pool) if !pool.distributed => pool,
+        _ => return Ok(vec![]),
+    };
+    pool.distributed = true;
+    pool.shares = match poll.reward_distribution {
+        RewardDistribution::ProRata => poll.ballot_weight,
+        RewardDistribution::Equal => poll.voter_count,
+    };
+    let mut msgs = vec![];
+    if pool.shares == 0 {
+        msgs.push(transfer_msg(&pool.asset, &poll.admin, pool.amount)?);
+        pool.claimed = pool.amount;
+    }
+    REWARD_POOLS.save(storage, poll_id.to_string(), &pool)?;
+    Ok(msgs)
+}
+
+/// Returns an undistributed reward pool to the poll admin.
+fn reclaim_rewards(
+    storage: &mut dyn Storage,
+    poll_id: &str,
+    poll: &Poll,
+) -> StdResult<Vec<CosmosMsg>> {
+    let mut pool = match REWARD_POOLS.may_load(storage, poll_id.to_string())? {
+        Some(pool) if !pool.distributed => pool,
+        _ => return Ok(vec![]),
+    };
+    pool.distributed = true;
+    REWARD_POOLS.save(storage, poll_id.to_string(), &pool)?;
+    Ok(vec![transfer_msg(&pool.asset, &poll.admin, pool.amount)?])
+}
+
+fn execute_comment(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    poll_id: String,
+    body: String,
+    reply_to: Option<u64>,
+) -> Result<Response, ContractError> {
+    ensure_not_banned(deps.as_ref(), &info.sender)?;
+    let poll = POLL
+        .may_load(deps.storage, poll_id.clone())?
+        .ok_or(ContractError::PollNotFound {})?;
+    if poll.status == PollStatus::Archived {
+        return Err(ContractError::PollArchived {});
+    }
+    let length = body.chars().count();
+    if length == 0 || length > MAX_COMMENT_LENGTH {
+        return Err(ContractError::InvalidCommentLength {
+            max: MAX_COMMENT_LENGTH,
+        });
+    }
+    if let Some(parent) = reply_to {
+        if !COMMENTS.has(deps.storage, (poll_id.clone(), parent)) {
+            return Err(ContractError::CommentNotFound {});
+        }
+    }
+
+    let last_id = COMMENTS
+        .prefix(poll_id.clone())
+        .keys(deps.storage, None, None, Order::Descending)
+        .next()
+        .transpose()?;
+    let id = last_id.map_or(1, |id| id + 1);
+    let comment = Comment {
+        id,
+        author: info.sender,
+        body,
+        reply_to,
+        created_at: env.block.time,
+    };
+    COMMENTS.save(deps.storage, (poll_id.clone(), id), &comment)?;
+    Ok(Response::new()
+        .add_attribute("action", "comment")
+        .add_attribute("poll_id", poll_id)
+        .add_attribute("comment_id", id.to_string()))
+}
+
+fn execute_suggest_option(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    poll_id: String,
+    option: String,
+) -> Result<Response, ContractError> {
+    ensure_not_banned(deps.as_ref(), &info.sender)?;
+    let mut poll = POLL
+        .may_load(deps.storage, poll_id.clone())?
+        .ok_or(ContractError::PollNotFound {})?;
+    if !poll.open_options {
+        return Err(ContractError::OptionsClosed {});
+    }
+    if !matches!(poll.status, PollStatus::Draft | PollStatus::Open) {
+        return Err(ContractError::PollNotOpen {});
+    }
+    let length = option.chars().count();
+    if length == 0 || length > MAX_SUGGESTION_LENGTH {
+        return Err(ContractError::InvalidSuggestionLength {
+            max: MAX_SUGGESTION_LENGTH,
+        });
+    }
+
+    let last_id = SUGGESTIONS
+        .prefix(poll_id.clone())
+        .keys(deps.storage, None, None, Order::Descending)
+        .next()
+        .transpose()?;
+    let id = last_id.map_or(1, |id| id + 1);
+    let suggestion = Suggestion {
+        id,
+        proposer: info.sender,
+        option,
+        status: SuggestionStatus::Pending,
+        created_at: env.block.time,
+    };
+    SUGGESTIONS.save(deps.storage, (poll_id.clone(), id), &suggestion)?;
+    poll.pending_suggestions += 1;
+    POLL.save(deps.storage, poll_id.clone(), &poll)?;
+    Ok(Response::new()
+        .add_attribute("action", "suggest option")
+        .add_attribute("poll_id", poll_id)
+        .add_attribute("suggestion_id", id.to_string()))
+}
+
+fn execute_review_suggestion(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    poll_id: String,
+    suggestion_id: u64,
+    approve: bool,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let mut poll = POLL
+        .may_load(deps.storage, poll_id.clone())?
+        .ok_or(ContractError::PollNotFound {})?;
+    if poll.admin != info.sender && !config.is_admin(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+    let key = (poll_id.clone(), suggestion_id);
+    let mut suggestion = match SUGGESTIONS.may_load(deps.storage, key.clone())? {
+        Some(suggestion) if suggestion.status == SuggestionStatus::Pending => suggestion,
+        _ => return Err(ContractError::SuggestionNotFound {}),
+    };
+
+    if approve {
+        if !matches!(poll.status, PollStatus::Draft | PollStatus::Open) {
+            return Err(ContractError::PollNotOpen {});
+        }
+        if poll.options.len() >= MAX_INLINE_OPTIONS {
+            return Err(ContractError::TooManyPollOptions {});
+        }
+        let option_id = poll
+            .options
+            .iter()
+            .map(|option| option.id + 1)
+            .max()
+            .unwrap_or(0);
+        poll.options.push(PollOption {
+            id: option_id,
+            label: suggestion.option.clone(),
+            votes: 0,
+            description: None,
+            image_uri: None,
+        });
+        suggestion.status = SuggestionStatus::Approved { option_id };
+        let change = PollChange::OptionAdded {
+            id: option_id,
+            label: suggestion.option.clone(),
+        };
+        record_change(deps.storage, &env.block, &poll_id, &info.sender, change)?;
+    } else {
+        suggestion.status = SuggestionStatus::Rejected;
+    }
+    poll.pending_suggestions = poll.pending_suggestions.saturating_sub(1);
+    POLL.save(deps.storage, poll_id.clone(), &poll)?;
+    SUGGESTIONS.save(deps.storage, key, &suggestion)?;
+    Ok(Response::new()
+        .add_attribute("action", "review suggestion")
+        .add_attribute("poll_id", poll_id)
+        .add_attribute("suggestion_id", suggestion_id.to_string())
+        .add_attribute("approved", approve.to_string()))
+}
+
+pub(crate) fn execute_close_poll(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    poll_id: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let mut poll = POLL
+        .may_load(deps.storage, poll_id.clone())?
+        .ok_or(ContractError::PollNotFound {})?;
+    ensure_can_moderate(deps.storage, &config, &poll, &info.sender)?;
+    if poll.status != PollStatus::Open {
+        return Err(ContractError::PollNotOpen {});
+    }
+    // Contract admins may override the minimum voting period.
+    let min_end = poll.start_time.plus_seconds(config.min_voting_period);
+    if env.block.time < min_end && !config.is_admin(&info.sender) {
+        return Err(ContractError::MinVotingPeriodNotElapsed {});
+    }
+
+    let refunds = close_poll(deps.storage, &env.block, &poll_id, &mut poll, &info.sender)?;
+    if poll.admin != info.sender {
+        log_admin_action(
+            deps.storage,
+            &env.block,
+            &info.sender,
+            "close poll",
+            &poll_id,
+        )?;
+    }
+    Ok(Response::new()
+        .add_messages(refunds)
+        .add_attribute("action", "close poll")
+        .add_attribute("poll_id", poll_id))
+}
+
+fn execute_freeze_poll(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    poll_id: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let mut poll = POLL
+        .may_load(deps.storage, poll_id.clone())?
+        .ok_or(ContractError::PollNotFound {})?;
+    ensure_can_moderate(deps.storage, &config, &poll, &info.sender)?;
+    if poll.status != PollStatus::Open {
+        return Err(ContractError::PollNotOpen {});
+    }
+    set_status(
+        deps.storage,
+        &env.block,
+        &poll_id,
+        &mut poll,
+        &info.sender,
+        PollStatus::Frozen,
+    )?;
+    POLL.save(deps.storage, poll_id.clone(), &poll)?;
+    log_admin_action(
+        deps.storage,
+        &env.block,
+        &info.sender,
+        "freeze poll",
+        &poll_id,
+    )?;
+    Ok(Response::new()
+        .add_attribute("action", "freeze poll")
+        .add_attribute("poll_id", poll_id))
+}
+
+/// Resumes voting. A poll whose end time passed while frozen reads as closed.
+fn execute_unfreeze_poll(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    poll_id: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let mut poll = POLL
+        .may_load(deps.storage, poll_id.clone())?
+        .ok_or(ContractError::PollNotFound {})?;
+    ensure_can_moderate(deps.storage, &config, &poll, &info.sender)?;
+    if poll.status != PollStatus::Frozen {
+        return Err(ContractError::PollNotFrozen {});
+    }
+    set_status(
+        deps.storage,
+        &env.block,
+        &poll_id,
+        &mut poll,
+        &info.sender,
+        PollStatus::Open,
+    )?;
+    POLL.save(deps.storage, poll_id.clone(), &poll)?;
+    // `FinalizeExpired` may have dropped it while frozen
+    index_end(deps.storage, &poll_id, &poll)?;
+    log_admin_action(
+        deps.storage,
+        &env.block,
+        &info.sender,
+        "unfreeze poll",
+        &poll_id,
+    )?;
+    Ok(Response::new()
+        .add_attribute("action", "unfreeze poll")
+        .add_attribute("poll_id", poll_id))
+}
+
+fn execute_reopen_poll(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    poll_id: String,
+    new_end: Timestamp,
+    reason: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if !config.is_admin(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+    let mut poll = POLL
+        .may_load(deps.storage, poll_id.clone())?
+        .ok_or(ContractError::PollNotFound {})?;
+    if poll.status != PollStatus::Closed {
+        return Err(ContractError::PollNotClosed {});
+    }
+    if matches!(
+        poll.mode,
+        VoteMode::Cw20Deposit { .. } | VoteMode::Conviction { .. }
+    ) {
+        return Err(ContractError::ReopenUnsupported {});
+    }
+    if new_end <= env.block.time {
+        return Err(ContractError::ReopenEndInPast {});
+    }
+    // the same bounds as creating or extending the poll
+    if new_end < env.block.time.plus_seconds(config.min_voting_period) {
+        return Err(ContractError::VotingPeriodTooShort {
+            min: config.min_voting_period,
+        });
+    }
+    if let Some(max) = config.max_voting_period {
+        if new_end > poll.start_time.plus_seconds(max) {
+            return Err(ContractError::VotingPeriodTooLong { max });
+        }
+    }
+    if reason.chars().count() > MAX_REASON_LENGTH {
+        return Err(ContractError::ReasonTooLong {
+            max: MAX_REASON_LENGTH,
+        });
+    }
+
+    // Closing settled decayed weights; votes keep moving at full weight
+    // until the next close.
+    settle_tallies(deps.storage, &env.block, &poll_id, &mut poll, false)?;
+    poll.end_time = Some(new_end);
+    poll.closed_at = None;
+    set_status(
+        deps.storage,
+        &env.block,
+        &poll_id,
+        &mut poll,
+        &info.sender,
+        PollStatus::Open,
+    )?;
+    POLL.save(deps.storage, poll_id.clone(), &poll)?;
+    index_end(deps.storage, &poll_id, &poll)?;
+    log_admin_action(
+        deps.storage,
+        &env.block,
+        &info.sender,
+        "reopen poll",
+        &poll_id,
+    )?;
+    Ok(Response::new()
+        .add_attribute("action", "reopen poll")
+        .add_attribute("poll_id", poll_id)
+        .add_attribute("new_end", new_end.to_string())
+        .add_attribute("reason", reason))
+}
+
+fn execute_cancel_poll(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    poll_id: String,
+    reason: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let mut poll = POLL
+        .may_load(deps.storage, poll_id.clone())?
+        .ok_or(ContractError::PollNotFound {})?;
+    ensure_can_moderate(deps.storage, &config, &poll, &info.sender)?;
+    if reason.chars().count() > MAX_REASON_LENGTH {
+        return Err(ContractError::ReasonTooLong {
+            max: MAX_REASON_LENGTH,
+        });
+    }
+
+    if !matches!(
+        poll.status,
+        PollStatus::Open | PollStatus::Frozen | PollStatus::Closed
+    ) {
+        return Err(ContractError::CannotCancel {});
+    }
+    let mut refunds = reclaim_rewards(deps.storage, &poll_id, &poll)?;
+    refunds.extend(return_creation_deposit(&mut poll)?);
+
+    set_status(
+        deps.storage,
+        &env.block,
+        &poll_id,
+        &mut poll,
+        &info.sender,
+        PollStatus::Cancelled,
+    )?;
+    poll.cancel_reason = Some(reason.clone());
+    POLL.save(deps.storage, poll_id.clone(), &poll)?;
+    if poll.admin != info.sender {
+        log_admin_action(
+            deps.storage,
+            &env.block,
+            &info.sender,
+            "cancel poll",
+            &poll_id,
+        )?;
+    }
+    Ok(Response::new()
+        .add_messages(refunds)
+        .add_attribute("action", "cancel poll")
+        .add_attribute("poll_id", poll_id)
+        .add_attribute("reason", reason))
+}
+
+fn execute_sponsor_poll(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    poll_id: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if !config.sponsors.contains(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+    let mut poll = POLL
+        .may_load(deps.storage, poll_id.clone())?
+        .ok_or(ContractError::PollNotFound {})?;
+    if poll.status != PollStatus::Draft {
+        return Err(ContractError::PollNotDraft {});
+    }
+    let key = (poll_id.clone(), info.sender.clone());
+    if SPONSORSHIPS.has(deps.storage, key.clone()) {
+        return Err(ContractError::AlreadySponsored {});
+    }
+    SPONSORSHIPS.save(deps.storage, key, &())?;
+    let sponsorships = SPONSORSHIPS
+        .prefix(poll_id.clone())
+        .keys(deps.storage, None, None, Order::Ascending)
+        .count() as u64;
+
+    let mut response = Response::new()
+        .add_attribute("action", "sponsor poll")
+        .add_attribute("poll_id", poll_id.clone())
+        .add_attribute("sponsorships", sponsorships.to_string());
+    if sponsorships >= config.required_sponsors {
+        set_status(
+            deps.storage,
+            &env.block,
+            &poll_id,
+            &mut poll,
+            &info.sender,
+            PollStatus::Open,
+        )?;
+        // a poll left too little time while a draft runs for as long as it
+        // was created to, counted from now
+        let min_end = env.block.time.plus_seconds(config.min_voting_period);
+        if let Some(old_end) = poll.end_time.filter(|end| *end < min_end) {
+            let duration = old_end.nanos().saturating_sub(poll.start_time.nanos());
+            let new_end = env.block.time.plus_nanos(duration).max(min_end);
+            poll.end_time = Some(new_end);
+            let change = PollChange::DeadlineExtended { old_end, new_end };
+            record_change(deps.storage, &env.block, &poll_id, &info.sender, change)?;
+            response = response.add_attribute("end_time", new_end.to_string());
+        }
+        poll.start_time = env.block.time;
+        POLL.save(deps.storage, poll_id.clone(), &poll)?;
+        // `FinalizeExpired` may have dropped it while a draft
+        index_end(deps.storage, &poll_id, &poll)?;
+        response = response.add_attribute("status", "open");
+    }
+    Ok(response)
+}
+
+fn execute_flag_poll(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    poll_id: String,
+) -> Result<Response, ContractError> {
+    ensure_not_banned(deps.as_ref(), &info.sender)?;
+    let config = CONFIG.load(deps.storage)?;
+    let mut poll = POLL
+        .may_load(deps.storage, poll_id.clone())?
+        .ok_or(ContractError::PollNotFound {})?;
+    if poll.status != PollStatus::Open {
+        return Err(ContractError::PollNotOpen {});
+    }
+    let key = (poll_id.clone(), info.sender.clone());
+    if FLAGS.has(deps.storage, key.clone()) {
+        return Err(ContractError::AlreadyFlagged {});
+    }
+    FLAGS.save(deps.storage, key, &())?;
+    poll.flags += 1;
+
+    let confirmed = config.is_admin(&info.sender) || config.is_moderator(&info.sender);
+    let enough = config.flag_threshold > 0 && poll.flags >= config.flag_threshold;
+    if !confirmed && !enough {
+        POLL.save(deps.storage, poll_id.clone(), &poll)?;
+        return Ok(Response::new()
+            .add_attribute("action", "flag poll")
+            .add_attribute("poll_id", poll_id)
+            .add_attribute("flags", poll.flags.to_string()));
+    }
+
+    let mut msgs = reclaim_rewards(deps.storage, &poll_id, &poll)?;
+    if let Some(deposit) = poll.creation_deposit.take() {
+        msgs.push(match &config.treasury {
+            Some(treasury) => BankMsg::Send {
+                to_address: treasury.to_string(),
+                amount: vec![deposit],
+            }
+            .into(),
+            None => BankMsg::Burn {
+                amount: vec![deposit],
+            }
+            .into(),
+        });
+    }
+    if let Some(deposit) = poll.cw20_creation_deposit.take() {
+        let asset = RewardAsset::Cw20(deposit.address.clone());
+        msgs.push(match &config.treasury {
+            Some(treasury) => transfer_msg(&asset, treasury, deposit.amount)?,
+            None => WasmMsg::Execute {
+                contract_addr: deposit.address.to_string(),
+                msg: to_binary(&Cw20ExecuteMsg::Burn {
+                    amount: deposit.amount,
+                })?,
+                funds: vec![],
+            }
+            .into(),
+        });
+    }
+    set_status(
+        deps.storage,
+        &env.block,
+        &poll_id,
+        &mut poll,
+        &info.sender,
+        PollStatus::Cancelled,
+    )?;
+    poll.cancel_reason = Some("flagged as spam".to_string());
+    POLL.save(deps.storage, poll_id.clone(), &poll)?;
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_attribute("action", "cancel spam poll")
+        .add_attribute("poll_id", poll_id)
+        .add_attribute("flags", poll.flags.to_string()))
+}
+
+fn execute_veto(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    poll_id: String,
+    reason: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    ensure_admin(&config, &info.sender)?;
+    if reason.chars().count() > MAX_REASON_LENGTH {
+        return Err(ContractError::ReasonTooLong {
+            max: MAX_REASON_LENGTH,
+        });
+    }
+    let mut poll = POLL
+        .may_load(deps.storage, poll_id.clone())?
+        .ok_or(ContractError::PollNotFound {})?;
+    // An expired poll may be vetoed before anyone closes it.
+    let closed_at = match (poll.status, poll.closed_at, poll.end_time) {
+        (PollStatus::Closed, Some(closed_at), _) => closed_at,
+        (PollStatus::Open, _, Some(end)) if poll.is_expired(&env.block) => end,
+        _ => return Err(ContractError::PollNotClosed {}),
+    };
+    if env.block.time >= closed_at.plus_seconds(config.veto_period) {
+        return Err(ContractError::VetoPeriodEnded {});
+    }
+    let mut refunds = reclaim_rewards(deps.storage, &poll_id, &poll)?;
+    refunds.extend(return_creation_deposit(&mut poll)?);
+
+    set_status(
+        deps.storage,
+        &env.block,
+        &poll_id,
+        &mut poll,
+        &info.sender,
+        PollStatus::Vetoed,
+    )?;
+    poll.cancel_reason = Some(reason.clone());
+    POLL.save(deps.storage, poll_id.clone(), &poll)?;
+    log_admin_action(deps.storage, &env.block, &info.sender, "veto", &poll_id)?;
+    Ok(Response::new()
+        .add_messages(refunds)
+        .add_attribute("action", "veto poll")
+        .add_attribute("poll_id", poll_id)
+        .add_attribute("reason", reason))
+}
+
+fn execute_extend_deadline(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    poll_id: String,
+    new_end: Timestamp,
+) -> Result<Response, ContractError> {
+    let mut poll = POLL
+        .may_load(deps.storage, poll_id.clone())?
+        .ok_or(ContractError::PollNotFound {})?;
+    if poll.admin != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    if poll.status != PollStatus::Open {
+        return Err(ContractError::PollNotOpen {});
+    }
+    if poll.is_expired(&env.block) {
+        return Err(ContractError::PollExpired {});
+    }
+    // A poll without a deadline cannot be given one, as that would shorten it.
+    let old_end = match poll.end_time {
+        Some(old_end) if new_end > old_end => old_end,
+        _ => return Err(ContractError::DeadlineNotExtended {}),
+    };
+    if let Some(max) = CONFIG.load(deps.storage)?.max_voting_period {
+        if new_end > poll.start_time.plus_seconds(max) {
+            return Err(ContractError::VotingPeriodTooLong { max });
+        }
+    }
+
+    poll.end_time = Some(new_end);
+    POLL.save(deps.storage, poll_id.clone(), &poll)?;
+    index_end(deps.storage, &poll_id, &poll)?;
+    let change = PollChange::DeadlineExtended { old_end, new_end };
+    record_change(deps.storage, &env.block, &poll_id, &info.sender, change)?;
+    Ok(Response::new()
+        .add_attribute("action", "extend deadline")
+        .add_attribute("poll_id", poll_id)
+        .add_attribute("old_end", old_end.to_string())
+        .add_attribute("new_end", new_end.to_string()))
+}
+
+fn execute_set_display_order(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    poll_id: String,
+    order: Vec<u8>,
+    shuffle: bool,
+) -> Result<Response, ContractError> {
+    let mut poll = POLL
+        .may_load(deps.storage, poll_id.clone())?
+        .ok_or(ContractError::PollNotFound {})?;
+    if poll.admin != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    // paged options are listed by id
+    if poll.option_count > 0 {
+        return Err(ContractError::PagedOptionsUnsupported {
+            max: MAX_INLINE_OPTIONS,
+        });
+    }
+    for (i, id) in order.iter().enumerate() {
+        if order[..i].contains(id) {
+            return Err(ContractError::DuplicateOption {});
+        }
+        if !poll.options.iter().any(|option| option.id == *id) {
+            return Err(ContractError::OptionNotFound {});
+        }
+    }
+    poll.display_order = order;
+    poll.shuffle_options = shuffle;
+    POLL.save(deps.storage, poll_id.clone(), &poll)?;
+    Ok(Response::new()
+        .add_attribute("action", "set display order")
+        .add_attribute("poll_id", poll_id)
+        .add_attribute("shuffle", shuffle.to_string()))
+}
+
+/// Puts the poll's inline options in display order, shuffling them with a
+/// seed that changes every block if the poll asks for it.
+fn arrange_options(poll: &mut Poll, poll_id: &str, block: &BlockInfo) {
+    if poll.shuffle_options {
+        let seed: [u8; 32] = Sha256::digest(format!("{}/{}", poll_id, block.height)).into();
+        for i in (1..poll.options.len()).rev() {
+            let j = seed[i % seed.len()] as usize % (i + 1);
+            poll.options.swap(i, j);
+        }
+    } else if !poll.display_order.is_empty() {
+        let order = &poll.display_order;
+        poll.options.sort_by_key(|option| {
+            order
+                .iter()
+                .position(|id| *id == option.id)
+                .unwrap_or(order.len())
+        });
+    }
+}
+
+/// Appends an entry to the poll's audit trail.
+fn record_change(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    poll_id: &str,
+    actor: &Addr,
+    change: PollChange,
+) -> StdResult<()> {
+    let last_id = POLL_HISTORY
+        .prefix(poll_id.to_string())
+        .keys(storage, None, None, Order::Descending)
+        .next()
+        .transpose()?;
+    let id = last_id.map_or(1, |id| id + 1);
+    let record = ChangeRecord {
+        id,
+        actor: actor.clone(),
+        height: block.height,
+        time: block.time,
+        change,
+    };
+    POLL_HISTORY.save(storage, (poll_id.to_string(), id), &record)
+}
+
+fn log_admin_action(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    actor: &Addr,
+    action: &str,
+    target: impl Into<String>,
+) -> StdResult<()> {
+    let last_id = ADMIN_LOG
+        .keys(storage, None, None, Order::Descending)
+        .next()
+        .transpose()?;
+    let id = last_id.map_or(1, |id| id + 1);
+    let entry = AdminAction {
+        id,
+        actor: actor.clone(),
+        action: action.to_string(),
+        target: target.into(),
+        height: block.height,
+        time: block.time,
+    };
+    ADMIN_LOG.save(storage, id, &entry)
+}
+
+/// Moves the poll to `status`, recording the transition. The caller saves the poll.
+fn set_status(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    poll_id: &str,
+    poll: &mut Poll,
+    actor: &Addr,
+    status: PollStatus,
+) -> StdResult<()> {
+    let change = PollChange::StatusChanged {
+        from: poll.status,
+        to: status,
+    };
+    record_change(storage, block, poll_id, actor, change)?;
+    poll.status = status;
+    Ok(())
+}
+
+/// Stops voting on the poll, settles time-dependent tallies and returns the
+/// creation deposit. Voters claim their escrowed deposits afterwards.
+fn close_poll(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    poll_id: &str,
+    poll: &mut Poll,
+    actor: &Addr,
+) -> Result<Vec<CosmosMsg>, ContractError> {
+    settle_tallies(storage, block, poll_id, poll, true)?;
+    set_status(storage, block, poll_id, poll, actor, PollStatus::Closed)?;
+    poll.closed_at = Some(poll.end_time.map_or(block.time, |end| end.min(block.time)));
+    let refunds = return_creation_deposit(poll)?;
+    POLL.save(storage, poll_id.to_string(), poll)?;
+    Ok(refunds)
+}
+
+/// Writes polls exported from another deployment. Every ballot must belong
+/// to an imported poll and name only its options.
+fn execute_set_import_mode(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    enabled: bool,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    ensure_admin(&config, &info.sender)?;
+    if enabled
+        && !config.import_mode
+        && POLL
+            .keys(deps.storage, None, None, Order::Ascending)
+            .next()
+            .is_some()
+    {
+        return Err(ContractError::ImportNotEmpty {});
+    }
+
+    config.import_mode = enabled;
+    CONFIG.save(deps.storage, &config)?;
+    log_admin_action(
+        deps.storage,
+        &env.block,
+        &info.sender,
+        "set import mode",
+        enabled.to_string(),
+    )?;
+    Ok(Response::new()
+        .add_attribute("action", "set import mode")
+        .add_attribute("enabled", enabled.to_string()))
+}
+
+fn execute_import_polls(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    polls: Vec<PollImport>,
+    ballots: Vec<BallotExport>,
+    records: Vec<RecordExport>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    ensure_admin(&config, &info.sender)?;
+    if !config.import_mode {
+        return Err(ContractError::ImportModeOff {});
+    }
+
+    let poll_count = polls.len();
+    let mut proposal_id = PROPOSAL_SEQ.may_load(deps.storage)?.unwrap_or_default();
+    for PollImport {
+        poll_id,
+        poll,
+        result,
+    } in polls
+    {
+        if POLL.has(deps.storage, poll_id.clone()) {
+            return Err(ContractError::PollIdTaken {});
+        }
+        deps.api.addr_validate(poll.admin.as_str())?;
+        // the source's salt stays behind, so voters couldn't find their ballots
+        let open = matches!(
+            poll.status,
+            PollStatus::Draft | PollStatus::Open | PollStatus::Frozen
+        );
+        if poll.pseudonymous && open {
+            return Err(ContractError::PseudonymousImport {});
+        }
+        proposal_id += 1;
+        PROPOSALS.save(deps.storage, proposal_id, &poll_id)?;
+        let mut poll = poll;
+        // archived polls may have pruned ballots, so only theirs are kept
+        if poll.status != PollStatus::Archived {
+            poll.voter_count = 0;
+            poll.ballot_weight = 0;
+        }
+        page_options(deps.storage, &poll_id, &mut poll)?;
+        for option in poll_options(deps.storage, &poll_id, &poll)? {
+            TALLIES.save(
+                deps.storage,
+                (poll_id.clone(), option.id),
+                &option.votes,
+                env.block.height,
+            )?;
+        }
+        POLL.save(deps.storage, poll_id.clone(), &poll)?;
+        index_end(deps.storage, &poll_id, &poll)?;
+        index_attributes(deps.storage, &poll_id, &poll)?;
+        let creator_key = (poll.admin.clone(), poll_id.clone());
+        if !CREATOR_POLLS.has(deps.storage, creator_key.clone()) {
+            CREATOR_POLLS.save(deps.storage, creator_key, &())?;
+            count_created(deps.storage, &poll.admin)?;
+        }
+        if poll.pseudonymous {
+            new_salt(deps.storage, &env, &poll_id, &poll.admin)?;
+        }
+        if let Some(result) = result {
+            RESULTS.save(deps.storage, poll_id, &result)?;
+        }
+    }
+    PROPOSAL_SEQ.save(deps.storage, &proposal_id)?;
+
+    let record_count = records.len();
+    for RecordExport { poll_id, record } in records {
+        let poll = POLL
+            .may_load(deps.storage, poll_id.clone())?
+            .ok_or(ContractError::PollNotFound {})?;
+        let ballot_voter = |voter: &Addr| match poll.pseudonymous {
+            true => Ok(()),
+            false => deps.api.addr_validate(voter.as_str()).map(|_| ()),
+        };
+        match record {
+            PollRecord::AllowedVoter { voter } => {
+                deps.api.addr_validate(voter.as_str())?;
+                ALLOWED_VOTERS.save(deps.storage, (poll_id, voter), &())?;
+            }
+            PollRecord::WeightOverride { voter, weight } => {
+                deps.api.addr_validate(voter.as_str())?;
+                WEIGHT_OVERRIDES.save(deps.storage, (poll_id, voter), &weight)?;
+            }
+            PollRecord::LockedSince { voter, since } => {
+                ballot_voter(&voter)?;
+                LOCKED_SINCE.save(deps.storage, (poll_id, voter), &since)?;
+            }
+            PollRecord::RewardPool { pool } => REWARD_POOLS.save(deps.storage, poll_id, &pool)?,
+            PollRecord::RewardClaim { voter, amount } => {
+                ballot_voter(&voter)?;
+                REWARD_CLAIMS.save(deps.storage, (poll_id, voter), &amount)?;
+            }
+            PollRecord::SatelliteTally { channel, tally } => {
+                SATELLITE_TALLIES.save(deps.storage, (poll_id, channel), &tally)?;
+            }
+        }
+    }
+
+    let ballot_count = ballots.len();
+    for BallotExport {
+        voter,
+        poll_id,
+        ballot,
+    } in ballots
+    {
+        let mut poll = POLL
+            .may_load(deps.storage, poll_id.clone())?
+            .ok_or(ContractError::PollNotFound {})?;
+        let shares = ballot.shares();
+        for id in shares.iter().map(|(id, _)| id).chain(&ballot.ranking) {
+            if !has_option(deps.storage, &poll_id, &poll, *id)? {
+                return Err(ContractError::OptionNotFound {});
+            }
+        }
+        if !poll.pseudonymous {
+            deps.api.addr_validate(voter.as_str())?;
+        }
+        let key = (voter.clone(), poll_id.clone());
+        let old = BALLOT.may_load(deps.storage, key.clone())?;
+        // pseudonymous ballots are keyed by a hash no address can be read from
+        if old.is_none() && !poll.pseudonymous {
+            if !SEEN_VOTERS.has(deps.storage, voter.clone()) {
+                SEEN_VOTERS.save(deps.storage, voter.clone(), &())?;
+                let mut stats = STATS.may_load(deps.storage)?.unwrap_or_default();
+                stats.unique_voters += 1;
+                STATS.save(deps.storage, &stats)?;
+            }
+            voter_stats().update(deps.storage, voter.clone(), |stats| -> StdResult<_> {
+                let mut stats = stats.unwrap_or_default();
+                stats.votes_cast += 1;
+                stats.first_vote = Some(
+                    stats
+                        .first_vote
+                        .map_or(ballot.voted_at, |first| first.min(ballot.voted_at)),
+                );
+                Ok(stats)
+            })?;
+        }
+        let old_shares = old.as_ref().map(Ballot::shares).unwrap_or_default();
+        let old_entries = old.as_ref().map(Ballot::raffle_options).unwrap_or_default();
+        index_shares(deps.storage, &poll_id, &voter, &old_shares, &shares)?;
+        enter_raffle(
+            deps.storage,
+            &poll_id,
+            &poll,
+            &voter,
+            &old_entries,
+            &ballot.raffle_options(),
+        )?;
+        if poll.status != PollStatus::Archived {
+            poll.voter_count += u64::from(old.is_none());
+            let old_weight = old.as_ref().map_or(0, |old| old.weight);
+            poll.ballot_weight = poll.ballot_weight.saturating_sub(old_weight) + ballot.weight;
+            POLL.save(deps.storage, poll_id.clone(), &poll)?;
+        }
+        POLL_VOTERS.save(deps.storage, (poll_id, voter), &())?;
+        BALLOT.save(deps.storage, key, &ballot)?;
+    }
+
+    log_admin_action(
+        deps.storage,
+        &env.block,
+        &info.sender,
+        "import polls",
+        poll_count.to_string(),
+    )?;
+    Ok(Response::new()
+        .add_attribute("action", "import_polls")
+        .add_attribute("polls", poll_count.to_string())
+        .add_attribute("records", record_count.to_string())
+        .add_attribute("ballots", ballot_count.to_string()))
+}
+
+/// Closes up to `limit` open polls past their deadline, earliest end first.
+fn execute_crank(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = CRANK_CURSOR.may_load(deps.storage)?.map(Bound::exclusive);
+    let end = Bound::exclusive((env.block.time.seconds() + 1, String::new()));
+    // Every entry passed counts against the limit. Later end times are
+    // always past the cursor, so nothing still open is missed.
+    let keys = POLL_ENDS
+        .keys(deps.storage, start, Some(end), Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+    if let Some(last) = keys.last() {
+        CRANK_CURSOR.save(deps.storage, last)?;
+    }
+
+    // Closed, draft and frozen polls keep their entries for `FinalizeExpired`;
+    // entries of finished or rescheduled polls are dropped as they are passed.
+    let mut expired = vec![];
+    let mut stale = vec![];
+    for (end, poll_id) in keys {
+        match POLL.may_load(deps.storage, poll_id.clone())? {
+            Some(poll) if poll.end_time.map(|time| time.seconds()) == Some(end) => {
+                match poll.status {
+                    PollStatus::Open if poll.is_expired(&env.block) => {
+                        expired.push((poll_id, poll))
+                    }
+                    PollStatus::Draft
+                    | PollStatus::Open
+                    | PollStatus::Frozen
+                    | PollStatus::Closed => {}
+                    _ => stale.push((end, poll_id)),
+                }
+            }
+            _ => stale.push((end, poll_id)),
+        }
+    }
+    for key in stale {
+        POLL_ENDS.remove(deps.storage, key);
+    }
+
+    let mut response = Response::new().add_attribute("action", "crank");
+    for (poll_id, mut poll) in expired {
+        let refunds = close_poll(deps.storage, &env.block, &poll_id, &mut poll, &info.sender)?;
+        response = response
+            .add_messages(refunds)
+            .add_attribute("closed", poll_id);
+    }
+    Ok(response)
+}
+
+/// Counts a new poll in its admin's `VoterStats`.
+fn count_created(storage: &mut dyn Storage, admin: &Addr) -> StdResult<()> {
+    voter_stats().update(storage, admin.clone(), |stats| -> StdResult<_> {
+        let mut stats = stats.unwrap_or_default();
+        stats.polls_created += 1;
+        Ok(stats)
+    })?;
+    Ok(())
+}
+
+/// Adds the poll under its end time in `POLL_ENDS`.
+fn index_end(storage: &mut dyn Storage, poll_id: &str, poll: &Poll) -> StdResult<()> {
+    match poll.end_time {
+        Some(end) => POLL_ENDS.save(storage, (end.seconds(), poll_id.to_string()), &()),
+        None => Ok(()),
+    }
+}
+
+/// Adds the poll under each of its attributes in `POLL_ATTRIBUTES`.
+fn index_attributes(storage: &mut dyn Storage, poll_id: &str, poll: &Poll) -> StdResult<()> {
+    for (key, value) in &poll.attributes {
+        POLL_ATTRIBUTES.save(
+            storage,
+            (key.clone(), value.clone(), poll_id.to_string()),
+            &(),
+        )?;
+    }
+    Ok(())
+}
+
+/// Deletes up to `limit` ballots of an archived poll along with the entries
+/// kept beside them.
+fn execute_prune_ballots(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    poll_id: String,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let mut poll = POLL
+        .may_load(deps.storage, poll_id.clone())?
+        .ok_or(ContractError::PollNotFound {})?;
+    if poll.status != PollStatus::Archived {
+        return Err(ContractError::PollNotArchived {});
+    }
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    // keyed as in `BALLOT`, so pseudonymous ballots are found by their hash
+    let mut voters = POLL_VOTERS
+        .prefix(poll_id.clone())
+        .keys(deps.storage, None, None, Order::Ascending)
+        .take(limit + 1)
+        .collect::<StdResult<Vec<_>>>()?;
+    let more = voters.len() > limit;
+    voters.truncate(limit);
+    let mut pool = REWARD_POOLS.may_load(deps.storage, poll_id.clone())?;
+    let deposit_token = match &poll.mode {
+        VoteMode::Cw20Deposit { token } | VoteMode::Conviction { token, .. } => {
+            Some(RewardAsset::Cw20(token.clone()))
+        }
+        _ => None,
+    };
+    let mut refunds = vec![];
+    for voter in &voters {
+        // an unclaimed reward share outlives the ballot it was earned by
+        let claim_key = (poll_id.clone(), voter.clone());
+        if let Some(pool) = pool
+            .as_mut()
+            .filter(|_| !REWARD_CLAIMS.has(deps.storage, claim_key.clone()))
+        {
+            let share = claim_share(deps.storage, &poll_id, voter, pool)?;
+            if !share.is_zero() {
+                REWARD_CLAIMS.save(deps.storage, claim_key, &share)?;
+            }
+        }
+        let key = (voter.clone(), poll_id.clone());
+        if let Some(ballot) = BALLOT.may_load(deps.storage, key.clone())? {
+            // deposits nobody claimed go back with the ballot
+            if let Some(token) = deposit_token.as_ref().filter(|_| !ballot.deposit.is_zero()) {
+                refunds.push(transfer_msg(token, voter, ballot.deposit)?);
+            }
+            for (id, _) in ballot.shares() {
+                OPTION_VOTERS.remove(deps.storage, (poll_id.clone(), id, voter.clone()));
+            }
+            enter_raffle(
+                deps.storage,
+                &poll_id,
+                &poll,
+                voter,
+                &ballot.raffle_options(),
+                &[],
+            )?;
+        }
+        BALLOT.remove(deps.storage, key);
+        POLL_VOTERS.remove(deps.storage, (poll_id.clone(), voter.clone()));
+        LOCKED_SINCE.remove(deps.storage, (poll_id.clone(), voter.clone()));
+    }
+    if let Some(pool) = pool {
+        REWARD_POOLS.save(deps.storage, poll_id.clone(), &pool)?;
+    }
+    poll.pruned_ballots += voters.len() as u64;
+    POLL.save(deps.storage, poll_id.clone(), &poll)?;
+    // polls pruned before the count was kept can only tell when they are done
+    let remaining = match more {
+        true => poll.voter_count.saturating_sub(poll.pruned_ballots).max(1),
+        false => 0,
+    };
+
+    Ok(Response::new()
+        .add_messages(refunds)
+        .add_attribute("action", "prune ballots")
+        .add_attribute("poll_id", poll_id)
+        .add_attribute("pruned", voters.len().to_string())
+        .add_attribute("remaining", remaining.to_string())
+        .add_attribute("more", more.to_string()))
+}
+
+fn execute_finalize_expired(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let config = CONFIG.load(deps.storage)?;
+    let cutoff = env.block.time.seconds().saturating_sub(config.veto_period);
+    let end = Bound::exclusive((cutoff + 1, String::new()));
+
+    // Every entry passed counts against the limit and is dropped. Draft and
+    // frozen polls are indexed again when they open or unfreeze.
+    let due = POLL_ENDS
+        .keys(deps.storage, None, Some(end), Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    // the crank never forwards funds, e.g. to a randomness proxy
+    let finalizer = MessageInfo {
+        sender: info.sender,
+        funds: vec![],
+    };
+    let mut response = Response::new().add_attribute("action", "finalize expired");
+    for (end, poll_id) in due {
+        POLL_ENDS.remove(deps.storage, (end, poll_id.clone()));
+        let Some(poll) = POLL.may_load(deps.storage, poll_id.clone())? else {
+            continue;
+        };
+        let current = poll.end_time.map(|time| time.seconds()) == Some(end);
+        if !current || !matches!(poll.status, PollStatus::Open | PollStatus::Closed) {
+            continue;
+        }
+        // a randomness request needs the proxy's fee, and failing it would
+        // revert the whole batch
+        if poll.raffle || poll.tie_break == TieBreakPolicy::Random {
+            response = response.add_attribute("skipped", poll_id);
+            continue;
+        }
+        // a poll that fails is left to `FinalizePoll` rather than holding
+        // up every poll behind it; it fails before writing anything
+        match execute_finalize_poll(
+            deps.branch(),
+            env.clone(),
+            finalizer.clone(),
+            poll_id.clone(),
+        ) {
+            Ok(finalized) => {
+                response = response
+                    .add_submessages(finalized.messages)
+                    .add_events(finalized.events)
+                    .add_attribute("finalized", poll_id);
+            }
+            Err(_) => response = response.add_attribute("failed", poll_id),
+        }
+    }
+    Ok(response)
+}
+
+/// Gives the creation deposit back to the poll's creator, at most once.
+fn return_creation_deposit(poll: &mut Poll) -> StdResult<Vec<CosmosMsg>> {
+    let mut msgs: Vec<CosmosMsg> = vec![];
+    if let Some(deposit) = poll.creation_deposit.take() {
+        msgs.push(
+            BankMsg::Send {
+                to_address: poll.admin.to_string(),
+                amount: vec![deposit],
+            }
+            .into(),
+        );
+    }
+    if let Some(deposit) = poll.cw20_creation_deposit.take() {
+        let asset = RewardAsset::Cw20(deposit.address);
+        msgs.push(transfer_msg(&asset, &poll.admin, deposit.amount)?);
+    }
+    Ok(msgs)
+}
+
+pub(crate) fn execute_finalize_poll(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    poll_id: String,
+) -> Result<Response, ContractError> {
+    let mut poll = POLL
+        .may_load(deps.storage, poll_id.clone())?
+        .ok_or(ContractError::PollNotFound {})?;
+    // Everything is checked before the first write, so `FinalizeExpired`
+    // can drop a failed poll without keeping half of it.
+    let expired = poll.status == PollStatus::Open && poll.is_expired(&env.block);
+    if !expired && poll.status != PollStatus::Closed {
+        return Err(ContractError::PollNotClosed {});
+    }
+    let closed_at = match expired {
+        true => poll.end_time,
+        false => poll.closed_at,
+    };
+    let config = CONFIG.load(deps.storage)?;
+    let veto_ends = closed_at
+        .unwrap_or(env.block.time)
+        .plus_seconds(config.veto_period);
+    if env.block.time < veto_ends {
+        return Err(ContractError::VetoPeriodActive { ends: veto_ends });
+    }
+    let may_draw = poll.raffle || poll.tie_break == TieBreakPolicy::Random;
+    if config.randomness_proxy.is_none() || !may_draw {
+        nonpayable(&info)?;
+    }
+
+    // An expired poll is closed as part of finalizing it.
+    let refunds = match expired {
+        true => close_poll(deps.storage, &env.block, &poll_id, &mut poll, &info.sender)?,
+        false => vec![],
+    };
+
+    let options = poll_options(deps.storage, &poll_id, &poll)?;
+    let mut result = compute_result(&poll, options, env.block.time);
+    if let VoteMode::Ranked { method } = poll.mode {
+        count_ranked(deps.storage, &poll_id, &poll, method, &mut result)?;
+    }
+    RESULTS.save(deps.storage, poll_id.clone(), &result)?;
+    let mut stats = STATS.may_load(deps.storage)?.unwrap_or_default();
+    stats.record_finalized(poll.voter_count);
+    STATS.save(deps.storage, &stats)?;
+    set_status(
+        deps.storage,
+        &env.block,
+        &poll_id,
+        &mut poll,
+        &info.sender,
+        PollStatus::Finalized,
+    )?;
+    POLL.save(deps.storage, poll_id.clone(), &poll)?;
+
+    let rewards = settle_rewards(deps.storage, &poll_id, &poll)?;
+    let mut response = Response::new();
+    // The finalizer pays the proxy's fee when randomness is requested.
+    match config
+        .randomness_proxy
+        .filter(|_| needs_randomness(&poll, &result))
+    {
+        Some(proxy) => {
+            response = response.add_message(WasmMsg::Execute {
+                contract_addr: proxy.to_string(),
+                msg: to_binary(&NoisProxyMsg::GetNextRandomness {
+                    job_id: poll_id.clone(),
+                })?,
+                funds: info.funds,
+            });
+        }
+        None => nonpayable(&info)?,
+    }
+    Ok(response
+        .add_messages(refunds)
+        .add_messages(rewards)
+        .add_event(finalized_event(&poll_id, &poll, &result))
+        .add_attribute("action", "finalize poll")
+        .add_attribute("poll_id", poll_id)
+        .add_attribute(
+            "winner",
+            result.winner.map(|id| id.to_string()).unwrap_or_default(),
+        )
+        .add_attribute("total", result.total.to_string()))
+}
+
+/// Emitted as `wasm-poll_finalized` so indexers and bots can react to the
+/// outcome from tx events alone.
+fn finalized_event(poll_id: &str, poll: &Poll, result: &PollResult) -> Event {
+    let mut event = Event::new("poll_finalized")
+        .add_attribute("poll_id", poll_id)
+        .add_attribute(
+            "winner",
+            result.winner.map(|id| id.to_string()).unwrap_or_default(),
+        )
+        .add_attribute("total", result.total.to_string())
+        .add_attribute("voters", poll.voter_count.to_string())
+        .add_attribute("quorum", quorum_status(result));
+    if let Some(reached) = result.min_voters_reached {
+        let status = if reached { "met" } else { "not_met" };
+        event = event.add_attribute("min_voters", status);
+    }
+    if let Some(electorate) = poll.electorate {
+        event = event.add_attribute("electorate", electorate.to_string());
+    }
+    event.add_attributes(
+        result
+            .tallies
+            .iter()
+            .map(|option| (format!("option_{}", option.id), option.votes.to_string())),
+    )
+}
+
+/// Whether the poll met its participation requirement.
+fn quorum_status(result: &PollResult) -> &'static str {
+    match result.quorum_reached {
+        None => "not_required",
+        Some(true) => "met",
+        Some(false) => "not_met",
+    }
+}
+
+fn compute_result(poll: &Poll, options: Vec<PollOption>, finalized_at: Timestamp) -> PollResult {
+    let total = options.iter().map(|option| option.votes).sum();
+    let top = options.iter().map(|option| option.votes).max();
+    let leaders: Vec<u8> = options
+        .iter()
+        .filter(|option| Some(option.votes) == top && option.votes > 0)
+        .map(|option| option.id)
+        .collect();
+
+    let quorum_reached = poll
+        .quorum
+        .as_ref()
+        .map(|quorum| quorum.is_met(total, poll.total_eligible()));
+    let threshold = poll
+        .quorum
+        .as_ref()
+        .and_then(|quorum| quorum.threshold(total, poll.total_eligible()))
+        .max(poll.win_threshold);
+    let min_voters_reached = poll
+        .min_voters
+        .map(|min| poll.voter_count >= u64::from(min));
+    let approved = threshold.is_none_or(|threshold| {
+        Decimal::from_ratio(top.unwrap_or_default(), total.max(1)) >= threshold
+    });
+
+    let (winner, tie) = match leaders.as_slice() {
+        _ if quorum_reached == Some(false) || min_voters_reached == Some(false) || !approved => {
+            (None, None)
+        }
+        [] => (None, None),
+        [winner] => (Some(*winner), None),
+        tied => {
+            let winner = match poll.tie_break {
+                TieBreakPolicy::FirstListed => Some(tied[0]),
+                _ => None,
+            };
+            let tie = TieResolution {
+                tied: tied.to_vec(),
+                policy: poll.tie_break,
+                resolved: matches!(
+                    poll.tie_break,
+                    TieBreakPolicy::None | TieBreakPolicy::FirstListed
+                ),
+            };
+            (winner, Some(tie))
+        }
+    };
+    PollResult {
+        winner,
+        tallies: options,
+        total,
+        finalized_at,
+        tie,
+        quorum_reached,
+        threshold,
+        eligible_weight: poll.total_eligible(),
+        min_voters_reached,
+        rounds: vec![],
+        pairwise: None,
+        randomness: None,
+        raffle_winner: None,
+    }
+}
+
+/// Replaces the plurality winner of a ranked poll with the one `method`
+/// counts. Ballots count at their cast weight. A dynamic quorum's threshold
+/// applies to the winner's final-round votes, or under Condorcet to its
+/// narrowest head-to-head win.
+fn count_ranked(
+    storage: &dyn Storage,
+    poll_id: &str,
+    poll: &Poll,
+    method: RankedMethod,
+    result: &mut PollResult,
+) -> StdResult<()> {
+    let ballots: Vec<(Vec<u8>, u64)> = poll_ballots(storage, poll_id)?
+        .into_iter()
+        .map(|(_, ballot)| (ballot.ranking, ballot.weight))
+        .collect();
+    let options: Vec<u8> = poll.options.iter().map(|option| option.id).collect();
+    let (winner, votes) = match method {
+        RankedMethod::InstantRunoff => {
+            let (winner, rounds) = tally::instant_runoff(&options, &ballots);
+            let votes = rounds
+                .last()
+                .and_then(|round| round.tallies.iter().find(|(id, _)| Some(*id) == winner))
+                .map_or(0, |(_, votes)| *votes);
+            result.rounds = rounds;
+            (winner, votes)
+        }
+        RankedMethod::Condorcet => {
+            let (winner, pairwise) = tally::condorcet(&options, &ballots);
+            let votes = pairwise
+                .preferences
+                .iter()
+                .filter(|(a, _, _)| Some(*a) == winner)
+                .map(|(_, _, weight)| *weight)
+                .min()
+                .unwrap_or_default();
+            result.pairwise = Some(pairwise);
+            (winner, votes)
+        }
+    };
+    let approved = result.quorum_reached != Some(false)
+        && result.min_voters_reached != Some(false)
+        && result
+            .threshold
+            .is_none_or(|threshold| Decimal::from_ratio(votes, result.total.max(1)) >= threshold);
+    result.winner = winner.filter(|_| approved);
+    result.tie = None;
+    Ok(())
+}
+
+fn execute_resolve_tie(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    poll_id: String,
+    option: u8,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let poll = POLL
+        .may_load(deps.storage, poll_id.clone())?
+        .ok_or(ContractError::PollNotFound {})?;
+    if poll.admin != info.sender && !config.is_admin(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut result = RESULTS
+        .may_load(deps.storage, poll_id.clone())?
+        .ok_or(ContractError::NoPendingTie {})?;
+    let tie = match result.tie.as_mut() {
+        Some(tie) if tie.policy == TieBreakPolicy::AdminDecides && !tie.resolved => tie,
+        _ => return Err(ContractError::NoPendingTie {}),
+    };
+    if !tie.tied.contains(&option) {
+        return Err(ContractError::OptionNotTied {});
+    }
+    tie.resolved = true;
+    result.winner = Some(option);
+    RESULTS.save(deps.storage, poll_id.clone(), &result)?;
+    Ok(Response::new()
+        .add_attribute("action", "resolve tie")
+        .add_attribute("poll_id", poll_id)
+        .add_attribute("winner", option.to_string()))
+}
+
+/// Whether a finalized poll still waits on a random draw: a tie left to
+/// chance, or a raffle among the voters of its winner.
+fn needs_randomness(poll: &Poll, result: &PollResult) -> bool {
+    let random_tie = result
+        .tie
+        .as_ref()
+        .is_some_and(|tie| tie.policy == TieBreakPolicy::Random && !tie.resolved);
+    random_tie || (poll.raffle && result.winner.is_some())
+}
+
+/// Uniform-enough index below `n` derived from the beacon and a `salt`, so
+/// the tie-break and the raffle draw independently from one beacon.
+fn draw(randomness: &HexBinary, salt: &str, n: usize) -> usize {
+    let hash: [u8; 32] = Sha256::digest([randomness.as_slice(), salt.as_bytes()].concat()).into();
+    let mut head = [0u8; 8];
+    head.copy_from_slice(&hash[..8]);
+    (u64::from_be_bytes(head) % n as u64) as usize
+}
+
+/// Draws an entrant for `winner` by number. A ranked ballot that lists the
+/// winner only counts if it prefers the winner to every option left in the
+/// final runoff round; others are redrawn up to `MAX_RAFFLE_DRAWS` times.
+fn draw_raffle(
+    storage: &dyn Storage,
+    poll_id: &str,
+    result: &PollResult,
+    winner: u8,
+    randomness: &HexBinary,
+) -> StdResult<Option<Addr>> {
+    let entrants = RAFFLE_COUNTS
+        .may_load(storage, (poll_id.to_string(), winner))?
+        .unwrap_or_default();
+    if entrants == 0 {
+        return Ok(None);
+    }
+    let standing: Option<Vec<u8>> = result
+        .rounds
+        .last()
+        .map(|round| round.tallies.iter().map(|(id, _)| *id).collect());
+    for attempt in 0..MAX_RAFFLE_DRAWS {
+        let salt = match attempt {
+            0 => "raffle".to_string(),
+            _ => format!("raffle{attempt}"),
+        };
+        let slot = draw(randomness, &salt, entrants as usize) as u64;
+        let entrant = RAFFLE_ENTRIES.load(storage, (poll_id.to_string(), winner, slot))?;
+        let Some(standing) = &standing else {
+            return Ok(Some(entrant));
+        };
+        let ballot = BALLOT.load(storage, (entrant.clone(), poll_id.to_string()))?;
+        if ballot.ranking.iter().find(|id| standing.contains(id)) == Some(&winner) {
+            return Ok(Some(entrant));
+        }
+    }
+    Ok(None)
+}
+
+fn execute_nois_receive(
+    deps: DepsMut,
+    info: MessageInfo,
+    callback: NoisCallback,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if config.randomness_proxy.as_ref() != Some(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+    let poll_id = callback.job_id;
+    let poll = POLL
+        .may_load(deps.storage, poll_id.clone())?
+        .ok_or(ContractError::PollNotFound {})?;
+    let mut result = RESULTS
+        .may_load(deps.storage, poll_id.clone())?
+        .ok_or(ContractError::NoRandomnessRequested {})?;
+    if result.randomness.is_some() {
+        return Err(ContractError::RandomnessDelivered {});
+    }
+    if !needs_randomness(&poll, &result) {
+        return Err(ContractError::NoRandomnessRequested {});
+    }
+
+    let mut response = Response::new()
+        .add_attribute("action", "receive randomness")
+        .add_attribute("poll_id", poll_id.clone());
+    if let Some(tie) = result.tie.as_mut() {
+        if tie.policy == TieBreakPolicy::Random && !tie.resolved {
+            let winner = tie.tied[draw(&callback.randomness, "tie", tie.tied.len())];
+            tie.resolved = true;
+            result.winner = Some(winner);
+            response = response.add_attribute("winner", winner.to_string());
+        }
+    }
+    if let (true, Some(winner)) = (poll.raffle, result.winner) {
+        if let Some(drawn) = draw_raffle(
+            deps.storage,
+            &poll_id,
+            &result,
+            winner,
+            &callback.randomness,
+        )? {
+            response = response.add_attribute("raffle_winner", drawn.to_string());
+            result.raffle_winner = Some(drawn);
+        }
+    }
+    result.randomness = Some(callback.randomness);
+    RESULTS.save(deps.storage, poll_id, &result)?;
+    Ok(response)
+}
+
+fn execute_try_early_close(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    poll_id: String,
+) -> Result<Response, ContractError> {
+    let mut poll = POLL
+        .may_load(deps.storage, poll_id.clone())?
+        .ok_or(ContractError::PollNotFound {})?;
+    if poll.status != PollStatus::Open {
+        return Err(ContractError::PollNotOpen {});
+    }
+    let options = poll_options(deps.storage, &poll_id, &poll)?;
+    if !is_decided(deps.storage, &poll_id, &poll, &options)? {
+        return Err(ContractError::OutcomeNotDecided {});
+    }
+
+    let refunds = close_poll(deps.storage, &env.block, &poll_id, &mut poll, &info.sender)?;
+    Ok(Response::new()
+        .add_messages(refunds)
+        .add_attribute("action", "early close poll")
+        .add_attribute("poll_id", poll_id))
+}
+
+/// Whether the leading option is ahead of the runner-up by more than the
+/// weight remaining in a standard poll with a fixed electorate.
+fn is_decided(
+    storage: &dyn Storage,
+    poll_id: &str,
+    poll: &Poll,
+    options: &[PollOption],
+) -> StdResult<bool> {
+    let electorate = match (&poll.mode, poll.electorate) {
+        (VoteMode::Standard, Some(electorate)) => electorate,
+        _ => return Ok(false),
+    };
+    // closing early would leave the poll short of its minimum voters
+    if poll
+        .min_voters
+        .is_some_and(|min| poll.voter_count < u64::from(min))
+    {
+        return Ok(false);
+    }
+    let mut tallies: Vec<u64> = options.iter().map(|option| option.votes).collect();
+    let total: u64 = tallies.iter().sum();
+    // nobody else has to vote, so a quorum not met yet may never be
+    let eligible = poll.total_eligible();
+    if poll
+        .quorum
+        .as_ref()
+        .is_some_and(|quorum| !quorum.is_met(total, eligible))
+    {
+        return Ok(false);
+    }
+    // votes pushed from satellite chains come from outside the electorate
+    let satellite = SATELLITE_TALLIES
+        .prefix(poll_id.to_string())
+        .range(storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, tally)| tally.tallies.iter().map(|(_, votes)| votes).sum()))
+        .sum::<StdResult<u64>>()?;
+    // delegated weight is already tallied, so count what is left by weight
+    let remaining = electorate.saturating_sub(total.saturating_sub(satellite));
+    tallies.sort_unstable_by(|a, b| b.cmp(a));
+    // the winner must also clear the strictest threshold turnout can still
+    // reach if everyone left votes against it
+    let dynamic = match &poll.quorum {
+        Some(quorum @ Quorum::Dynamic { curve }) => {
+            let turnout = Decimal::from_ratio(total, eligible.unwrap_or_default().max(1));
+            curve
+                .iter()
+                .filter(|(step, _)| *step > turnout)
+                .map(|(_, threshold)| *threshold)
+                .chain(quorum.threshold(total, eligible))
+                .max()
+        }
+        _ => None,
+    };
+    let threshold = dynamic.max(poll.win_threshold);
+    let clears_threshold = |first: u64| {
+        threshold.is_none_or(|threshold| {
+            Decimal::from_ratio(first, (total + remaining).max(1)) >= threshold
+        })
+    };
+    Ok(match tallies.as_slice() {
+        [first, second, ..] => first - second > remaining && clears_threshold(*first),
+        [first] => *first > 0 && clears_threshold(*first),
+        [] => false,
+    })
+}
+
+fn execute_archive_poll(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    poll_id: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let mut poll = POLL
+        .may_load(deps.storage, poll_id.clone())?
+        .ok_or(ContractError::PollNotFound {})?;
+    ensure_can_moderate(deps.storage, &config, &poll, &info.sender)?;
+    if poll.status == PollStatus::Archived {
+        return Err(ContractError::PollArchived {});
+    }
+
+    let mut refunds = reclaim_rewards(deps.storage, &poll_id, &poll)?;
+    refunds.extend(return_creation_deposit(&mut poll)?);
+    set_status(
+        deps.storage,
+        &env.block,
+        &poll_id,
+        &mut poll,
+        &info.sender,
+        PollStatus::Archived,
+    )?;
+    POLL.save(deps.storage, poll_id.clone(), &poll)?;
+    if poll.admin != info.sender {
+        log_admin_action(
+            deps.storage,
+            &env.block,
+            &info.sender,
+            "archive poll",
+            &poll_id,
+        )?;
+    }
+    Ok(Response::new()
+        .add_messages(refunds)
+        .add_attribute("action", "archive poll")
+        .add_attribute("poll_id", poll_id))
+}
+
+#[allow(clippy::too_many_arguments)]
+fn execute_update_config(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    min_voting_period: Option<u64>,
+    max_voting_period: Option<u64>,
+    max_delegation_depth: Option<u64>,
+    veto_period: Option<u64>,
+    restricted_creation: Option<bool>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    ensure_admin(&config, &info.sender)?;
+    if let Some(min_voting_period) = min_voting_period {
+        config.min_voting_period = min_voting_period;
+    }
+    if let Some(max_voting_period) = max_voting_period {
+        config.max_voting_period = Some(max_voting_period).filter(|max| *max > 0);
+    }
+    if config
+        .max_voting_period
+        .is_some_and(|max| max < config.min_voting_period)
+    {
+        return Err(ContractError::InvalidVotingPeriod {});
+    }
+    if let Some(max_delegation_depth) = max_delegation_depth {
+        config.max_delegation_depth = max_delegation_depth;
+    }
+    if let Some(veto_period) = veto_period {
+        config.veto_period = veto_period;
+    }
+    if let Some(restricted_creation) = restricted_creation {
+        config.restricted_creation = restricted_creation;
+    }
+
+    CONFIG.save(deps.storage, &config)?;
+    log_admin_action(deps.storage, &env.block, &info.sender, "update config", "")?;
+    Ok(Response::new()
+        .add_attribute("action", "update config")
+        .add_attribute("min_voting_period", config.min_voting_period.to_string())
+        .add_attribute(
+            "max_voting_period",
+            config
+                .max_voting_period
+                .map_or_else(|| "none".to_string(), |max| max.to_string()),
+        )
+        .add_attribute(
+            "max_delegation_depth",
+            config.max_delegation_depth.to_string(),
+        )
+        .add_attribute("veto_period", config.veto_period.to_string())
+        .add_attribute(
+            "restricted_creation",
+            config.restricted_creation.to_string(),
+        ))
+}
+
+fn execute_delegate(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    to: String,
+) -> Result<Response, ContractError> {
+    ensure_not_banned(deps.as_ref(), &info.sender)?;
+    let delegate = deps.api.addr_validate(&to)?;
+    if delegate == info.sender {
+        return Err(ContractError::CannotDelegateToSelf {});
+    }
+    // Delegations form a forest, so this walk always ends.
+    let mut next = Some(delegate.clone());
+    while let Some(addr) = next {
+        if addr == info.sender {
+            return Err(ContractError::DelegationCycle {});
+        }
+        next = DELEGATIONS.may_load(deps.storage, addr)?;
+    }
+
+    if let Some(previous) = DELEGATIONS.may_load(deps.storage, info.sender.clone())? {
+        DELEGATORS.remove(deps.storage, (previous, info.sender.clone()));
+    }
+    DELEGATIONS.save(deps.storage, info.sender.clone(), &delegate)?;
+    DELEGATORS.save(deps.storage, (delegate.clone(), info.sender), &())?;
+    Ok(Response::new()
+        .add_attribute("action", "delegate")
+        .add_attribute("delegate", delegate))
+}
+
+fn execute_undelegate(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let delegate = DELEGATIONS
+        .may_load(deps.storage, info.sender.clone())?
+        .ok_or(ContractError::NotDelegating {})?;
+    DELEGATIONS.remove(deps.storage, info.sender.clone());
+    DELEGATORS.remove(deps.storage, (delegate, info.sender));
+    Ok(Response::new().add_attribute("action", "undelegate"))
+}
+
+/// Binds `pubkey` to the sender once the key has signed over the sender's
+/// address, so nobody can claim a key they do not hold.
+fn execute_register_voting_key(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    pubkey: Binary,
+    signature: Binary,
+) -> Result<Response, ContractError> {
+    let payload = to_vec(&KeyRegistration {
+        chain_id: env.block.chain_id,
+        contract: env.contract.address.to_string(),
+        voter: info.sender.to_string(),
+    })?;
+    let hash = Sha256::digest(payload);
+    let valid = deps
+        .api
+        .secp256k1_verify(&hash, &signature, &pubkey)
+        .map_err(|_| ContractError::InvalidSignature {})?;
+    if !valid {
+        return Err(ContractError::InvalidSignature {});
+    }
+    if let Some(owner) = VOTING_KEYS.may_load(deps.storage, &pubkey)? {
+        if owner != info.sender {
+            return Err(ContractError::VotingKeyTaken {});
+        }
+    }
+    VOTING_KEYS.save(deps.storage, &pubkey, &info.sender)?;
+    Ok(Response::new()
+        .add_attribute("action", "register voting key")
+        .add_attribute("voter", info.sender))
+}
+
+/// Casts each ballot as its signer. The batch fails as a whole if any
+/// signature, nonce or vote is rejected.
+fn execute_submit_signed_votes(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    votes: Vec<SignedVote>,
+) -> Result<Response, ContractError> {
+    let mut response = Response::new()
+        .add_attribute("action", "submit signed votes")
+        .add_attribute("relayer", info.sender);
+    for vote in votes {
+        let voter = VOTING_KEYS
+            .may_load(deps.storage, &vote.pubkey)?
+            .ok_or(ContractError::VotingKeyNotRegistered {})?;
+        let last = VOTE_NONCES
+            .may_load(deps.storage, voter.clone())?
+            .unwrap_or_default();
+        if vote.nonce <= last {
+            return Err(ContractError::StaleNonce { last });
+        }
+        let payload = to_vec(&VotePayload {
+            chain_id: env.block.chain_id.clone(),
+            contract: env.contract.address.to_string(),
+            poll_id: vote.poll_id.clone(),
+            option: vote.option,
+            nonce: vote.nonce,
+        })?;
+        let hash = Sha256::digest(payload);
+        let valid = deps
+            .api
+            .secp256k1_verify(&hash, &vote.signature, &vote.pubkey)
+            .map_err(|_| ContractError::InvalidSignature {})?;
+        if !valid {
+            return Err(ContractError::InvalidSignature {});
+        }
+        VOTE_NONCES.save(deps.storage, voter.clone(), &vote.nonce)?;
+
+        let signer = MessageInfo {
+            sender: voter,
+            funds: vec![],
+        };
+        let res = execute_vote(
+            deps.branch(),
+            env.clone(),
+            signer,
+            vote.poll_id,
+            vote.option,
+            None,
+            None,
+        )?;
+        response = response
+            .add_submessages(res.messages)
+            .add_event(Event::new("signed_vote").add_attributes(res.attributes))
+            .add_events(res.events);
+    }
+    Ok(response)
+}
+
+fn execute_add_admin(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    ensure_admin(&config, &info.sender)?;
+    let admin = deps.api.addr_validate(&address)?;
+    if config.is_admin(&admin) {
+        return Err(ContractError::AlreadyAdmin {});
+    }
+
+    config.admins.push(admin.clone());
+    CONFIG.save(deps.storage, &config)?;
+    log_admin_action(deps.storage, &env.block, &info.sender, "add admin", &admin)?;
+    Ok(Response::new()
+        .add_attribute("action", "add admin")
+        .add_attribute("admin", admin))
+}
+
+fn execute_remove_admin(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    ensure_admin(&config, &info.sender)?;
+    let admin = deps.api.addr_validate(&address)?;
+    if !config.is_admin(&admin) {
+        return Err(ContractError::NotAdmin {});
+    }
+    if config.admins.len() == 1 {
+        return Err(ContractError::CannotRemoveLastAdmin {});
+    }
+
+    config.admins.retain(|a| *a != admin);
+    CONFIG.save(deps.storage, &config)?;
+    log_admin_action(
+        deps.storage,
+        &env.block,
+        &info.sender,
+        "remove admin",
+        &admin,
+    )?;
+    Ok(Response::new()
+        .add_attribute("action", "remove admin")
+        .add_attribute("admin", admin))
+}
+
+fn execute_add_moderator(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    ensure_admin(&config, &info.sender)?;
+    let moderator = deps.api.addr_validate(&address)?;
+    if config.is_moderator(&moderator) {
+        return Err(ContractError::AlreadyModerator {});
+    }
+
+    config.moderators.push(moderator.clone());
+    CONFIG.save(deps.storage, &config)?;
+    log_admin_action(
+        deps.storage,
+        &env.block,
+        &info.sender,
+        "add moderator",
+        &moderator,
+    )?;
+    Ok(Response::new()
+        .add_attribute("action", "add moderator")
+        .add_attribute("moderator", moderator))
+}
+
+fn execute_remove_moderator(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    ensure_admin(&config, &info.sender)?;
+    let moderator = deps.api.addr_validate(&address)?;
+    if !config.is_moderator(&moderator) {
+        return Err(ContractError::NotModerator {});
+    }
+
+    config.moderators.retain(|m| *m != moderator);
+    CONFIG.save(deps.storage, &config)?;
+    log_admin_action(
+        deps.storage,
+        &env.block,
+        &info.sender,
+        "remove moderator",
+        &moderator,
+    )?;
+    Ok(Response::new()
+        .add_attribute("action", "remove moderator")
+        .add_attribute("moderator", moderator))
+}
+
+fn execute_add_category_admin(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    category: String,
+    address: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    ensure_admin(&config, &info.sender)?;
+    let admin = deps.api.addr_validate(&address)?;
+    let key = (category.clone(), admin.clone());
+    if CATEGORY_ADMINS.has(deps.storage, key.clone()) {
+        return Err(ContractError::AlreadyCategoryAdmin {});
+    }
+
+    CATEGORY_ADMINS.save(deps.storage, key, &())?;
+    log_admin_action(
+        deps.storage,
+        &env.block,
+        &info.sender,
+        "add category admin",
+        format!("{}/{}", category, admin),
+    )?;
+    Ok(Response::new()
+        .add_attribute("action", "add category admin")
+        .add_attribute("category", category)
+        .add_attribute("admin", admin))
+}
+
+fn execute_remove_category_admin(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    category: String,
+    address: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    ensure_admin(&config, &info.sender)?;
+    let admin = deps.api.addr_validate(&address)?;
+    let key = (category.clone(), admin.clone());
+    if !CATEGORY_ADMINS.has(deps.storage, key.clone()) {
+        return Err(ContractError::NotCategoryAdmin {});
+    }
+
+    CATEGORY_ADMINS.remove(deps.storage, key);
+    log_admin_action(
+        deps.storage,
+        &env.block,
+        &info.sender,
+        "remove category admin",
+        format!("{}/{}", category, admin),
+    )?;
+    Ok(Response::new()
+        .add_attribute("action", "remove category admin")
+        .add_attribute("category", category)
+        .add_attribute("admin", admin))
+}
+
+fn execute_ban_voter(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    ensure_admin(&config, &info.sender)?;
+    let voter = deps.api.addr_validate(&address)?;
+    if BANNED.has(deps.storage, voter.clone()) {
+        return Err(ContractError::AlreadyBanned {});
+    }
+
+    BANNED.save(deps.storage, voter.clone(), &())?;
+    log_admin_action(deps.storage, &env.block, &info.sender, "ban voter", &voter)?;
+    Ok(Response::new()
+        .add_attribute("action", "ban voter")
+        .add_attribute("voter", voter))
+}
+
+fn execute_add_creator(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    ensure_admin(&config, &info.sender)?;
+    let creator = deps.api.addr_validate(&address)?;
+    if CREATORS.has(deps.storage, creator.clone()) {
+        return Err(ContractError::AlreadyCreator {});
+    }
+
+    CREATORS.save(deps.storage, creator.clone(), &())?;
+    log_admin_action(
+        deps.storage,
+        &env.block,
+        &info.sender,
+        "add creator",
+        &creator,
+    )?;
+    Ok(Response::new()
+        .add_attribute("action", "add creator")
+        .add_attribute("creator", creator))
+}
+
+fn execute_remove_creator(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    ensure_admin(&config, &info.sender)?;
+    let creator = deps.api.addr_validate(&address)?;
+    if !CREATORS.has(deps.storage, creator.clone()) {
+        return Err(ContractError::NotCreator {});
+    }
+
+    CREATORS.remove(deps.storage, creator.clone());
+    log_admin_action(
+        deps.storage,
+        &env.block,
+        &info.sender,
+        "remove creator",
+        &creator,
+    )?;
+    Ok(Response::new()
+        .add_attribute("action", "remove creator")
+        .add_attribute("creator", creator))
+}
+
+fn execute_unban_voter(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    ensure_admin(&config, &info.sender)?;
+    let voter = deps.api.addr_validate(&address)?;
+    if !BANNED.has(deps.storage, voter.clone()) {
+        return Err(ContractError::NotBanned {});
+    }
+
+    BANNED.remove(deps.storage, voter.clone());
+    log_admin_action(
+        deps.storage,
+        &env.block,
+        &info.sender,
+        "unban voter",
+        &voter,
+    )?;
+    Ok(Response::new()
+        .add_attribute("action", "unban voter")
+        .add_attribute("voter", voter))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    let previous = get_contract_version(deps.storage)?;
+    if previous.contract != CONTRACT_NAME {
+        return Err(ContractError::CannotMigrate {
+            previous_contract: previous.contract,
+        });
+    }
+    let applied = migrations::upgrade(deps.storage, &previous.version, CONTRACT_VERSION)?;
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    Ok(Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("from_version", previous.version)
+        .add_attribute("steps", applied.join(",")))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn sudo(deps: DepsMut, env: Env, msg: SudoMsg) -> Result<Response, ContractError> {
+    match msg {
+        SudoMsg::UpdateAdmin { admin } => {
+            let admin = deps.api.addr_validate(&admin)?;
+            let mut config = CONFIG.load(deps.storage)?;
+            let previous = std::mem::replace(&mut config.admins, vec![admin.clone()]);
+            CONFIG.save(deps.storage, &config)?;
+            log_admin_action(
+                deps.storage,
+                &env.block,
+                &env.contract.address,
+                "sudo update admin",
+                &admin,
+            )?;
+            let previous = previous
+                .iter()
+                .map(Addr::as_str)
+                .collect::<Vec<_>>()
+                .join(",");
+            // governance overriding the admins should stand out to indexers
+            let event = Event::new("admin_recovered")
+                .add_attribute("previous_admins", previous)
+                .add_attribute("admin", &admin);
+            Ok(Response::new()
+                .add_event(event)
+                .add_attribute("action", "sudo update admin")
+                .add_attribute("admin", admin))
+        }
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(_deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+    let action = match msg.id {
+        NOTIFY_REPLY_ID => "notification failed",
+        RECEIPT_REPLY_ID => "receipt failed",
+        id => return Err(StdError::generic_err(format!("unknown reply id {id}")).into()),
+    };
+    let err = msg.result.unwrap_err();
+    Ok(Response::new()
+        .add_attribute("action", action)
+        .add_attribute("error", err))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: ContractQueryMsg) -> StdResult<Binary> {
+    let msg = match msg {
+        ContractQueryMsg::Poll(msg) => msg,
+        ContractQueryMsg::Cw3(msg) => return query_cw3(deps, env, msg),
+        ContractQueryMsg::Dao(msg) => return query_dao(deps, env, msg),
+    };
+    match msg {
+        QueryMsg::Config {} => to_binary(&query_config(deps, env)?),
+        QueryMsg::AllPoll {} => to_binary(&query_all_poll(deps, env)?),
+        QueryMsg::PollSummaries {
+            start_after,
+            limit,
+            status,
+        } => to_binary(&query_poll_summaries(
+            deps,
+            env,
+            start_after,
+            limit,
+            status,
+        )?),
+        QueryMsg::PollsByAttribute {
+            key,
+            value,
+            start_after,
+            limit,
+        } => to_binary(&query_polls_by_attribute(
+            deps,
+            env,
+            key,
+            value,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::Poll {
+            poll_id,
+            locale,
+            viewer,
+        } => to_binary(&query_poll(deps, env, poll_id, locale, viewer)?),
+        QueryMsg::VerifyContent { poll_id, hash } => {
+            to_binary(&query_verify_content(deps, env, poll_id, hash)?)
+        }
+        QueryMsg::Vote {
+            poll_id,
+            address,
+            proof,
+            viewer,
+        } => to_binary(&query_vote(deps, env, poll_id, address, proof, viewer)?),
+        QueryMsg::Ballot {
+            poll_id,
+            address,
+            proof,
+            viewer,
+        } => to_binary(&query_ballot(deps, env, poll_id, address, proof, viewer)?),
+        QueryMsg::CreatorDashboard {
+            address,
+            start_after,
+            limit,
+        } => to_binary(&query_creator_dashboard(
+            deps,
+            env,
+            address,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::ListVotes {
+            poll_id,
+            start_after,
+            limit,
+            viewer,
+        } => to_binary(&query_list_votes(
+            deps,
+            env,
+            poll_id,
+            start_after,
+            limit,
+            viewer,
+        )?),
+        QueryMsg::VotersForOption {
+            poll_id,
+            option,
+            start_after,
+            limit,
+            viewer,
+        } => to_binary(&query_voters_for_option(
+            deps,
+            env,
+            poll_id,
+            option,
+            start_after,
+            limit,
+            viewer,
+        )?),
+        QueryMsg::ExportState {
+            section,
+            start_after,
+            limit,
+        } => to_binary(&query_export_state(deps, env, section, start_after, limit)?),
+        QueryMsg::ExportBallots { start_after, limit } => {
+            to_binary(&query_export_ballots(deps, env, start_after, limit)?)
+        }
+        QueryMsg::Comments {
+            poll_id,
+            start_after,
+            limit,
+        } => to_binary(&query_comments(deps, env, poll_id, start_after, limit)?),
+        QueryMsg::Options {
+            poll_id,
+            start_after,
+            limit,
+            viewer,
+        } => to_binary(&query_options(
+            deps,
+            env,
+            poll_id,
+            start_after,
+            limit,
+            viewer,
+        )?),
+        QueryMsg::Suggestions {
+            poll_id,
+            start_after,
+            limit,
+        } => to_binary(&query_suggestions(deps, env, poll_id, start_after, limit)?),
+        QueryMsg::ChainTallies { poll_id } => to_binary(&query_chain_tallies(deps, env, poll_id)?),
+        QueryMsg::TallyAtHeight { poll_id, height } => {
+            to_binary(&query_tally_at_height(deps, env, poll_id, height)?)
+        }
+        QueryMsg::PollHistory {
+            poll_id,
+            start_after,
+            limit,
+        } => to_binary(&query_poll_history(deps, env, poll_id, start_after, limit)?),
+        QueryMsg::AdminLog { start_after, limit } => {
+            to_binary(&query_admin_log(deps, env, start_after, limit)?)
+        }
+        QueryMsg::FinalResult { poll_id } => to_binary(&query_final_result(deps, env, poll_id)?),
+        QueryMsg::ResolvedWeight { address, poll_id } => {
+            to_binary(&query_resolved_weight(deps, env, address, poll_id)?)
+        }
+        QueryMsg::TurnoutStats {} => to_binary(&query_turnout_stats(deps, env)?),
+        QueryMsg::TopVoters { limit } => to_binary(&query_top_voters(deps, env, limit)?),
+        QueryMsg::VoterInfo { address } => to_binary(&query_voter_info(deps, env, address)?),
+        QueryMsg::CategoryAdmins {
+            category,
+            start_after,
+            limit,
+        } => to_binary(&query_category_admins(
+            deps,
+            env,
+            category,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::WeightOverrides {
+            poll_id,
+            start_after,
+            limit,
+        } => to_binary(&query_weight_overrides(
+            deps,
+            env,
+            poll_id,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::BannedVoters { start_after, limit } => {
+            to_binary(&query_banned_voters(deps, env, start_after, limit)?)
+        }
+        QueryMsg::Creators { start_after, limit } => {
+            to_binary(&query_creators(deps, env, start_after, limit)?)
+        }
+        QueryMsg::RewardPool { poll_id } => to_binary(&query_reward_pool(deps, env, poll_id)?),
+        QueryMsg::ClaimableReward { poll_id, address } => {
+            to_binary(&query_claimable_reward(deps, env, poll_id, address)?)
+        }
+        QueryMsg::Stake {
+            token,
+            address,
+            height,
+        } => to_binary(&query_stake(deps, env, token, address, height)?),
+    }
+}
+
+fn query_dao(deps: Deps, env: Env, msg: DaoQueryMsg) -> StdResult<Binary> {
+    match msg {
+        DaoQueryMsg::VotingPowerAtHeight { address, height } => {
+            let address = deps.api.addr_validate(&address)?;
+            let height = height.unwrap_or(env.block.height);
+            let config = CONFIG.load(deps.storage)?;
+            let power = match config.voting_token {
+                Some(token) => STAKES
+                    .may_load_at_height(deps.storage, (token, address), height)?
+                    .unwrap_or_default(),
+                None => Uint128::zero(),
+            };
+            to_binary(&VotingPowerAtHeightResponse { power, height })
+        }
+        DaoQueryMsg::TotalPowerAtHeight { height } => {
+            let height = height.unwrap_or(env.block.height);
+            let config = CONFIG.load(deps.storage)?;
+            let power = match config.voting_token {
+                Some(token) => total_stake_at(deps.storage, token, height)?,
+                None => Uint128::zero(),
+            };
+            to_binary(&TotalPowerAtHeightResponse { power, height })
+        }
+        DaoQueryMsg::Dao {} => {
+            let config = CONFIG.load(deps.storage)?;
+            let dao = config
+                .admins
+                .first()
+                .cloned()
+                .ok_or_else(|| StdError::not_found("admin"))?;
+            to_binary(&dao)
+        }
+        DaoQueryMsg::Info {} => to_binary(&InfoResponse {
+            info: get_contract_version(deps.storage)?,
+        }),
+    }
+}
+
+/// Sum of every staker's stake of `token` at `height`.
+fn total_stake_at(storage: &dyn Storage, token: Addr, height: u64) -> StdResult<Uint128> {
+    Ok(STAKE_TOTALS
+        .may_load_at_height(storage, token, height)?
+        .unwrap_or_default())
+}
+
+fn query_cw3(deps: Deps, env: Env, msg: Cw3QueryMsg) -> StdResult<Binary> {
+    match msg {
+        Cw3QueryMsg::Proposal { proposal_id } => {
+            to_binary(&query_proposal(deps, env, proposal_id)?)
+        }
+        Cw3QueryMsg::ListProposals { start_after, limit } => {
+            to_binary(&query_list_proposals(deps, env, start_after, limit)?)
+        }
+        Cw3QueryMsg::Vote { proposal_id, voter } => {
+            to_binary(&query_cw3_vote(deps, env, proposal_id, voter)?)
+        }
+        Cw3QueryMsg::ListVotes {
+            proposal_id,
+            start_after,
+            limit,
+        } => to_binary(&query_cw3_list_votes(
+            deps,
+            env,
+            proposal_id,
+            start_after,
+            limit,
+        )?),
+        Cw3QueryMsg::Voter {
+            address,
+            proposal_id,
+        } => to_binary(&query_voter(deps, env, address, proposal_id)?),
+    }
+}
+
+fn proposal_response(
+    deps: Deps,
+    block: &BlockInfo,
+    proposal_id: u64,
+    poll_id: String,
+) -> StdResult<Cw3ProposalResponse> {
+    let mut poll = POLL.load(deps.storage, poll_id.clone())?;
+    poll.catch_up(block);
+    let passed = RESULTS
+        .may_load(deps.storage, poll_id.clone())?
+        .is_some_and(|result| result.winner.is_some());
+    let status = match poll.status {
+        _ if passed => Cw3Status::Passed,
+        PollStatus::Draft => Cw3Status::Pending,
+        PollStatus::Open | PollStatus::Frozen | PollStatus::Closed => Cw3Status::Open,
+        _ => Cw3Status::Rejected,
+    };
+    // without an electorate the total is the live turnout, hidden like tallies
+    let total_weight = match poll.electorate {
+        Some(electorate) => electorate,
+        None if !can_view_results(deps, &poll_id, &poll, None)? => 0,
+        None => poll_options(deps.storage, &poll_id, &poll)?
+            .iter()
+            .map(|option| option.votes)
+            .sum(),
+    };
+    Ok(Cw3ProposalResponse {
+        id: proposal_id,
+        title: poll_id,
+        description: poll.question,
+        msgs: vec![],
+        status,
+        expires: poll
+            .end_time
+            .map_or(Expiration::Never {}, Expiration::AtTime),
+        threshold: Cw3ThresholdResponse::AbsoluteCount {
+            weight: 1,
+            total_weight,
+        },
+        proposer: poll.admin,
+    })
+}
+
+fn cw3_vote_info(
+    proposal_id: u64,
+    voter: Addr,
+    ballot: Ballot,
+    options: &[PollOption],
+) -> Cw3VoteInfo {
+    let label = |id: u8| {
+        options
+            .iter()
+            .find(|option| option.id == id)
+            .map(|option| option.label.trim().to_lowercase())
+    };
+    let yes_no = ["yes", "no"].iter().all(|name| {
+        options
+            .iter()
+            .any(|option| option.label.trim().eq_ignore_ascii_case(name))
+    });
+    let single = ballot.allocations.is_empty() && ballot.ranking.is_empty();
+    let vote = match label(ballot.option).filter(|_| yes_no && single).as_deref() {
+        Some("yes") => Cw3Vote::Yes,
+        Some("no") => Cw3Vote::No,
+        Some("veto") => Cw3Vote::Veto,
+        _ => Cw3Vote::Abstain,
+    };
+    Cw3VoteInfo {
+        proposal_id,
+        voter: voter.into_string(),
+        vote,
+        weight: ballot.weight,
+    }
+}
+
+fn query_proposal(deps: Deps, env: Env, proposal_id: u64) -> StdResult<Cw3ProposalResponse> {
+    let poll_id = PROPOSALS.load(deps.storage, proposal_id)?;
+    proposal_response(deps, &env.block, proposal_id, poll_id)
+}
+
+fn query_list_proposals(
+    deps: Deps,
+    env: Env,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Cw3ProposalListResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    let proposals = PROPOSALS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.and_then(|(id, poll_id)| proposal_response(deps, &env.block, id, poll_id)))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(Cw3ProposalListResponse { proposals })
+}
+
+fn query_cw3_vote(
+    deps: Deps,
+    env: Env,
+    proposal_id: u64,
+    voter: String,
+) -> StdResult<Cw3VoteResponse> {
+    let poll_id = PROPOSALS.load(deps.storage, proposal_id)?;
+    let mut poll = POLL.load(deps.storage, poll_id.clone())?;
+    poll.catch_up(&env.block);
+    ensure_can_view_ballots(deps, &poll_id, &poll, None)?;
+    let options = poll_options(deps.storage, &poll_id, &poll)?;
+    let key = ballot_cursor(deps, &poll, voter)?;
+    let vote = BALLOT
+        .may_load(deps.storage, (key.clone(), poll_id))?
+        .map(|ballot| cw3_vote_info(proposal_id, key, ballot, &options));
+    Ok(Cw3VoteResponse { vote })
+}
+
+fn query_cw3_list_votes(
+    deps: Deps,
+    env: Env,
+    proposal_id: u64,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Cw3VoteListResponse> {
+    let poll_id = PROPOSALS.load(deps.storage, proposal_id)?;
+    let mut poll = POLL.load(deps.storage, poll_id.clone())?;
+    poll.catch_up(&env.block);
+    ensure_can_view_ballots(deps, &poll_id, &poll, None)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after
+        .map(|voter| ballot_cursor(deps, &poll, voter))
+        .transpose()?;
+    let options = poll_options(deps.storage, &poll_id, &poll)?;
+    let votes = ballots_page(deps.storage, &poll_id, start, limit)?
+        .into_iter()
+        .map(|(voter, ballot)| cw3_vote_info(proposal_id, voter, ballot, &options))
+        .collect();
+    Ok(Cw3VoteListResponse { votes })
+}
+
+/// Outside a proposal, any address that is not banned holds one plain vote.
+fn query_voter(
+    deps: Deps,
+    _env: Env,
+    address: String,
+    proposal_id: Option<u64>,
+) -> StdResult<Cw3VoterResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let weight = match proposal_id {
+        Some(proposal_id) => {
+            let poll_id = PROPOSALS.load(deps.storage, proposal_id)?;
+            let poll = POLL.load(deps.storage, poll_id.clone())?;
+            voter_weight(deps, &poll_id, &poll, &address)?
+        }
+        None if BANNED.has(deps.storage, address) => None,
+        None => Some(1),
+    };
+    Ok(Cw3VoterResponse { weight })
+}
+
+/// The weight `voter` votes with in a poll, resolved as `execute_vote` does
+/// but without claiming delegated weight; `None` if they cannot vote in it
+/// or their weight is only known once they pay or prove it.
+fn voter_weight(deps: Deps, poll_id: &str, poll: &Poll, voter: &Addr) -> StdResult<Option<u64>> {
+    if BANNED.has(deps.storage, voter.clone())
+        || (poll.electorate.is_some()
+            && !ALLOWED_VOTERS.has(deps.storage, (poll_id.to_string(), voter.clone())))
+        || ensure_min_balance(deps, poll, voter).is_err()
+    {
+        return Ok(None);
+    }
+    // a pseudonymous ballot can't be found from the voter's address
+    let key = (voter.clone(), poll_id.to_string());
+    if let Some(ballot) = BALLOT
+        .may_load(deps.storage, key)?
+        .filter(|_| !poll.pseudonymous)
+    {
+        return Ok(Some(ballot.weight));
+    }
+    // cumulative budgets and paid-in weight ignore overrides
+    let fixed_weight = match poll.mode {
+        VoteMode::Standard
+        | VoteMode::Ranked { .. }
+        | VoteMode::Snapshot { .. }
+        | VoteMode::Vesting { .. }
+        | VoteMode::Staking { .. } => {
+            WEIGHT_OVERRIDES.may_load(deps.storage, (poll_id.to_string(), voter.clone()))?
+        }
+        _ => None,
+    };
+    let weight = match (fixed_weight, &poll.mode) {
+        (Some(weight), _) => Ok(weight),
+        (None, _) if poll.merkle_root.is_some() => return Ok(None),
+        (None, VoteMode::Standard | VoteMode::Ranked { .. }) if poll.pseudonymous => Ok(1),
+        (None, VoteMode::Standard | VoteMode::Ranked { .. }) => {
+            Ok(delegation_units(deps.storage, poll_id, poll, voter)?.len() as u64)
+        }
+        (None, VoteMode::Snapshot { token }) => {
+            staked_at(deps.storage, token, voter, poll.snapshot_height)
+                .and_then(|stake| token_weight(poll, stake))
+        }
+        (None, VoteMode::Vesting { contract }) => {
+            vesting_grant(deps, contract, voter).and_then(|grant| token_weight(poll, grant))
+        }
+        (None, VoteMode::Staking { contract }) => {
+            staked_balance(deps, contract, voter, poll.snapshot_height)
+                .and_then(|stake| token_weight(poll, stake))
+        }
+        (
+            None,
+            VoteMode::Cumulative {
+                budget: VoteBudget::Points(points),
+            },
+        ) => to_weight(*points),
+        (
+            None,
+            VoteMode::Cumulative {
+                budget: VoteBudget::Stake { token },
+            },
+        ) => staked_at(deps.storage, token, voter, poll.snapshot_height).and_then(to_weight),
+        (
+            None,
+            VoteMode::Cumulative {
+                budget: VoteBudget::Snapshot {},
+            }
+            | VoteMode::Cw20Deposit { .. }
+            | VoteMode::Burn { .. }
+            | VoteMode::Conviction { .. },
+        ) => return Ok(None),
+    };
+    // split ballots are not capped
+    let cap = poll
+        .weight_cap()
+        .filter(|_| !matches!(poll.mode, VoteMode::Cumulative { .. }));
+    Ok(weight
+        .ok()
+        .map(|weight| cap.map_or(weight, |cap| weight.min(cap))))
+}
+
+fn query_config(deps: Deps, _env: Env) -> StdResult<ConfigResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    Ok(ConfigResponse { config })
+}
+
+fn query_all_poll(deps: Deps, env: Env) -> StdResult<AllPollResponse> {
+    let polls = POLL
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+            item.map(|(id, mut poll)| {
+                poll.catch_up(&env.block);
+                let results_hidden = poll.hides_results();
+                if results_hidden {
+                    hide_results(&mut poll);
+                }
+                PollEntry {
+                    id,
+                    poll,
+                    results_hidden,
+                }
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(AllPollResponse { polls })
+}
+
+fn query_poll_summaries(
+    deps: Deps,
+    env: Env,
+    start_after: Option<String>,
+    limit: Option<u32>,
+    status: Option<PollStatus>,
+) -> StdResult<PollSummariesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    let mut polls = vec![];
+    for item in POLL.range(deps.storage, start, None, Order::Ascending) {
+        let (id, mut poll) = item?;
+        poll.catch_up(&env.block);
+        if status.is_some_and(|status| status != poll.status) {
+            continue;
+        }
+        polls.push(PollSummary {
+            id,
+            question: poll.question,
+            status: poll.status,
+            end_time: poll.end_time,
+            total_votes: poll.voter_count,
+        });
+        if polls.len() == limit {
+            break;
+        }
+    }
+    Ok(PollSummariesResponse { polls })
+}
+
+fn query_polls_by_attribute(
+    deps: Deps,
+    env: Env,
+    key: String,
+    value: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<PollSummariesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    let polls = POLL_ATTRIBUTES
+        .prefix((key, value))
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|id| {
+            let id = id?;
+            let mut poll = POLL.load(deps.storage, id.clone())?;
+            poll.catch_up(&env.block);
+            Ok(PollSummary {
+                id,
+                question: poll.question,
+                status: poll.status,
+                end_time: poll.end_time,
+                total_votes: poll.voter_count,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(PollSummariesResponse { polls })
+}
+
+fn query_poll(
+    deps: Deps,
+    env: Env,
+    poll_id: String,
+    locale: Option<String>,
+    viewer: Option<String>,
+) -> StdResult<PollResponse> {
+    let mut poll = POLL.may_load(deps.storage, poll_id.clone())?;
+    let mut results_hidden = false;
+    if let Some(poll) = poll.as_mut() {
+        poll.catch_up(&env.block);
+        // translations follow creation order
+        if let Some(locale) = locale {
+            poll.localize(&locale);
+        }
+        arrange_options(poll, &poll_id, &env.block);
+        if !can_view_results(deps, &poll_id, poll, viewer)? {
+            hide_results(poll);
+            results_hidden = true;
+        }
+    }
+    Ok(PollResponse {
+        poll,
+        results_hidden,
+    })
+}
+
+/// Whether `viewer` may see the poll's tallies and ballots.
+fn can_view_results(
+    deps: Deps,
+    poll_id: &str,
+    poll: &Poll,
+    viewer: Option<String>,
+) -> StdResult<bool> {
+    if !poll.hides_results() {
+        return Ok(true);
+    }
+    if poll.awaits_quorum() {
+        return Ok(false);
+    }
+    match viewer {
+        Some(viewer) => {
+            let viewer = ballot_cursor(deps, poll, viewer)?;
+            Ok(BALLOT.has(deps.storage, (viewer, poll_id.to_string())))
+        }
+        None => Ok(false),
+    }
+}
+
+fn hide_results(poll: &mut Poll) {
+    for option in poll.options.iter_mut() {
+        option.votes = 0;
+    }
+}
+
+fn query_verify_content(
+    deps: Deps,
+    _env: Env,
+    poll_id: String,
+    hash: HexBinary,
+) -> StdResult<VerifyContentResponse> {
+    let poll = POLL.load(deps.storage, poll_id)?;
+    Ok(VerifyContentResponse {
+        matches: poll.content_hash == Some(hash),
+    })
+}
+
+fn query_vote(
+    deps: Deps,
+    env: Env,
+    poll_id: String,
+    address: String,
+    proof: Option<OwnerProof>,
+    viewer: Option<String>,
+) -> StdResult<VoteResponse> {
+    let mut poll = POLL.load(deps.storage, poll_id.clone())?;
+    poll.catch_up(&env.block);
+    let key = queried_ballot_key(deps, &env, &poll_id, &poll, address, proof, viewer)?;
+    let vote = BALLOT.may_load(deps.storage, key)?;
+    Ok(VoteResponse { vote })
+}
+
+/// `BALLOT` key for a voter named in a query, once `viewer` may see ballots.
+/// A pseudonymous poll is only searched by address with the owner's `proof`,
+/// and otherwise by the hashes `ListVotes` shows, so nobody can test a
+/// guessed address for a ballot.
+fn queried_ballot_key(
+    deps: Deps,
+    env: &Env,
+    poll_id: &str,
+    poll: &Poll,
+    voter: String,
+    proof: Option<OwnerProof>,
+    viewer: Option<String>,
+) -> StdResult<(Addr, String)> {
+    let Some(proof) = proof.filter(|_| poll.pseudonymous) else {
+        ensure_can_view_ballots(deps, poll_id, poll, viewer)?;
+        return Ok((ballot_cursor(deps, poll, voter)?, poll_id.to_string()));
+    };
+    let voter = deps.api.addr_validate(&voter)?;
+    let payload = to_vec(&BallotLookup {
+        chain_id: env.block.chain_id.clone(),
+        contract: env.contract.address.to_string(),
+        poll_id: poll_id.to_string(),
+        voter: voter.to_string(),
+    })?;
+    let hash = Sha256::digest(payload);
+    let owner = VOTING_KEYS.may_load(deps.storage, &proof.pubkey)?;
+    let valid = owner.as_ref() == Some(&voter)
+        && deps
+            .api
+            .secp256k1_verify(&hash, &proof.signature, &proof.pubkey)
+            .unwrap_or(false);
+    if !valid {
+        return Err(StdError::generic_err("Invalid proof of address ownership"));
+    }
+    let key = ballot_key(deps.storage, poll_id, poll, &voter)?;
+    // the proven owner views as the voter they are
+    let viewer = viewer.or_else(|| Some(key.0.to_string()));
+    ensure_can_view_ballots(deps, poll_id, poll, viewer)?;
+    Ok(key)
+}
+
+fn query_ballot(
+    deps: Deps,
+    env: Env,
+    poll_id: String,
+    address: String,
+    proof: Option<OwnerProof>,
+    viewer: Option<String>,
+) -> StdResult<BallotResponse> {
+    let mut poll = POLL.load(deps.storage, poll_id.clone())?;
+    poll.catch_up(&env.block);
+    let key = queried_ballot_key(deps, &env, &poll_id, &poll, address, proof, viewer)?;
+    let Some(ballot) = BALLOT.may_load(deps.storage, key)? else {
+        return Ok(BallotResponse { ballot: None });
+    };
+    let options = poll_options(deps.storage, &poll_id, &poll)?;
+    let share = |option: u8, weight: u64| BallotShare {
+        option,
+        label: options
+            .iter()
+            .find(|candidate| candidate.id == option)
+            .map(|candidate| candidate.label.clone())
+            .unwrap_or_default(),
+        weight,
+    };
+    let shares = ballot
+        .shares()
+        .into_iter()
+        .map(|(option, weight)| share(option, weight))
+        .collect();
+    let ranking = ballot
+        .ranking
+        .iter()
+        .enumerate()
+        .map(|(rank, option)| share(*option, if rank == 0 { ballot.weight } else { 0 }))
+        .collect();
+    Ok(BallotResponse {
+        ballot: Some(BallotDetail {
+            weight: ballot.weight,
+            deposit: ballot.deposit,
+            voted_at: ballot.voted_at,
+            memo: ballot.memo,
+            shares,
+            ranking,
+        }),
+    })
+}
+
+fn query_creator_dashboard(
+    deps: Deps,
+    env: Env,
+    address: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<CreatorDashboardResponse> {
+    let creator = deps.api.addr_validate(&address)?;
+    let config = CONFIG.load(deps.storage)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    let poll_ids = CREATOR_POLLS
+        .prefix(creator)
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+    let mut dashboard = CreatorDashboardResponse {
+        open_polls: vec![],
+        finalized_polls: vec![],
+        deposits: vec![],
+        pending_actions: vec![],
+    };
+    for poll_id in poll_ids {
+        let mut poll = POLL.load(deps.storage, poll_id.clone())?;
+        poll.catch_up(&env.block);
+        if poll.creation_deposit.is_some() || poll.cw20_creation_deposit.is_some() {
+            dashboard.deposits.push(HeldDeposit {
+                poll_id: poll_id.clone(),
+                native: poll.creation_deposit.clone(),
+                cw20: poll.cw20_creation_deposit.clone(),
+            });
+        }
+        if poll.pending_suggestions > 0 {
+            dashboard
+                .pending_actions
+                .push(PendingAction::ReviewSuggestions {
+                    poll_id: poll_id.clone(),
+                    count: poll.pending_suggestions.into(),
+                });
+        }
+        match poll.status {
+            PollStatus::Draft | PollStatus::Open | PollStatus::Frozen => {
+                dashboard.open_polls.push(PollSummary {
+                    id: poll_id,
+                    question: poll.question,
+                    status: poll.status,
+                    end_time: poll.end_time,
+                    total_votes: poll.voter_count,
+                });
+            }
+            PollStatus::Closed => {
+                let veto_ends = poll
+                    .closed_at
+                    .unwrap_or(env.block.time)
+                    .plus_seconds(config.veto_period);
+                if env.block.time >= veto_ends {
+                    dashboard
+                        .pending_actions
+                        .push(PendingAction::Finalize { poll_id });
+                }
+            }
+            _ => {
+                let Some(result) = RESULTS.may_load(deps.storage, poll_id.clone())? else {
+                    continue;
+                };
+                if let Some(tie) = &result.tie {
+                    if tie.policy == TieBreakPolicy::AdminDecides && !tie.resolved {
+                        dashboard.pending_actions.push(PendingAction::ResolveTie {
+                            poll_id: poll_id.clone(),
+                            tied: tie.tied.clone(),
+                        });
+                    }
+                }
+                dashboard.finalized_polls.push(FinalizedPoll {
+                    id: poll_id,
+                    question: poll.question,
+                    winner: result.winner,
+                    total: result.total,
+                    finalized_at: result.finalized_at,
+                });
+            }
+        }
+    }
+    dashboard
+        .finalized_polls
+        .sort_by_key(|poll| std::cmp::Reverse(poll.finalized_at));
+    Ok(dashboard)
+}
+
+fn query_list_votes(
+    deps: Deps,
+    env: Env,
+    poll_id: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+    viewer: Option<String>,
+) -> StdResult<ListVotesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let mut poll = POLL.load(deps.storage, poll_id.clone())?;
+    poll.catch_up(&env.block);
+    ensure_can_view_ballots(deps, &poll_id, &poll, viewer)?;
+    let start = start_after
+        .map(|voter| ballot_cursor(deps, &poll, voter))
+        .transpose()?;
+    let votes = ballots_page(deps.storage, &poll_id, start, limit)?
+        .into_iter()
+        .map(|(voter, ballot)| VoteEntry { voter, ballot })
+        .collect();
+    Ok(ListVotesResponse { votes })
+}
+
+fn query_voters_for_option(
+    deps: Deps,
+    env: Env,
+    poll_id: String,
+    option: u8,
+    start_after: Option<String>,
+    limit: Option<u32>,
+    viewer: Option<String>,
+) -> StdResult<VotersForOptionResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let mut poll = POLL.load(deps.storage, poll_id.clone())?;
+    poll.catch_up(&env.block);
+    ensure_can_view_ballots(deps, &poll_id, &poll, viewer)?;
+    let start = start_after
+        .map(|voter| ballot_cursor(deps, &poll, voter))
+        .transpose()?
+        .map(Bound::exclusive);
+    let voters = OPTION_VOTERS
+        .prefix((poll_id, option))
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(voter, weight)| OptionVoter { voter, weight }))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(VotersForOptionResponse { voters })
+}
+
+fn ensure_can_view_ballots(
+    deps: Deps,
+    poll_id: &str,
+    poll: &Poll,
+    viewer: Option<String>,
+) -> StdResult<()> {
+    if !can_view_results(deps, poll_id, poll, viewer)? {
+        return Err(StdError::generic_err(
+            "Ballots are only visible to voters until the poll closes",
+        ));
+    }
+    Ok(())
+}
+
+/// A voter named in a query: an address, or in a pseudonymous poll one of
+/// the hashes `ListVotes` shows.
+fn ballot_cursor(deps: Deps, poll: &Poll, voter: String) -> StdResult<Addr> {
+    if poll.pseudonymous {
+        Ok(Addr::unchecked(voter))
+    } else {
+        deps.api.addr_validate(&voter)
+    }
+}
+
+fn query_export_state(
+    deps: Deps,
+    _env: Env,
+    section: ExportSection,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<ExportStateResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let entries = match section {
+        ExportSection::Config {} => vec![ExportEntry::Config(CONFIG.load(deps.storage)?)],
+        ExportSection::Polls {} => POLL
+            .range(
+                deps.storage,
+                start_after.map(Bound::exclusive),
+                None,
+                Order::Ascending,
+            )
+            .take(limit)
+            .map(|item| {
+                let (poll_id, mut poll) = item?;
+                poll.options = poll_options(deps.storage, &poll_id, &poll)?;
+                Ok(ExportEntry::Poll { poll_id, poll })
+            })
+            .collect::<StdResult<_>>()?,
+        ExportSection::Results {} => RESULTS
+            .range(
+                deps.storage,
+                start_after.map(Bound::exclusive),
+                None,
+                Order::Ascending,
+            )
+            .take(limit)
+            .map(|item| item.map(|(poll_id, result)| ExportEntry::Result { poll_id, result }))
+            .collect::<StdResult<_>>()?,
+        ExportSection::Ballots { poll_id } => {
+            let poll = POLL.load(deps.storage, poll_id.clone())?;
+            let start = start_after
+                .map(|voter| ballot_cursor(deps, &poll, voter))
+                .transpose()?;
+            ballots_page(deps.storage, &poll_id, start, limit)?
+                .into_iter()
+                .map(|(voter, ballot)| ExportEntry::Ballot { voter, ballot })
+                .collect()
+        }
+        ExportSection::AllowedVoters { poll_id } => {
+            let page = voter_page(deps.storage, ALLOWED_VOTERS, &poll_id, start_after, limit)?;
+            records(&poll_id, page, |voter, ()| PollRecord::AllowedVoter {
+                voter,
+            })
+        }
+        ExportSection::WeightOverrides { poll_id } => {
+            let page = voter_page(deps.storage, WEIGHT_OVERRIDES, &poll_id, start_after, limit)?;
+            records(&poll_id, page, |voter, weight| PollRecord::WeightOverride {
+                voter,
+                weight,
+            })
+        }
+        ExportSection::LockedSince { poll_id } => {
+            let page = voter_page(deps.storage, LOCKED_SINCE, &poll_id, start_after, limit)?;
+            records(&poll_id, page, |voter, since| PollRecord::LockedSince {
+                voter,
+                since,
+            })
+        }
+        ExportSection::RewardPools {} => REWARD_POOLS
+            .range(
+                deps.storage,
+                start_after.map(Bound::exclusive),
+                None,
+                Order::Ascending,
+            )
+            .take(limit)
+            .map(|item| {
+                item.map(|(poll_id, pool)| ExportEntry::Record {
+                    poll_id,
+                    record: PollRecord::RewardPool { pool },
+                })
+            })
+            .collect::<StdResult<_>>()?,
+        ExportSection::RewardClaims { poll_id } => {
+            let page = voter_page(deps.storage, REWARD_CLAIMS, &poll_id, start_after, limit)?;
+            records(&poll_id, page, |voter, amount| PollRecord::RewardClaim {
+                voter,
+                amount,
+            })
+        }
+        ExportSection::SatelliteTallies { poll_id } => {
+            let page = SATELLITE_TALLIES
+                .prefix(poll_id.clone())
+                .range(
+                    deps.storage,
+                    start_after.map(Bound::exclusive),
+                    None,
+                    Order::Ascending,
+                )
+                .take(limit)
+                .collect::<StdResult<Vec<_>>>()?;
+            records(&poll_id, page, |channel, tally| {
+                PollRecord::SatelliteTally { channel, tally }
+            })
+        }
+    };
+    Ok(ExportStateResponse {
+        format: EXPORT_FORMAT,
+        contract_version: get_contract_version(deps.storage)?.version,
+        entries,
+    })
+}
+
+/// A page of one poll's entries in a map keyed by `(poll_id, voter)`.
+fn voter_page<T: Serialize + DeserializeOwned>(
+    storage: &dyn Storage,
+    map: Map<(String, Addr), T>,
+    poll_id: &str,
+    start_after: Option<String>,
+    limit: usize,
+) -> StdResult<Vec<(Addr, T)>> {
+    // only a storage cursor, which is a voter hash for pseudonymous ballots
+    let start = start_after.map(|voter| Bound::exclusive(Addr::unchecked(voter)));
+    map.prefix(poll_id.to_string())
+        .range(storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect()
+}
+
+fn records<K, T>(
+    poll_id: &str,
+    page: Vec<(K, T)>,
+    record: impl Fn(K, T) -> PollRecord,
+) -> Vec<ExportEntry> {
+    page.into_iter()
+        .map(|(key, value)| ExportEntry::Record {
+            poll_id: poll_id.to_string(),
+            record: record(key, value),
+        })
+        .collect()
+}
+
+fn query_export_ballots(
+    deps: Deps,
+    _env: Env,
+    start_after: Option<(String, String)>,
+    limit: Option<u32>,
+) -> StdResult<ExportBallotsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    // only a storage cursor, which is a voter hash for pseudonymous ballots
+    let start =
+        start_after.map(|(voter, poll_id)| Bound::exclusive((Addr::unchecked(voter), poll_id)));
+    let ballots = BALLOT
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            item.map(|((voter, poll_id), ballot)| BallotExport {
+                voter,
+                poll_id,
+                ballot,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(ExportBallotsResponse { ballots })
+}
+
+fn query_comments(
+    deps: Deps,
+    _env: Env,
+    poll_id: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<CommentsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    let comments = COMMENTS
+        .prefix(poll_id)
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, comment)| comment))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(CommentsResponse { comments })
+}
+
+fn query_options(
+    deps: Deps,
+    env: Env,
+    poll_id: String,
+    start_after: Option<u8>,
+    limit: Option<u32>,
+    viewer: Option<String>,
+) -> StdResult<OptionsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let mut poll = POLL.load(deps.storage, poll_id.clone())?;
+    poll.catch_up(&env.block);
+    arrange_options(&mut poll, &poll_id, &env.block);
+    let mut options = match poll.option_count {
+        // inline options page in display order, from after `start_after`
+        0 => {
+            let skip = start_after.map_or(0, |start| {
+                poll.options
+                    .iter()
+                    .position(|option| option.id == start)
+                    .map_or(poll.options.len(), |position| position + 1)
+            });
+            poll.options
+                .iter()
+                .skip(skip)
+                .take(limit)
+                .cloned()
+                .collect()
+        }
+        _ => OPTIONS
+            .prefix(poll_id.clone())
+            .range(
+                deps.storage,
+                start_after.map(Bound::exclusive),
+                None,
+                Order::Ascending,
+            )
+            .take(limit)
+            .map(|item| item.map(|(_, option)| option))
+            .collect::<StdResult<Vec<_>>>()?,
+    };
+    let results_hidden = !can_view_results(deps, &poll_id, &poll, viewer)?;
+    if results_hidden {
+        for option in options.iter_mut() {
+            option.votes = 0;
+        }
+    }
+    Ok(OptionsResponse {
+        options,
+        results_hidden,
+    })
+}
+
+fn query_suggestions(
+    deps: Deps,
+    _env: Env,
+    poll_id: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<SuggestionsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    let suggestions = SUGGESTIONS
+        .prefix(poll_id)
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, suggestion)| suggestion))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(SuggestionsResponse { suggestions })
+}
+
+fn query_chain_tallies(deps: Deps, _env: Env, poll_id: String) -> StdResult<ChainTalliesResponse> {
+    let tallies = SATELLITE_TALLIES
+        .prefix(poll_id)
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(ChainTalliesResponse { tallies })
+}
+
+fn query_tally_at_height(
+    deps: Deps,
+    env: Env,
+    poll_id: String,
+    height: u64,
+) -> StdResult<TallyAtHeightResponse> {
+    let mut poll = POLL.load(deps.storage, poll_id.clone())?;
+    poll.catch_up(&env.block);
+    if !can_view_results(deps, &poll_id, &poll, None)? {
+        return Err(StdError::generic_err(
+            "Tallies are only visible to voters until the poll closes",
+        ));
+    }
+    let tallies = poll_options(deps.storage, &poll_id, &poll)?
+        .into_iter()
+        .map(|option| -> StdResult<_> {
+            let votes = TALLIES
+                .may_load_at_height(deps.storage, (poll_id.clone(), option.id), height)?
+                .unwrap_or_default();
+            Ok((option.id, votes))
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(TallyAtHeightResponse { height, tallies })
+}
+
+fn query_poll_history(
+    deps: Deps,
+    _env: Env,
+    poll_id: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<PollHistoryResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    let history = POLL_HISTORY
+        .prefix(poll_id)
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, record)| record))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(PollHistoryResponse { history })
+}
+
+fn query_admin_log(
+    deps: Deps,
+    _env: Env,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<AdminLogResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    let actions = ADMIN_LOG
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, action)| action))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(AdminLogResponse { actions })
+}
+
+fn query_final_result(deps: Deps, _env: Env, poll_id: String) -> StdResult<FinalResultResponse> {
+    let result = RESULTS.may_load(deps.storage, poll_id)?;
+    Ok(FinalResultResponse { result })
+}
+
+fn query_turnout_stats(deps: Deps, _env: Env) -> StdResult<TurnoutStatsResponse> {
+    let stats = STATS.may_load(deps.storage)?.unwrap_or_default();
+    let average_turnout = if stats.finalized_polls == 0 {
+        Decimal::zero()
+    } else {
+        Decimal::from_ratio(stats.finalized_voters, stats.finalized_polls)
+    };
+    Ok(TurnoutStatsResponse {
+        finalized_polls: stats.finalized_polls,
+        average_turnout,
+        unique_voters: stats.unique_voters,
+        voter_histogram: stats.voter_histogram.to_vec(),
+    })
+}
+
+fn query_resolved_weight(
+    deps: Deps,
+    _env: Env,
+    address: String,
+    poll_id: String,
+) -> StdResult<ResolvedWeightResponse> {
+    let poll = POLL.load(deps.storage, poll_id.clone())?;
+    let address = ballot_cursor(deps, &poll, address)?;
+    let weight = BALLOT
+        .may_load(deps.storage, (address.clone(), poll_id.clone()))?
+        .map_or(0, |ballot| ballot.weight);
+    let delegators = REPRESENTED
+        .prefix(poll_id.clone())
+        .range(deps.storage, None, None, Order::Ascending)
+        .filter(|item| !matches!(item, Ok((_, representative)) if *representative != address))
+        .map(|item| item.map(|(delegator, _)| delegator))
+        .collect::<StdResult<Vec<_>>>()?;
+    let represented_by = REPRESENTED.may_load(deps.storage, (poll_id, address))?;
+    Ok(ResolvedWeightResponse {
+        weight,
+        delegators,
+        represented_by,
+    })
+}
+
+fn query_voter_info(deps: Deps, _env: Env, address: String) -> StdResult<VoterInfoResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let stats = voter_stats()
+        .may_load(deps.storage, address)?
+        .unwrap_or_default();
+    Ok(VoterInfoResponse {
+        first_vote: stats.first_vote,
+        votes_cast: stats.votes_cast,
+        polls_created: stats.polls_created,
+    })
+}
+
+fn query_top_voters(deps: Deps, _env: Env, limit: Option<u32>) -> StdResult<TopVotersResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let voters = voter_stats()
+        .idx
+        .votes_cast
+        .range(deps.storage, None, None, Order::Descending)
+        // creators who never voted come last
+        .take_while(|item| {
+            item.as_ref()
+                .map_or(true, |(_, stats)| stats.votes_cast > 0)
+        })
+        .take(limit)
+        .map(|item| {
+            item.map(|(address, stats)| TopVoter {
+                address,
+                votes_cast: stats.votes_cast,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(TopVotersResponse { voters })
+}
+
+fn query_category_admins(
+    deps: Deps,
+    _env: Env,
+    category: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<CategoryAdminsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?
+        .map(Bound::exclusive);
+    let admins = CATEGORY_ADMINS
+        .prefix(category)
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(CategoryAdminsResponse { admins })
+}
+
+fn query_weight_overrides(
+    deps: Deps,
+    _env: Env,
+    poll_id: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<WeightOverridesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?
+        .map(Bound::exclusive);
+    let overrides = WEIGHT_OVERRIDES
+        .prefix(poll_id)
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(WeightOverridesResponse { overrides })
+}
+
+fn query_creators(
+    deps: Deps,
+    _env: Env,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<CreatorsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?
+        .map(Bound::exclusive);
+    let creators = CREATORS
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(CreatorsResponse { creators })
+}
+
+fn query_banned_voters(
+    deps: Deps,
+    _env: Env,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<BannedVotersResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?
+        .map(Bound::exclusive);
+    let voters = BANNED
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(BannedVotersResponse { voters })
+}
+
+fn query_reward_pool(deps: Deps, _env: Env, poll_id: String) -> StdResult<RewardPoolResponse> {
+    let pool = REWARD_POOLS.may_load(deps.storage, poll_id)?;
+    Ok(RewardPoolResponse { pool })
+}
+
+fn query_claimable_reward(
+    deps: Deps,
+    _env: Env,
+    poll_id: String,
+    address: String,
+) -> StdResult<ClaimableRewardResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let amount = match REWARD_POOLS.may_load(deps.storage, poll_id.clone())? {
+        Some(mut pool) => claim_share(deps.storage, &poll_id, &address, &mut pool)?,
+        None => Uint128::zero(),
+    };
+    Ok(ClaimableRewardResponse { amount })
+}
+
+fn query_stake(
+    deps: Deps,
+    _env: Env,
+    token: String,
+    address: String,
+    height: Option<u64>,
+) -> StdResult<StakeResponse> {
+    let key = (
+        deps.api.addr_validate(&token)?,
+        deps.api.addr_validate(&address)?,
+    );
+    let amount = match height {
+        Some(height) => STAKES.may_load_at_height(deps.storage, key, height)?,
+        None => STAKES.may_load(deps.storage, key)?,
+    }
+    .unwrap_or_default();
+    Ok(StakeResponse { amount })
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_schema::QueryResponses;
+    use std::collections::BTreeMap;
+
+    use crate::contract::{
+        execute, execute_create_poll, instantiate, query, query_config, query_poll, reply, sudo,
+    };
+    use crate::msg::{
+        AdminLogResponse, AllPollResponse, AllocationResponse, BallotExport, BallotLookup,
+        BallotResponse, BallotShare, BannedVotersResponse, CategoryAdminsResponse,
+        ClaimableRewardResponse, CommentsResponse, ConfigResponse, ContractQueryMsg,
+        CreatorDashboardResponse, CreatorsResponse, Cw3ProposalListResponse, Cw3ProposalResponse,
+        Cw3QueryMsg, Cw3Status, Cw3ThresholdResponse, Cw3Vote, Cw3VoteInfo, Cw3VoteListResponse,
+        Cw3VoteResponse, Cw3VoterResponse, DaoQueryMsg, ExecuteMsg, ExportBallotsResponse,
+        ExportEntry, ExportSection, ExportStateResponse, FinalResultResponse, FinalizedPoll,
+        InfoResponse, InstantiateMsg, KeyRegistration, ListVotesResponse, MerkleProof, NewPoll,
+        NoisCallback, NoisProxyMsg, NotifyMsg, OptionMetadata, OptionTally, OptionsResponse,
+        OwnerProof, PendingAction, PollHistoryResponse, PollImport, PollRecord, PollResponse,
+        PollSummariesResponse, PollSummary, QueryMsg, ReceiptMetadata, ReceiptMintMsg, ReceiveMsg,
+        RecordExport, ResolvedWeightResponse, SignedVote, StakedBalanceAtHeightResponse,
+        StakingQueryMsg, SudoMsg, SuggestionsResponse, TallyAtHeightResponse, TallyDelta, TopVoter,
+        TopVotersResponse, TotalPowerAtHeightResponse, TotalStakedAtHeightResponse,
+        TurnoutStatsResponse, VerifyContentResponse, VestingQueryMsg, VotePayload, VoteResponse,
+        VoterInfoResponse, VotersForOptionResponse, VotingPowerAtHeightResponse,
+        WeightOverridesResponse, EXPORT_FORMAT,
+    };
+    use crate::state::{
+        voter_stats, Comment, LocalizedText, Poll, PollChange, PollOption, PollResult, PollStatus,
+        Quorum, RankedMethod, RevoteWindow, RewardDistribution, RunoffRound, SuggestionStatus,
+        TieBreakPolicy, TieResolution, VoteBudget, VoteMode, WeightDecay, WeightFormula, BALLOT,
+        OPTION_VOTERS, POLL, POLL_ENDS, RAFFLE_COUNTS, RAFFLE_ENTRIES, RESULTS, SEEN_VOTERS,
+        TALLIES, WEIGHT_OVERRIDES,
+    };
+    use crate::ContractError;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cosmwasm_std::{
+        attr, coin, coins, from_binary, to_binary, to_vec, Addr, BankMsg, Binary,
+        ContractInfoResponse, ContractResult, CosmosMsg, Decimal, Deps, Event, HexBinary, Order,
+        Reply, StdResult, SubMsg, SubMsgResult, SystemError, SystemResult, Timestamp, Uint128,
+        WasmMsg, WasmQuery,
+    };
+    use cw20::{BalanceResponse, Cw20Coin, Cw20ExecuteMsg, Cw20QueryMsg, Cw20ReceiveMsg};
+    use cw_utils::{Expiration, PaymentError};
+    use k256::ecdsa::signature::Signer;
+    use k256::ecdsa::{Signature, SigningKey};
+    use sha2::{Digest, Sha256};
+
+    fn labelled(options: &[PollOption]) -> Vec<(&str, u64)> {
+        options
+            .iter()
+            .map(|option| (option.label.as_str(), option.votes))
+            .collect()
+    }
+
+    #[test]
+    fn test_instantiate() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info("addr1", &[]);
+        let msg = InstantiateMsg {
+            admin: Some("addr1".to_string()),
+            ..Default::default()
+        };
+        let resp = instantiate(deps.as_mut(), env, info, msg).unwrap();
+        assert_eq!(
+            resp.attributes,
+            vec![
+                attr("action", "instantiate"),
+                attr("addr1".to_string(), "addr1".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_execute_create_poll() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info("addr1", &[]);
+        let msg = InstantiateMsg {
+            admin: Some("addr1".to_string()),
+            ..Default::default()
+        };
+        let resp = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        assert_eq!(
+            resp.attributes,
+            vec![
+                attr("action", "instantiate"),
+                attr("addr1".to_string(), "addr1".to_string())
+            ]
+        );
+        let poll_id = "1".to_string();
+        let question = "Should We Have a Meeting Today".to_string();
+        let options = vec![String::from("Yes"), String::from("No")];
+        let resp = execute_create_poll(
+            deps.as_mut(),
+            env,
+            info,
+            NewPoll {
+                poll_id,
+                question,
+                options,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(resp.attributes, vec![attr("action", "create poll")])
+    }
+
+    #[test]
+    fn test_execute_vote() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("addr1", &[]);
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            admin: Some("addr1".to_string()),
+            ..Default::default()
+        };
+        let resp = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        assert_eq!(
+            resp.attributes,
+            vec![
+                attr("action", "instantiate"),
+                attr("addr1".to_string(), "addr1".to_string())
+            ]
+        );
+
+        let poll_id = "1".to_string();
+        let question = "Should We Have a Meeting Today".to_string();
+        let options = vec![String::from("Yes"), String::from("No")];
+        let resp = execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            NewPoll {
+                poll_id,
+                question,
+                options,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(resp.attributes, vec![attr("action", "create poll")]);
+
+        let msg = ExecuteMsg::Vote {
+            poll_id: "1".to_string(),
+            vote: 1,
+            memo: None,
+            proof: None,
+        };
+        let resp = execute(deps.as_mut(), env, info, msg).unwrap();
+        assert_eq!(resp.attributes, vec![attr("action", "vote in poll")])
+    }
+
+    #[test]
+    fn test_query_poll() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("addr1", &[]);
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            admin: Some("addr1".to_string()),
+            ..Default::default()
+        };
+        let resp = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        assert_eq!(
+            resp.attributes,
+            vec![
+                attr("action", "instantiate"),
+                attr("addr1".to_string(), "addr1".to_string())
+            ]
+        );
+        let poll_id = "1".to_string();
+        let question = "Should We Have a Meeting Today".to_string();
+        let options = vec![String::from("Yes"), String::from("No")];
+        let resp = execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            info,
+            NewPoll {
+                poll_id,
+                question,
+                options,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(resp.attributes, vec![attr("action", "create poll")]);
+
+        let msg = QueryMsg::Poll {
+            poll_id: "1".to_string(),
+            locale: None,
+            viewer: None,
+        };
+
+        let resp = query(deps.as_ref(), env, msg.into()).unwrap();
+        let get_poll: PollResponse = from_binary(&resp).unwrap();
+        assert_eq!(
+            get_poll,
+            PollResponse {
+                poll: Some(Poll {
+                    admin: Addr::unchecked("addr1"),
+                    question: "Should We Have a Meeting Today".to_string(),
+                    options: vec![
+                        PollOption {
+                            id: 0,
+                            label: "Yes".to_string(),
+                            votes: 0,
+                            description: None,
+                            image_uri: None,
+                        },
+                        PollOption {
+                            id: 1,
+                            label: "No".to_string(),
+                            votes: 0,
+                            description: None,
+                            image_uri: None,
+                        },
+                    ],
+                    status: PollStatus::Open,
+                    start_time: mock_env().block.time,
+                    end_time: None,
+                    mode: VoteMode::Standard,
+                    reward_distribution: RewardDistribution::ProRata,
+                    snapshot_height: mock_env().block.height,
+                    tie_break: TieBreakPolicy::None,
+                    voter_count: 0,
+                    electorate: None,
+                    eligible_weight: None,
+                    cancel_reason: None,
+                    min_balance: None,
+                    merkle_root: None,
+                    decay: None,
+                    category: None,
+                    creation_deposit: None,
+                    cw20_creation_deposit: None,
+                    flags: 0,
+                    closed_at: None,
+                    content_hash: None,
+                    max_voters: None,
+                    min_voters: None,
+                    quorum: None,
+                    pseudonymous: false,
+                    default_locale: None,
+                    translations: BTreeMap::new(),
+                    voters_only_results: false,
+                    results_after_quorum: false,
+                    revote_window: None,
+                    open_options: false,
+                    satellite_channels: vec![],
+                    weight_fn: WeightFormula::Linear,
+                    max_weight_per_voter: None,
+                    raffle: false,
+                    win_threshold: None,
+                    attributes: vec![],
+                    display_order: vec![],
+                    shuffle_options: false,
+                    supersedes: None,
+                    superseded_by: None,
+                    option_count: 0,
+                    pending_suggestions: 0,
+                    ballot_weight: 0,
+                    pruned_ballots: 0,
+                }),
+                results_hidden: false,
+            }
+        );
+    }
+
+    #[test]
+    fn test_poll_summaries() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            admin: Some("admin".to_string()),
+            ..Default::default()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+        for poll_id in ["1", "2", "3"] {
+            let new_poll = NewPoll {
+                poll_id: poll_id.to_string(),
+                question: format!("Question {}", poll_id),
+                options: vec![String::from("Yes"), String::from("No")],
+                ..Default::default()
+            };
+            execute_create_poll(
+                deps.as_mut(),
+                env.clone(),
+                mock_info("creator", &[]),
+                new_poll,
+            )
+            .unwrap();
+        }
+        let msg = ExecuteMsg::Vote {
+            poll_id: "1".to_string(),
+            vote: 0,
+            memo: None,
+            proof: None,
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("voter", &[]), msg).unwrap();
+        let msg = ExecuteMsg::ClosePoll {
+            poll_id: "2".to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+
+        let summaries = |start_after: Option<&str>, status: Option<PollStatus>| {
+            let msg = QueryMsg::PollSummaries {
+                start_after: start_after.map(String::from),
+                limit: Some(1),
+                status,
+            };
+            let resp = query(deps.as_ref(), env.clone(), msg.into()).unwrap();
+            from_binary::<PollSummariesResponse>(&resp).unwrap().polls
+        };
+        assert_eq!(
+            summaries(None, None),
+            vec![PollSummary {
+                id: "1".to_string(),
+                question: "Question 1".to_string(),
+                status: PollStatus::Open,
+                end_time: None,
+                total_votes: 1,
+            }]
+        );
+        assert_eq!(summaries(Some("1"), None)[0].id, "2");
+        let open = summaries(Some("1"), Some(PollStatus::Open));
+        assert_eq!(open[0].id, "3");
+        assert!(summaries(Some("3"), None).is_empty());
+    }
+
+    #[test]
+    fn test_polls_by_attribute() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info("creator", &[]);
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            InstantiateMsg::default(),
+        )
+        .unwrap();
+        let tagged = |poll_id: &str, attributes: Vec<(&str, &str)>| NewPoll {
+            poll_id: poll_id.to_string(),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            attributes: attributes
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+            ..Default::default()
+        };
+        for (poll_id, thread) in [("1", "42"), ("2", "7"), ("3", "42")] {
+            let new_poll = tagged(poll_id, vec![("forum", thread), ("chain", "juno")]);
+            execute_create_poll(deps.as_mut(), env.clone(), info.clone(), new_poll).unwrap();
+        }
+
+        let by_attribute = |deps: Deps, key: &str, value: &str| {
+            let msg = QueryMsg::PollsByAttribute {
+                key: key.to_string(),
+                value: value.to_string(),
+                start_after: None,
+                limit: None,
+            };
+            let resp: PollSummariesResponse =
+                from_binary(&query(deps, env.clone(), msg.into()).unwrap()).unwrap();
+            resp.polls
+                .into_iter()
+                .map(|poll| poll.id)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(by_attribute(deps.as_ref(), "forum", "42"), vec!["1", "3"]);
+        assert_eq!(by_attribute(deps.as_ref(), "chain", "juno").len(), 3);
+
+        let long_value = "x".repeat(101);
+        for attributes in [
+            vec![("forum", "1"), ("forum", "2")],
+            vec![("", "1")],
+            vec![("forum", long_value.as_str())],
+        ] {
+            let err = execute_create_poll(
+                deps.as_mut(),
+                env.clone(),
+                info.clone(),
+                tagged("4", attributes),
+            )
+            .unwrap_err();
+            assert!(matches!(err, ContractError::InvalidAttributes { .. }));
+        }
+    }
+
+    #[test]
+    fn test_supersede_poll() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info("creator", &[]);
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            InstantiateMsg::default(),
+        )
+        .unwrap();
+        let new_poll = |poll_id: &str, supersedes: Option<&str>| NewPoll {
+            poll_id: poll_id.to_string(),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            supersedes: supersedes.map(String::from),
+            ..Default::default()
+        };
+        execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            new_poll("1", None),
+        )
+        .unwrap();
+
+        let err = execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("stranger", &[]),
+            new_poll("2", Some("1")),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let msg = new_poll("2", Some("1"));
+        execute_create_poll(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let poll = |deps: Deps, poll_id: &str| {
+            let msg = QueryMsg::Poll {
+                poll_id: poll_id.to_string(),
+                locale: None,
+                viewer: None,
+            };
+            let resp: PollResponse =
+                from_binary(&query(deps, env.clone(), msg.into()).unwrap()).unwrap();
+            resp.poll.unwrap()
+        };
+        let old = poll(deps.as_ref(), "1");
+        assert_eq!(old.status, PollStatus::Closed);
+        assert_eq!(old.superseded_by, Some("2".to_string()));
+        assert_eq!(poll(deps.as_ref(), "2").supersedes, Some("1".to_string()));
+
+        // a poll is superseded once
+        let err = execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            new_poll("3", Some("1")),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidSupersede {}));
+    }
+
+    #[test]
+    fn test_creator_dashboard() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let info = mock_info("creator", &[]);
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            InstantiateMsg::default(),
+        )
+        .unwrap();
+        for (poll_id, creator) in [
+            ("1", "creator"),
+            ("2", "creator"),
+            ("3", "creator"),
+            ("4", "other"),
+        ] {
+            let new_poll = NewPoll {
+                poll_id: poll_id.to_string(),
+                question: format!("Question {}", poll_id),
+                options: vec![String::from("Yes"), String::from("No")],
+                open_options: true,
+                tie_break: Some(TieBreakPolicy::AdminDecides),
+                ..Default::default()
+            };
+            execute_create_poll(
+                deps.as_mut(),
+                env.clone(),
+                mock_info(creator, &[]),
+                new_poll,
+            )
+            .unwrap();
+        }
+        let msg = ExecuteMsg::SuggestOption {
+            poll_id: "1".to_string(),
+            option: "Maybe".to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("voter", &[]), msg).unwrap();
+        // poll 3 ties and waits on the creator to break it
+        for (voter, vote) in [("voter1", 0), ("voter2", 1)] {
+            let msg = ExecuteMsg::Vote {
+                poll_id: "3".to_string(),
+                vote,
+                memo: None,
+                proof: None,
+            };
+            execute(deps.as_mut(), env.clone(), mock_info(voter, &[]), msg).unwrap();
+        }
+        for poll_id in ["2", "3"] {
+            let msg = ExecuteMsg::ClosePoll {
+                poll_id: poll_id.to_string(),
+            };
+            execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        }
+        env.block.time = env.block.time.plus_seconds(10);
+        let msg = ExecuteMsg::FinalizePoll {
+            poll_id: "3".to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        let msg = QueryMsg::CreatorDashboard {
+            address: "creator".to_string(),
+            start_after: None,
+            limit: None,
+        };
+        let dashboard: CreatorDashboardResponse =
+            from_binary(&query(deps.as_ref(), env.clone(), msg.into()).unwrap()).unwrap();
+        let open: Vec<_> = dashboard
+            .open_polls
+            .iter()
+            .map(|poll| poll.id.as_str())
+            .collect();
+        assert_eq!(open, vec!["1"]);
+        assert_eq!(
+            dashboard.finalized_polls,
+            vec![FinalizedPoll {
+                id: "3".to_string(),
+                question: "Question 3".to_string(),
+                winner: None,
+                total: 2,
+                finalized_at: env.block.time,
+            }]
+        );
+        assert!(dashboard.deposits.is_empty());
+        assert_eq!(
+            dashboard.pending_actions,
+            vec![
+                PendingAction::ReviewSuggestions {
+                    poll_id: "1".to_string(),
+                    count: 1,
+                },
+                PendingAction::Finalize {
+                    poll_id: "2".to_string(),
+                },
+                PendingAction::ResolveTie {
+                    poll_id: "3".to_string(),
+                    tied: vec![0, 1],
+                },
+            ]
+        );
+
+        // a reviewed suggestion no longer waits, and pages pick up after `start_after`
+        let msg = ExecuteMsg::ReviewSuggestion {
+            poll_id: "1".to_string(),
+            suggestion_id: 1,
+            approve: false,
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+        let msg = QueryMsg::CreatorDashboard {
+            address: "creator".to_string(),
+            start_after: Some("1".to_string()),
+            limit: Some(1),
+        };
+        let dashboard: CreatorDashboardResponse =
+            from_binary(&query(deps.as_ref(), env.clone(), msg.into()).unwrap()).unwrap();
+        assert!(dashboard.open_polls.is_empty());
+        assert!(dashboard.finalized_polls.is_empty());
+        assert_eq!(
+            dashboard.pending_actions,
+            vec![PendingAction::Finalize {
+                poll_id: "2".to_string(),
+            }]
+        );
+        let poll = POLL.load(deps.as_ref().storage, "1".to_string()).unwrap();
+        assert_eq!(poll.pending_suggestions, 0);
+    }
+
+    #[test]
+    fn test_typed_queries() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info("creator", &[]);
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            InstantiateMsg::default(),
+        )
+        .unwrap();
+        let new_poll = NewPoll {
+            poll_id: "1".to_string(),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            ..Default::default()
+        };
+        execute_create_poll(deps.as_mut(), env.clone(), info, new_poll).unwrap();
+
+        // handlers hand back the response the entry point serializes
+        let typed = query_poll(deps.as_ref(), env.clone(), "1".to_string(), None, None).unwrap();
+        let msg = QueryMsg::Poll {
+            poll_id: "1".to_string(),
+            locale: None,
+            viewer: None,
+        };
+        let raw: PollResponse =
+            from_binary(&query(deps.as_ref(), env.clone(), msg.into()).unwrap()).unwrap();
+        assert_eq!(typed, raw);
+        let config = query_config(deps.as_ref(), env).unwrap();
+        assert_eq!(config.config.admins, vec![Addr::unchecked("creator")]);
+
+        // every query names its response in the schema
+        let schemas = QueryMsg::response_schemas().unwrap();
+        for query in ["poll", "poll_summaries", "tally_at_height", "stake"] {
+            assert!(schemas.contains_key(query));
+        }
+    }
+
+    #[test]
+    fn test_tally_at_height() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let msg = InstantiateMsg {
+            admin: Some("admin".to_string()),
+            ..Default::default()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+        let new_poll = NewPoll {
+            poll_id: "1".to_string(),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            ..Default::default()
+        };
+        execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            new_poll,
+        )
+        .unwrap();
+
+        let start = env.block.height;
+        for (voter, vote) in [("voter1", 0), ("voter2", 1), ("voter1", 1)] {
+            env.block.height += 1;
+            let msg = ExecuteMsg::Vote {
+                poll_id: "1".to_string(),
+                vote,
+                memo: None,
+                proof: None,
+            };
+            execute(deps.as_mut(), env.clone(), mock_info(voter, &[]), msg).unwrap();
+        }
+
+        let tally_at = |height: u64| {
+            let msg = QueryMsg::TallyAtHeight {
+                poll_id: "1".to_string(),
+                height,
+            };
+            let resp = query(deps.as_ref(), env.clone(), msg.into()).unwrap();
+            from_binary::<TallyAtHeightResponse>(&resp).unwrap().tallies
+        };
+        assert_eq!(tally_at(start + 1), vec![(0, 0), (1, 0)]);
+        assert_eq!(tally_at(start + 2), vec![(0, 1), (1, 0)]);
+        assert_eq!(tally_at(start + 3), vec![(0, 1), (1, 1)]);
+        assert_eq!(tally_at(start + 4), vec![(0, 0), (1, 2)]);
+    }
+
+    #[test]
+    fn test_moderator_close_poll() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            admin: Some("admin".to_string()),
+            ..Default::default()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+        execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            NewPoll {
+                poll_id: "1".to_string(),
+                question: "Should We Have a Meeting Today".to_string(),
+                options: vec![String::from("Yes"), String::from("No")],
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let close = ExecuteMsg::ClosePoll {
+            poll_id: "1".to_string(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("mod", &[]),
+            close.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        // moderators cannot manage other moderators
+        let msg = ExecuteMsg::AddModerator {
+            address: "mod".to_string(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("mod", &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+
+        let resp = execute(deps.as_mut(), env.clone(), mock_info("mod", &[]), close).unwrap();
+        assert_eq!(
+            resp.attributes,
+            vec![attr("action", "close poll"), attr("poll_id", "1")]
+        );
+
+        let msg = ExecuteMsg::Vote {
+            poll_id: "1".to_string(),
+            vote: 0,
+            memo: None,
+            proof: None,
+        };
+        let err = execute(deps.as_mut(), env, mock_info("voter", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::PollNotOpen {}));
+    }
+
+    #[test]
+    fn test_add_and_remove_admin() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            admin: Some("admin1".to_string()),
+            ..Default::default()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("admin1", &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::RemoveAdmin {
+            address: "admin1".to_string(),
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info("admin1", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::CannotRemoveLastAdmin {}));
+
+        let msg = ExecuteMsg::AddAdmin {
+            address: "admin2".to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("admin1", &[]), msg).unwrap();
+
+        // the new admin has full authority, including removing the original one
+        let msg = ExecuteMsg::RemoveAdmin {
+            address: "admin1".to_string(),
+        };
+        let resp = execute(deps.as_mut(), env.clone(), mock_info("admin2", &[]), msg).unwrap();
+        assert_eq!(
+            resp.attributes,
+            vec![attr("action", "remove admin"), attr("admin", "admin1")]
+        );
+
+        let msg = ExecuteMsg::AddModerator {
+            address: "mod".to_string(),
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info("admin1", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        let resp = query(deps.as_ref(), env, QueryMsg::Config {}.into()).unwrap();
+        let config: ConfigResponse = from_binary(&resp).unwrap();
+        assert_eq!(config.config.admins, vec![Addr::unchecked("admin2")]);
+    }
+
+    #[test]
+    fn test_restricted_creation() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            admin: Some("admin".to_string()),
+            restricted_creation: true,
+            creators: Some(vec!["council".to_string()]),
+            ..Default::default()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+        let poll = |poll_id: &str| NewPoll {
+            poll_id: poll_id.to_string(),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            ..Default::default()
+        };
+
+        execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            poll("1"),
+        )
+        .unwrap();
+        execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("council", &[]),
+            poll("2"),
+        )
+        .unwrap();
+        let err = execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("alice", &[]),
+            poll("3"),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::CreationRestricted {}));
+
+        let add = ExecuteMsg::AddCreator {
+            address: "alice".to_string(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("alice", &[]),
+            add.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            add.clone(),
+        )
+        .unwrap();
+        let err = execute(deps.as_mut(), env.clone(), mock_info("admin", &[]), add).unwrap_err();
+        assert!(matches!(err, ContractError::AlreadyCreator {}));
+        execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("alice", &[]),
+            poll("3"),
+        )
+        .unwrap();
+
+        let msg = QueryMsg::Creators {
+            start_after: None,
+            limit: None,
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg.into()).unwrap();
+        let creators: CreatorsResponse = from_binary(&resp).unwrap();
+        assert_eq!(
+            creators.creators,
+            vec![Addr::unchecked("alice"), Addr::unchecked("council")]
+        );
+
+        let remove = ExecuteMsg::RemoveCreator {
+            address: "alice".to_string(),
+        };
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            remove.clone(),
+        )
+        .unwrap();
+        let err = execute(deps.as_mut(), env.clone(), mock_info("admin", &[]), remove).unwrap_err();
+        assert!(matches!(err, ContractError::NotCreator {}));
+        let err = execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("alice", &[]),
+            poll("4"),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::CreationRestricted {}));
+
+        let msg = ExecuteMsg::UpdateConfig {
+            min_voting_period: None,
+            max_voting_period: None,
+            max_delegation_depth: None,
+            veto_period: None,
+            restricted_creation: Some(false),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+        execute_create_poll(deps.as_mut(), env, mock_info("alice", &[]), poll("4")).unwrap();
+    }
+
+    #[test]
+    fn test_banned_voter() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            admin: Some("admin".to_string()),
+            ..Default::default()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+        execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            NewPoll {
+                poll_id: "1".to_string(),
+                question: "Should We Have a Meeting Today".to_string(),
+                options: vec![String::from("Yes"), String::from("No")],
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        for address in ["troll2", "troll1"] {
+            let msg = ExecuteMsg::BanVoter {
+                address: address.to_string(),
+            };
+            execute(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+        }
+
+        let msg = ExecuteMsg::Vote {
+            poll_id: "1".to_string(),
+            vote: 0,
+            memo: None,
+            proof: None,
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("troll1", &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::VoterBanned {}));
+        let err = execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("troll2", &[]),
+            NewPoll {
+                poll_id: "2".to_string(),
+                question: "Spam?".to_string(),
+                options: vec![String::from("Yes")],
+                ..Default::default()
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::VoterBanned {}));
+
+        let msg = QueryMsg::BannedVoters {
+            start_after: None,
+            limit: Some(1),
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg.into()).unwrap();
+        let banned: BannedVotersResponse = from_binary(&resp).unwrap();
+        assert_eq!(banned.voters, vec![Addr::unchecked("troll1")]);
+        let msg = QueryMsg::BannedVoters {
+            start_after: Some("troll1".to_string()),
+            limit: None,
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg.into()).unwrap();
+        let banned: BannedVotersResponse = from_binary(&resp).unwrap();
+        assert_eq!(banned.voters, vec![Addr::unchecked("troll2")]);
+
+        let msg = ExecuteMsg::UnbanVoter {
+            address: "troll1".to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+        let msg = ExecuteMsg::Vote {
+            poll_id: "1".to_string(),
+            vote: 0,
+            memo: None,
+            proof: None,
+        };
+        execute(deps.as_mut(), env, mock_info("troll1", &[]), msg).unwrap();
+    }
+
+    #[test]
+    fn test_cw20_deposit_vote() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            admin: Some("admin".to_string()),
+            ..Default::default()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+        let new_poll = NewPoll {
+            poll_id: "1".to_string(),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            mode: Some(VoteMode::Cw20Deposit {
+                token: Addr::unchecked("token"),
+            }),
+            ..Default::default()
+        };
+        execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            new_poll,
+        )
+        .unwrap();
+
+        let msg = ExecuteMsg::Vote {
+            poll_id: "1".to_string(),
+            vote: 0,
+            memo: None,
+            proof: None,
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info("voter1", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::DepositRequired {}));
+
+        let deposit = |sender: &str, amount: u128, option: u8| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: sender.to_string(),
+                amount: Uint128::new(amount),
+                msg: to_binary(&ReceiveMsg::Vote {
+                    poll_id: "1".to_string(),
+                    option,
+                    memo: None,
+                })
+                .unwrap(),
+            })
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("other_token", &[]),
+            deposit("voter1", 100, 0),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidDepositToken {}));
+
+        let token = mock_info("token", &[]);
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            token.clone(),
+            deposit("voter1", 100, 0),
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            token.clone(),
+            deposit("voter2", 30, 0),
+        )
+        .unwrap();
+        // a second deposit adds to the ballot and moves it to the new option
+        execute(deps.as_mut(), env.clone(), token, deposit("voter2", 20, 1)).unwrap();
+
+        let msg = QueryMsg::Poll {
+            poll_id: "1".to_string(),
+            locale: None,
+            viewer: None,
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg.into()).unwrap();
+        let poll: PollResponse = from_binary(&resp).unwrap();
+        assert_eq!(
+            labelled(&poll.poll.unwrap().options),
+            vec![("Yes", 100), ("No", 50)]
+        );
+
+        // deposits stay escrowed while the poll is open, then each voter claims theirs
+        let claim = ExecuteMsg::ClaimDeposit {
+            poll_id: "1".to_string(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("voter1", &[]),
+            claim.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::DepositLocked {}));
+        let msg = ExecuteMsg::ClosePoll {
+            poll_id: "1".to_string(),
+        };
+        let resp = execute(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+        assert!(resp.messages.is_empty());
+        let refund = |recipient: &str, amount: u128| -> CosmosMsg {
+            WasmMsg::Execute {
+                contract_addr: "token".to_string(),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: recipient.to_string(),
+                    amount: Uint128::new(amount),
+                })
+                .unwrap(),
+                funds: vec![],
+            }
+            .into()
+        };
+        let resp = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("voter1", &[]),
+            claim.clone(),
+        )
+        .unwrap();
+        assert_eq!(resp.messages, vec![SubMsg::new(refund("voter1", 100))]);
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("voter1", &[]),
+            claim.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NoDepositToClaim {}));
+
+        // pruning returns the deposits still unclaimed
+        let msg = ExecuteMsg::ArchivePoll {
+            poll_id: "1".to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+        let msg = ExecuteMsg::PruneBallots {
+            poll_id: "1".to_string(),
+            limit: None,
+        };
+        let resp = execute(deps.as_mut(), env.clone(), mock_info("anyone", &[]), msg).unwrap();
+        assert_eq!(resp.messages, vec![SubMsg::new(refund("voter2", 50))]);
+        let err = execute(deps.as_mut(), env, mock_info("voter2", &[]), claim).unwrap_err();
+        assert!(matches!(err, ContractError::NoDepositToClaim {}));
+    }
+
+    #[test]
+    fn test_reward_distribution() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            admin: Some("admin".to_string()),
+            ..Default::default()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+        let new_poll = NewPoll {
+            poll_id: "1".to_string(),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            ..Default::default()
+        };
+        execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            new_poll,
+        )
+        .unwrap();
+
+        let fund = ExecuteMsg::FundRewards {
+            poll_id: "1".to_string(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &coins(100, "ujuno")),
+            fund.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &coins(100, "ujuno")),
+            fund,
+        )
+        .unwrap();
+
+        for voter in ["voter1", "voter2", "voter3"] {
+            let msg = ExecuteMsg::Vote {
+                poll_id: "1".to_string(),
+                vote: 0,
+                memo: None,
+                proof: None,
+            };
+            execute(deps.as_mut(), env.clone(), mock_info(voter, &[]), msg).unwrap();
+        }
+
+        let msg = ExecuteMsg::ClosePoll {
+            poll_id: "1".to_string(),
+        };
+        let resp = execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+        assert!(resp.messages.is_empty());
+        let msg = ExecuteMsg::FinalizePoll {
+            poll_id: "1".to_string(),
+        };
+        let resp = execute(deps.as_mut(), env.clone(), mock_info("anyone", &[]), msg).unwrap();
+        let send = |to: &str, amount: u128| -> CosmosMsg {
+            BankMsg::Send {
+                to_address: to.to_string(),
+                amount: coins(amount, "ujuno"),
+            }
+            .into()
+        };
+        assert!(resp.messages.is_empty());
+
+        // voters claim their share; the last claim takes the rounding dust
+        let msg = QueryMsg::ClaimableReward {
+            poll_id: "1".to_string(),
+            address: "voter2".to_string(),
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg.into()).unwrap();
+        let claimable: ClaimableRewardResponse = from_binary(&resp).unwrap();
+        assert_eq!(claimable.amount, Uint128::new(33));
+        let msg = ExecuteMsg::ClaimReward {
+            poll_id: "1".to_string(),
+        };
+        for (voter, amount) in [("voter2", 33), ("voter1", 33), ("voter3", 34)] {
+            let resp = execute(
+                deps.as_mut(),
+                env.clone(),
+                mock_info(voter, &[]),
+                msg.clone(),
+            )
+            .unwrap();
+            assert_eq!(resp.messages, vec![SubMsg::new(send(voter, amount))]);
+        }
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("voter2", &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NoRewardToClaim {}));
+        let err = execute(deps.as_mut(), env, mock_info("creator", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::NoRewardToClaim {}));
+    }
+
+    #[test]
+    fn test_burn_to_vote() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            admin: Some("admin".to_string()),
+            ..Default::default()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+        let new_poll = NewPoll {
+            poll_id: "1".to_string(),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            mode: Some(VoteMode::Burn {
+                denom: "ujuno".to_string(),
+            }),
+            ..Default::default()
+        };
+        execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            new_poll,
+        )
+        .unwrap();
+
+        let msg = ExecuteMsg::Vote {
+            poll_id: "1".to_string(),
+            vote: 0,
+            memo: None,
+            proof: None,
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("voter", &coins(10, "uatom")),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::BurnRequired { .. }));
+
+        let resp = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("voter", &coins(10, "ujuno")),
+            msg.clone(),
+        )
+        .unwrap();
+        assert_eq!(
+            resp.messages,
+            vec![SubMsg::new(BankMsg::Burn {
+                amount: coins(10, "ujuno")
+            })]
+        );
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("voter", &coins(5, "ujuno")),
+            msg,
+        )
+        .unwrap();
+
+        let msg = QueryMsg::Poll {
+            poll_id: "1".to_string(),
+            locale: None,
+            viewer: None,
+        };
+        let resp = query(deps.as_ref(), env, msg.into()).unwrap();
+        let poll: PollResponse = from_binary(&resp).unwrap();
+        assert_eq!(
+            labelled(&poll.poll.unwrap().options),
+            vec![("Yes", 15), ("No", 0)]
+        );
+    }
+
+    #[test]
+    fn test_nonpayable() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            admin: Some("admin".to_string()),
+            ..Default::default()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+        let new_poll = NewPoll {
+            poll_id: "1".to_string(),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            ..Default::default()
+        };
+        let funds = coins(10, "ujuno");
+        let err = execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &funds),
+            new_poll.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::Payment(PaymentError::NonPayable {})
+        ));
+        execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            new_poll,
+        )
+        .unwrap();
+
+        let vote = ExecuteMsg::Vote {
+            poll_id: "1".to_string(),
+            vote: 0,
+            memo: None,
+            proof: None,
+        };
+        let err =
+            execute(deps.as_mut(), env.clone(), mock_info("voter", &funds), vote).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::Payment(PaymentError::NonPayable {})
+        ));
+        let close = ExecuteMsg::ClosePoll {
+            poll_id: "1".to_string(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &funds),
+            close.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::Payment(PaymentError::NonPayable {})
+        ));
+        execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), close).unwrap();
+
+        // nothing to pay for without a randomness request
+        let finalize = ExecuteMsg::FinalizePoll {
+            poll_id: "1".to_string(),
+        };
+        let err = execute(deps.as_mut(), env, mock_info("creator", &funds), finalize).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::Payment(PaymentError::NonPayable {})
+        ));
+    }
+
+    #[test]
+    fn test_snapshot_weighted_vote() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let msg = InstantiateMsg {
+            admin: Some("admin".to_string()),
+            ..Default::default()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+
+        let stake = |staker: &str, amount: u128| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: staker.to_string(),
+                amount: Uint128::new(amount),
+                msg: to_binary(&ReceiveMsg::Stake {}).unwrap(),
+            })
+        };
+        let token = mock_info("token", &[]);
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            token.clone(),
+            stake("voter1", 40),
+        )
+        .unwrap();
+
+        env.block.height += 1;
+        let new_poll = NewPoll {
+            poll_id: "1".to_string(),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            mode: Some(VoteMode::Snapshot {
+                token: Addr::unchecked("token"),
+            }),
+            ..Default::default()
+        };
+        execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            new_poll,
+        )
+        .unwrap();
+
+        // stake bought after the poll opened does not count
+        env.block.height += 1;
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            token.clone(),
+            stake("voter1", 60),
+        )
+        .unwrap();
+        execute(deps.as_mut(), env.clone(), token, stake("voter2", 500)).unwrap();
+
+        let vote = ExecuteMsg::Vote {
+            poll_id: "1".to_string(),
+            vote: 0,
+            memo: None,
+            proof: None,
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("voter2", &[]),
+            vote.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NoVotingPower {}));
+        execute(deps.as_mut(), env.clone(), mock_info("voter1", &[]), vote).unwrap();
+
+        let msg = ExecuteMsg::Unstake {
+            token: "token".to_string(),
+            amount: Uint128::new(100),
+        };
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("voter1", &[]),
+            msg.clone(),
+        )
+        .unwrap();
+        let err = execute(deps.as_mut(), env.clone(), mock_info("voter1", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InsufficientStake {}));
+
+        let msg = QueryMsg::Poll {
+            poll_id: "1".to_string(),
+            locale: None,
+            viewer: None,
+        };
+        let resp = query(deps.as_ref(), env, msg.into()).unwrap();
+        let poll: PollResponse = from_binary(&resp).unwrap();
+        assert_eq!(
+            labelled(&poll.poll.unwrap().options),
+            vec![("Yes", 40), ("No", 0)]
+        );
+    }
+
+    #[test]
+    fn test_weight_overrides() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let msg = InstantiateMsg {
+            admin: Some("admin".to_string()),
+            ..Default::default()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+        let stake = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "holder".to_string(),
+            amount: Uint128::new(40),
+            msg: to_binary(&ReceiveMsg::Stake {}).unwrap(),
+        });
+        execute(deps.as_mut(), env.clone(), mock_info("token", &[]), stake).unwrap();
+
+        env.block.height += 1;
+        let new_poll = NewPoll {
+            poll_id: "1".to_string(),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            mode: Some(VoteMode::Snapshot {
+                token: Addr::unchecked("token"),
+            }),
+            weight_overrides: Some(vec![
+                ("council1".to_string(), 5),
+                ("council2".to_string(), 0),
+            ]),
+            ..Default::default()
+        };
+        let err = execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            new_poll.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidWeightOverrides {}));
+        let new_poll = NewPoll {
+            weight_overrides: Some(vec![
+                ("council1".to_string(), 5),
+                ("council2".to_string(), 2),
+                // the override wins over the holder's stake
+                ("holder".to_string(), 1),
+            ]),
+            ..new_poll
+        };
+        execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            new_poll,
+        )
+        .unwrap();
+
+        for (voter, vote) in [("council1", 0), ("council2", 1), ("holder", 1)] {
+            let msg = ExecuteMsg::Vote {
+                poll_id: "1".to_string(),
+                vote,
+                memo: None,
+                proof: None,
+            };
+            execute(deps.as_mut(), env.clone(), mock_info(voter, &[]), msg).unwrap();
+        }
+        let msg = QueryMsg::Poll {
+            poll_id: "1".to_string(),
+            locale: None,
+            viewer: None,
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg.into()).unwrap();
+        let poll: PollResponse = from_binary(&resp).unwrap();
+        assert_eq!(
+            labelled(&poll.poll.unwrap().options),
+            vec![("Yes", 5), ("No", 3)]
+        );
+
+        let msg = QueryMsg::WeightOverrides {
+            poll_id: "1".to_string(),
+            start_after: Some("council1".to_string()),
+            limit: Some(1),
+        };
+        let resp = query(deps.as_ref(), env, msg.into()).unwrap();
+        let overrides: WeightOverridesResponse = from_binary(&resp).unwrap();
+        assert_eq!(overrides.overrides, vec![(Addr::unchecked("council2"), 2)]);
+    }
+
+    #[test]
+    fn test_finalize_poll() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            admin: Some("admin".to_string()),
+            ..Default::default()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+        let new_poll = NewPoll {
+            poll_id: "1".to_string(),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            ..Default::default()
+        };
+        execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            new_poll,
+        )
+        .unwrap();
+        for (voter, vote) in [("voter1", 0), ("voter2", 1), ("voter3", 0)] {
+            let msg = ExecuteMsg::Vote {
+                poll_id: "1".to_string(),
+                vote,
+                memo: None,
+                proof: None,
+            };
+            execute(deps.as_mut(), env.clone(), mock_info(voter, &[]), msg).unwrap();
+        }
+
+        let finalize = ExecuteMsg::FinalizePoll {
+            poll_id: "1".to_string(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            finalize.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::PollNotClosed {}));
+
+        let msg = ExecuteMsg::ClosePoll {
+            poll_id: "1".to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            finalize.clone(),
+        )
+        .unwrap();
+        assert_eq!(res.events.len(), 1);
+        assert_eq!(res.events[0].ty, "poll_finalized");
+        assert_eq!(
+            res.events[0].attributes,
+            vec![
+                attr("poll_id", "1"),
+                attr("winner", "0"),
+                attr("total", "3"),
+                attr("voters", "3"),
+                attr("quorum", "not_required"),
+                attr("option_0", "2"),
+                attr("option_1", "1"),
+            ]
+        );
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            finalize,
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::PollNotClosed {}));
+
+        let msg = QueryMsg::FinalResult {
+            poll_id: "1".to_string(),
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg.into()).unwrap();
+        let result: FinalResultResponse = from_binary(&resp).unwrap();
+        assert_eq!(
+            result.result,
+            Some(PollResult {
+                winner: Some(0),
+                tallies: vec![
+                    PollOption {
+                        id: 0,
+                        label: "Yes".to_string(),
+                        votes: 2,
+                        description: None,
+                        image_uri: None,
+                    },
+                    PollOption {
+                        id: 1,
+                        label: "No".to_string(),
+                        votes: 1,
+                        description: None,
+                        image_uri: None,
+                    },
+                ],
+                total: 3,
+                finalized_at: env.block.time,
+                tie: None,
+                quorum_reached: None,
+                threshold: None,
+                eligible_weight: None,
+                min_voters_reached: None,
+                rounds: vec![],
+                pairwise: None,
+                randomness: None,
+                raffle_winner: None,
+            })
+        );
+    }
+
+    #[test]
+    fn test_tie_break_policies() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            admin: Some("admin".to_string()),
+            ..Default::default()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+
+        let policies = [
+            ("1", TieBreakPolicy::None),
+            ("2", TieBreakPolicy::FirstListed),
+            ("3", TieBreakPolicy::AdminDecides),
+        ];
+        for (poll_id, policy) in policies {
+            let new_poll = NewPoll {
+                poll_id: poll_id.to_string(),
+                question: "Should We Have a Meeting Today".to_string(),
+                options: vec![String::from("Yes"), String::from("No")],
+                tie_break: Some(policy),
+                ..Default::default()
+            };
+            execute_create_poll(
+                deps.as_mut(),
+                env.clone(),
+                mock_info("creator", &[]),
+                new_poll,
+            )
+            .unwrap();
+            for (voter, vote) in [("voter1", 0), ("voter2", 1)] {
+                let msg = ExecuteMsg::Vote {
+                    poll_id: poll_id.to_string(),
+                    vote,
+                    memo: None,
+                    proof: None,
+                };
+                execute(deps.as_mut(), env.clone(), mock_info(voter, &[]), msg).unwrap();
+            }
+            let msg = ExecuteMsg::ClosePoll {
+                poll_id: poll_id.to_string(),
+            };
+            execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+            let msg = ExecuteMsg::FinalizePoll {
+                poll_id: poll_id.to_string(),
+            };
+            execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+        }
+
+        let final_result = |deps: &cosmwasm_std::OwnedDeps<_, _, _>, poll_id: &str| {
+            let msg = QueryMsg::FinalResult {
+                poll_id: poll_id.to_string(),
+            };
+            let resp = query(deps.as_ref(), mock_env(), msg.into()).unwrap();
+            from_binary::<FinalResultResponse>(&resp)
+                .unwrap()
+                .result
+                .unwrap()
+        };
+        assert_eq!(final_result(&deps, "1").winner, None);
+        assert_eq!(final_result(&deps, "2").winner, Some(0));
+        let pending = final_result(&deps, "3");
+        assert_eq!(pending.winner, None);
+        assert!(!pending.tie.unwrap().resolved);
+
+        let msg = ExecuteMsg::ResolveTie {
+            poll_id: "3".to_string(),
+            option: 1,
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("voter1", &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            msg.clone(),
+        )
+        .unwrap();
+        let err = execute(deps.as_mut(), env, mock_info("creator", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::NoPendingTie {}));
+
+        let resolved = final_result(&deps, "3");
+        assert_eq!(resolved.winner, Some(1));
+        assert_eq!(
+            resolved.tie,
+            Some(TieResolution {
+                tied: vec![0, 1],
+                policy: TieBreakPolicy::AdminDecides,
+                resolved: true,
+            })
+        );
+    }
+
+    #[test]
+    fn test_random_tie_break() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            admin: Some("admin".to_string()),
+            ..Default::default()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+        let new_poll = NewPoll {
+            poll_id: "1".to_string(),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            tie_break: Some(TieBreakPolicy::Random),
+            raffle: true,
+            ..Default::default()
+        };
+        let err = execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            new_poll.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::RandomnessUnavailable {}));
+
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            admin: Some("admin".to_string()),
+            randomness_proxy: Some("nois".to_string()),
+            ..Default::default()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+        execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            new_poll,
+        )
+        .unwrap();
+        for (voter, vote) in [("voter1", 0), ("voter2", 1)] {
+            let msg = ExecuteMsg::Vote {
+                poll_id: "1".to_string(),
+                vote,
+                memo: None,
+                proof: None,
+            };
+            execute(deps.as_mut(), env.clone(), mock_info(voter, &[]), msg).unwrap();
+        }
+        let msg = ExecuteMsg::ClosePoll {
+            poll_id: "1".to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+        let msg = ExecuteMsg::FinalizePoll {
+            poll_id: "1".to_string(),
+        };
+        let fee = coins(50, "unois");
+        let res = execute(deps.as_mut(), env.clone(), mock_info("creator", &fee), msg).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "nois".to_string(),
+                msg: to_binary(&NoisProxyMsg::GetNextRandomness {
+                    job_id: "1".to_string(),
+                })
+                .unwrap(),
+                funds: fee,
+            })
+        );
+
+        let msg = ExecuteMsg::NoisReceive {
+            callback: NoisCallback {
+                job_id: "1".to_string(),
+                published: env.block.time,
+                randomness: HexBinary::from(Sha256::digest(b"beacon").to_vec()),
+            },
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("voter1", &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("nois", &[]),
+            msg.clone(),
+        )
+        .unwrap();
+        let err = execute(deps.as_mut(), env, mock_info("nois", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::RandomnessDelivered {}));
+
+        let result = RESULTS.load(&deps.storage, "1".to_string()).unwrap();
+        assert!(result.tie.unwrap().resolved);
+        let expected = match result.winner {
+            Some(0) => "voter1",
+            Some(1) => "voter2",
+            other => panic!("unexpected winner {:?}", other),
+        };
+        assert_eq!(result.raffle_winner, Some(Addr::unchecked(expected)));
+        assert!(result.randomness.is_some());
+    }
+
+    #[test]
+    fn test_ranked_raffle() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            admin: Some("admin".to_string()),
+            randomness_proxy: Some("nois".to_string()),
+            ..Default::default()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+        let new_poll = NewPoll {
+            poll_id: "1".to_string(),
+            question: "Where should we meet".to_string(),
+            options: vec![
+                String::from("Park"),
+                String::from("Cafe"),
+                String::from("Library"),
+            ],
+            mode: Some(VoteMode::Ranked {
+                method: RankedMethod::InstantRunoff,
+            }),
+            raffle: true,
+            ..Default::default()
+        };
+        execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            new_poll,
+        )
+        .unwrap();
+        // voter3 leaves Library, so voter4 takes over its first number
+        for (voter, ranking) in [
+            ("voter3", vec![2]),
+            ("voter4", vec![2, 0]),
+            ("voter3", vec![1, 0]),
+            ("voter1", vec![0, 1]),
+            ("voter2", vec![0]),
+            ("voter5", vec![1]),
+        ] {
+            let msg = ExecuteMsg::RankVotes {
+                poll_id: "1".to_string(),
+                ranking,
+                memo: None,
+                proof: None,
+            };
+            execute(deps.as_mut(), env.clone(), mock_info(voter, &[]), msg).unwrap();
+        }
+        let count = |option: u8| {
+            RAFFLE_COUNTS
+                .load(&deps.storage, ("1".to_string(), option))
+                .unwrap()
+        };
+        assert_eq!((count(0), count(1), count(2)), (4, 3, 1));
+        assert_eq!(
+            RAFFLE_ENTRIES
+                .load(&deps.storage, ("1".to_string(), 2, 0))
+                .unwrap(),
+            Addr::unchecked("voter4")
+        );
+
+        let msg = ExecuteMsg::ClosePoll {
+            poll_id: "1".to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+        let msg = ExecuteMsg::FinalizePoll {
+            poll_id: "1".to_string(),
+        };
+        let fee = coins(50, "unois");
+        execute(deps.as_mut(), env.clone(), mock_info("creator", &fee), msg).unwrap();
+        let msg = ExecuteMsg::NoisReceive {
+            callback: NoisCallback {
+                job_id: "1".to_string(),
+                published: env.block.time,
+                randomness: HexBinary::from(Sha256::digest(b"beacon").to_vec()),
+            },
+        };
+        execute(deps.as_mut(), env, mock_info("nois", &[]), msg).unwrap();
+
+        // Park wins once Library's voter4 transfers; voter3 ranked Park but
+        // backed Cafe in the final round, so the draws landing on them repeat
+        let result = RESULTS.load(&deps.storage, "1".to_string()).unwrap();
+        assert_eq!(result.winner, Some(0));
+        assert_eq!(result.raffle_winner, Some(Addr::unchecked("voter1")));
+    }
+
+    #[test]
+    fn test_try_early_close() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            admin: Some("admin".to_string()),
+            ..Default::default()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+        let new_poll = NewPoll {
+            poll_id: "1".to_string(),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            allowed_voters: Some(
+                ["voter1", "voter2", "voter3", "voter4"]
+                    .iter()
+                    .map(|v| v.to_string())
+                    .collect(),
+            ),
+            ..Default::default()
+        };
+        execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            new_poll,
+        )
+        .unwrap();
+
+        let vote = |poll_id: &str| ExecuteMsg::Vote {
+            poll_id: poll_id.to_string(),
+            vote: 0,
+            memo: None,
+            proof: None,
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("outsider", &[]),
+            vote("1"),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NotAllowedToVote {}));
+
+        let early_close = ExecuteMsg::TryEarlyClose {
+            poll_id: "1".to_string(),
+        };
+        for voter in ["voter1", "voter2"] {
+            let err = execute(
+                deps.as_mut(),
+                env.clone(),
+                mock_info("anyone", &[]),
+                early_close.clone(),
+            )
+            .unwrap_err();
+            assert!(matches!(err, ContractError::OutcomeNotDecided {}));
+            execute(deps.as_mut(), env.clone(), mock_info(voter, &[]), vote("1")).unwrap();
+        }
+        // Yes leads 2-0 with two votes left: still reversible to a tie
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            early_close.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::OutcomeNotDecided {}));
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("voter3", &[]),
+            vote("1"),
+        )
+        .unwrap();
+        let resp = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            early_close,
+        )
+        .unwrap();
+        assert_eq!(
+            resp.attributes,
+            vec![attr("action", "early close poll"), attr("poll_id", "1")]
+        );
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("voter4", &[]),
+            vote("1"),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::PollNotOpen {}));
+
+        // the same lead doesn't close a poll whose quorum is still unmet
+        let new_poll = NewPoll {
+            poll_id: "2".to_string(),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            allowed_voters: Some(
+                ["voter1", "voter2", "voter3", "voter4"]
+                    .iter()
+                    .map(|v| v.to_string())
+                    .collect(),
+            ),
+            quorum: Some(Quorum::AbsoluteWeight(Uint128::new(4))),
+            ..Default::default()
+        };
+        execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            new_poll,
+        )
+        .unwrap();
+        for voter in ["voter1", "voter2", "voter3"] {
+            execute(deps.as_mut(), env.clone(), mock_info(voter, &[]), vote("2")).unwrap();
+        }
+        let early_close = ExecuteMsg::TryEarlyClose {
+            poll_id: "2".to_string(),
+        };
+        let err = execute(deps.as_mut(), env, mock_info("anyone", &[]), early_close).unwrap_err();
+        assert!(matches!(err, ContractError::OutcomeNotDecided {}));
+    }
+
+    #[test]
+    fn test_early_close_weighs_outstanding_voters() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            admin: Some("admin".to_string()),
+            ..Default::default()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+        let new_poll = NewPoll {
+            poll_id: "1".to_string(),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            allowed_voters: Some(vec!["light".to_string(), "heavy".to_string()]),
+            weight_overrides: Some(vec![("light".to_string(), 2), ("heavy".to_string(), 5)]),
+            ..Default::default()
+        };
+        execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            new_poll,
+        )
+        .unwrap();
+        let poll = query_poll(deps.as_ref(), env.clone(), "1".to_string(), None, None).unwrap();
+        assert_eq!(poll.poll.unwrap().electorate, Some(7));
+
+        let vote = |vote: u8| ExecuteMsg::Vote {
+            poll_id: "1".to_string(),
+            vote,
+            memo: None,
+            proof: None,
+        };
+        let early_close = ExecuteMsg::TryEarlyClose {
+            poll_id: "1".to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("light", &[]), vote(0)).unwrap();
+        // Yes leads 2-0 but the heavy voter's 5 can still overturn it
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            early_close.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::OutcomeNotDecided {}));
+
+        execute(deps.as_mut(), env.clone(), mock_info("heavy", &[]), vote(1)).unwrap();
+        execute(deps.as_mut(), env, mock_info("anyone", &[]), early_close).unwrap();
+    }
+
+    #[test]
+    fn test_max_voting_period() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            admin: Some("admin".to_string()),
+            min_voting_period: Some(86400),
+            max_voting_period: Some(3600),
+            ..Default::default()
+        };
+        let err = instantiate(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidVotingPeriod {}));
+        let msg = InstantiateMsg {
+            max_voting_period: Some(14 * 86400),
+            ..msg
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+
+        let new_poll = |poll_id: &str, days: Option<u64>| NewPoll {
+            poll_id: poll_id.to_string(),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            end_time: days.map(|days| env.block.time.plus_seconds(days * 86400)),
+            ..Default::default()
+        };
+        for days in [None, Some(15)] {
+            let err = execute_create_poll(
+                deps.as_mut(),
+                env.clone(),
+                mock_info("creator", &[]),
+                new_poll("1", days),
+            )
+            .unwrap_err();
+            assert!(matches!(
+                err,
+                ContractError::VotingPeriodTooLong { max: 1209600 }
+            ));
+        }
+        execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            new_poll("1", Some(7)),
+        )
+        .unwrap();
+
+        let extend = |days: u64| ExecuteMsg::ExtendDeadline {
+            poll_id: "1".to_string(),
+            new_end: env.block.time.plus_seconds(days * 86400),
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            extend(20),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::VotingPeriodTooLong { .. }));
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            extend(14),
+        )
+        .unwrap();
+
+        // zero lifts the limit
+        let msg = ExecuteMsg::UpdateConfig {
+            min_voting_period: None,
+            max_voting_period: Some(0),
+            max_delegation_depth: None,
+            veto_period: None,
+            restricted_creation: None,
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+        execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            new_poll("2", None),
+        )
+        .unwrap();
+
+        // zero means no limit from the start too
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            max_voting_period: Some(0),
+            ..Default::default()
+        };
+        instantiate(deps.as_mut(), env, mock_info("admin", &[]), msg).unwrap();
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(config.max_voting_period, None);
+    }
+
+    #[test]
+    fn test_min_voting_period() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let msg = InstantiateMsg {
+            admin: Some("admin".to_string()),
+            min_voting_period: Some(3600),
+            ..Default::default()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+
+        let new_poll = |poll_id: &str, end_time| NewPoll {
+            poll_id: poll_id.to_string(),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            end_time,
+            ..Default::default()
+        };
+        let err = execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            new_poll("1", Some(env.block.time.plus_seconds(30))),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::VotingPeriodTooShort { min: 3600 }
+        ));
+        for poll_id in ["1", "2"] {
+            execute_create_poll(
+                deps.as_mut(),
+                env.clone(),
+                mock_info("creator", &[]),
+                new_poll(poll_id, Some(env.block.time.plus_seconds(7200))),
+            )
+            .unwrap();
+        }
+
+        let close = |poll_id: &str| ExecuteMsg::ClosePoll {
+            poll_id: poll_id.to_string(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            close("1"),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::MinVotingPeriodNotElapsed {}));
+        // contract admins may override
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            close("1"),
+        )
+        .unwrap();
+
+        env.block.time = env.block.time.plus_seconds(3600);
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            close("2"),
+        )
+        .unwrap();
+
+        // votes after the deadline are rejected, and an expired poll can be finalized directly
+        execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            new_poll("3", Some(env.block.time.plus_seconds(3600))),
+        )
+        .unwrap();
+        env.block.time = env.block.time.plus_seconds(3600);
+        let msg = ExecuteMsg::Vote {
+            poll_id: "3".to_string(),
+            vote: 0,
+            memo: None,
+            proof: None,
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info("voter", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::PollExpired {}));
+        let msg = ExecuteMsg::FinalizePoll {
+            poll_id: "3".to_string(),
+        };
+        execute(deps.as_mut(), env, mock_info("anyone", &[]), msg).unwrap();
+    }
+
+    #[test]
+    fn test_extend_deadline() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let msg = InstantiateMsg {
+            admin: Some("admin".to_string()),
+            ..Default::default()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+        let end_time = env.block.time.plus_seconds(3600);
+        let new_poll = NewPoll {
+            poll_id: "1".to_string(),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            end_time: Some(end_time),
+            ..Default::default()
+        };
+        execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            new_poll,
+        )
+        .unwrap();
+
+        let extend = |new_end| ExecuteMsg::ExtendDeadline {
+            poll_id: "1".to_string(),
+            new_end,
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            extend(end_time.plus_seconds(60)),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            extend(end_time.minus_seconds(60)),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::DeadlineNotExtended {}));
+
+        let new_end = end_time.plus_seconds(60);
+        let resp = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            extend(new_end),
+        )
+        .unwrap();
+        assert_eq!(
+            resp.attributes,
+            vec![
+                attr("action", "extend deadline"),
+                attr("poll_id", "1"),
+                attr("old_end", end_time.to_string()),
+                attr("new_end", new_end.to_string()),
+            ]
+        );
+
+        // still open past the original deadline
+        env.block.time = end_time;
+        let msg = ExecuteMsg::Vote {
+            poll_id: "1".to_string(),
+            vote: 0,
+            memo: None,
+            proof: None,
+        };
+        execute(deps.as_mut(), env, mock_info("voter", &[]), msg).unwrap();
+    }
+
+    #[test]
+    fn test_vote_memo() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            admin: Some("admin".to_string()),
+            ..Default::default()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+        let new_poll = NewPoll {
+            poll_id: "1".to_string(),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            ..Default::default()
+        };
+        execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            new_poll,
+        )
+        .unwrap();
+
+        let msg = ExecuteMsg::Vote {
+            poll_id: "1".to_string(),
+            vote: 1,
+            memo: Some("x".repeat(281)),
+            proof: None,
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info("voter1", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::MemoTooLong { max: 280 }));
+
+        for (voter, memo) in [("voter1", Some("Too many meetings")), ("voter2", None)] {
+            let msg = ExecuteMsg::Vote {
+                poll_id: "1".to_string(),
+                vote: 1,
+                memo: memo.map(String::from),
+                proof: None,
+            };
+            execute(deps.as_mut(), env.clone(), mock_info(voter, &[]), msg).unwrap();
+        }
+
+        let msg = QueryMsg::ListVotes {
+            poll_id: "1".to_string(),
+            start_after: None,
+            limit: None,
+            viewer: None,
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg.into()).unwrap();
+        let votes: ListVotesResponse = from_binary(&resp).unwrap();
+        let memos: Vec<_> = votes.votes.into_iter().map(|v| v.ballot.memo).collect();
+        assert_eq!(memos, vec![Some("Too many meetings".to_string()), None]);
+
+        let msg = QueryMsg::Vote {
+            poll_id: "1".to_string(),
+            address: "voter1".to_string(),
+            proof: None,
+            viewer: None,
+        };
+        let resp = query(deps.as_ref(), env, msg.into()).unwrap();
+        let vote: VoteResponse = from_binary(&resp).unwrap();
+        assert_eq!(vote.vote.unwrap().option, 1);
+    }
+
+    #[test]
+    fn test_comments() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            admin: Some("admin".to_string()),
+            ..Default::default()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+        let new_poll = NewPoll {
+            poll_id: "1".to_string(),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            ..Default::default()
+        };
+        execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            new_poll,
+        )
+        .unwrap();
+
+        let comment = |body: &str, reply_to| ExecuteMsg::Comment {
+            poll_id: "1".to_string(),
+            body: body.to_string(),
+            reply_to,
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("alice", &[]),
+            comment("", None),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidCommentLength { .. }));
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("alice", &[]),
+            comment("Agreed", Some(7)),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::CommentNotFound {}));
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("alice", &[]),
+            comment("We met yesterday", None),
+        )
+        .unwrap();
+        let resp = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("bob", &[]),
+            comment("That was a different topic", Some(1)),
+        )
+        .unwrap();
+        assert_eq!(
+            resp.attributes,
+            vec![
+                attr("action", "comment"),
+                attr("poll_id", "1"),
+                attr("comment_id", "2")
+            ]
+        );
+
+        let msg = QueryMsg::Comments {
+            poll_id: "1".to_string(),
+            start_after: Some(1),
+            limit: None,
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg.into()).unwrap();
+        let comments: CommentsResponse = from_binary(&resp).unwrap();
+        assert_eq!(
+            comments.comments,
+            vec![Comment {
+                id: 2,
+                author: Addr::unchecked("bob"),
+                body: "That was a different topic".to_string(),
+                reply_to: Some(1),
+                created_at: env.block.time,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_cancel_poll() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            admin: Some("admin".to_string()),
+            ..Default::default()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+        let new_poll = NewPoll {
+            poll_id: "1".to_string(),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            mode: Some(VoteMode::Cw20Deposit {
+                token: Addr::unchecked("token"),
+            }),
+            ..Default::default()
+        };
+        execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            new_poll,
+        )
+        .unwrap();
+        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "voter".to_string(),
+            amount: Uint128::new(25),
+            msg: to_binary(&ReceiveMsg::Vote {
+                poll_id: "1".to_string(),
+                option: 0,
+                memo: None,
+            })
+            .unwrap(),
+        });
+        execute(deps.as_mut(), env.clone(), mock_info("token", &[]), msg).unwrap();
+
+        let cancel = ExecuteMsg::CancelPoll {
+            poll_id: "1".to_string(),
+            reason: "Duplicate of poll 2".to_string(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("voter", &[]),
+            cancel.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let resp = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            cancel,
+        )
+        .unwrap();
+        assert!(resp.messages.is_empty());
+        let claim = ExecuteMsg::ClaimDeposit {
+            poll_id: "1".to_string(),
+        };
+        let resp = execute(deps.as_mut(), env.clone(), mock_info("voter", &[]), claim).unwrap();
+        let refund: CosmosMsg = WasmMsg::Execute {
+            contract_addr: "token".to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: "voter".to_string(),
+                amount: Uint128::new(25),
+            })
+            .unwrap(),
+            funds: vec![],
+        }
+        .into();
+        assert_eq!(resp.messages, vec![SubMsg::new(refund)]);
+
+        let msg = ExecuteMsg::FinalizePoll {
+            poll_id: "1".to_string(),
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info("anyone", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::PollNotClosed {}));
+
+        let msg = QueryMsg::Poll {
+            poll_id: "1".to_string(),
+            locale: None,
+            viewer: None,
+        };
+        let resp = query(deps.as_ref(), env, msg.into()).unwrap();
+        let poll = from_binary::<PollResponse>(&resp).unwrap().poll.unwrap();
+        assert_eq!(poll.status, PollStatus::Cancelled);
+        assert_eq!(poll.cancel_reason, Some("Duplicate of poll 2".to_string()));
+    }
+
+    #[test]
+    fn test_turnout_stats() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            admin: Some("admin".to_string()),
+            ..Default::default()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+
+        let ballots = [
+            ("1", vec!["voter1", "voter2", "voter3"]),
+            ("2", vec!["voter1"]),
+            ("3", vec!["voter4"]),
+        ];
+        for (poll_id, voters) in ballots {
+            let new_poll = NewPoll {
+                poll_id: poll_id.to_string(),
+                question: "Should We Have a Meeting Today".to_string(),
+                options: vec![String::from("Yes"), String::from("No")],
+                ..Default::default()
+            };
+            execute_create_poll(
+                deps.as_mut(),
+                env.clone(),
+                mock_info("admin", &[]),
+                new_poll,
+            )
+            .unwrap();
+            for voter in voters {
+                let msg = ExecuteMsg::Vote {
+                    poll_id: poll_id.to_string(),
+                    vote: 0,
+                    memo: None,
+                    proof: None,
+                };
+                execute(deps.as_mut(), env.clone(), mock_info(voter, &[]), msg).unwrap();
+            }
+        }
+        // poll 3 is cancelled and never counted towards turnout
+        let msg = ExecuteMsg::CancelPoll {
+            poll_id: "3".to_string(),
+            reason: "Test".to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+        for poll_id in ["1", "2"] {
+            let msg = ExecuteMsg::ClosePoll {
+                poll_id: poll_id.to_string(),
+            };
+            execute(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+            let msg = ExecuteMsg::FinalizePoll {
+                poll_id: poll_id.to_string(),
+            };
+            execute(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+        }
+
+        let resp = query(deps.as_ref(), env, QueryMsg::TurnoutStats {}.into()).unwrap();
+        let stats: TurnoutStatsResponse = from_binary(&resp).unwrap();
+        assert_eq!(
+            stats,
+            TurnoutStatsResponse {
+                finalized_polls: 2,
+                average_turnout: Decimal::percent(200),
+                unique_voters: 4,
+                voter_histogram: vec![0, 2, 0, 0, 0],
+            }
+        );
+    }
+
+    #[test]
+    fn test_voter_info() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let msg = InstantiateMsg {
+            admin: Some("admin".to_string()),
+            ..Default::default()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+        let first_vote = env.block.time;
+        for poll_id in ["1", "2"] {
+            let new_poll = NewPoll {
+                poll_id: poll_id.to_string(),
+                question: "Should We Have a Meeting Today".to_string(),
+                options: vec![String::from("Yes"), String::from("No")],
+                ..Default::default()
+            };
+            execute_create_poll(
+                deps.as_mut(),
+                env.clone(),
+                mock_info("creator", &[]),
+                new_poll,
+            )
+            .unwrap();
+            let msg = ExecuteMsg::Vote {
+                poll_id: poll_id.to_string(),
+                vote: 0,
+                memo: None,
+                proof: None,
+            };
+            execute(deps.as_mut(), env.clone(), mock_info("voter", &[]), msg).unwrap();
+            env.block.time = env.block.time.plus_seconds(60);
+        }
+
+        let info = |address: &str| -> VoterInfoResponse {
+            let msg = QueryMsg::VoterInfo {
+                address: address.to_string(),
+            };
+            from_binary(&query(deps.as_ref(), mock_env(), msg.into()).unwrap()).unwrap()
+        };
+        assert_eq!(
+            info("voter"),
+            VoterInfoResponse {
+                first_vote: Some(first_vote),
+                votes_cast: 2,
+                polls_created: 0,
+            }
+        );
+        assert_eq!(
+            info("creator"),
+            VoterInfoResponse {
+                first_vote: None,
+                votes_cast: 0,
+                polls_created: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn test_top_voters() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            admin: Some("admin".to_string()),
+            ..Default::default()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+
+        let ballots = [
+            ("1", vec!["voter1", "voter2", "voter3"]),
+            ("2", vec!["voter2", "voter3"]),
+            ("3", vec!["voter2"]),
+        ];
+        for (poll_id, voters) in ballots {
+            let new_poll = NewPoll {
+                poll_id: poll_id.to_string(),
+                question: "Should We Have a Meeting Today".to_string(),
+                options: vec![String::from("Yes"), String::from("No")],
+                ..Default::default()
+            };
+            execute_create_poll(
+                deps.as_mut(),
+                env.clone(),
+                mock_info("admin", &[]),
+                new_poll,
+            )
+            .unwrap();
+            for voter in voters {
+                let msg = ExecuteMsg::Vote {
+                    poll_id: poll_id.to_string(),
+                    vote: 0,
+                    memo: None,
+                    proof: None,
+                };
+                execute(deps.as_mut(), env.clone(), mock_info(voter, &[]), msg).unwrap();
+            }
+        }
+        // changing a vote does not count as another vote cast
+        let msg = ExecuteMsg::Vote {
+            poll_id: "1".to_string(),
+            vote: 1,
+            memo: None,
+            proof: None,
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("voter1", &[]), msg).unwrap();
+
+        let msg = QueryMsg::TopVoters { limit: Some(2) };
+        let resp = query(deps.as_ref(), env, msg.into()).unwrap();
+        let top: TopVotersResponse = from_binary(&resp).unwrap();
+        assert_eq!(
+            top.voters,
+            vec![
+                TopVoter {
+                    address: Addr::unchecked("voter2"),
+                    votes_cast: 3,
+                },
+                TopVoter {
+                    address: Addr::unchecked("voter3"),
+                    votes_cast: 2,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cw3_queries() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            admin: Some("admin".to_string()),
+            ..Default::default()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+
+        for (poll_id, allowed_voters) in [
+            (
+                "first",
+                Some(vec!["voter1".to_string(), "heavy".to_string()]),
+            ),
+            ("second", None),
+        ] {
+            let new_poll = NewPoll {
+                poll_id: poll_id.to_string(),
+                question: "Should We Have a Meeting Today".to_string(),
+                options: vec![String::from("Yes"), String::from("No")],
+                weight_overrides: allowed_voters
+                    .as_ref()
+                    .map(|_| vec![("heavy".to_string(), 5)]),
+                allowed_voters,
+                ..Default::default()
+            };
+            execute_create_poll(
+                deps.as_mut(),
+                env.clone(),
+                mock_info("admin", &[]),
+                new_poll,
+            )
+            .unwrap();
+        }
+        let msg = ExecuteMsg::Vote {
+            poll_id: "second".to_string(),
+            vote: 1,
+            memo: None,
+            proof: None,
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("voter1", &[]), msg).unwrap();
+
+        // cw3 requests arrive as raw JSON, with numeric proposal ids
+        let msg: ContractQueryMsg = from_binary(&Binary::from(
+            br#"{"proposal":{"proposal_id":2}}"#.as_slice(),
+        ))
+        .unwrap();
+        let resp = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let proposal: Cw3ProposalResponse = from_binary(&resp).unwrap();
+        assert_eq!(proposal.id, 2);
+        assert_eq!(proposal.title, "second");
+        assert_eq!(proposal.status, Cw3Status::Open);
+        assert_eq!(proposal.expires, Expiration::Never {});
+
+        let msg = Cw3QueryMsg::ListProposals {
+            start_after: Some(1),
+            limit: None,
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg.into()).unwrap();
+        let list: Cw3ProposalListResponse = from_binary(&resp).unwrap();
+        assert_eq!(list.proposals, vec![proposal]);
+
+        let msg: ContractQueryMsg = from_binary(&Binary::from(
+            br#"{"vote":{"proposal_id":2,"voter":"voter1"}}"#.as_slice(),
+        ))
+        .unwrap();
+        let resp = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let vote: Cw3VoteResponse = from_binary(&resp).unwrap();
+        let expected = Cw3VoteInfo {
+            proposal_id: 2,
+            voter: "voter1".to_string(),
+            vote: Cw3Vote::No,
+            weight: 1,
+        };
+        assert_eq!(vote.vote, Some(expected.clone()));
+
+        let msg = Cw3QueryMsg::ListVotes {
+            proposal_id: 2,
+            start_after: None,
+            limit: None,
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg.into()).unwrap();
+        let votes: Cw3VoteListResponse = from_binary(&resp).unwrap();
+        assert_eq!(votes.votes, vec![expected]);
+
+        // the contract's own vote query still resolves with the same variant name
+        let msg: ContractQueryMsg = from_binary(&Binary::from(
+            br#"{"vote":{"poll_id":"second","address":"voter1"}}"#.as_slice(),
+        ))
+        .unwrap();
+        assert!(matches!(msg, ContractQueryMsg::Poll(QueryMsg::Vote { .. })));
+
+        // within a proposal the weight follows its allowlist and overrides
+        let weight = |address: &str, proposal_id| {
+            let msg = Cw3QueryMsg::Voter {
+                address: address.to_string(),
+                proposal_id,
+            };
+            let resp = query(deps.as_ref(), env.clone(), msg.into()).unwrap();
+            from_binary::<Cw3VoterResponse>(&resp).unwrap().weight
+        };
+        assert_eq!(weight("voter1", None), Some(1));
+        assert_eq!(weight("voter1", Some(1)), Some(1));
+        assert_eq!(weight("heavy", Some(1)), Some(5));
+        assert_eq!(weight("outsider", Some(1)), None);
+        assert_eq!(weight("outsider", Some(2)), Some(1));
+
+        // votes map by label, and only in yes/no polls
+        for (poll_id, options) in [("third", ["No", "Yes"]), ("fourth", ["Yes", "Maybe"])] {
+            let new_poll = NewPoll {
+                poll_id: poll_id.to_string(),
+                question: "Should We Have a Meeting Today".to_string(),
+                options: options.map(String::from).to_vec(),
+                ..Default::default()
+            };
+            execute_create_poll(
+                deps.as_mut(),
+                env.clone(),
+                mock_info("admin", &[]),
+                new_poll,
+            )
+            .unwrap();
+            let msg = ExecuteMsg::Vote {
+                poll_id: poll_id.to_string(),
+                vote: 0,
+                memo: None,
+                proof: None,
+            };
+            execute(deps.as_mut(), env.clone(), mock_info("voter1", &[]), msg).unwrap();
+        }
+        let vote = |proposal_id| {
+            let msg = Cw3QueryMsg::Vote {
+                proposal_id,
+                voter: "voter1".to_string(),
+            };
+            let resp = query(deps.as_ref(), env.clone(), msg.into()).unwrap();
+            from_binary::<Cw3VoteResponse>(&resp)
+                .unwrap()
+                .vote
+                .unwrap()
+                .vote
+        };
+        assert_eq!(vote(3), Cw3Vote::No);
+        assert_eq!(vote(4), Cw3Vote::Abstain);
+    }
+
+    #[test]
+    fn test_cumulative_vote() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            admin: Some("admin".to_string()),
+            ..Default::default()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+        let new_poll = NewPoll {
+            poll_id: "1".to_string(),
+            question: "Where should the budget go".to_string(),
+            options: vec![
+                String::from("Docs"),
+                String::from("Audits"),
+                String::from("Grants"),
+            ],
+            mode: Some(VoteMode::Cumulative {
+                budget: VoteBudget::Points(Uint128::new(10)),
+            }),
+            ..Default::default()
+        };
+        execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            new_poll,
+        )
+        .unwrap();
+
+        let msg = ExecuteMsg::Vote {
+            poll_id: "1".to_string(),
+            vote: 0,
+            memo: None,
+            proof: None,
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info("voter1", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::AllocationsRequired {}));
+
+        let allocate = |allocations: &[(u8, u128)]| ExecuteMsg::AllocateVotes {
+            poll_id: "1".to_string(),
+            allocations: allocations
+                .iter()
+                .map(|(option, points)| (*option, Uint128::new(*points)))
+                .collect(),
+            memo: None,
+            proof: None,
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("voter1", &[]),
+            allocate(&[(0, 6), (1, 5)]),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::AllocationMismatch { .. }));
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("voter1", &[]),
+            allocate(&[(0, 5), (0, 5)]),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::DuplicateOption {}));
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("voter1", &[]),
+            allocate(&[(0, 5), (3, 5)]),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::OptionNotFound {}));
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("voter1", &[]),
+            allocate(&[(0, 6), (1, 4)]),
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("voter2", &[]),
+            allocate(&[(2, 10)]),
+        )
+        .unwrap();
+        // a new allocation replaces the voter's previous split
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("voter1", &[]),
+            allocate(&[(1, 3), (2, 7)]),
+        )
+        .unwrap();
+
+        let msg = QueryMsg::Poll {
+            poll_id: "1".to_string(),
+            locale: None,
+            viewer: None,
+        };
+        let resp = query(deps.as_ref(), env, msg.into()).unwrap();
+        let poll = from_binary::<PollResponse>(&resp).unwrap().poll.unwrap();
+        assert_eq!(
+            labelled(&poll.options),
+            vec![("Docs", 0), ("Audits", 3), ("Grants", 17)]
+        );
+        assert_eq!(poll.voter_count, 2);
+    }
+
+    #[test]
+    fn test_transitive_delegation() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            admin: Some("admin".to_string()),
+            max_delegation_depth: Some(2),
+            ..Default::default()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+
+        // alice -> bob -> carol, and dave -> alice sits three hops from carol
+        for (from, to) in [("alice", "bob"), ("bob", "carol"), ("dave", "alice")] {
+            let msg = ExecuteMsg::Delegate { to: to.to_string() };
+            execute(deps.as_mut(), env.clone(), mock_info(from, &[]), msg).unwrap();
+        }
+        let msg = ExecuteMsg::Delegate {
+            to: "dave".to_string(),
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info("carol", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::DelegationCycle {}));
+        let msg = ExecuteMsg::Delegate {
+            to: "carol".to_string(),
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info("carol", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::CannotDelegateToSelf {}));
+
+        let new_poll = NewPoll {
+            poll_id: "1".to_string(),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            ..Default::default()
+        };
+        execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            new_poll,
+        )
+        .unwrap();
+        let vote = |option: u8| ExecuteMsg::Vote {
+            poll_id: "1".to_string(),
+            vote: option,
+            memo: None,
+            proof: None,
+        };
+        let resolved = |deps: Deps, address: &str| {
+            let msg = QueryMsg::ResolvedWeight {
+                address: address.to_string(),
+                poll_id: "1".to_string(),
+            };
+            let resp = query(deps, mock_env(), msg.into()).unwrap();
+            from_binary::<ResolvedWeightResponse>(&resp).unwrap()
+        };
+
+        execute(deps.as_mut(), env.clone(), mock_info("carol", &[]), vote(0)).unwrap();
+        assert_eq!(
+            resolved(deps.as_ref(), "carol"),
+            ResolvedWeightResponse {
+                weight: 3,
+                delegators: vec![Addr::unchecked("alice"), Addr::unchecked("bob")],
+                represented_by: None,
+            }
+        );
+
+        // bob votes himself and takes back his own weight and alice's
+        execute(deps.as_mut(), env.clone(), mock_info("bob", &[]), vote(1)).unwrap();
+        assert_eq!(resolved(deps.as_ref(), "carol").weight, 1);
+        let bob = resolved(deps.as_ref(), "bob");
+        assert_eq!(bob.weight, 3);
+        assert_eq!(
+            bob.delegators,
+            vec![Addr::unchecked("alice"), Addr::unchecked("dave")]
+        );
+        assert_eq!(
+            resolved(deps.as_ref(), "alice").represented_by,
+            Some(Addr::unchecked("bob"))
+        );
+
+        let msg = QueryMsg::Poll {
+            poll_id: "1".to_string(),
+            locale: None,
+            viewer: None,
+        };
+        let resp = query(deps.as_ref(), env, msg.into()).unwrap();
+        let poll = from_binary::<PollResponse>(&resp).unwrap().poll.unwrap();
+        assert_eq!(labelled(&poll.options), vec![("Yes", 1), ("No", 3)]);
+    }
+
+    #[test]
+    fn test_poll_history() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let msg = InstantiateMsg {
+            admin: Some("admin".to_string()),
+            ..Default::default()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+        let old_end = env.block.time.plus_seconds(3600);
+        let new_poll = NewPoll {
+            poll_id: "1".to_string(),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            end_time: Some(old_end),
+            ..Default::default()
+        };
+        execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            new_poll,
+        )
+        .unwrap();
+
+        let new_end = old_end.plus_seconds(3600);
+        let msg = ExecuteMsg::ExtendDeadline {
+            poll_id: "1".to_string(),
+            new_end,
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+
+        env.block.time = new_end;
+        env.block.height += 100;
+        let msg = ExecuteMsg::FinalizePoll {
+            poll_id: "1".to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("anyone", &[]), msg).unwrap();
+
+        let msg = QueryMsg::PollHistory {
+            poll_id: "1".to_string(),
+            start_after: None,
+            limit: None,
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg.into()).unwrap();
+        let history: PollHistoryResponse = from_binary(&resp).unwrap();
+        let changes: Vec<(u64, &str, PollChange)> = history
+            .history
+            .iter()
+            .map(|record| (record.id, record.actor.as_str(), record.change.clone()))
+            .collect();
+        assert_eq!(
+            changes,
+            vec![
+                (1, "creator", PollChange::Created),
+                (
+                    2,
+                    "creator",
+                    PollChange::DeadlineExtended { old_end, new_end }
+                ),
+                (
+                    3,
+                    "anyone",
+                    PollChange::StatusChanged {
+                        from: PollStatus::Open,
+                        to: PollStatus::Closed,
+                    }
+                ),
+                (
+                    4,
+                    "anyone",
+                    PollChange::StatusChanged {
+                        from: PollStatus::Closed,
+                        to: PollStatus::Finalized,
+                    }
+                ),
+            ]
+        );
+        assert_eq!(history.history[3].time, new_end);
+
+        let msg = QueryMsg::PollHistory {
+            poll_id: "1".to_string(),
+            start_after: Some(3),
+            limit: None,
+        };
+        let resp = query(deps.as_ref(), env, msg.into()).unwrap();
+        let history: PollHistoryResponse = from_binary(&resp).unwrap();
+        assert_eq!(history.history.len(), 1);
+    }
+
+    #[test]
+    fn test_min_balance() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            admin: Some("admin".to_string()),
+            ..Default::default()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+        deps.querier.update_balance("whale", coins(500, "ujuno"));
+        deps.querier.update_balance("minnow", coins(50, "ujuno"));
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::ContractInfo { contract_addr } if contract_addr == "token" => {
+                SystemResult::Ok(ContractResult::Ok(
+                    to_binary(&ContractInfoResponse::default()).unwrap(),
+                ))
+            }
+            WasmQuery::Smart { contract_addr, msg } if contract_addr == "token" => {
+                let balance = match from_binary(msg).unwrap() {
+                    Cw20QueryMsg::Balance { address } if address == "minnow" => 1000u128,
+                    _ => 0,
+                };
+                SystemResult::Ok(ContractResult::Ok(
+                    to_binary(&BalanceResponse {
+                        balance: Uint128::new(balance),
+                    })
+                    .unwrap(),
+                ))
+            }
+            _ => SystemResult::Err(SystemError::UnsupportedRequest {
+                kind: "wasm".to_string(),
+            }),
+        });
+
+        for (poll_id, asset) in [("1", "ujuno"), ("2", "token")] {
+            let new_poll = NewPoll {
+                poll_id: poll_id.to_string(),
+                question: "Should We Have a Meeting Today".to_string(),
+                options: vec![String::from("Yes"), String::from("No")],
+                min_balance: Some((asset.to_string(), Uint128::new(100))),
+                ..Default::default()
+            };
+            execute_create_poll(
+                deps.as_mut(),
+                env.clone(),
+                mock_info("creator", &[]),
+                new_poll,
+            )
+            .unwrap();
+        }
+        let vote = |poll_id: &str| ExecuteMsg::Vote {
+            poll_id: poll_id.to_string(),
+            vote: 0,
+            memo: None,
+            proof: None,
+        };
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("whale", &[]),
+            vote("1"),
+        )
+        .unwrap();
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("minnow", &[]),
+            vote("1"),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::BalanceTooLow { min, asset } if min == Uint128::new(100) && asset == "ujuno"
+        ));
+
+        // cw20 balances are queried from the token contract
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("minnow", &[]),
+            vote("2"),
+        )
+        .unwrap();
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("whale", &[]),
+            vote("2"),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::BalanceTooLow { .. }));
+
+        // the denom gate also applies to ballots cast with a cw20 deposit
+        let new_poll = NewPoll {
+            poll_id: "3".to_string(),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            mode: Some(VoteMode::Cw20Deposit {
+                token: Addr::unchecked("token"),
+            }),
+            min_balance: Some(("ujuno".to_string(), Uint128::new(100))),
+            ..Default::default()
+        };
+        execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            new_poll,
+        )
+        .unwrap();
+        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "minnow".to_string(),
+            amount: Uint128::new(25),
+            msg: to_binary(&ReceiveMsg::Vote {
+                poll_id: "3".to_string(),
+                option: 0,
+                memo: None,
+            })
+            .unwrap(),
+        });
+        let err = execute(deps.as_mut(), env, mock_info("token", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::BalanceTooLow { .. }));
+    }
+
+    #[test]
+    fn test_signed_votes() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            admin: Some("admin".to_string()),
+            ..Default::default()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+        let new_poll = NewPoll {
+            poll_id: "1".to_string(),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            ..Default::default()
+        };
+        execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            new_poll,
+        )
+        .unwrap();
+
+        let key = SigningKey::from_bytes(&[7u8; 32]).unwrap();
+        let pubkey = Binary::from(key.verifying_key().to_bytes().to_vec());
+        let sign = |option: u8, nonce: u64| {
+            let payload = VotePayload {
+                chain_id: env.block.chain_id.clone(),
+                contract: env.contract.address.to_string(),
+                poll_id: "1".to_string(),
+                option,
+                nonce,
+            };
+            let signature: Signature = key.sign(&to_vec(&payload).unwrap());
+            SignedVote {
+                pubkey: pubkey.clone(),
+                poll_id: "1".to_string(),
+                option,
+                nonce,
+                signature: Binary::from(signature.as_ref().to_vec()),
+            }
+        };
+        let submit = |votes: Vec<SignedVote>| ExecuteMsg::SubmitSignedVotes { votes };
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("relayer", &[]),
+            submit(vec![sign(0, 1)]),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::VotingKeyNotRegistered {}));
+
+        let register = |voter: &str| {
+            let payload = KeyRegistration {
+                chain_id: env.block.chain_id.clone(),
+                contract: env.contract.address.to_string(),
+                voter: voter.to_string(),
+            };
+            let signature: Signature = key.sign(&to_vec(&payload).unwrap());
+            ExecuteMsg::RegisterVotingKey {
+                pubkey: pubkey.clone(),
+                signature: Binary::from(signature.as_ref().to_vec()),
+            }
+        };
+
+        // front-running with the victim's pubkey and signature is rejected
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("attacker", &[]),
+            register("voter1"),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidSignature {}));
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("voter1", &[]),
+            register("voter1"),
+        )
+        .unwrap();
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("voter2", &[]),
+            register("voter2"),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::VotingKeyTaken {}));
+
+        // a signature over a different option is rejected
+        let mut forged = sign(0, 1);
+        forged.option = 1;
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("relayer", &[]),
+            submit(vec![forged]),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidSignature {}));
+
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("relayer", &[]),
+            submit(vec![sign(0, 1)]),
+        )
+        .unwrap();
+        assert_eq!(res.events[0].ty, "signed_vote");
+
+        // replaying the same ballot fails
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("relayer", &[]),
+            submit(vec![sign(0, 1)]),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::StaleNonce { last: 1 }));
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("relayer", &[]),
+            submit(vec![sign(1, 2)]),
+        )
+        .unwrap();
+        let msg = QueryMsg::Vote {
+            poll_id: "1".to_string(),
+            address: "voter1".to_string(),
+            proof: None,
+            viewer: None,
+        };
+        let resp = query(deps.as_ref(), env, msg.into()).unwrap();
+        let vote: VoteResponse = from_binary(&resp).unwrap();
+        assert_eq!(vote.vote.unwrap().option, 1);
+    }
+
+    #[test]
+    fn test_merkle_allowlist() {
+        let leaf = |voter: &str, weight: u128| -> [u8; 32] {
+            Sha256::digest(format!("{}{}", voter, weight).as_bytes()).into()
+        };
+        let parent = |a: [u8; 32], b: [u8; 32]| -> [u8; 32] {
+            let (first, second) = if a < b { (a, b) } else { (b, a) };
+            Sha256::digest([first, second].concat()).into()
+        };
+        let (alice, bob, carol) = (leaf("alice", 5), leaf("bob", 3), leaf("carol", 1));
+        let root = parent(parent(alice, bob), carol);
+
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            admin: Some("admin".to_string()),
+            ..Default::default()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+        let new_poll = |merkle_root: &str| NewPoll {
+            poll_id: "1".to_string(),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            merkle_root: Some(merkle_root.to_string()),
+            ..Default::default()
+        };
+        let err = execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            new_poll("not hex"),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidMerkleRoot {}));
+        execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            new_poll(&hex::encode(root)),
+        )
+        .unwrap();
+
+        let vote = |option: u8, proof: Option<MerkleProof>| ExecuteMsg::Vote {
+            poll_id: "1".to_string(),
+            vote: option,
+            memo: None,
+            proof,
+        };
+        let proof = |weight: u128, siblings: &[[u8; 32]]| MerkleProof {
+            weight: Uint128::new(weight),
+            proof: siblings.iter().map(hex::encode).collect(),
+        };
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("alice", &[]),
+            vote(0, None),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::MerkleProofRequired {}));
+        // claiming a larger weight than the allowlist grants fails
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("alice", &[]),
+            vote(0, Some(proof(50, &[bob, carol]))),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidMerkleProof {}));
+        // so does borrowing someone else's proof
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("mallory", &[]),
+            vote(0, Some(proof(5, &[bob, carol]))),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidMerkleProof {}));
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("alice", &[]),
+            vote(0, Some(proof(5, &[bob, carol]))),
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("carol", &[]),
+            vote(1, Some(proof(1, &[parent(alice, bob)]))),
+        )
+        .unwrap();
+
+        let msg = QueryMsg::Poll {
+            poll_id: "1".to_string(),
+            locale: None,
+            viewer: None,
+        };
+        let resp = query(deps.as_ref(), env, msg.into()).unwrap();
+        let poll = from_binary::<PollResponse>(&resp).unwrap().poll.unwrap();
+        assert_eq!(labelled(&poll.options), vec![("Yes", 5), ("No", 1)]);
+    }
+
+    #[test]
+    fn test_conviction_vote() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let msg = InstantiateMsg {
+            admin: Some("admin".to_string()),
+            ..Default::default()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+        let new_poll = NewPoll {
+            poll_id: "1".to_string(),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            end_time: Some(env.block.time.plus_seconds(100)),
+            mode: Some(VoteMode::Conviction {
+                token: Addr::unchecked("token"),
+                max_multiplier: 3,
+                ramp_seconds: 100,
+            }),
+            ..Default::default()
+        };
+        execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            new_poll,
+        )
+        .unwrap();
+        let lock = |sender: &str, amount: u128, option: u8| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: sender.to_string(),
+                amount: Uint128::new(amount),
+                msg: to_binary(&ReceiveMsg::Lock {
+                    poll_id: "1".to_string(),
+                    option,
+                })
+                .unwrap(),
+            })
+        };
+        let token = mock_info("token", &[]);
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("other", &[]),
+            lock("voter1", 100, 0),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidDepositToken {}));
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            token.clone(),
+            lock("voter1", 100, 0),
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            token.clone(),
+            lock("voter3", 500, 1),
+        )
+        .unwrap();
+
+        env.block.time = env.block.time.plus_seconds(50);
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            token.clone(),
+            lock("voter2", 120, 1),
+        )
+        .unwrap();
+
+        // withdrawing early returns the tokens and drops the conviction
+        let msg = ExecuteMsg::Unlock {
+            poll_id: "1".to_string(),
+        };
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("voter3", &[]),
+            msg.clone(),
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(WasmMsg::Execute {
+                contract_addr: "token".to_string(),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: "voter3".to_string(),
+                    amount: Uint128::new(500),
+                })
+                .unwrap(),
+                funds: vec![],
+            })]
+        );
+        let err = execute(deps.as_mut(), env.clone(), mock_info("voter3", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::NothingLocked {}));
+
+        // conviction is settled at the deadline even if finalized later
+        env.block.time = env.block.time.plus_seconds(500);
+        let msg = ExecuteMsg::FinalizePoll {
+            poll_id: "1".to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+        let msg = QueryMsg::FinalResult {
+            poll_id: "1".to_string(),
+        };
+        let resp = query(deps.as_ref(), env, msg.into()).unwrap();
+        let result = from_binary::<FinalResultResponse>(&resp)
+            .unwrap()
+            .result
+            .unwrap();
+        assert_eq!(labelled(&result.tallies), vec![("Yes", 300), ("No", 240)]);
+        assert_eq!(result.winner, Some(0));
+    }
+
+    #[test]
+    fn test_weight_decay() {
+        let half_life = WeightDecay::HalfLife { half_life: 10 };
+        assert_eq!(half_life.apply(100, 0), 100);
+        assert_eq!(half_life.apply(100, 15), 38);
+        assert_eq!(half_life.apply(100, 20), 25);
+
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let msg = InstantiateMsg {
+            admin: Some("admin".to_string()),
+            ..Default::default()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+        let start = env.block.time;
+        let new_poll = NewPoll {
+            poll_id: "1".to_string(),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            end_time: Some(start.plus_seconds(100)),
+            mode: Some(VoteMode::Cumulative {
+                budget: VoteBudget::Points(Uint128::new(100)),
+            }),
+            decay: Some(WeightDecay::Linear { period: 100 }),
+            ..Default::default()
+        };
+        execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            new_poll,
+        )
+        .unwrap();
+        let allocate = |option: u8| ExecuteMsg::AllocateVotes {
+            poll_id: "1".to_string(),
+            allocations: vec![(option, Uint128::new(100))],
+            memo: None,
+            proof: None,
+        };
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("voter1", &[]),
+            allocate(0),
+        )
+        .unwrap();
+        env.block.time = start.plus_seconds(60);
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("voter2", &[]),
+            allocate(1),
+        )
+        .unwrap();
+        // re-confirming restarts the decay
+        env.block.time = start.plus_seconds(80);
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("voter1", &[]),
+            allocate(0),
+        )
+        .unwrap();
+
+        env.block.time = start.plus_seconds(100);
+        let msg = ExecuteMsg::FinalizePoll {
+            poll_id: "1".to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+        let msg = QueryMsg::FinalResult {
+            poll_id: "1".to_string(),
+        };
+        let resp = query(deps.as_ref(), env, msg.into()).unwrap();
+        let result = from_binary::<FinalResultResponse>(&resp)
+            .unwrap()
+            .result
+            .unwrap();
+        assert_eq!(labelled(&result.tallies), vec![("Yes", 80), ("No", 60)]);
+    }
+
+    #[test]
+    fn test_category_admins() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            admin: Some("admin".to_string()),
+            ..Default::default()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+        for (poll_id, category) in [("1", "treasury"), ("2", "events")] {
+            let new_poll = NewPoll {
+                poll_id: poll_id.to_string(),
+                question: "Should We Have a Meeting Today".to_string(),
+                options: vec![String::from("Yes"), String::from("No")],
+                category: Some(category.to_string()),
+                ..Default::default()
+            };
+            execute_create_poll(
+                deps.as_mut(),
+                env.clone(),
+                mock_info("creator", &[]),
+                new_poll,
+            )
+            .unwrap();
+        }
+
+        let add = ExecuteMsg::AddCategoryAdmin {
+            category: "treasury".to_string(),
+            address: "steward".to_string(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("steward", &[]),
+            add.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            add.clone(),
+        )
+        .unwrap();
+        let err = execute(deps.as_mut(), env.clone(), mock_info("admin", &[]), add).unwrap_err();
+        assert!(matches!(err, ContractError::AlreadyCategoryAdmin {}));
+
+        let msg = QueryMsg::CategoryAdmins {
+            category: "treasury".to_string(),
+            start_after: None,
+            limit: None,
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg.into()).unwrap();
+        let admins: CategoryAdminsResponse = from_binary(&resp).unwrap();
+        assert_eq!(admins.admins, vec![Addr::unchecked("steward")]);
+
+        let close = |poll_id: &str| ExecuteMsg::ClosePoll {
+            poll_id: poll_id.to_string(),
+        };
+        // only polls in the steward's category
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("steward", &[]),
+            close("2"),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("steward", &[]),
+            close("1"),
+        )
+        .unwrap();
+        // contract admins keep global power
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            close("2"),
+        )
+        .unwrap();
+
+        let remove = ExecuteMsg::RemoveCategoryAdmin {
+            category: "treasury".to_string(),
+            address: "steward".to_string(),
+        };
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            remove.clone(),
+        )
+        .unwrap();
+        let err = execute(deps.as_mut(), env, mock_info("admin", &[]), remove).unwrap_err();
+        assert!(matches!(err, ContractError::NotCategoryAdmin {}));
+    }
+
+    #[test]
+    fn test_flag_poll() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            admin: Some("admin".to_string()),
+            creation_deposit: Some(coin(100, "ujuno")),
+            treasury: Some("treasury".to_string()),
+            flag_threshold: Some(2),
+            ..Default::default()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+        let new_poll = |poll_id: &str| {
+            ExecuteMsg::CreatePoll(NewPoll {
+                poll_id: poll_id.to_string(),
+                question: "Should We Have a Meeting Today".to_string(),
+                options: vec![String::from("Yes"), String::from("No")],
+                ..Default::default()
+            })
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &coins(50, "ujuno")),
+            new_poll("1"),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::CreationDepositRequired { .. }));
+        for poll_id in ["1", "2", "3"] {
+            execute(
+                deps.as_mut(),
+                env.clone(),
+                mock_info("creator", &coins(100, "ujuno")),
+                new_poll(poll_id),
+            )
+            .unwrap();
+        }
+        let flag = |poll_id: &str| ExecuteMsg::FlagPoll {
+            poll_id: poll_id.to_string(),
+        };
+        let deposit_to = |to: &str| {
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: to.to_string(),
+                amount: coins(100, "ujuno"),
+            })]
+        };
+
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("user1", &[]),
+            flag("1"),
+        )
+        .unwrap();
+        assert!(res.messages.is_empty());
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("user1", &[]),
+            flag("1"),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::AlreadyFlagged {}));
+        // the second distinct flagger cancels the poll and slashes the deposit
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("user2", &[]),
+            flag("1"),
+        )
+        .unwrap();
+        assert_eq!(res.messages, deposit_to("treasury"));
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("user3", &[]),
+            flag("1"),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::PollNotOpen {}));
+
+        // a moderator's flag is enough on its own
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            flag("2"),
+        )
+        .unwrap();
+        assert_eq!(res.messages, deposit_to("treasury"));
+        let msg = QueryMsg::Poll {
+            poll_id: "2".to_string(),
+            locale: None,
+            viewer: None,
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg.into()).unwrap();
+        let poll = from_binary::<PollResponse>(&resp).unwrap().poll.unwrap();
+        assert_eq!(poll.status, PollStatus::Cancelled);
+
+        // closing normally returns the deposit to the creator
+        let msg = ExecuteMsg::ClosePoll {
+            poll_id: "3".to_string(),
+        };
+        let res = execute(deps.as_mut(), env, mock_info("creator", &[]), msg).unwrap();
+        assert_eq!(res.messages, deposit_to("creator"));
+    }
+
+    #[test]
+    fn test_refund_excess_deposit() {
+        let new_poll = NewPoll {
+            poll_id: "1".to_string(),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            ..Default::default()
+        };
+
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            admin: Some("admin".to_string()),
+            creation_deposit: Some(coin(100, "ujuno")),
+            ..Default::default()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+        let resp = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &coins(130, "ujuno")),
+            ExecuteMsg::CreatePoll(new_poll.clone()),
+        )
+        .unwrap();
+        assert_eq!(
+            resp.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: "creator".to_string(),
+                amount: coins(30, "ujuno"),
+            })]
+        );
+        assert!(resp.attributes.contains(&attr("refunded", "30")));
+        let poll = POLL.load(&deps.storage, "1".to_string()).unwrap();
+        assert_eq!(poll.creation_deposit, Some(coin(100, "ujuno")));
+
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            admin: Some("admin".to_string()),
+            cw20_creation_deposit: Some(Cw20Coin {
+                address: "token".to_string(),
+                amount: Uint128::new(50),
+            }),
+            ..Default::default()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+        let receive = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "creator".to_string(),
+            amount: Uint128::new(80),
+            msg: to_binary(&ReceiveMsg::CreatePoll(new_poll)).unwrap(),
+        });
+        let resp = execute(deps.as_mut(), env, mock_info("token", &[]), receive).unwrap();
+        assert_eq!(
+            resp.messages,
+            vec![SubMsg::new(WasmMsg::Execute {
+                contract_addr: "token".to_string(),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: "creator".to_string(),
+                    amount: Uint128::new(30),
+                })
+                .unwrap(),
+                funds: vec![],
+            })]
+        );
+        let poll = POLL.load(&deps.storage, "1".to_string()).unwrap();
+        assert_eq!(
+            poll.cw20_creation_deposit.map(|deposit| deposit.amount),
+            Some(Uint128::new(50))
+        );
+    }
+
+    #[test]
+    fn test_veto() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let msg = InstantiateMsg {
+            admin: Some("admin".to_string()),
+            veto_period: Some(100),
+            ..Default::default()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+        let start = env.block.time;
+        for poll_id in ["1", "2"] {
+            let new_poll = NewPoll {
+                poll_id: poll_id.to_string(),
+                question: "Should We Have a Meeting Today".to_string(),
+                options: vec![String::from("Yes"), String::from("No")],
+                end_time: Some(start.plus_seconds(10)),
+                ..Default::default()
+            };
+            execute_create_poll(
+                deps.as_mut(),
+                env.clone(),
+                mock_info("creator", &[]),
+                new_poll,
+            )
+            .unwrap();
+        }
+        let finalize = |poll_id: &str| ExecuteMsg::FinalizePoll {
+            poll_id: poll_id.to_string(),
+        };
+        let veto = |poll_id: &str| ExecuteMsg::Veto {
+            poll_id: poll_id.to_string(),
+            reason: "Executes a malicious migration".to_string(),
+        };
+
+        env.block.time = start.plus_seconds(20);
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            finalize("1"),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::VetoPeriodActive { ends } if ends == start.plus_seconds(110)
+        ));
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            veto("1"),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            veto("1"),
+        )
+        .unwrap();
+        let msg = QueryMsg::Poll {
+            poll_id: "1".to_string(),
+            locale: None,
+            viewer: None,
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg.into()).unwrap();
+        let poll = from_binary::<PollResponse>(&resp).unwrap().poll.unwrap();
+        assert_eq!(poll.status, PollStatus::Vetoed);
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            finalize("1"),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::PollNotClosed {}));
+
+        // the window runs from the deadline, not from the later close
+        let msg = ExecuteMsg::ClosePoll {
+            poll_id: "2".to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+        env.block.time = start.plus_seconds(110);
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            veto("2"),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::VetoPeriodEnded {}));
+        execute(deps.as_mut(), env, mock_info("anyone", &[]), finalize("2")).unwrap();
+    }
+
+    #[test]
+    fn test_all_poll() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            admin: Some("admin".to_string()),
+            ..Default::default()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+        for (poll_id, question) in [
+            ("budget", "Approve the budget?"),
+            ("meeting", "Meet today?"),
+        ] {
+            let new_poll = NewPoll {
+                poll_id: poll_id.to_string(),
+                question: question.to_string(),
+                options: vec![String::from("Yes"), String::from("No")],
+                ..Default::default()
+            };
+            execute_create_poll(
+                deps.as_mut(),
+                env.clone(),
+                mock_info("creator", &[]),
+                new_poll,
+            )
+            .unwrap();
+        }
+
+        let resp = query(deps.as_ref(), env, QueryMsg::AllPoll {}.into()).unwrap();
+        let polls = from_binary::<AllPollResponse>(&resp).unwrap().polls;
+        let entries: Vec<(&str, &str)> = polls
+            .iter()
+            .map(|entry| (entry.id.as_str(), entry.poll.question.as_str()))
+            .collect();
+        assert_eq!(
+            entries,
+            vec![
+                ("budget", "Approve the budget?"),
+                ("meeting", "Meet today?")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_content_hash() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            admin: Some("admin".to_string()),
+            ..Default::default()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+        let document = HexBinary::from(Sha256::digest(b"Full proposal text").to_vec());
+        let new_poll = |content_hash: Option<HexBinary>| NewPoll {
+            poll_id: "1".to_string(),
+            question: "Adopt the proposal?".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            content_hash,
+            ..Default::default()
+        };
+        let err = execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            new_poll(Some(HexBinary::from(vec![]))),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidContentHash { max: 64 }));
+        execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            new_poll(Some(document.clone())),
+        )
+        .unwrap();
+        let err = execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            new_poll(None),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::PollIdTaken {}));
+
+        let verify = |hash: HexBinary| QueryMsg::VerifyContent {
+            poll_id: "1".to_string(),
+            hash,
+        };
+        let resp = query(deps.as_ref(), env.clone(), verify(document).into()).unwrap();
+        assert!(from_binary::<VerifyContentResponse>(&resp).unwrap().matches);
+        let tampered = HexBinary::from(Sha256::digest(b"Edited proposal text").to_vec());
+        let resp = query(deps.as_ref(), env, verify(tampered).into()).unwrap();
+        assert!(!from_binary::<VerifyContentResponse>(&resp).unwrap().matches);
+    }
+
+    #[test]
+    fn test_vote_notification() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            admin: Some("admin".to_string()),
+            notify_contract: Some("badges".to_string()),
+            ..Default::default()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+        let new_poll = NewPoll {
+            poll_id: "1".to_string(),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            ..Default::default()
+        };
+        execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            new_poll,
+        )
+        .unwrap();
+
+        let vote = ExecuteMsg::Vote {
+            poll_id: "1".to_string(),
+            vote: 1,
+            memo: Some("ship it".to_string()),
+            proof: None,
+        };
+        let resp = execute(deps.as_mut(), env.clone(), mock_info("voter", &[]), vote).unwrap();
+        let notification = WasmMsg::Execute {
+            contract_addr: "badges".to_string(),
+            msg: to_binary(&NotifyMsg::VoteCast {
+                poll_id: "1".to_string(),
+                voter: "voter".to_string(),
+                shares: Some(vec![(1, 1)]),
+                memo: Some("ship it".to_string()),
+            })
+            .unwrap(),
+            funds: vec![],
+        };
+        assert_eq!(resp.messages, vec![SubMsg::reply_on_error(notification, 0)]);
+
+        // a failing receiver does not revert the vote
+        let failed = Reply {
+            id: 0,
+            result: SubMsgResult::Err("out of badges".to_string()),
+        };
+        let resp = reply(deps.as_mut(), env, failed).unwrap();
+        assert_eq!(resp.attributes[0], attr("action", "notification failed"));
+    }
+
+    #[test]
+    fn test_ballot_query() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info("creator", &[]);
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            InstantiateMsg::default(),
+        )
+        .unwrap();
+        for (poll_id, mode) in [
+            (
+                "1",
+                VoteMode::Cumulative {
+                    budget: VoteBudget::Points(Uint128::new(10)),
+                },
+            ),
+            (
+                "2",
+                VoteMode::Ranked {
+                    method: RankedMethod::InstantRunoff,
+                },
+            ),
+        ] {
+            let new_poll = NewPoll {
+                poll_id: poll_id.to_string(),
+                question: "Where should the budget go".to_string(),
+                options: vec![
+                    String::from("Events"),
+                    String::from("Docs"),
+                    String::from("Grants"),
+                ],
+                mode: Some(mode),
+                ..Default::default()
+            };
+            execute_create_poll(deps.as_mut(), env.clone(), info.clone(), new_poll).unwrap();
+        }
+        let msg = ExecuteMsg::AllocateVotes {
+            poll_id: "1".to_string(),
+            allocations: vec![(0, Uint128::new(6)), (2, Uint128::new(4))],
+            memo: Some("mostly events".to_string()),
+            proof: None,
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("voter", &[]), msg).unwrap();
+        let msg = ExecuteMsg::RankVotes {
+            poll_id: "2".to_string(),
+            ranking: vec![2, 0],
+            memo: None,
+            proof: None,
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("voter", &[]), msg).unwrap();
+
+        let ballot = |poll_id: &str, address: &str| {
+            let msg = QueryMsg::Ballot {
+                poll_id: poll_id.to_string(),
+                address: address.to_string(),
+                proof: None,
+                viewer: None,
+            };
+            let resp: BallotResponse =
+                from_binary(&query(deps.as_ref(), env.clone(), msg.into()).unwrap()).unwrap();
+            resp.ballot
+        };
+        let share = |option: u8, label: &str, weight: u64| BallotShare {
+            option,
+            label: label.to_string(),
+            weight,
+        };
+        let split = ballot("1", "voter").unwrap();
+        assert_eq!(split.weight, 10);
+        assert_eq!(split.memo, Some("mostly events".to_string()));
+        assert_eq!(split.voted_at, env.block.time);
+        assert_eq!(
+            split.shares,
+            vec![share(0, "Events", 6), share(2, "Grants", 4)]
+        );
+        assert!(split.ranking.is_empty());
+
+        let ranked = ballot("2", "voter").unwrap();
+        assert_eq!(ranked.shares, vec![share(2, "Grants", 1)]);
+        assert_eq!(
+            ranked.ranking,
+            vec![share(2, "Grants", 1), share(0, "Events", 0)]
+        );
+        assert_eq!(ballot("2", "stranger"), None);
+    }
+
+    #[test]
+    fn test_vote_receipt() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            admin: Some("admin".to_string()),
+            receipt_nft: Some("badges".to_string()),
+            ..Default::default()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+        let new_poll = NewPoll {
+            poll_id: "1".to_string(),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            ..Default::default()
+        };
+        execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            new_poll,
+        )
+        .unwrap();
+
+        let vote = |vote| ExecuteMsg::Vote {
+            poll_id: "1".to_string(),
+            vote,
+            memo: None,
+            proof: None,
+        };
+        let resp = execute(deps.as_mut(), env.clone(), mock_info("voter", &[]), vote(1)).unwrap();
+        let receipt = WasmMsg::Execute {
+            contract_addr: "badges".to_string(),
+            msg: to_binary(&ReceiptMintMsg::Mint {
+                token_id: "1/voter".to_string(),
+                owner: "voter".to_string(),
+                token_uri: None,
+                extension: ReceiptMetadata {
+                    poll_id: "1".to_string(),
+                    options: vec![1],
+                },
+            })
+            .unwrap(),
+            funds: vec![],
+        };
+        assert_eq!(
+            resp.messages,
+            vec![SubMsg::reply_on_error(receipt, u64::MAX)]
+        );
+
+        // changing the vote keeps the receipt already minted
+        let resp = execute(deps.as_mut(), env.clone(), mock_info("voter", &[]), vote(0)).unwrap();
+        assert!(resp.messages.is_empty());
+
+        let failed = Reply {
+            id: u64::MAX,
+            result: SubMsgResult::Err("collection paused".to_string()),
+        };
+        let resp = reply(deps.as_mut(), env, failed).unwrap();
+        assert_eq!(resp.attributes[0], attr("action", "receipt failed"));
+    }
+
+    #[test]
+    fn test_min_voters() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let info = mock_info("creator", &[]);
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            InstantiateMsg::default(),
+        )
+        .unwrap();
+        let start = env.block.time;
+        let new_poll = |poll_id: &str, min_voters, max_voters| NewPoll {
+            poll_id: poll_id.to_string(),
+            question: "Fund the grants program".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            end_time: Some(start.plus_seconds(10)),
+            mode: Some(VoteMode::Burn {
+                denom: "ujuno".to_string(),
+            }),
+            min_voters: Some(min_voters),
+            max_voters,
+            ..Default::default()
+        };
+        for (min_voters, max_voters) in [(0, None), (3, Some(2))] {
+            let err = execute_create_poll(
+                deps.as_mut(),
+                env.clone(),
+                info.clone(),
+                new_poll("0", min_voters, max_voters),
+            )
+            .unwrap_err();
+            assert!(matches!(err, ContractError::InvalidMinVoters {}));
+        }
+
+        // a whale alone cannot carry the poll, however much it burns
+        let voters: [&[(&str, u128)]; 2] = [
+            &[("whale", 1000)],
+            &[("whale", 1000), ("alice", 1), ("bob", 1)],
+        ];
+        for (poll_id, voters) in ["1", "2"].into_iter().zip(voters) {
+            let msg = new_poll(poll_id, 3, None);
+            execute_create_poll(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+            for (voter, amount) in voters {
+                let vote = ExecuteMsg::Vote {
+                    poll_id: poll_id.to_string(),
+                    vote: 0,
+                    memo: None,
+                    proof: None,
+                };
+                let voter = mock_info(voter, &coins(*amount, "ujuno"));
+                execute(deps.as_mut(), env.clone(), voter, vote).unwrap();
+            }
+        }
+
+        env.block.time = start.plus_seconds(20);
+        for (poll_id, winner, status) in [("1", None, "not_met"), ("2", Some(0), "met")] {
+            let finalize = ExecuteMsg::FinalizePoll {
+                poll_id: poll_id.to_string(),
+            };
+            let resp = execute(deps.as_mut(), env.clone(), info.clone(), finalize).unwrap();
+            assert!(resp.events[0]
+                .attributes
+                .contains(&attr("min_voters", status)));
+            let result = RESULTS.load(&deps.storage, poll_id.to_string()).unwrap();
+            assert_eq!(result.winner, winner);
+            assert_eq!(result.min_voters_reached, Some(status == "met"));
+        }
+    }
+
+    #[test]
+    fn test_max_voters() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info("creator", &[]);
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            InstantiateMsg::default(),
+        )
+        .unwrap();
+        let new_poll = NewPoll {
+            poll_id: "1".to_string(),
+            question: "Who gets a whitelist spot".to_string(),
+            options: vec![String::from("Enter")],
+            max_voters: Some(2),
+            ..Default::default()
+        };
+        execute_create_poll(deps.as_mut(), env.clone(), info, new_poll).unwrap();
+        let vote = ExecuteMsg::Vote {
+            poll_id: "1".to_string(),
+            vote: 0,
+            memo: None,
+            proof: None,
+        };
+        for voter in ["voter1", "voter2"] {
+            execute(
+                deps.as_mut(),
+                env.clone(),
+                mock_info(voter, &[]),
+                vote.clone(),
+            )
+            .unwrap();
+        }
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("voter3", &[]),
+            vote.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::PollFull { max_voters: 2 }));
+
+        // voters already counted can still change their ballot
+        execute(deps.as_mut(), env, mock_info("voter1", &[]), vote).unwrap();
+    }
+
+    #[test]
+    fn test_absolute_quorum() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let info = mock_info("creator", &[]);
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            InstantiateMsg::default(),
+        )
+        .unwrap();
+        let start = env.block.time;
+        for (poll_id, quorum) in [("1", 5u128), ("2", 10)] {
+            let new_poll = NewPoll {
+                poll_id: poll_id.to_string(),
+                question: "Fund the grants program".to_string(),
+                options: vec![String::from("Yes"), String::from("No")],
+                end_time: Some(start.plus_seconds(10)),
+                mode: Some(VoteMode::Burn {
+                    denom: "ujuno".to_string(),
+                }),
+                quorum: Some(Quorum::AbsoluteWeight(Uint128::new(quorum))),
+                ..Default::default()
+            };
+            execute_create_poll(deps.as_mut(), env.clone(), info.clone(), new_poll).unwrap();
+            let vote = ExecuteMsg::Vote {
+                poll_id: poll_id.to_string(),
+                vote: 0,
+                memo: None,
+                proof: None,
+            };
+            let voter = mock_info("whale", &coins(7, "ujuno"));
+            execute(deps.as_mut(), env.clone(), voter, vote).unwrap();
+        }
+
+        env.block.time = start.plus_seconds(20);
+        for (poll_id, winner, quorum) in [("1", Some(0), "met"), ("2", None, "not_met")] {
+            let finalize = ExecuteMsg::FinalizePoll {
+                poll_id: poll_id.to_string(),
+            };
+            let resp = execute(deps.as_mut(), env.clone(), info.clone(), finalize).unwrap();
+            assert!(resp.events[0].attributes.contains(&attr("quorum", quorum)));
+            let msg = QueryMsg::FinalResult {
+                poll_id: poll_id.to_string(),
+            };
+            let resp = query(deps.as_ref(), env.clone(), msg.into()).unwrap();
+            let result: FinalResultResponse = from_binary(&resp).unwrap();
+            let result = result.result.unwrap();
+            assert_eq!(result.winner, winner);
+            assert_eq!(result.quorum_reached, Some(quorum == "met"));
+        }
+
+        // a percentage quorum needs an electorate to be a share of
+        let new_poll = NewPoll {
+            poll_id: "3".to_string(),
+            question: "Fund the grants program".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            quorum: Some(Quorum::Percentage(Decimal::percent(40))),
+            ..Default::default()
+        };
+        let err = execute_create_poll(deps.as_mut(), env, info, new_poll).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidQuorum {}));
+    }
+
+    #[test]
+    fn test_weighted_quorum() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let info = mock_info("creator", &[]);
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            InstantiateMsg::default(),
+        )
+        .unwrap();
+        // 1000 staked in total, of which the voter holds 400
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::Smart { msg, .. } => {
+                let staked = match from_binary(msg).unwrap() {
+                    StakingQueryMsg::StakedBalanceAtHeight { height, .. } => {
+                        to_binary(&StakedBalanceAtHeightResponse {
+                            balance: Uint128::new(400),
+                            height: height.unwrap(),
+                        })
+                    }
+                    StakingQueryMsg::TotalStakedAtHeight { height } => {
+                        to_binary(&TotalStakedAtHeightResponse {
+                            total: Uint128::new(1000),
+                            height: height.unwrap(),
+                        })
+                    }
+                };
+                SystemResult::Ok(ContractResult::Ok(staked.unwrap()))
+            }
+            _ => SystemResult::Err(SystemError::UnsupportedRequest {
+                kind: "wasm".to_string(),
+            }),
+        });
+        let start = env.block.time;
+        for (poll_id, share) in [("1", 40), ("2", 50)] {
+            let new_poll = NewPoll {
+                poll_id: poll_id.to_string(),
+                question: "Fund the grants program".to_string(),
+                options: vec![String::from("Yes"), String::from("No")],
+                end_time: Some(start.plus_seconds(10)),
+                mode: Some(VoteMode::Staking {
+                    contract: Addr::unchecked("stake"),
+                }),
+                quorum: Some(Quorum::Percentage(Decimal::percent(share))),
+                ..Default::default()
+            };
+            execute_create_poll(deps.as_mut(), env.clone(), info.clone(), new_poll).unwrap();
+            let vote = ExecuteMsg::Vote {
+                poll_id: poll_id.to_string(),
+                vote: 0,
+                memo: None,
+                proof: None,
+            };
+            execute(deps.as_mut(), env.clone(), mock_info("staker", &[]), vote).unwrap();
+        }
+
+        env.block.time = start.plus_seconds(20);
+        for (poll_id, met) in [("1", true), ("2", false)] {
+            let finalize = ExecuteMsg::FinalizePoll {
+                poll_id: poll_id.to_string(),
+            };
+            execute(deps.as_mut(), env.clone(), info.clone(), finalize).unwrap();
+            let msg = QueryMsg::FinalResult {
+                poll_id: poll_id.to_string(),
+            };
+            let resp = query(deps.as_ref(), env.clone(), msg.into()).unwrap();
+            let result: FinalResultResponse = from_binary(&resp).unwrap();
+            let result = result.result.unwrap();
+            assert_eq!(result.total, 400);
+            assert_eq!(result.eligible_weight, Some(1000));
+            assert_eq!(result.quorum_reached, Some(met));
+        }
+
+        // a Merkle total needs the root it describes
+        let new_poll = NewPoll {
+            poll_id: "3".to_string(),
+            question: "Fund the grants program".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            merkle_total_weight: Some(Uint128::new(1000)),
+            ..Default::default()
+        };
+        let err = execute_create_poll(deps.as_mut(), env, info, new_poll).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidMerkleRoot {}));
+    }
+
+    #[test]
+    fn test_dynamic_quorum() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let info = mock_info("creator", &[]);
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            InstantiateMsg::default(),
+        )
+        .unwrap();
+        let start = env.block.time;
+        let electorate: Vec<String> = (0..10).map(|i| format!("voter{}", i)).collect();
+        let curve = vec![
+            (Decimal::percent(20), Decimal::percent(67)),
+            (Decimal::percent(50), Decimal::percent(50)),
+        ];
+        // 2 of 3 at 30% turnout misses the supermajority; 3 of 5 at 50% passes
+        for (poll_id, yes, no) in [("1", 2, 1), ("2", 3, 2)] {
+            let new_poll = NewPoll {
+                poll_id: poll_id.to_string(),
+                question: "Amend the charter".to_string(),
+                options: vec![String::from("Yes"), String::from("No")],
+                end_time: Some(start.plus_seconds(10)),
+                allowed_voters: Some(electorate.clone()),
+                quorum: Some(Quorum::Dynamic {
+                    curve: curve.clone(),
+                }),
+                ..Default::default()
+            };
+            execute_create_poll(deps.as_mut(), env.clone(), info.clone(), new_poll).unwrap();
+            for (i, voter) in electorate.iter().take(yes + no).enumerate() {
+                let vote = ExecuteMsg::Vote {
+                    poll_id: poll_id.to_string(),
+                    vote: u8::from(i >= yes),
+                    memo: None,
+                    proof: None,
+                };
+                execute(deps.as_mut(), env.clone(), mock_info(voter, &[]), vote).unwrap();
+            }
+        }
+
+        env.block.time = start.plus_seconds(20);
+        for (poll_id, winner, threshold) in [("1", None, 67), ("2", Some(0), 50)] {
+            let finalize = ExecuteMsg::FinalizePoll {
+                poll_id: poll_id.to_string(),
+            };
+            execute(deps.as_mut(), env.clone(), info.clone(), finalize).unwrap();
+            let msg = QueryMsg::FinalResult {
+                poll_id: poll_id.to_string(),
+            };
+            let resp = query(deps.as_ref(), env.clone(), msg.into()).unwrap();
+            let result: FinalResultResponse = from_binary(&resp).unwrap();
+            let result = result.result.unwrap();
+            assert_eq!(result.winner, winner);
+            assert_eq!(result.quorum_reached, Some(true));
+            assert_eq!(result.threshold, Some(Decimal::percent(threshold)));
+        }
+
+        let new_poll = NewPoll {
+            poll_id: "3".to_string(),
+            question: "Amend the charter".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            allowed_voters: Some(electorate),
+            quorum: Some(Quorum::Dynamic {
+                curve: curve.into_iter().rev().collect(),
+            }),
+            ..Default::default()
+        };
+        let err = execute_create_poll(deps.as_mut(), env, info, new_poll).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidQuorum {}));
+    }
+
+    #[test]
+    fn test_win_threshold() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let info = mock_info("creator", &[]);
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            InstantiateMsg::default(),
+        )
+        .unwrap();
+        let start = env.block.time;
+        let supermajority = |poll_id: &str, win_threshold| NewPoll {
+            poll_id: poll_id.to_string(),
+            question: "Amend the charter".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            end_time: Some(start.plus_seconds(10)),
+            win_threshold: Some(win_threshold),
+            ..Default::default()
+        };
+        let err = execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            supermajority("0", Decimal::zero()),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidWinThreshold {}));
+
+        // 3 of 5 is short of 60%+; 2 of 3 clears it
+        for (poll_id, yes, no) in [("1", 3, 2), ("2", 2, 1)] {
+            let new_poll = supermajority(poll_id, Decimal::percent(61));
+            execute_create_poll(deps.as_mut(), env.clone(), info.clone(), new_poll).unwrap();
+            for i in 0..yes + no {
+                let vote = ExecuteMsg::Vote {
+                    poll_id: poll_id.to_string(),
+                    vote: u8::from(i >= yes),
+                    memo: None,
+                    proof: None,
+                };
+                let voter = format!("voter{}", i);
+                execute(deps.as_mut(), env.clone(), mock_info(&voter, &[]), vote).unwrap();
+            }
+        }
+
+        env.block.time = start.plus_seconds(20);
+        for (poll_id, winner) in [("1", None), ("2", Some(0))] {
+            let finalize = ExecuteMsg::FinalizePoll {
+                poll_id: poll_id.to_string(),
+            };
+            execute(deps.as_mut(), env.clone(), info.clone(), finalize).unwrap();
+            let msg = QueryMsg::FinalResult {
+                poll_id: poll_id.to_string(),
+            };
+            let resp = query(deps.as_ref(), env.clone(), msg.into()).unwrap();
+            let result = from_binary::<FinalResultResponse>(&resp)
+                .unwrap()
+                .result
+                .unwrap();
+            assert_eq!(result.winner, winner);
+            assert_eq!(result.threshold, Some(Decimal::percent(61)));
+        }
+    }
+
+    #[test]
+    fn test_clone_poll() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let info = mock_info("creator", &[]);
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            InstantiateMsg::default(),
+        )
+        .unwrap();
+        let new_poll = NewPoll {
+            poll_id: "1".to_string(),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            end_time: Some(env.block.time.plus_seconds(100)),
+            tie_break: Some(TieBreakPolicy::AdminDecides),
+            allowed_voters: Some(vec!["voter1".to_string(), "voter2".to_string()]),
+            ..Default::default()
+        };
+        execute_create_poll(deps.as_mut(), env.clone(), info.clone(), new_poll).unwrap();
+        let vote = ExecuteMsg::Vote {
+            poll_id: "1".to_string(),
+            vote: 0,
+            memo: None,
+            proof: None,
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("voter1", &[]), vote).unwrap();
+
+        env.block.time = env.block.time.plus_seconds(500);
+        let clone = |source: &str| ExecuteMsg::ClonePoll {
+            source_poll_id: source.to_string(),
+            new_poll_id: "2".to_string(),
+        };
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), clone("9")).unwrap_err();
+        assert!(matches!(err, ContractError::PollNotFound {}));
+        // only the creator or an admin may copy the poll's allowlist
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("outsider", &[]),
+            clone("1"),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let resp = execute(deps.as_mut(), env.clone(), info.clone(), clone("1")).unwrap();
+        assert!(resp.attributes.contains(&attr("cloned_from", "1")));
+        let err = execute(deps.as_mut(), env.clone(), info, clone("1")).unwrap_err();
+        assert!(matches!(err, ContractError::PollIdTaken {}));
+
+        let msg = QueryMsg::Poll {
+            poll_id: "2".to_string(),
+            locale: None,
+            viewer: None,
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg.into()).unwrap();
+        let poll: PollResponse = from_binary(&resp).unwrap();
+        let poll = poll.poll.unwrap();
+        assert_eq!(poll.question, "Should We Have a Meeting Today");
+        assert_eq!(labelled(&poll.options), vec![("Yes", 0), ("No", 0)]);
+        assert_eq!(poll.end_time, Some(env.block.time.plus_seconds(100)));
+        assert_eq!(poll.tie_break, TieBreakPolicy::AdminDecides);
+        assert_eq!(poll.electorate, Some(2));
+        assert_eq!(poll.voter_count, 0);
+    }
+
+    #[test]
+    fn test_export_ballots() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info("creator", &[]);
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            InstantiateMsg::default(),
+        )
+        .unwrap();
+        // poll 3 keys its ballots by a hash of the voter
+        for poll_id in ["1", "2", "3"] {
+            let new_poll = NewPoll {
+                poll_id: poll_id.to_string(),
+                question: "Should We Have a Meeting Today".to_string(),
+                options: vec![String::from("Yes"), String::from("No")],
+                pseudonymous: poll_id == "3",
+                ..Default::default()
+            };
+            execute_create_poll(deps.as_mut(), env.clone(), info.clone(), new_poll).unwrap();
+        }
+        for (voter, poll_id) in [
+            ("voter2", "1"),
+            ("voter1", "2"),
+            ("voter1", "1"),
+            ("voter3", "3"),
+        ] {
+            let vote = ExecuteMsg::Vote {
+                poll_id: poll_id.to_string(),
+                vote: 1,
+                memo: None,
+                proof: None,
+            };
+            execute(deps.as_mut(), env.clone(), mock_info(voter, &[]), vote).unwrap();
+        }
+
+        let export = |start_after: Option<(&str, &str)>| {
+            let msg = QueryMsg::ExportBallots {
+                start_after: start_after
+                    .map(|(voter, poll_id)| (voter.to_string(), poll_id.to_string())),
+                limit: Some(2),
+            };
+            let resp = query(deps.as_ref(), env.clone(), msg.into()).unwrap();
+            let resp: ExportBallotsResponse = from_binary(&resp).unwrap();
+            resp.ballots
+                .into_iter()
+                .map(|entry| (entry.voter.to_string(), entry.poll_id, entry.ballot.option))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            export(None),
+            vec![
+                ("voter1".to_string(), "1".to_string(), 1),
+                ("voter1".to_string(), "2".to_string(), 1),
+            ]
+        );
+        let page = export(Some(("voter1", "2")));
+        assert_eq!(page[0], ("voter2".to_string(), "1".to_string(), 1));
+        // pages can end on a pseudonymous ballot
+        let (hash, poll_id, _) = &page[1];
+        assert_eq!(poll_id, "3");
+        assert!(export(Some((hash.as_str(), "3"))).is_empty());
+    }
+
+    #[test]
+    fn test_pseudonymous_poll() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info("creator", &[]);
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            InstantiateMsg::default(),
+        )
+        .unwrap();
+        let new_poll = NewPoll {
+            poll_id: "1".to_string(),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            pseudonymous: true,
+            ..Default::default()
+        };
+        execute_create_poll(deps.as_mut(), env.clone(), info.clone(), new_poll.clone()).unwrap();
+        for vote in [0, 1] {
+            let msg = ExecuteMsg::Vote {
+                poll_id: "1".to_string(),
+                vote,
+                memo: None,
+                proof: None,
+            };
+            execute(deps.as_mut(), env.clone(), mock_info("voter1", &[]), msg).unwrap();
+        }
+
+        let msg = QueryMsg::ListVotes {
+            poll_id: "1".to_string(),
+            start_after: None,
+            limit: None,
+            viewer: None,
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg.into()).unwrap();
+        let votes: ListVotesResponse = from_binary(&resp).unwrap();
+        assert_eq!(votes.votes.len(), 1);
+        let hashed = votes.votes[0].voter.to_string();
+        assert_eq!(hashed.len(), 64);
+        assert_ne!(hashed, "voter1");
+
+        // a bare address doesn't find the ballot
+        let lookup = |proof: Option<OwnerProof>| QueryMsg::Vote {
+            poll_id: "1".to_string(),
+            address: "voter1".to_string(),
+            proof,
+            viewer: None,
+        };
+        let resp = query(deps.as_ref(), env.clone(), lookup(None).into()).unwrap();
+        let vote: VoteResponse = from_binary(&resp).unwrap();
+        assert!(vote.vote.is_none());
+        let msg = QueryMsg::VoterInfo {
+            address: "voter1".to_string(),
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg.into()).unwrap();
+        let info_resp: VoterInfoResponse = from_binary(&resp).unwrap();
+        assert_eq!(info_resp.votes_cast, 0);
+        assert!(info_resp.first_vote.is_none());
+
+        // the voter can still look up their own ballot by proving the address
+        let key = SigningKey::from_bytes(&[9u8; 32]).unwrap();
+        let pubkey = Binary::from(key.verifying_key().to_bytes().to_vec());
+        let payload = KeyRegistration {
+            chain_id: env.block.chain_id.clone(),
+            contract: env.contract.address.to_string(),
+            voter: "voter1".to_string(),
+        };
+        let signature: Signature = key.sign(&to_vec(&payload).unwrap());
+        let msg = ExecuteMsg::RegisterVotingKey {
+            pubkey: pubkey.clone(),
+            signature: Binary::from(signature.as_ref().to_vec()),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("voter1", &[]), msg).unwrap();
+        let payload = BallotLookup {
+            chain_id: env.block.chain_id.clone(),
+            contract: env.contract.address.to_string(),
+            poll_id: "1".to_string(),
+            voter: "voter1".to_string(),
+        };
+        let signature: Signature = key.sign(&to_vec(&payload).unwrap());
+        let proof = OwnerProof {
+            pubkey: pubkey.clone(),
+            signature: Binary::from(signature.as_ref().to_vec()),
+        };
+        let resp = query(
+            deps.as_ref(),
+            env.clone(),
+            lookup(Some(proof.clone())).into(),
+        )
+        .unwrap();
+        let vote: VoteResponse = from_binary(&resp).unwrap();
+        assert_eq!(vote.vote.unwrap().option, 1);
+
+        // the same proof doesn't unlock another address
+        let msg = QueryMsg::Vote {
+            poll_id: "1".to_string(),
+            address: "voter2".to_string(),
+            proof: Some(proof),
+            viewer: None,
+        };
+        query(deps.as_ref(), env.clone(), msg.into()).unwrap_err();
+        let msg = QueryMsg::ListVotes {
+            poll_id: "1".to_string(),
+            start_after: Some(hashed),
+            limit: None,
+            viewer: None,
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg.into()).unwrap();
+        let votes: ListVotesResponse = from_binary(&resp).unwrap();
+        assert!(votes.votes.is_empty());
+
+        // escrowed deposits would have to be refunded to a known address
+        let new_poll = NewPoll {
+            poll_id: "2".to_string(),
+            mode: Some(VoteMode::Cw20Deposit {
+                token: Addr::unchecked("token"),
+            }),
+            ..new_poll
+        };
+        let err = execute_create_poll(deps.as_mut(), env, info, new_poll).unwrap_err();
+        assert!(matches!(err, ContractError::PseudonymousPayout {}));
+    }
+
+    #[test]
+    fn test_snapshot_budget() {
+        let leaf = |voter: &str, weight: u128| -> [u8; 32] {
+            Sha256::digest(format!("{}{}", voter, weight).as_bytes()).into()
+        };
+        let parent = |a: [u8; 32], b: [u8; 32]| -> [u8; 32] {
+            let (first, second) = if a < b { (a, b) } else { (b, a) };
+            Sha256::digest([first, second].concat()).into()
+        };
+        let (alice, bob) = (leaf("alice", 60), leaf("bob", 40));
+        let root = parent(alice, bob);
+
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info("creator", &[]);
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            InstantiateMsg::default(),
+        )
+        .unwrap();
+        let new_poll = |merkle_root: Option<String>| NewPoll {
+            poll_id: "1".to_string(),
+            question: "Split the treasury".to_string(),
+            options: vec![String::from("Grants"), String::from("Buybacks")],
+            mode: Some(VoteMode::Cumulative {
+                budget: VoteBudget::Snapshot {},
+            }),
+            merkle_root,
+            ..Default::default()
+        };
+        let err = execute_create_poll(deps.as_mut(), env.clone(), info.clone(), new_poll(None))
+            .unwrap_err();
+        assert!(matches!(err, ContractError::SnapshotRootRequired {}));
+        let root = Some(hex::encode(root));
+        execute_create_poll(deps.as_mut(), env.clone(), info, new_poll(root)).unwrap();
+
+        let allocate =
+            |allocations: &[(u8, u128)], proof: Option<MerkleProof>| ExecuteMsg::AllocateVotes {
+                poll_id: "1".to_string(),
+                allocations: allocations
+                    .iter()
+                    .map(|(option, amount)| (*option, Uint128::new(*amount)))
+                    .collect(),
+                memo: None,
+                proof,
+            };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("bob", &[]),
+            allocate(&[(0, 40)], None),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::MerkleProofRequired {}));
+
+        // the budget is the proven snapshot balance, not a live one
+        let proof = MerkleProof {
+            weight: Uint128::new(60),
+            proof: vec![hex::encode(bob)],
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("alice", &[]),
+            allocate(&[(0, 40)], Some(proof.clone())),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::AllocationMismatch { budget } if budget == Uint128::new(60)
+        ));
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("alice", &[]),
+            allocate(&[(0, 40), (1, 20)], Some(proof)),
+        )
+        .unwrap();
+
+        let msg = QueryMsg::Poll {
+            poll_id: "1".to_string(),
+            locale: None,
+            viewer: None,
+        };
+        let resp = query(deps.as_ref(), env, msg.into()).unwrap();
+        let poll: PollResponse = from_binary(&resp).unwrap();
+        assert_eq!(
+            labelled(&poll.poll.unwrap().options),
+            vec![("Grants", 40), ("Buybacks", 20)]
+        );
+    }
+
+    #[test]
+    fn test_sponsor_poll() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            admin: Some("admin".to_string()),
+            sponsors: Some(vec!["sponsor1".to_string(), "sponsor2".to_string()]),
+            required_sponsors: Some(2),
+            min_voting_period: Some(3600),
+            ..Default::default()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+        let new_poll = NewPoll {
+            poll_id: "1".to_string(),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            ..Default::default()
+        };
+        execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            new_poll,
+        )
+        .unwrap();
+        let vote = ExecuteMsg::Vote {
+            poll_id: "1".to_string(),
+            vote: 0,
+            memo: None,
+            proof: None,
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("voter", &[]),
+            vote.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::PollNotOpen {}));
+
+        let sponsor = ExecuteMsg::SponsorPoll {
+            poll_id: "1".to_string(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            sponsor.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("sponsor1", &[]),
+            sponsor.clone(),
+        )
+        .unwrap();
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("sponsor1", &[]),
+            sponsor.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::AlreadySponsored {}));
+        let resp = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("sponsor2", &[]),
+            sponsor.clone(),
+        )
+        .unwrap();
+        assert!(resp.attributes.contains(&attr("status", "open")));
+
+        execute(deps.as_mut(), env.clone(), mock_info("voter", &[]), vote).unwrap();
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("sponsor1", &[]),
+            sponsor,
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::PollNotDraft {}));
+
+        // a draft sponsored too close to its end keeps its full duration
+        let new_poll = NewPoll {
+            poll_id: "2".to_string(),
+            question: "Should We Have a Meeting Tomorrow".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            end_time: Some(env.block.time.plus_seconds(7200)),
+            ..Default::default()
+        };
+        execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            new_poll,
+        )
+        .unwrap();
+        let sponsor = ExecuteMsg::SponsorPoll {
+            poll_id: "2".to_string(),
+        };
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("sponsor1", &[]),
+            sponsor.clone(),
+        )
+        .unwrap();
+        let mut later = env.clone();
+        later.block.time = env.block.time.plus_seconds(5000);
+        later.block.height += 1000;
+        execute(
+            deps.as_mut(),
+            later.clone(),
+            mock_info("sponsor2", &[]),
+            sponsor,
+        )
+        .unwrap();
+        let poll = POLL.load(&deps.storage, "2".to_string()).unwrap();
+        assert_eq!(poll.status, PollStatus::Open);
+        assert_eq!(poll.start_time, later.block.time);
+        let new_end = later.block.time.plus_seconds(7200);
+        assert_eq!(poll.end_time, Some(new_end));
+        let indexed = POLL_ENDS.has(&deps.storage, (new_end.seconds(), "2".to_string()));
+        assert!(indexed);
+    }
+
+    #[test]
+    fn test_option_metadata() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info("creator", &[]);
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            InstantiateMsg::default(),
+        )
+        .unwrap();
+        let new_poll = |option_metadata: Vec<OptionMetadata>| NewPoll {
+            poll_id: "1".to_string(),
+            question: "Who should chair the council".to_string(),
+            options: vec![String::from("Ada"), String::from("Grace")],
+            option_metadata: Some(option_metadata),
+            ..Default::default()
+        };
+        let ada = OptionMetadata {
+            description: Some("Incumbent".to_string()),
+            image_uri: Some("ipfs://ada.png".to_string()),
+        };
+
+        let err = execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            new_poll(vec![ada.clone()]),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidOptionMetadata { .. }));
+        let rambling = OptionMetadata {
+            description: Some("x".repeat(501)),
+            image_uri: None,
+        };
+        let err = execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            new_poll(vec![ada.clone(), rambling]),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidOptionMetadata { .. }));
+
+        execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            info,
+            new_poll(vec![ada, OptionMetadata::default()]),
+        )
+        .unwrap();
+        let msg = QueryMsg::Poll {
+            poll_id: "1".to_string(),
+            locale: None,
+            viewer: None,
+        };
+        let resp = query(deps.as_ref(), env, msg.into()).unwrap();
+        let poll: PollResponse = from_binary(&resp).unwrap();
+        let options = poll.poll.unwrap().options;
+        assert_eq!(options[0].description.as_deref(), Some("Incumbent"));
+        assert_eq!(options[0].image_uri.as_deref(), Some("ipfs://ada.png"));
+        assert_eq!(options[1].description, None);
+    }
+
+    #[test]
+    fn test_localized_poll() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info("creator", &[]);
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            InstantiateMsg::default(),
+        )
+        .unwrap();
+        let french = LocalizedText {
+            question: "Faut-il se réunir aujourd'hui".to_string(),
+            options: vec![String::from("Oui"), String::from("Non")],
+        };
+        let new_poll = |default_locale: Option<&str>, options: Vec<String>| NewPoll {
+            poll_id: "1".to_string(),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            default_locale: default_locale.map(String::from),
+            translations: Some(BTreeMap::from([(
+                "fr".to_string(),
+                LocalizedText {
+                    options,
+                    ..french.clone()
+                },
+            )])),
+            ..Default::default()
+        };
+        let err = execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            new_poll(None, french.options.clone()),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidTranslation { .. }));
+        let err = execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            new_poll(Some("en"), vec![String::from("Oui")]),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidTranslation { .. }));
+        execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            info,
+            new_poll(Some("en"), french.options.clone()),
+        )
+        .unwrap();
+
+        let poll = |locale: Option<&str>| {
+            let msg = QueryMsg::Poll {
+                poll_id: "1".to_string(),
+                locale: locale.map(String::from),
+                viewer: None,
+            };
+            let resp = query(deps.as_ref(), env.clone(), msg.into()).unwrap();
+            let resp: PollResponse = from_binary(&resp).unwrap();
+            resp.poll.unwrap()
+        };
+        let localized = poll(Some("fr"));
+        assert_eq!(localized.question, french.question);
+        assert_eq!(labelled(&localized.options), vec![("Oui", 0), ("Non", 0)]);
+        // unknown locales fall back to the default text
+        for locale in [None, Some("de")] {
+            let poll = poll(locale);
+            assert_eq!(poll.question, "Should We Have a Meeting Today");
+            assert_eq!(labelled(&poll.options), vec![("Yes", 0), ("No", 0)]);
+        }
+    }
+
+    #[test]
+    fn test_voters_for_option() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info("creator", &[]);
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            InstantiateMsg::default(),
+        )
+        .unwrap();
+        let new_poll = NewPoll {
+            poll_id: "1".to_string(),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            ..Default::default()
+        };
+        execute_create_poll(deps.as_mut(), env.clone(), info, new_poll).unwrap();
+        for (voter, vote) in [("voter1", 0), ("voter2", 1), ("voter3", 0), ("voter1", 1)] {
+            let msg = ExecuteMsg::Vote {
+                poll_id: "1".to_string(),
+                vote,
+                memo: None,
+                proof: None,
+            };
+            execute(deps.as_mut(), env.clone(), mock_info(voter, &[]), msg).unwrap();
+        }
+
+        let voters = |option: u8, start_after: Option<&str>| {
+            let msg = QueryMsg::VotersForOption {
+                poll_id: "1".to_string(),
+                option,
+                start_after: start_after.map(String::from),
+                limit: None,
+                viewer: None,
+            };
+            let resp = query(deps.as_ref(), env.clone(), msg.into()).unwrap();
+            let resp: VotersForOptionResponse = from_binary(&resp).unwrap();
+            resp.voters
+                .into_iter()
+                .map(|entry| (entry.voter.to_string(), entry.weight))
+                .collect::<Vec<_>>()
+        };
+        // voter1 moved from Yes to No
+        assert_eq!(voters(0, None), vec![("voter3".to_string(), 1)]);
+        assert_eq!(
+            voters(1, None),
+            vec![("voter1".to_string(), 1), ("voter2".to_string(), 1)]
+        );
+        assert_eq!(voters(1, Some("voter1")), vec![("voter2".to_string(), 1)]);
+    }
+
+    #[test]
+    fn test_initial_polls() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let seed = |poll_id: &str, options: usize| NewPoll {
+            poll_id: poll_id.to_string(),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: (0..options).map(|i| format!("Option {}", i)).collect(),
+            ..Default::default()
+        };
+        let msg = InstantiateMsg {
+            admin: Some("dao".to_string()),
+            initial_polls: Some(vec![seed("1", 2), seed("2", 256)]),
+            ..Default::default()
+        };
+        let err =
+            instantiate(deps.as_mut(), env.clone(), mock_info("factory", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::TooManyPollOptions {}));
+
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            admin: Some("dao".to_string()),
+            creation_deposit: Some(coin(100, "ujuno")),
+            initial_polls: Some(vec![seed("1", 2), seed("2", 3)]),
+            ..Default::default()
+        };
+        let resp = instantiate(deps.as_mut(), env.clone(), mock_info("factory", &[]), msg).unwrap();
+        assert_eq!(resp.attributes[0], attr("initial_polls", "2"));
+        let msg = QueryMsg::Poll {
+            poll_id: "2".to_string(),
+            locale: None,
+            viewer: None,
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg.into()).unwrap();
+        let poll = from_binary::<PollResponse>(&resp).unwrap().poll.unwrap();
+        assert_eq!(poll.admin, Addr::unchecked("dao"));
+        assert_eq!(poll.status, PollStatus::Open);
+        assert_eq!(poll.options.len(), 3);
+        assert_eq!(poll.creation_deposit, None);
+
+        let vote = ExecuteMsg::Vote {
+            poll_id: "1".to_string(),
+            vote: 0,
+            memo: None,
+            proof: None,
+        };
+        execute(deps.as_mut(), env, mock_info("voter", &[]), vote).unwrap();
+    }
+
+    #[test]
+    fn test_voters_only_results() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info("creator", &[]);
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            InstantiateMsg::default(),
+        )
+        .unwrap();
+        let new_poll = NewPoll {
+            poll_id: "1".to_string(),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            voters_only_results: true,
+            ..Default::default()
+        };
+        execute_create_poll(deps.as_mut(), env.clone(), info.clone(), new_poll).unwrap();
+        let vote = ExecuteMsg::Vote {
+            poll_id: "1".to_string(),
+            vote: 0,
+            memo: None,
+            proof: None,
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("voter1", &[]), vote).unwrap();
+
+        let poll = |deps: Deps, viewer: Option<&str>| {
+            let msg = QueryMsg::Poll {
+                poll_id: "1".to_string(),
+                locale: None,
+                viewer: viewer.map(String::from),
+            };
+            let resp = query(deps, env.clone(), msg.into()).unwrap();
+            from_binary::<PollResponse>(&resp).unwrap()
+        };
+        let list_votes = |deps: Deps, viewer: Option<&str>| {
+            let msg = QueryMsg::ListVotes {
+                poll_id: "1".to_string(),
+                start_after: None,
+                limit: None,
+                viewer: viewer.map(String::from),
+            };
+            query(deps, env.clone(), msg.into())
+        };
+        let ballot = |deps: Deps, viewer: Option<&str>| {
+            let msg = QueryMsg::Ballot {
+                poll_id: "1".to_string(),
+                address: "voter1".to_string(),
+                proof: None,
+                viewer: viewer.map(String::from),
+            };
+            query(deps, env.clone(), msg.into())
+        };
+        for viewer in [None, Some("voter2")] {
+            let resp = poll(deps.as_ref(), viewer);
+            assert!(resp.results_hidden);
+            assert_eq!(
+                labelled(&resp.poll.unwrap().options),
+                vec![("Yes", 0), ("No", 0)]
+            );
+            list_votes(deps.as_ref(), viewer).unwrap_err();
+            ballot(deps.as_ref(), viewer).unwrap_err();
+        }
+        let resp = poll(deps.as_ref(), Some("voter1"));
+        assert!(!resp.results_hidden);
+        assert_eq!(
+            labelled(&resp.poll.unwrap().options),
+            vec![("Yes", 1), ("No", 0)]
+        );
+        list_votes(deps.as_ref(), Some("voter1")).unwrap();
+        ballot(deps.as_ref(), Some("voter1")).unwrap();
+
+        // the cw3 view has no viewer, so it stays hidden
+        let msg = Cw3QueryMsg::Vote {
+            proposal_id: 1,
+            voter: "voter1".to_string(),
+        };
+        query(deps.as_ref(), env.clone(), msg.clone().into()).unwrap_err();
+        let list = Cw3QueryMsg::ListVotes {
+            proposal_id: 1,
+            start_after: None,
+            limit: None,
+        };
+        query(deps.as_ref(), env.clone(), list.into()).unwrap_err();
+        let proposal = Cw3QueryMsg::Proposal { proposal_id: 1 };
+        let resp = query(deps.as_ref(), env.clone(), proposal.into()).unwrap();
+        let proposal: Cw3ProposalResponse = from_binary(&resp).unwrap();
+        assert!(matches!(
+            proposal.threshold,
+            Cw3ThresholdResponse::AbsoluteCount {
+                total_weight: 0,
+                ..
+            }
+        ));
+
+        // everything is public once the poll closes
+        let close = ExecuteMsg::ClosePoll {
+            poll_id: "1".to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), info, close).unwrap();
+        let resp = poll(deps.as_ref(), None);
+        assert_eq!(
+            labelled(&resp.poll.unwrap().options),
+            vec![("Yes", 1), ("No", 0)]
+        );
+        list_votes(deps.as_ref(), None).unwrap();
+        query(deps.as_ref(), env.clone(), msg.into()).unwrap();
+    }
+
+    #[test]
+    fn test_results_after_quorum() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info("creator", &[]);
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            InstantiateMsg::default(),
+        )
+        .unwrap();
+        let mut new_poll = NewPoll {
+            poll_id: "1".to_string(),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            results_after_quorum: true,
+            ..Default::default()
+        };
+        let err = execute_create_poll(deps.as_mut(), env.clone(), info.clone(), new_poll.clone())
+            .unwrap_err();
+        assert!(matches!(err, ContractError::QuorumRequired {}));
+        new_poll.quorum = Some(Quorum::AbsoluteWeight(Uint128::new(2)));
+        execute_create_poll(deps.as_mut(), env.clone(), info, new_poll).unwrap();
+
+        let vote = |deps: &mut cosmwasm_std::OwnedDeps<_, _, _>, voter: &str| {
+            let msg = ExecuteMsg::Vote {
+                poll_id: "1".to_string(),
+                vote: 0,
+                memo: None,
+                proof: None,
+            };
+            execute(deps.as_mut(), env.clone(), mock_info(voter, &[]), msg).unwrap();
+        };
+        let poll = |deps: Deps, viewer: Option<&str>| {
+            let msg = QueryMsg::Poll {
+                poll_id: "1".to_string(),
+                locale: None,
+                viewer: viewer.map(String::from),
+            };
+            let resp = query(deps, env.clone(), msg.into()).unwrap();
+            from_binary::<PollResponse>(&resp).unwrap()
+        };
+
+        // voting does not unlock the results before the quorum is met
+        vote(&mut deps, "voter1");
+        for viewer in [None, Some("voter1")] {
+            let resp = poll(deps.as_ref(), viewer);
+            assert!(resp.results_hidden);
+            assert_eq!(
+                labelled(&resp.poll.unwrap().options),
+                vec![("Yes", 0), ("No", 0)]
+            );
+        }
+
+        vote(&mut deps, "voter2");
+        let resp = poll(deps.as_ref(), None);
+        assert!(!resp.results_hidden);
+        assert_eq!(
+            labelled(&resp.poll.unwrap().options),
+            vec![("Yes", 2), ("No", 0)]
+        );
+    }
+
+    #[test]
+    fn test_vote_by_index() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info("creator", &[]);
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            InstantiateMsg::default(),
+        )
+        .unwrap();
+        let new_poll = NewPoll {
+            poll_id: "1".to_string(),
+            question: "Favourite fruit".to_string(),
+            options: vec![String::from("🍎 "), String::from("🍐")],
+            ..Default::default()
+        };
+        execute_create_poll(deps.as_mut(), env.clone(), info.clone(), new_poll).unwrap();
+
+        let vote = |option_index| ExecuteMsg::VoteByIndex {
+            poll_id: "1".to_string(),
+            option_index,
+            memo: None,
+            proof: None,
+        };
+        let err =
+            execute(deps.as_mut(), env.clone(), mock_info("voter", &[]), vote(2)).unwrap_err();
+        assert!(matches!(err, ContractError::OptionNotFound {}));
+        execute(deps.as_mut(), env.clone(), mock_info("voter", &[]), vote(1)).unwrap();
+
+        let voted = |deps: Deps, address: &str| {
+            let msg = QueryMsg::Vote {
+                poll_id: "1".to_string(),
+                address: address.to_string(),
+                proof: None,
+                viewer: None,
+            };
+            let resp = query(deps, mock_env(), msg.into()).unwrap();
+            let vote: VoteResponse = from_binary(&resp).unwrap();
+            vote.vote.unwrap().option
+        };
+        assert_eq!(voted(deps.as_ref(), "voter"), 1);
+
+        // positions follow the display order
+        let set_order = |shuffle| ExecuteMsg::SetDisplayOrder {
+            poll_id: "1".to_string(),
+            order: vec![1, 0],
+            shuffle,
+        };
+        execute(deps.as_mut(), env.clone(), info.clone(), set_order(false)).unwrap();
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("voter2", &[]),
+            vote(0),
+        )
+        .unwrap();
+        assert_eq!(voted(deps.as_ref(), "voter2"), 1);
+
+        execute(deps.as_mut(), env.clone(), info, set_order(true)).unwrap();
+        let err = execute(deps.as_mut(), env, mock_info("voter3", &[]), vote(0)).unwrap_err();
+        assert!(matches!(err, ContractError::ShuffledOptions {}));
+    }
+
+    #[test]
+    fn test_revote_tally_delta() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            admin: Some("admin".to_string()),
+            ..Default::default()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+        let new_poll = NewPoll {
+            poll_id: "1".to_string(),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            ..Default::default()
+        };
+        execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            new_poll,
+        )
+        .unwrap();
+        let vote = |vote: u8| ExecuteMsg::Vote {
+            poll_id: "1".to_string(),
+            vote,
+            memo: None,
+            proof: None,
+        };
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("voter1", &[]),
+            vote(0),
+        )
+        .unwrap();
+        let resp = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("voter2", &[]),
+            vote(0),
+        )
+        .unwrap();
+        let delta: TallyDelta = from_binary(&resp.data.unwrap()).unwrap();
+        assert!(delta.decremented.is_empty());
+        assert_eq!(
+            delta.incremented,
+            vec![OptionTally {
+                option: 0,
+                votes: 2
+            }]
+        );
+
+        let resp = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("voter2", &[]),
+            vote(1),
+        )
+        .unwrap();
+        assert_eq!(
+            resp.events,
+            vec![Event::new("vote_tally").add_attributes(vec![
+                ("poll_id", "1"),
+                ("decremented", "0"),
+                ("decremented_votes", "1"),
+                ("incremented", "1"),
+                ("incremented_votes", "1"),
+            ])]
+        );
+        let delta: TallyDelta = from_binary(&resp.data.unwrap()).unwrap();
+        assert_eq!(
+            delta,
+            TallyDelta {
+                poll_id: "1".to_string(),
+                decremented: vec![OptionTally {
+                    option: 0,
+                    votes: 1
+                }],
+                incremented: vec![OptionTally {
+                    option: 1,
+                    votes: 1
+                }],
+            }
+        );
+
+        // revoting for the same option moves nothing
+        let resp = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("voter2", &[]),
+            vote(1),
+        )
+        .unwrap();
+        let delta: TallyDelta = from_binary(&resp.data.unwrap()).unwrap();
+        assert!(delta.decremented.is_empty() && delta.incremented.is_empty());
+
+        // polls hiding their results don't even name the options that moved
+        let new_poll = NewPoll {
+            poll_id: "2".to_string(),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            voters_only_results: true,
+            ..Default::default()
+        };
+        execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            new_poll,
+        )
+        .unwrap();
+        let msg = ExecuteMsg::Vote {
+            poll_id: "2".to_string(),
+            vote: 1,
+            memo: None,
+            proof: None,
+        };
+        let resp = execute(deps.as_mut(), env, mock_info("voter1", &[]), msg).unwrap();
+        assert_eq!(
+            resp.events,
+            vec![Event::new("vote_tally").add_attribute("poll_id", "2")]
+        );
+        let delta: TallyDelta = from_binary(&resp.data.unwrap()).unwrap();
+        assert!(delta.decremented.is_empty() && delta.incremented.is_empty());
+    }
+
+    #[test]
+    fn test_revote_window() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let info = mock_info("creator", &[]);
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            InstantiateMsg::default(),
+        )
+        .unwrap();
+        let new_poll = NewPoll {
+            poll_id: "1".to_string(),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            revote_window: Some(RevoteWindow::AfterFirstVote { seconds: 3600 }),
+            ..Default::default()
+        };
+        execute_create_poll(deps.as_mut(), env.clone(), info, new_poll).unwrap();
+        let vote = |vote| ExecuteMsg::Vote {
+            poll_id: "1".to_string(),
+            vote,
+            memo: None,
+            proof: None,
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("voter", &[]), vote(0)).unwrap();
+
+        // changing within the window keeps the first vote's time
+        env.block.time = env.block.time.plus_seconds(1800);
+        execute(deps.as_mut(), env.clone(), mock_info("voter", &[]), vote(1)).unwrap();
+        env.block.time = env.block.time.plus_seconds(1800);
+        let err =
+            execute(deps.as_mut(), env.clone(), mock_info("voter", &[]), vote(0)).unwrap_err();
+        assert!(matches!(err, ContractError::RevoteWindowClosed {}));
+        // repeating the same choice is not a change
+        execute(deps.as_mut(), env.clone(), mock_info("voter", &[]), vote(1)).unwrap();
+
+        let msg = QueryMsg::Vote {
+            poll_id: "1".to_string(),
+            address: "voter".to_string(),
+            proof: None,
+            viewer: None,
+        };
+        let resp = query(deps.as_ref(), env, msg.into()).unwrap();
+        let ballot = from_binary::<VoteResponse>(&resp).unwrap().vote.unwrap();
+        assert_eq!(ballot.option, 1);
+        assert_eq!(ballot.voted_at, mock_env().block.time);
+    }
+
+    #[test]
+    fn test_revote_window_before_close() {
+        let window = RevoteWindow::BeforeClose { seconds: 100 };
+        let now = Timestamp::from_seconds(1000);
+        assert!(window.is_open(now, None, now));
+        assert!(window.is_open(now, Some(now.plus_seconds(101)), now));
+        assert!(!window.is_open(now, Some(now.plus_seconds(100)), now));
+    }
+
+    #[test]
+    fn test_crank() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let info = mock_info("creator", &[]);
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            InstantiateMsg::default(),
+        )
+        .unwrap();
+        for (poll_id, end_time) in [
+            ("1", Some(100)),
+            ("2", None),
+            ("3", Some(100)),
+            ("4", Some(200)),
+        ] {
+            let new_poll = NewPoll {
+                poll_id: poll_id.to_string(),
+                question: "Should We Have a Meeting Today".to_string(),
+                options: vec![String::from("Yes"), String::from("No")],
+                end_time: end_time.map(|seconds| env.block.time.plus_seconds(seconds)),
+                ..Default::default()
+            };
+            execute_create_poll(deps.as_mut(), env.clone(), info.clone(), new_poll).unwrap();
+        }
+        env.block.time = env.block.time.plus_seconds(150);
+
+        let status = |deps: Deps, poll_id: &str| {
+            let msg = QueryMsg::Poll {
+                poll_id: poll_id.to_string(),
+                locale: None,
+                viewer: None,
+            };
+            let resp: PollResponse =
+                from_binary(&query(deps, env.clone(), msg.into()).unwrap()).unwrap();
+            resp.poll.unwrap().status
+        };
+        // queries see the deadline before the poll is closed
+        assert_eq!(status(deps.as_ref(), "1"), PollStatus::Closed);
+        assert_eq!(status(deps.as_ref(), "4"), PollStatus::Open);
+
+        let msg = ExecuteMsg::Crank { limit: Some(1) };
+        let resp = execute(deps.as_mut(), env.clone(), mock_info("anyone", &[]), msg).unwrap();
+        assert_eq!(
+            resp.attributes,
+            vec![attr("action", "crank"), attr("closed", "1")]
+        );
+        let msg = ExecuteMsg::Crank { limit: None };
+        let resp = execute(deps.as_mut(), env.clone(), mock_info("anyone", &[]), msg).unwrap();
+        assert_eq!(
+            resp.attributes,
+            vec![attr("action", "crank"), attr("closed", "3")]
+        );
+        assert_eq!(status(deps.as_ref(), "2"), PollStatus::Open);
+        // closed polls stay indexed for `FinalizeExpired`
+        let ends = POLL_ENDS
+            .keys(&deps.storage, None, None, Order::Ascending)
+            .count();
+        assert_eq!(ends, 3);
+
+        // ...without taking up the next crank's limit
+        env.block.time = env.block.time.plus_seconds(100);
+        let msg = ExecuteMsg::Crank { limit: Some(1) };
+        let resp = execute(deps.as_mut(), env.clone(), mock_info("anyone", &[]), msg).unwrap();
+        assert_eq!(
+            resp.attributes,
+            vec![attr("action", "crank"), attr("closed", "4")]
+        );
+    }
+
+    #[test]
+    fn test_finalize_expired() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let info = mock_info("creator", &[]);
+        let msg = InstantiateMsg {
+            randomness_proxy: Some("nois".to_string()),
+            ..Default::default()
+        };
+        instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        for (poll_id, end_time) in [
+            ("1", Some(100)),
+            ("2", None),
+            ("3", Some(120)),
+            ("4", Some(130)),
+        ] {
+            let new_poll = NewPoll {
+                poll_id: poll_id.to_string(),
+                question: "Should We Have a Meeting Today".to_string(),
+                options: vec![String::from("Yes"), String::from("No")],
+                end_time: end_time.map(|seconds| env.block.time.plus_seconds(seconds)),
+                ..Default::default()
+            };
+            execute_create_poll(deps.as_mut(), env.clone(), info.clone(), new_poll).unwrap();
+        }
+        // a later deadline leaves a stale entry behind
+        let msg = ExecuteMsg::ExtendDeadline {
+            poll_id: "4".to_string(),
+            new_end: env.block.time.plus_seconds(500),
+        };
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        env.block.time = env.block.time.plus_seconds(150);
+
+        let msg = ExecuteMsg::FinalizeExpired { limit: Some(1) };
+        let resp = execute(deps.as_mut(), env.clone(), mock_info("anyone", &[]), msg).unwrap();
+        assert_eq!(
+            resp.attributes,
+            vec![attr("action", "finalize expired"), attr("finalized", "1")]
+        );
+        assert_eq!(resp.events.len(), 1);
+        assert_eq!(resp.events[0].ty, "poll_finalized");
+
+        let msg = ExecuteMsg::FinalizeExpired { limit: None };
+        let resp = execute(deps.as_mut(), env.clone(), mock_info("anyone", &[]), msg).unwrap();
+        assert_eq!(
+            resp.attributes,
+            vec![attr("action", "finalize expired"), attr("finalized", "3")]
+        );
+        for poll_id in ["1", "3"] {
+            assert!(RESULTS.has(&deps.storage, poll_id.to_string()));
+        }
+
+        // a poll that cannot be finalized is skipped, not fatal to the batch
+        let new_poll = |poll_id: &str| NewPoll {
+            poll_id: poll_id.to_string(),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            end_time: Some(env.block.time.plus_seconds(10)),
+            ..Default::default()
+        };
+        for poll_id in ["5", "6"] {
+            execute_create_poll(
+                deps.as_mut(),
+                env.clone(),
+                mock_info("creator", &[]),
+                new_poll(poll_id),
+            )
+            .unwrap();
+        }
+        // the crank can't pay for randomness, so raffles are left alone
+        let raffle = NewPoll {
+            raffle: true,
+            ..new_poll("7")
+        };
+        execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            raffle,
+        )
+        .unwrap();
+        let mut poll = POLL.load(&deps.storage, "5".to_string()).unwrap();
+        poll.status = PollStatus::Closed;
+        poll.closed_at = Some(env.block.time.plus_seconds(1000));
+        POLL.save(deps.as_mut().storage, "5".to_string(), &poll)
+            .unwrap();
+        env.block.time = env.block.time.plus_seconds(20);
+        let msg = ExecuteMsg::FinalizeExpired { limit: None };
+        let resp = execute(deps.as_mut(), env.clone(), mock_info("anyone", &[]), msg).unwrap();
+        assert_eq!(
+            resp.attributes,
+            vec![
+                attr("action", "finalize expired"),
+                attr("failed", "5"),
+                attr("finalized", "6"),
+                attr("skipped", "7"),
+            ]
+        );
+        for poll_id in ["2", "4", "5", "7"] {
+            assert!(!RESULTS.has(&deps.storage, poll_id.to_string()));
+        }
+        // only poll 4's current deadline is still indexed
+        let ends = POLL_ENDS
+            .keys(&deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()
+            .unwrap();
+        assert_eq!(ends.len(), 1);
+        assert_eq!(ends[0].1, "4");
+    }
+
+    #[test]
+    fn test_prune_ballots() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            admin: Some("admin".to_string()),
+            ..Default::default()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+        let new_poll = NewPoll {
+            poll_id: "1".to_string(),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            ..Default::default()
+        };
+        execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            new_poll,
+        )
+        .unwrap();
+        for voter in ["voter1", "voter2", "voter3"] {
+            let msg = ExecuteMsg::Vote {
+                poll_id: "1".to_string(),
+                vote: 0,
+                memo: None,
+                proof: None,
+            };
+            execute(deps.as_mut(), env.clone(), mock_info(voter, &[]), msg).unwrap();
+        }
+
+        let prune = ExecuteMsg::PruneBallots {
+            poll_id: "1".to_string(),
+            limit: Some(2),
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            prune.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::PollNotArchived {}));
+
+        let archive = ExecuteMsg::ArchivePoll {
+            poll_id: "1".to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("admin", &[]), archive).unwrap();
+        let resp = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            prune.clone(),
+        )
+        .unwrap();
+        assert_eq!(
+            resp.attributes[2..],
+            [
+                attr("pruned", "2"),
+                attr("remaining", "1"),
+                attr("more", "true")
+            ]
+        );
+        let resp = execute(deps.as_mut(), env, mock_info("anyone", &[]), prune).unwrap();
+        assert_eq!(
+            resp.attributes[2..],
+            [
+                attr("pruned", "1"),
+                attr("remaining", "0"),
+                attr("more", "false")
+            ]
+        );
+        assert!(OPTION_VOTERS.is_empty(&deps.storage));
+        for voter in ["voter1", "voter2", "voter3"] {
+            let key = (Addr::unchecked(voter), "1".to_string());
+            assert!(!BALLOT.has(&deps.storage, key));
+        }
+    }
+
+    #[test]
+    fn test_status_at_block() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let info = mock_info("creator", &[]);
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            InstantiateMsg::default(),
+        )
+        .unwrap();
+        let end_time = env.block.time.plus_seconds(100);
+        let new_poll = NewPoll {
+            poll_id: "1".to_string(),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            end_time: Some(end_time),
+            voters_only_results: true,
+            ..Default::default()
+        };
+        execute_create_poll(deps.as_mut(), env.clone(), info, new_poll).unwrap();
+        let vote = ExecuteMsg::Vote {
+            poll_id: "1".to_string(),
+            vote: 0,
+            memo: None,
+            proof: None,
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("voter", &[]), vote).unwrap();
+        env.block.time = end_time;
+
+        // the deadline alone closes the poll and reveals its results
+        let resp = query(deps.as_ref(), env.clone(), QueryMsg::AllPoll {}.into()).unwrap();
+        let resp: AllPollResponse = from_binary(&resp).unwrap();
+        let entry = &resp.polls[0];
+        assert_eq!(entry.poll.status, PollStatus::Closed);
+        assert_eq!(entry.poll.closed_at, Some(end_time));
+        assert!(!entry.results_hidden);
+        assert_eq!(entry.poll.options[0].votes, 1);
+
+        let msg = QueryMsg::ListVotes {
+            poll_id: "1".to_string(),
+            start_after: None,
+            limit: None,
+            viewer: None,
+        };
+        let resp = query(deps.as_ref(), env, msg.into()).unwrap();
+        let votes: ListVotesResponse = from_binary(&resp).unwrap();
+        assert_eq!(votes.votes.len(), 1);
+    }
+
+    #[test]
+    fn test_ranked_poll() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let info = mock_info("creator", &[]);
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            InstantiateMsg::default(),
+        )
+        .unwrap();
+        let new_poll = NewPoll {
+            poll_id: "1".to_string(),
+            question: "Where should we meet".to_string(),
+            options: vec![
+                String::from("Park"),
+                String::from("Cafe"),
+                String::from("Library"),
+            ],
+            mode: Some(VoteMode::Ranked {
+                method: RankedMethod::InstantRunoff,
+            }),
+            ..Default::default()
+        };
+        execute_create_poll(deps.as_mut(), env.clone(), info.clone(), new_poll).unwrap();
+
+        let rank = |ranking: Vec<u8>| ExecuteMsg::RankVotes {
+            poll_id: "1".to_string(),
+            ranking,
+            memo: None,
+            proof: None,
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("voter1", &[]),
+            rank(vec![0, 0]),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::DuplicateOption {}));
+        let vote = ExecuteMsg::Vote {
+            poll_id: "1".to_string(),
+            vote: 0,
+            memo: None,
+            proof: None,
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info("voter1", &[]), vote).unwrap_err();
+        assert!(matches!(err, ContractError::RankingRequired {}));
+
+        for (voter, ranking) in [
+            ("voter1", vec![0]),
+            ("voter2", vec![0, 2]),
+            ("voter3", vec![1]),
+            ("voter4", vec![1, 0]),
+            ("voter5", vec![2, 1]),
+        ] {
+            execute(
+                deps.as_mut(),
+                env.clone(),
+                mock_info(voter, &[]),
+                rank(ranking),
+            )
+            .unwrap();
+        }
+        let close = ExecuteMsg::ClosePoll {
+            poll_id: "1".to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), info.clone(), close).unwrap();
+        env.block.time = env.block.time.plus_seconds(86400);
+        let finalize = ExecuteMsg::FinalizePoll {
+            poll_id: "1".to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), info, finalize).unwrap();
+
+        let msg = QueryMsg::FinalResult {
+            poll_id: "1".to_string(),
+        };
+        let resp = query(deps.as_ref(), env, msg.into()).unwrap();
+        let result: FinalResultResponse = from_binary(&resp).unwrap();
+        let result = result.result.unwrap();
+        // first preferences tie; the library voter's second choice decides it
+        assert_eq!(result.winner, Some(1));
+        assert_eq!(
+            result.rounds,
+            vec![
+                RunoffRound {
+                    tallies: vec![(0, 2), (1, 2), (2, 1)],
+                    eliminated: Some(2),
+                    transfers: vec![(1, 1)],
+                    exhausted: 0,
+                },
+                RunoffRound {
+                    tallies: vec![(0, 2), (1, 3)],
+                    eliminated: None,
+                    transfers: vec![],
+                    exhausted: 0,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_recounted_voter_limit() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info("creator", &[]);
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            InstantiateMsg::default(),
+        )
+        .unwrap();
+        let new_poll = |poll_id: &str, mode, max_voters| NewPoll {
+            poll_id: poll_id.to_string(),
+            question: "Where should we meet".to_string(),
+            options: vec![String::from("Park"), String::from("Cafe")],
+            mode: Some(mode),
+            max_voters,
+            ..Default::default()
+        };
+        let ranked = VoteMode::Ranked {
+            method: RankedMethod::Condorcet,
+        };
+
+        let err = execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            new_poll("1", ranked.clone(), Some(1001)),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::TooManyVoters { max: 1000 }));
+        for (poll_id, mode, max_voters) in [
+            ("1", ranked.clone(), None),
+            ("2", ranked, Some(50)),
+            ("3", VoteMode::Standard, None),
+        ] {
+            execute_create_poll(
+                deps.as_mut(),
+                env.clone(),
+                info.clone(),
+                new_poll(poll_id, mode, max_voters),
+            )
+            .unwrap();
+        }
+        let limits: Vec<_> = ["1", "2", "3"]
+            .into_iter()
+            .map(|poll_id| {
+                let poll = POLL.load(&deps.storage, poll_id.to_string()).unwrap();
+                poll.max_voters
+            })
+            .collect();
+        assert_eq!(limits, vec![Some(1000), Some(50), None]);
+    }
+
+    #[test]
+    fn test_suggest_option() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info("creator", &[]);
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            InstantiateMsg::default(),
+        )
+        .unwrap();
+        let new_poll = NewPoll {
+            poll_id: "1".to_string(),
+            question: "Where should we meet".to_string(),
+            options: vec![String::from("Park"), String::from("Cafe")],
+            open_options: true,
+            ..Default::default()
+        };
+        execute_create_poll(deps.as_mut(), env.clone(), info.clone(), new_poll).unwrap();
+
+        for option in ["Library", "Moon"] {
+            let msg = ExecuteMsg::SuggestOption {
+                poll_id: "1".to_string(),
+                option: option.to_string(),
+            };
+            execute(deps.as_mut(), env.clone(), mock_info("voter", &[]), msg).unwrap();
+        }
+        let review = |suggestion_id, approve| ExecuteMsg::ReviewSuggestion {
+            poll_id: "1".to_string(),
+            suggestion_id,
+            approve,
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("voter", &[]),
+            review(1, true),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(deps.as_mut(), env.clone(), info.clone(), review(1, true)).unwrap();
+        execute(deps.as_mut(), env.clone(), info.clone(), review(2, false)).unwrap();
+        let err = execute(deps.as_mut(), env.clone(), info, review(2, true)).unwrap_err();
+        assert!(matches!(err, ContractError::SuggestionNotFound {}));
+
+        let msg = QueryMsg::Suggestions {
+            poll_id: "1".to_string(),
+            start_after: None,
+            limit: None,
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg.into()).unwrap();
+        let resp: SuggestionsResponse = from_binary(&resp).unwrap();
+        let statuses: Vec<_> = resp.suggestions.into_iter().map(|s| s.status).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                SuggestionStatus::Approved { option_id: 2 },
+                SuggestionStatus::Rejected
+            ]
+        );
+        let msg = QueryMsg::PollHistory {
+            poll_id: "1".to_string(),
+            start_after: Some(1),
+            limit: None,
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg.into()).unwrap();
+        let history: PollHistoryResponse = from_binary(&resp).unwrap();
+        let changes: Vec<_> = history.history.into_iter().map(|r| r.change).collect();
+        assert_eq!(
+            changes,
+            vec![PollChange::OptionAdded {
+                id: 2,
+                label: "Library".to_string(),
+            }]
+        );
+
+        // the approved option is votable
+        let vote = ExecuteMsg::Vote {
+            poll_id: "1".to_string(),
+            vote: 2,
+            memo: None,
+            proof: None,
+        };
+        execute(deps.as_mut(), env, mock_info("voter", &[]), vote).unwrap();
+    }
+
+    #[test]
+    fn test_cw20_creation_deposit() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            cw20_creation_deposit: Some(Cw20Coin {
+                address: "token".to_string(),
+                amount: Uint128::new(50),
+            }),
+            ..Default::default()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+        let new_poll = NewPoll {
+            poll_id: "1".to_string(),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            ..Default::default()
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::CreatePoll(new_poll.clone()),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::Cw20CreationDepositRequired { .. }
+        ));
+
+        let receive = |amount: u128| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: "creator".to_string(),
+                amount: Uint128::new(amount),
+                msg: to_binary(&ReceiveMsg::CreatePoll(new_poll.clone())).unwrap(),
+            })
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("token", &[]),
+            receive(10),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::Cw20CreationDepositRequired { .. }
+        ));
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("token", &[]),
+            receive(50),
+        )
+        .unwrap();
+
+        // closing returns the deposit to the creator as a cw20 transfer
+        let close = ExecuteMsg::ClosePoll {
+            poll_id: "1".to_string(),
+        };
+        let resp = execute(deps.as_mut(), env, mock_info("creator", &[]), close).unwrap();
+        assert_eq!(
+            resp.messages,
+            vec![SubMsg::new(WasmMsg::Execute {
+                contract_addr: "token".to_string(),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: "creator".to_string(),
+                    amount: Uint128::new(50),
+                })
+                .unwrap(),
+                funds: vec![],
+            })]
+        );
+    }
+
+    #[test]
+    fn test_vesting_weight() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info("creator", &[]);
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            InstantiateMsg::default(),
+        )
+        .unwrap();
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::Smart { contract_addr, msg } if contract_addr == "vesting" => {
+                let allocation = match from_binary(msg).unwrap() {
+                    VestingQueryMsg::Allocation { address } if address == "founder" => {
+                        AllocationResponse {
+                            vested: Uint128::new(100),
+                            unvested: Uint128::new(900),
+                        }
+                    }
+                    VestingQueryMsg::Allocation { .. } => AllocationResponse {
+                        vested: Uint128::zero(),
+                        unvested: Uint128::zero(),
+                    },
+                };
+                SystemResult::Ok(ContractResult::Ok(to_binary(&allocation).unwrap()))
+            }
+            _ => SystemResult::Err(SystemError::UnsupportedRequest {
+                kind: "wasm".to_string(),
+            }),
+        });
+        let new_poll = NewPoll {
+            poll_id: "1".to_string(),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            mode: Some(VoteMode::Vesting {
+                contract: Addr::unchecked("vesting"),
+            }),
+            ..Default::default()
+        };
+        execute_create_poll(deps.as_mut(), env.clone(), info, new_poll).unwrap();
+
+        let vote = ExecuteMsg::Vote {
+            poll_id: "1".to_string(),
+            vote: 0,
+            memo: None,
+            proof: None,
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("stranger", &[]),
+            vote.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NoVotingPower {}));
+        execute(deps.as_mut(), env.clone(), mock_info("founder", &[]), vote).unwrap();
+
+        let msg = QueryMsg::Vote {
+            poll_id: "1".to_string(),
+            address: "founder".to_string(),
+            proof: None,
+            viewer: None,
+        };
+        let resp = query(deps.as_ref(), env, msg.into()).unwrap();
+        let vote: VoteResponse = from_binary(&resp).unwrap();
+        assert_eq!(vote.vote.unwrap().weight, 1000);
+    }
+
+    #[test]
+    fn test_staking_weight() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let info = mock_info("creator", &[]);
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            InstantiateMsg::default(),
+        )
+        .unwrap();
+        let created_at = env.block.height;
+        // "staker" held 400 when the poll was created; "buyer" staked later
+        deps.querier.update_wasm(move |query| match query {
+            WasmQuery::Smart { contract_addr, msg } if contract_addr == "stake" => {
+                let staked = match from_binary(msg).unwrap() {
+                    StakingQueryMsg::StakedBalanceAtHeight { address, height } => {
+                        let height = height.unwrap();
+                        let balance = match address.as_str() {
+                            "staker" => 400,
+                            "buyer" if height > created_at => 900,
+                            _ => 0,
+                        };
+                        to_binary(&StakedBalanceAtHeightResponse {
+                            balance: Uint128::new(balance),
+                            height,
+                        })
+                    }
+                    StakingQueryMsg::TotalStakedAtHeight { height } => {
+                        to_binary(&TotalStakedAtHeightResponse {
+                            total: Uint128::new(400),
+                            height: height.unwrap(),
+                        })
+                    }
+                };
+                SystemResult::Ok(ContractResult::Ok(staked.unwrap()))
+            }
+            _ => SystemResult::Err(SystemError::UnsupportedRequest {
+                kind: "wasm".to_string(),
+            }),
+        });
+        let new_poll = NewPoll {
+            poll_id: "1".to_string(),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            mode: Some(VoteMode::Staking {
+                contract: Addr::unchecked("stake"),
+            }),
+            ..Default::default()
+        };
+        execute_create_poll(deps.as_mut(), env.clone(), info, new_poll).unwrap();
+
+        env.block.height += 10;
+        let vote = ExecuteMsg::Vote {
+            poll_id: "1".to_string(),
+            vote: 0,
+            memo: None,
+            proof: None,
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("buyer", &[]),
+            vote.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NoVotingPower {}));
+        execute(deps.as_mut(), env.clone(), mock_info("staker", &[]), vote).unwrap();
+
+        let msg = QueryMsg::Vote {
+            poll_id: "1".to_string(),
+            address: "staker".to_string(),
+            proof: None,
+            viewer: None,
+        };
+        let resp = query(deps.as_ref(), env, msg.into()).unwrap();
+        let vote: VoteResponse = from_binary(&resp).unwrap();
+        assert_eq!(vote.vote.unwrap().weight, 400);
+    }
+
+    #[test]
+    fn test_dao_voting_module() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let msg = InstantiateMsg {
+            voting_token: Some("token".to_string()),
+            ..Default::default()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("dao", &[]), msg).unwrap();
+        let stake = |staker: &str, amount: u128| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: staker.to_string(),
+                amount: Uint128::new(amount),
+                msg: to_binary(&ReceiveMsg::Stake {}).unwrap(),
+            })
+        };
+        let token = mock_info("token", &[]);
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            token.clone(),
+            stake("voter1", 40),
+        )
+        .unwrap();
+        let start = env.block.height;
+        env.block.height += 1;
+        execute(deps.as_mut(), env.clone(), token, stake("voter2", 60)).unwrap();
+        env.block.height += 1;
+
+        let power = |address: &str, height| {
+            let msg = DaoQueryMsg::VotingPowerAtHeight {
+                address: address.to_string(),
+                height,
+            };
+            let resp = query(deps.as_ref(), env.clone(), msg.into()).unwrap();
+            from_binary::<VotingPowerAtHeightResponse>(&resp)
+                .unwrap()
+                .power
+        };
+        assert_eq!(power("voter1", None), Uint128::new(40));
+        assert_eq!(power("voter2", Some(start + 1)), Uint128::zero());
+        let total = |height| {
+            let msg = DaoQueryMsg::TotalPowerAtHeight { height };
+            let resp = query(deps.as_ref(), env.clone(), msg.into()).unwrap();
+            from_binary::<TotalPowerAtHeightResponse>(&resp)
+                .unwrap()
+                .power
+        };
+        assert_eq!(total(None), Uint128::new(100));
+        assert_eq!(total(Some(start + 1)), Uint128::new(40));
+
+        let resp = query(deps.as_ref(), env.clone(), DaoQueryMsg::Dao {}.into()).unwrap();
+        assert_eq!(from_binary::<Addr>(&resp).unwrap(), Addr::unchecked("dao"));
+        let resp = query(deps.as_ref(), env, DaoQueryMsg::Info {}.into()).unwrap();
+        let info: InfoResponse = from_binary(&resp).unwrap();
+        assert_eq!(info.info.version, env!("CARGO_PKG_VERSION"));
+    }
+
+    #[test]
+    fn test_export_state() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info("creator", &[]);
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            InstantiateMsg::default(),
+        )
+        .unwrap();
+        // hidden results are exported as stored all the same
+        for poll_id in ["1", "2"] {
+            let new_poll = NewPoll {
+                poll_id: poll_id.to_string(),
+                question: "Should We Have a Meeting Today".to_string(),
+                options: vec![String::from("Yes"), String::from("No")],
+                allowed_voters: (poll_id == "1").then(|| vec!["voter3".to_string()]),
+                voters_only_results: poll_id == "2",
+                ..Default::default()
+            };
+            execute_create_poll(deps.as_mut(), env.clone(), info.clone(), new_poll).unwrap();
+        }
+        for voter in ["voter1", "voter2"] {
+            let vote = ExecuteMsg::Vote {
+                poll_id: "2".to_string(),
+                vote: 1,
+                memo: None,
+                proof: None,
+            };
+            execute(deps.as_mut(), env.clone(), mock_info(voter, &[]), vote).unwrap();
+        }
+
+        let export = |section, start_after: Option<&str>| {
+            let msg = QueryMsg::ExportState {
+                section,
+                start_after: start_after.map(str::to_string),
+                limit: Some(1),
+            };
+            let resp = query(deps.as_ref(), env.clone(), msg.into()).unwrap();
+            from_binary::<ExportStateResponse>(&resp).unwrap()
+        };
+        let config = export(ExportSection::Config {}, None);
+        assert_eq!(config.format, EXPORT_FORMAT);
+        assert!(matches!(config.entries[..], [ExportEntry::Config(_)]));
+        let polls = export(ExportSection::Polls {}, Some("1"));
+        let [ExportEntry::Poll { poll_id, poll }] = &polls.entries[..] else {
+            panic!("unexpected entries {:?}", polls.entries);
+        };
+        assert_eq!(poll_id, "2");
+        assert_eq!(labelled(&poll.options), vec![("Yes", 0), ("No", 2)]);
+        let section = ExportSection::AllowedVoters {
+            poll_id: "1".to_string(),
+        };
+        assert_eq!(
+            export(section, None).entries,
+            vec![ExportEntry::Record {
+                poll_id: "1".to_string(),
+                record: PollRecord::AllowedVoter {
+                    voter: Addr::unchecked("voter3"),
+                },
+            }]
+        );
+
+        let ballots = |start_after| {
+            let section = ExportSection::Ballots {
+                poll_id: "2".to_string(),
+            };
+            export(section, start_after)
+                .entries
+                .into_iter()
+                .map(|entry| match entry {
+                    ExportEntry::Ballot { voter, .. } => voter.to_string(),
+                    entry => panic!("unexpected entry {:?}", entry),
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ballots(None), vec!["voter1"]);
+        assert_eq!(ballots(Some("voter1")), vec!["voter2"]);
+        assert!(ballots(Some("voter2")).is_empty());
+    }
+
+    #[test]
+    fn test_import_polls() {
+        let env = mock_env();
+        let info = mock_info("creator", &[]);
+        let mut source = mock_dependencies();
+        instantiate(
+            source.as_mut(),
             env.clone(),
             info.clone(),
-            poll_id,
-            question,
-            options,
+            InstantiateMsg::default(),
         )
         .unwrap();
-        assert_eq!(resp.attributes, vec![attr("action", "create poll")]);
+        let new_poll = NewPoll {
+            poll_id: "1".to_string(),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            ..Default::default()
+        };
+        execute_create_poll(source.as_mut(), env.clone(), info.clone(), new_poll).unwrap();
+        let vote = ExecuteMsg::Vote {
+            poll_id: "1".to_string(),
+            vote: 1,
+            memo: None,
+            proof: None,
+        };
+        execute(source.as_mut(), env.clone(), mock_info("voter1", &[]), vote).unwrap();
+
+        let export = |section| {
+            let msg = QueryMsg::ExportState {
+                section,
+                start_after: None,
+                limit: None,
+            };
+            let resp = query(source.as_ref(), env.clone(), msg.into()).unwrap();
+            from_binary::<ExportStateResponse>(&resp).unwrap().entries
+        };
+        let mut polls = export(ExportSection::Polls {})
+            .into_iter()
+            .map(|entry| match entry {
+                ExportEntry::Poll { poll_id, poll } => PollImport {
+                    poll_id,
+                    poll,
+                    result: None,
+                },
+                entry => panic!("unexpected entry {:?}", entry),
+            })
+            .collect::<Vec<_>>();
+        // counts are rebuilt from the ballots rather than trusted
+        polls[0].poll.voter_count = 5;
+        let section = ExportSection::Ballots {
+            poll_id: "1".to_string(),
+        };
+        let ballots = export(section)
+            .into_iter()
+            .map(|entry| match entry {
+                ExportEntry::Ballot { voter, ballot } => BallotExport {
+                    voter,
+                    poll_id: "1".to_string(),
+                    ballot,
+                },
+                entry => panic!("unexpected entry {:?}", entry),
+            })
+            .collect::<Vec<_>>();
+
+        let target = || {
+            let mut deps = mock_dependencies();
+            instantiate(
+                deps.as_mut(),
+                env.clone(),
+                info.clone(),
+                InstantiateMsg::default(),
+            )
+            .unwrap();
+            let msg = ExecuteMsg::SetImportMode { enabled: true };
+            execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+            deps
+        };
+        let mut deps = target();
+        let msg = ExecuteMsg::ImportPolls {
+            polls: polls.clone(),
+            ballots: ballots.clone(),
+            records: vec![],
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info("anyone", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        // a ballot for an option the poll does not have is rejected
+        let mut bad = ballots.clone();
+        bad[0].ballot.option = 7;
+        let msg = ExecuteMsg::ImportPolls {
+            polls: polls.clone(),
+            ballots: bad,
+            records: vec![],
+        };
+        let err = execute(target().as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::OptionNotFound {}));
+
+        // polls, their records and ballots may arrive in separate batches
+        let msg = ExecuteMsg::ImportPolls {
+            polls,
+            ballots: vec![],
+            records: vec![],
+        };
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let new_poll = NewPoll {
+            poll_id: "2".to_string(),
+            question: "Should We Have a Meeting Tomorrow".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            ..Default::default()
+        };
+        let err =
+            execute_create_poll(deps.as_mut(), env.clone(), info.clone(), new_poll).unwrap_err();
+        assert!(matches!(err, ContractError::ImportInProgress {}));
+        let records = vec![RecordExport {
+            poll_id: "1".to_string(),
+            record: PollRecord::WeightOverride {
+                voter: Addr::unchecked("voter2"),
+                weight: 3,
+            },
+        }];
+        let msg = ExecuteMsg::ImportPolls {
+            polls: vec![],
+            ballots,
+            records,
+        };
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let poll = POLL.load(&deps.storage, "1".to_string()).unwrap();
+        assert_eq!((poll.voter_count, poll.ballot_weight), (1, 1));
+        let key = ("1".to_string(), Addr::unchecked("voter2"));
+        assert_eq!(WEIGHT_OVERRIDES.load(&deps.storage, key).unwrap(), 3);
+        let tally = TALLIES.load(&deps.storage, ("1".to_string(), 1)).unwrap();
+        assert_eq!(tally, 1);
+        let msg = QueryMsg::VotersForOption {
+            poll_id: "1".to_string(),
+            option: 1,
+            start_after: None,
+            limit: None,
+            viewer: None,
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg.into()).unwrap();
+        let resp: VotersForOptionResponse = from_binary(&resp).unwrap();
+        assert_eq!(resp.voters.len(), 1);
+        assert_eq!(resp.voters[0].voter, "voter1");
+        let stats = voter_stats()
+            .load(&deps.storage, Addr::unchecked("voter1"))
+            .unwrap();
+        assert_eq!(stats.votes_cast, 1);
+        assert!(SEEN_VOTERS.has(&deps.storage, Addr::unchecked("voter1")));
+
+        // once import mode is off it cannot come back on to mix in another deployment
+        let msg = ExecuteMsg::SetImportMode { enabled: false };
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = ExecuteMsg::ImportPolls {
+            polls: vec![],
+            ballots: vec![],
+            records: vec![],
+        };
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::ImportModeOff {}));
+        let msg = ExecuteMsg::SetImportMode { enabled: true };
+        let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert!(matches!(err, ContractError::ImportNotEmpty {}));
+    }
+
+    #[test]
+    fn test_display_order() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info("creator", &[]);
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            InstantiateMsg::default(),
+        )
+        .unwrap();
+        let new_poll = NewPoll {
+            poll_id: "1".to_string(),
+            question: "Pick a logo".to_string(),
+            options: ["A", "B", "C", "D"].map(String::from).to_vec(),
+            ..Default::default()
+        };
+        execute_create_poll(deps.as_mut(), env.clone(), info.clone(), new_poll).unwrap();
+        let set_order = |order: Vec<u8>, shuffle: bool| ExecuteMsg::SetDisplayOrder {
+            poll_id: "1".to_string(),
+            order,
+            shuffle,
+        };
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("stranger", &[]),
+            set_order(vec![2, 0], false),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        for (order, duplicate) in [(vec![2, 2], true), (vec![9], false)] {
+            let err = execute(
+                deps.as_mut(),
+                env.clone(),
+                info.clone(),
+                set_order(order, false),
+            )
+            .unwrap_err();
+            match duplicate {
+                true => assert!(matches!(err, ContractError::DuplicateOption {})),
+                false => assert!(matches!(err, ContractError::OptionNotFound {})),
+            }
+        }
+        let msg = set_order(vec![2, 0], false);
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let listed = |deps: Deps, height: u64| {
+            let mut env = mock_env();
+            env.block.height = height;
+            let msg = QueryMsg::Poll {
+                poll_id: "1".to_string(),
+                locale: None,
+                viewer: None,
+            };
+            let resp: PollResponse = from_binary(&query(deps, env, msg.into()).unwrap()).unwrap();
+            let options = resp.poll.unwrap().options;
+            options
+                .into_iter()
+                .map(|option| option.id)
+                .collect::<Vec<_>>()
+        };
+        let page = |deps: Deps, start_after: Option<u8>| {
+            let msg = QueryMsg::Options {
+                poll_id: "1".to_string(),
+                start_after,
+                limit: Some(2),
+                viewer: None,
+            };
+            let resp: OptionsResponse =
+                from_binary(&query(deps, mock_env(), msg.into()).unwrap()).unwrap();
+            resp.options
+                .into_iter()
+                .map(|option| option.id)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(listed(deps.as_ref(), env.block.height), vec![2, 0, 1, 3]);
+        assert_eq!(page(deps.as_ref(), None), vec![2, 0]);
+        assert_eq!(page(deps.as_ref(), Some(0)), vec![1, 3]);
+
+        // a shuffled order is stable within a block and lists every option
+        let msg = set_order(vec![], true);
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        let shuffled = listed(deps.as_ref(), env.block.height);
+        assert_eq!(listed(deps.as_ref(), env.block.height), shuffled);
+        let mut ids = shuffled;
+        ids.sort();
+        assert_eq!(ids, vec![0, 1, 2, 3]);
+        let orders: Vec<_> = (1..=5)
+            .map(|blocks| listed(deps.as_ref(), env.block.height + blocks))
+            .collect();
+        assert!(orders.iter().any(|order| *order != orders[0]));
+    }
+
+    #[test]
+    fn test_paged_options() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info("creator", &[]);
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            InstantiateMsg::default(),
+        )
+        .unwrap();
+        let new_poll = NewPoll {
+            poll_id: "1".to_string(),
+            question: "Who should join the validator set".to_string(),
+            options: (0..40).map(|i| format!("Validator {}", i)).collect(),
+            ..Default::default()
+        };
+        execute_create_poll(deps.as_mut(), env.clone(), info.clone(), new_poll).unwrap();
+
+        let ranked = NewPoll {
+            poll_id: "2".to_string(),
+            question: "Who should join the validator set".to_string(),
+            options: (0..40).map(|i| format!("Validator {}", i)).collect(),
+            mode: Some(VoteMode::Ranked {
+                method: RankedMethod::InstantRunoff,
+            }),
+            ..Default::default()
+        };
+        let err =
+            execute_create_poll(deps.as_mut(), env.clone(), info.clone(), ranked).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::PagedOptionsUnsupported { max: 10 }
+        ));
 
+        for voter in ["voter1", "voter2"] {
+            let msg = ExecuteMsg::Vote {
+                poll_id: "1".to_string(),
+                vote: 35,
+                memo: None,
+                proof: None,
+            };
+            execute(deps.as_mut(), env.clone(), mock_info(voter, &[]), msg).unwrap();
+        }
+        let msg = ExecuteMsg::VoteByIndex {
+            poll_id: "1".to_string(),
+            option_index: 12,
+            memo: None,
+            proof: None,
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("voter3", &[]), msg).unwrap();
         let msg = ExecuteMsg::Vote {
             poll_id: "1".to_string(),
-            vote: "No".to_string(),
+            vote: 40,
+            memo: None,
+            proof: None,
         };
-        let resp = execute(deps.as_mut(), env, info, msg).unwrap();
-        assert_eq!(resp.attributes, vec![attr("action", "vote in poll")])
+        let err = execute(deps.as_mut(), env.clone(), mock_info("voter4", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::OptionNotFound {}));
+
+        // the poll itself only carries the count
+        let msg = QueryMsg::Poll {
+            poll_id: "1".to_string(),
+            locale: None,
+            viewer: None,
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg.into()).unwrap();
+        let poll = from_binary::<PollResponse>(&resp).unwrap().poll.unwrap();
+        assert!(poll.options.is_empty());
+        assert_eq!(poll.option_count, 40);
+
+        let msg = QueryMsg::Options {
+            poll_id: "1".to_string(),
+            start_after: Some(11),
+            limit: Some(2),
+            viewer: None,
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg.into()).unwrap();
+        let resp: OptionsResponse = from_binary(&resp).unwrap();
+        let page: Vec<(u8, u64)> = resp
+            .options
+            .iter()
+            .map(|option| (option.id, option.votes))
+            .collect();
+        assert_eq!(page, vec![(12, 1), (13, 0)]);
+
+        let msg = ExecuteMsg::ClosePoll {
+            poll_id: "1".to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        let msg = ExecuteMsg::FinalizePoll {
+            poll_id: "1".to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("anyone", &[]), msg).unwrap();
+        let msg = QueryMsg::FinalResult {
+            poll_id: "1".to_string(),
+        };
+        let resp = query(deps.as_ref(), env, msg.into()).unwrap();
+        let result = from_binary::<FinalResultResponse>(&resp)
+            .unwrap()
+            .result
+            .unwrap();
+        assert_eq!(result.winner, Some(35));
+        assert_eq!(result.tallies.len(), 40);
+        assert_eq!(result.total, 3);
     }
 
     #[test]
-    fn test_query_poll() {
+    fn test_weight_formula() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info("creator", &[]);
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            InstantiateMsg::default(),
+        )
+        .unwrap();
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::Smart { contract_addr, .. } if contract_addr == "vesting" => {
+                let allocation = AllocationResponse {
+                    vested: Uint128::new(1000),
+                    unvested: Uint128::zero(),
+                };
+                SystemResult::Ok(ContractResult::Ok(to_binary(&allocation).unwrap()))
+            }
+            _ => SystemResult::Err(SystemError::UnsupportedRequest {
+                kind: "wasm".to_string(),
+            }),
+        });
+        let formulas = [
+            ("1", WeightFormula::Sqrt, 31),
+            ("2", WeightFormula::Log, 10),
+            (
+                "3",
+                WeightFormula::CappedLinear {
+                    cap: Uint128::new(500),
+                },
+                500,
+            ),
+        ];
+        for (poll_id, weight_fn, expected) in formulas {
+            let new_poll = NewPoll {
+                poll_id: poll_id.to_string(),
+                question: "Should We Have a Meeting Today".to_string(),
+                options: vec![String::from("Yes"), String::from("No")],
+                mode: Some(VoteMode::Vesting {
+                    contract: Addr::unchecked("vesting"),
+                }),
+                weight_fn: Some(weight_fn),
+                ..Default::default()
+            };
+            execute_create_poll(deps.as_mut(), env.clone(), info.clone(), new_poll).unwrap();
+            let vote = ExecuteMsg::Vote {
+                poll_id: poll_id.to_string(),
+                vote: 0,
+                memo: None,
+                proof: None,
+            };
+            execute(deps.as_mut(), env.clone(), mock_info("whale", &[]), vote).unwrap();
+
+            let msg = QueryMsg::Poll {
+                poll_id: poll_id.to_string(),
+                locale: None,
+                viewer: None,
+            };
+            let resp = query(deps.as_ref(), env.clone(), msg.into()).unwrap();
+            let poll = from_binary::<PollResponse>(&resp).unwrap().poll.unwrap();
+            assert_eq!(poll.options[0].votes, expected);
+        }
+
+        // burned coins are added per vote, so only linear weights are sound
+        let new_poll = NewPoll {
+            poll_id: "4".to_string(),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            mode: Some(VoteMode::Burn {
+                denom: "ujuno".to_string(),
+            }),
+            weight_fn: Some(WeightFormula::Sqrt),
+            ..Default::default()
+        };
+        let err = execute_create_poll(deps.as_mut(), env, info, new_poll).unwrap_err();
+        assert!(matches!(err, ContractError::UnsupportedWeightFormula {}));
+    }
+
+    #[test]
+    fn test_sudo_update_admin() {
         let mut deps = mock_dependencies();
-        let info = mock_info("addr1", &[]);
         let env = mock_env();
         let msg = InstantiateMsg {
-            admin: Some("addr1".to_string()),
+            admin: Some("lost".to_string()),
+            ..Default::default()
         };
-        let resp = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+
+        let msg = SudoMsg::UpdateAdmin {
+            admin: "recovered".to_string(),
+        };
+        let resp = sudo(deps.as_mut(), env.clone(), msg).unwrap();
         assert_eq!(
-            resp.attributes,
-            vec![
-                attr("action", "instantiate"),
-                attr("addr1".to_string(), "addr1".to_string())
-            ]
+            resp.events,
+            vec![Event::new("admin_recovered")
+                .add_attribute("previous_admins", "lost")
+                .add_attribute("admin", "recovered")]
         );
-        let poll_id = "1".to_string();
-        let question = "Should We Have a Meeting Today".to_string();
-        let options = vec![String::from("Yes"), String::from("No")];
-        let resp =
-            execute_create_poll(deps.as_mut(), env.clone(), info, poll_id, question, options)
-                .unwrap();
-        assert_eq!(resp.attributes, vec![attr("action", "create poll")]);
 
+        let msg = ExecuteMsg::AddAdmin {
+            address: "other".to_string(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("lost", &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(deps.as_mut(), env, mock_info("recovered", &[]), msg).unwrap();
+    }
+
+    #[test]
+    fn test_freeze_poll() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            admin: Some("admin".to_string()),
+            ..Default::default()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+        let new_poll = NewPoll {
+            poll_id: "1".to_string(),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            ..Default::default()
+        };
+        execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            new_poll,
+        )
+        .unwrap();
+        let freeze = ExecuteMsg::FreezePoll {
+            poll_id: "1".to_string(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("voter", &[]),
+            freeze.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(deps.as_mut(), env.clone(), mock_info("admin", &[]), freeze).unwrap();
+
+        let vote = ExecuteMsg::Vote {
+            poll_id: "1".to_string(),
+            vote: 0,
+            memo: None,
+            proof: None,
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("voter", &[]),
+            vote.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::PollFrozen {}));
         let msg = QueryMsg::Poll {
             poll_id: "1".to_string(),
+            locale: None,
+            viewer: None,
         };
+        let resp = query(deps.as_ref(), env.clone(), msg.into()).unwrap();
+        let poll = from_binary::<PollResponse>(&resp).unwrap().poll.unwrap();
+        assert_eq!(poll.status, PollStatus::Frozen);
 
-        let resp = query(deps.as_ref(), env, msg).unwrap();
-        let get_poll: PollResponse = from_binary(&resp).unwrap();
+        let unfreeze = ExecuteMsg::UnfreezePoll {
+            poll_id: "1".to_string(),
+        };
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            unfreeze.clone(),
+        )
+        .unwrap();
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            unfreeze,
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::PollNotFrozen {}));
+        execute(deps.as_mut(), env, mock_info("voter", &[]), vote).unwrap();
+    }
+
+    #[test]
+    fn test_reopen_poll() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            admin: Some("admin".to_string()),
+            ..Default::default()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+        let new_poll = NewPoll {
+            poll_id: "1".to_string(),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            ..Default::default()
+        };
+        execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            new_poll,
+        )
+        .unwrap();
+        let vote = |vote| ExecuteMsg::Vote {
+            poll_id: "1".to_string(),
+            vote,
+            memo: None,
+            proof: None,
+        };
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("voter1", &[]),
+            vote(0),
+        )
+        .unwrap();
+        let msg = ExecuteMsg::ClosePoll {
+            poll_id: "1".to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+
+        let reopen = |new_end| ExecuteMsg::ReopenPoll {
+            poll_id: "1".to_string(),
+            new_end,
+            reason: "closed by mistake".to_string(),
+        };
+        let new_end = env.block.time.plus_seconds(3600);
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            reopen(new_end),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            reopen(env.block.time),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::ReopenEndInPast {}));
+
+        // the voting period bounds apply to the new end
+        let msg = ExecuteMsg::UpdateConfig {
+            min_voting_period: Some(600),
+            max_voting_period: Some(7200),
+            max_delegation_depth: None,
+            veto_period: None,
+            restricted_creation: None,
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            reopen(env.block.time.plus_seconds(60)),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::VotingPeriodTooShort { min: 600 }
+        ));
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            reopen(env.block.time.plus_seconds(7201)),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::VotingPeriodTooLong { max: 7200 }
+        ));
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            reopen(new_end),
+        )
+        .unwrap();
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("voter2", &[]),
+            vote(0),
+        )
+        .unwrap();
+        let poll = POLL.load(&deps.storage, "1".to_string()).unwrap();
+        assert_eq!(poll.status, PollStatus::Open);
+        assert_eq!(poll.end_time, Some(new_end));
+        assert_eq!(poll.closed_at, None);
+        assert_eq!(poll.options[0].votes, 2);
+
+        let mut later = env.clone();
+        later.block.time = new_end;
+        let msg = ExecuteMsg::FinalizePoll {
+            poll_id: "1".to_string(),
+        };
+        execute(deps.as_mut(), later.clone(), mock_info("admin", &[]), msg).unwrap();
+        let err = execute(
+            deps.as_mut(),
+            later.clone(),
+            mock_info("admin", &[]),
+            reopen(later.block.time.plus_seconds(60)),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::PollNotClosed {}));
+    }
+
+    #[test]
+    fn test_admin_log() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let msg = InstantiateMsg {
+            admin: Some("admin".to_string()),
+            ..Default::default()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+        let new_poll = NewPoll {
+            poll_id: "1".to_string(),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            ..Default::default()
+        };
+        execute_create_poll(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            new_poll,
+        )
+        .unwrap();
+
+        let msg = ExecuteMsg::BanVoter {
+            address: "spammer".to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+        env.block.height += 1;
+        let msg = ExecuteMsg::FreezePoll {
+            poll_id: "1".to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+        // the creator unfreezing their own poll is still a moderation action
+        let msg = ExecuteMsg::UnfreezePoll {
+            poll_id: "1".to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+        // closing one's own poll is not
+        let msg = ExecuteMsg::ClosePoll {
+            poll_id: "1".to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+
+        let log = |start_after| {
+            let msg = QueryMsg::AdminLog {
+                start_after,
+                limit: None,
+            };
+            let resp = query(deps.as_ref(), env.clone(), msg.into()).unwrap();
+            from_binary::<AdminLogResponse>(&resp).unwrap().actions
+        };
+        let actions = log(None);
+        let summary: Vec<(&str, &str, &str)> = actions
+            .iter()
+            .map(|a| (a.actor.as_str(), a.action.as_str(), a.target.as_str()))
+            .collect();
         assert_eq!(
-            get_poll,
-            PollResponse {
-                poll: Some(Poll {
-                    admin: Addr::unchecked("addr1"),
-                    question: "Should We Have a Meeting Today".to_string(),
-                    options: vec![(String::from("Yes"), 0), (String::from("No"), 0)],
-                })
-            }
+            summary,
+            vec![
+                ("admin", "ban voter", "spammer"),
+                ("admin", "freeze poll", "1"),
+                ("creator", "unfreeze poll", "1"),
+            ]
         );
+        assert_eq!(actions[1].height, actions[0].height + 1);
+        assert_eq!(log(Some(2)).len(), 1);
+    }
+
+    #[test]
+    fn test_weight_cap() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info("creator", &[]);
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            InstantiateMsg::default(),
+        )
+        .unwrap();
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::Smart { contract_addr, msg } if contract_addr == "vesting" => {
+                let vested = match from_binary(msg).unwrap() {
+                    VestingQueryMsg::Allocation { address } if address == "whale" => 1000,
+                    VestingQueryMsg::Allocation { .. } => 300,
+                };
+                let allocation = AllocationResponse {
+                    vested: Uint128::new(vested),
+                    unvested: Uint128::zero(),
+                };
+                SystemResult::Ok(ContractResult::Ok(to_binary(&allocation).unwrap()))
+            }
+            _ => SystemResult::Err(SystemError::UnsupportedRequest {
+                kind: "wasm".to_string(),
+            }),
+        });
+        let new_poll = NewPoll {
+            poll_id: "1".to_string(),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            mode: Some(VoteMode::Vesting {
+                contract: Addr::unchecked("vesting"),
+            }),
+            max_weight_per_voter: Some(Uint128::new(400)),
+            ..Default::default()
+        };
+        execute_create_poll(deps.as_mut(), env.clone(), info, new_poll).unwrap();
+        for (voter, option) in [("whale", 0), ("minnow", 1)] {
+            let vote = ExecuteMsg::Vote {
+                poll_id: "1".to_string(),
+                vote: option,
+                memo: None,
+                proof: None,
+            };
+            execute(deps.as_mut(), env.clone(), mock_info(voter, &[]), vote).unwrap();
+        }
+
+        let msg = QueryMsg::Poll {
+            poll_id: "1".to_string(),
+            locale: None,
+            viewer: None,
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg.into()).unwrap();
+        let poll = from_binary::<PollResponse>(&resp).unwrap().poll.unwrap();
+        let votes: Vec<u64> = poll.options.iter().map(|option| option.votes).collect();
+        assert_eq!(votes, vec![400, 300]);
+
+        let ballot = |address: &str| {
+            let msg = QueryMsg::Vote {
+                poll_id: "1".to_string(),
+                address: address.to_string(),
+                proof: None,
+                viewer: None,
+            };
+            let resp = query(deps.as_ref(), env.clone(), msg.into()).unwrap();
+            from_binary::<VoteResponse>(&resp).unwrap().vote.unwrap()
+        };
+        let whale = ballot("whale");
+        assert_eq!((whale.weight, whale.raw_weight), (400, Some(1000)));
+        assert_eq!(ballot("minnow").raw_weight, None);
     }
 }
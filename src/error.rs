@@ -1,4 +1,5 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{Coin, StdError, Timestamp, Uint128};
+use cw_utils::PaymentError;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -6,12 +7,357 @@ pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
+    #[error("{0}")]
+    Payment(#[from] PaymentError),
+
     #[error("Custom Error val: {val:?}")]
     CustomError { val: String },
 
     #[error("Too many poll options")]
     TooManyPollOptions {},
 
+    #[error("Translation {locale} must cover every option and needs a default locale")]
+    InvalidTranslation { locale: String },
+
+    #[error("Option metadata must match the options, with descriptions of at most {max_description} and image URIs of at most {max_uri} characters")]
+    InvalidOptionMetadata {
+        max_description: usize,
+        max_uri: usize,
+    },
+
+    #[error("A poll takes at most {max_count} attributes with unique, non-empty keys and keys and values of at most {max_length} characters")]
+    InvalidAttributes { max_count: usize, max_length: usize },
+
     #[error("Unauthorized")]
     Unauthorized {},
+
+    #[error("Poll not found")]
+    PollNotFound {},
+
+    #[error("A poll with this id already exists")]
+    PollIdTaken {},
+
+    #[error("Ballots can only be pruned once their poll is archived")]
+    PollNotArchived {},
+
+    #[error("Pseudonymous polls cannot escrow deposits or pay rewards")]
+    PseudonymousPayout {},
+
+    #[error("Poll is not open")]
+    PollNotOpen {},
+
+    #[error("Poll is not a draft")]
+    PollNotDraft {},
+
+    #[error("Poll already sponsored by this address")]
+    AlreadySponsored {},
+
+    #[error("Poll has ended")]
+    PollExpired {},
+
+    #[error("Poll has reached its limit of {max_voters} voters")]
+    PollFull { max_voters: u32 },
+
+    #[error("Ranked, conviction and decaying polls take at most {max} voters")]
+    TooManyVoters { max: u32 },
+
+    #[error("New deadline must be later than the current one")]
+    DeadlineNotExtended {},
+
+    #[error("Voting period is shorter than the minimum of {min} seconds")]
+    VotingPeriodTooShort { min: u64 },
+
+    #[error("Polls must end within the maximum voting period of {max} seconds")]
+    VotingPeriodTooLong { max: u64 },
+
+    #[error("Maximum voting period is shorter than the minimum")]
+    InvalidVotingPeriod {},
+
+    #[error("Poll cannot be closed before its minimum voting period elapses")]
+    MinVotingPeriodNotElapsed {},
+
+    #[error("Poll can no longer be cancelled")]
+    CannotCancel {},
+
+    #[error("Poll must be closed before it is finalized")]
+    PollNotClosed {},
+
+    #[error("No unresolved tie awaiting an admin decision")]
+    NoPendingTie {},
+
+    #[error("Option is not one of the tied options")]
+    OptionNotTied {},
+
+    #[error("Poll is already archived")]
+    PollArchived {},
+
+    #[error("Address is already an admin")]
+    AlreadyAdmin {},
+
+    #[error("Address is not an admin")]
+    NotAdmin {},
+
+    #[error("Cannot remove the last admin")]
+    CannotRemoveLastAdmin {},
+
+    #[error("Option not found")]
+    OptionNotFound {},
+
+    #[error("Options are reshuffled every block; vote by option id")]
+    ShuffledOptions {},
+
+    #[error("Memo exceeds {max} characters")]
+    MemoTooLong { max: usize },
+
+    #[error("Comment must be between 1 and {max} characters")]
+    InvalidCommentLength { max: usize },
+
+    #[error("Comment being replied to not found")]
+    CommentNotFound {},
+
+    #[error("Poll does not take suggested options")]
+    OptionsClosed {},
+
+    #[error("Suggested option must be between 1 and {max} characters")]
+    InvalidSuggestionLength { max: usize },
+
+    #[error("No pending suggestion with that id")]
+    SuggestionNotFound {},
+
+    #[error("Channel {channel_id} is not a satellite of this poll")]
+    UnknownSatellite { channel_id: String },
+
+    #[error("Tally sequence {sequence} is not newer than the last one received")]
+    StaleTally { sequence: u64 },
+
+    #[error("Reason exceeds {max} characters")]
+    ReasonTooLong { max: usize },
+
+    #[error("Votes in this poll require a token deposit")]
+    DepositRequired {},
+
+    #[error("Token is not accepted by this poll")]
+    InvalidDepositToken {},
+
+    #[error("Vote weight overflow")]
+    WeightOverflow {},
+
+    #[error("Votes in this poll require burning {denom}")]
+    BurnRequired { denom: String },
+
+    #[error("No voting power at the poll's snapshot height")]
+    NoVotingPower {},
+
+    #[error("Insufficient stake")]
+    InsufficientStake {},
+
+    #[error("Exactly one coin must be sent")]
+    InvalidFunds {},
+
+    #[error("Reward pool is funded with a different asset")]
+    RewardAssetMismatch {},
+
+    #[error("No reward to claim")]
+    NoRewardToClaim {},
+
+    #[error("Pseudonymous polls can only be imported once voting has ended")]
+    PseudonymousImport {},
+
+    #[error("Address is not allowed to vote in this poll")]
+    NotAllowedToVote {},
+
+    #[error("Poll outcome is not yet decided")]
+    OutcomeNotDecided {},
+
+    #[error("Voter is banned")]
+    VoterBanned {},
+
+    #[error("Address is already banned")]
+    AlreadyBanned {},
+
+    #[error("Address is not banned")]
+    NotBanned {},
+
+    #[error("Weight overrides must be nonzero and need a one-vote, ranked or token-weighted poll")]
+    InvalidWeightOverrides {},
+
+    #[error("Only allowed creators may create polls")]
+    CreationRestricted {},
+
+    #[error("Address may already create polls")]
+    AlreadyCreator {},
+
+    #[error("Address is not an allowed creator")]
+    NotCreator {},
+
+    #[error("Address is already a moderator")]
+    AlreadyModerator {},
+
+    #[error("Address is not a moderator")]
+    NotModerator {},
+
+    #[error("Address is already an admin of this category")]
+    AlreadyCategoryAdmin {},
+
+    #[error("Address is not an admin of this category")]
+    NotCategoryAdmin {},
+
+    #[error("Voting requires a balance of at least {min} {asset}")]
+    BalanceTooLow { min: Uint128, asset: String },
+
+    #[error("Poll does not take split ballots")]
+    NotCumulative {},
+
+    #[error("Poll requires splitting the budget with AllocateVotes")]
+    AllocationsRequired {},
+
+    #[error("Poll requires ranking the options with RankVotes")]
+    RankingRequired {},
+
+    #[error("Poll does not take ranked ballots")]
+    NotRanked {},
+
+    #[error("Allocations must add up to the budget of {budget}")]
+    AllocationMismatch { budget: Uint128 },
+
+    #[error("Option is listed more than once")]
+    DuplicateOption {},
+
+    #[error("Cannot delegate to yourself")]
+    CannotDelegateToSelf {},
+
+    #[error("Delegation would create a cycle")]
+    DelegationCycle {},
+
+    #[error("Address has not delegated")]
+    NotDelegating {},
+
+    #[error("Creating a poll requires a deposit of {deposit}")]
+    CreationDepositRequired { deposit: Coin },
+
+    #[error("Creating a poll requires a deposit of {amount} {token}")]
+    Cw20CreationDepositRequired { token: String, amount: Uint128 },
+
+    #[error("Content hash must be between 1 and {max} bytes")]
+    InvalidContentHash { max: usize },
+
+    #[error("Poll is in its veto period until {ends}")]
+    VetoPeriodActive { ends: Timestamp },
+
+    #[error("Veto period has ended")]
+    VetoPeriodEnded {},
+
+    #[error("Poll already flagged by this address")]
+    AlreadyFlagged {},
+
+    #[error("Decay period must be greater than zero")]
+    InvalidDecay {},
+
+    #[error("Quorum shares must be at most 100%, ascending, and need allowed voters")]
+    InvalidQuorum {},
+
+    #[error("Showing results once quorum is reached needs a quorum")]
+    QuorumRequired {},
+
+    #[error("Conviction needs a multiplier of at least 1 and a non-zero ramp")]
+    InvalidConviction {},
+
+    #[error("Poll is not a conviction poll")]
+    NotConviction {},
+
+    #[error("No locked tokens to withdraw")]
+    NothingLocked {},
+
+    #[error("Deposits stay escrowed until the poll closes")]
+    DepositLocked {},
+
+    #[error("No deposit to claim")]
+    NoDepositToClaim {},
+
+    #[error("Merkle root must be a hex-encoded sha256 hash")]
+    InvalidMerkleRoot {},
+
+    #[error("Poll requires a Merkle proof of eligibility")]
+    MerkleProofRequired {},
+
+    #[error("Merkle proof does not match the poll's root")]
+    InvalidMerkleProof {},
+
+    #[error("Snapshot budgets need a Merkle root")]
+    SnapshotRootRequired {},
+
+    #[error("Voting key is registered to another address")]
+    VotingKeyTaken {},
+
+    #[error("Voting key is not registered")]
+    VotingKeyNotRegistered {},
+
+    #[error("Invalid signature")]
+    InvalidSignature {},
+
+    #[error("Nonce must be greater than {last}")]
+    StaleNonce { last: u64 },
+
+    #[error("Cannot migrate from {previous_contract}")]
+    CannotMigrate { previous_contract: String },
+
+    #[error("The window for changing this vote has closed")]
+    RevoteWindowClosed {},
+
+    #[error("No migration path from version {version}")]
+    UnsupportedVersion { version: String },
+
+    #[error("Only unordered IBC channels are supported")]
+    OnlyUnorderedChannel {},
+
+    #[error("IBC channel version must be {expected}")]
+    InvalidIbcVersion { expected: String },
+
+    #[error("Import mode can only be turned on in a contract without polls")]
+    ImportNotEmpty {},
+
+    #[error("Polls can only be imported in import mode")]
+    ImportModeOff {},
+
+    #[error("Polls cannot be created in import mode")]
+    ImportInProgress {},
+
+    #[error("Polls with more than {max} options only support plain and cumulative voting")]
+    PagedOptionsUnsupported { max: usize },
+
+    #[error("Burn, conviction and cumulative polls only support linear weights")]
+    UnsupportedWeightFormula {},
+
+    #[error("Cumulative and conviction polls cannot cap voter weight")]
+    UnsupportedWeightCap {},
+
+    #[error("Voting on this poll is frozen")]
+    PollFrozen {},
+
+    #[error("Poll is not frozen")]
+    PollNotFrozen {},
+
+    #[error("Random tie-breaks and raffles need a randomness proxy")]
+    RandomnessUnavailable {},
+
+    #[error("Randomness for this poll was already delivered")]
+    RandomnessDelivered {},
+
+    #[error("Poll is not waiting for randomness")]
+    NoRandomnessRequested {},
+
+    #[error("Win threshold must be above zero and at most one")]
+    InvalidWinThreshold {},
+
+    #[error("Only a poll that has not been superseded yet can be superseded")]
+    InvalidSupersede {},
+
+    #[error("Minimum voters must be at least one and reachable under the poll's voter limits")]
+    InvalidMinVoters {},
+
+    #[error("A reopened poll must end in the future")]
+    ReopenEndInPast {},
+
+    #[error("Deposit and conviction polls cannot be reopened once their deposits are refunded")]
+    ReopenUnsupported {},
 }
@@ -0,0 +1,446 @@
+//! Lets contracts on other chains read finalized poll results over IBC, and
+//! satellite contracts push the votes cast on their chains into a poll here.
+//!
+//! A counterparty sends a `PollPacket` and gets the answer back in the
+//! acknowledgement. This contract never sends packets of its own.
+
+use std::collections::BTreeMap;
+
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{
+    from_slice, to_binary, Binary, BlockInfo, DepsMut, Env, IbcBasicResponse, IbcChannel,
+    IbcChannelCloseMsg, IbcChannelConnectMsg, IbcChannelOpenMsg, IbcOrder, IbcPacketAckMsg,
+    IbcPacketReceiveMsg, IbcPacketTimeoutMsg, IbcReceiveResponse,
+};
+
+use crate::contract::{has_option, update_option};
+use crate::error::ContractError;
+use crate::msg::{FinalResultResponse, PollAck, PollPacket};
+use crate::state::{PollStatus, SatelliteTally, POLL, RESULTS, SATELLITE_TALLIES};
+
+pub const IBC_VERSION: &str = "poll-results-1";
+
+fn validate_channel(
+    channel: &IbcChannel,
+    counterparty_version: Option<&str>,
+) -> Result<(), ContractError> {
+    if channel.order != IbcOrder::Unordered {
+        return Err(ContractError::OnlyUnorderedChannel {});
+    }
+    if channel.version != IBC_VERSION || counterparty_version.is_some_and(|v| v != IBC_VERSION) {
+        return Err(ContractError::InvalidIbcVersion {
+            expected: IBC_VERSION.to_string(),
+        });
+    }
+    Ok(())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_channel_open(
+    _deps: DepsMut,
+    _env: Env,
+    msg: IbcChannelOpenMsg,
+) -> Result<(), ContractError> {
+    validate_channel(msg.channel(), msg.counterparty_version())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_channel_connect(
+    _deps: DepsMut,
+    _env: Env,
+    msg: IbcChannelConnectMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    let channel = msg.channel();
+    validate_channel(channel, msg.counterparty_version())?;
+    Ok(IbcBasicResponse::new()
+        .add_attribute("action", "ibc connect")
+        .add_attribute("channel_id", &channel.endpoint.channel_id))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_channel_close(
+    _deps: DepsMut,
+    _env: Env,
+    msg: IbcChannelCloseMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    Ok(IbcBasicResponse::new()
+        .add_attribute("action", "ibc close")
+        .add_attribute("channel_id", &msg.channel().endpoint.channel_id))
+}
+
+/// Always writes an acknowledgement; failures are reported inside it rather
+/// than by failing the transaction.
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_packet_receive(
+    deps: DepsMut,
+    env: Env,
+    msg: IbcPacketReceiveMsg,
+) -> Result<IbcReceiveResponse, ContractError> {
+    let channel_id = &msg.packet.dest.channel_id;
+    let ack = match handle_packet(deps, &env.block, channel_id, &msg.packet.data) {
+        Ok(ack) => ack,
+        Err(err) => PollAck::Error(err.to_string()),
+    };
+    Ok(IbcReceiveResponse::new()
+        .set_ack(to_binary(&ack)?)
+        .add_attribute("action", "ibc receive"))
+}
+
+fn handle_packet(
+    deps: DepsMut,
+    block: &BlockInfo,
+    channel_id: &str,
+    data: &Binary,
+) -> Result<PollAck, ContractError> {
+    match from_slice(data)? {
+        PollPacket::FinalResult { poll_id } => {
+            let result = RESULTS.may_load(deps.storage, poll_id)?;
+            Ok(PollAck::Result(FinalResultResponse { result }))
+        }
+        PollPacket::SubmitTally {
+            poll_id,
+            sequence,
+            tallies,
+            voters,
+        } => {
+            let tally = SatelliteTally {
+                sequence,
+                tallies,
+                voters,
+            };
+            submit_tally(deps, block, channel_id, poll_id, tally)?;
+            Ok(PollAck::TallyAccepted {})
+        }
+    }
+}
+
+/// Swaps the channel's previous tally for `tally` in the poll's option votes.
+fn submit_tally(
+    deps: DepsMut,
+    block: &BlockInfo,
+    channel_id: &str,
+    poll_id: String,
+    tally: SatelliteTally,
+) -> Result<(), ContractError> {
+    let mut poll = POLL
+        .may_load(deps.storage, poll_id.clone())?
+        .ok_or(ContractError::PollNotFound {})?;
+    if !poll.satellite_channels.iter().any(|id| id == channel_id) {
+        return Err(ContractError::UnknownSatellite {
+            channel_id: channel_id.to_string(),
+        });
+    }
+    // a closed poll's outcome is settled; late pushes do not change it
+    if poll.status != PollStatus::Open {
+        return Err(ContractError::PollNotOpen {});
+    }
+    if poll.is_expired(block) {
+        return Err(ContractError::PollExpired {});
+    }
+    let key = (poll_id.clone(), channel_id.to_string());
+    let previous = SATELLITE_TALLIES.may_load(deps.storage, key.clone())?;
+    if previous
+        .as_ref()
+        .is_some_and(|previous| tally.sequence <= previous.sequence)
+    {
+        return Err(ContractError::StaleTally {
+            sequence: tally.sequence,
+        });
+    }
+    for (id, _) in &tally.tallies {
+        if !has_option(deps.storage, &poll_id, &poll, *id)? {
+            return Err(ContractError::OptionNotFound {});
+        }
+    }
+
+    // (removed, added) by option, applied together so a shrinking tally
+    // cannot underflow partway through
+    let mut changes = BTreeMap::<u8, (u64, u64)>::new();
+    for (id, votes) in previous
+        .map(|previous| previous.tallies)
+        .unwrap_or_default()
+    {
+        let change = changes.entry(id).or_default();
+        change.0 = change.0.saturating_add(votes);
+    }
+    for (id, votes) in &tally.tallies {
+        let change = changes.entry(*id).or_default();
+        change.1 = change.1.saturating_add(*votes);
+    }
+    for (id, (removed, added)) in changes {
+        let mut overflow = false;
+        update_option(
+            deps.storage,
+            block,
+            &poll_id,
+            &mut poll,
+            id,
+            |option| match option
+                .votes
+                .checked_add(added)
+                .and_then(|votes| votes.checked_sub(removed))
+            {
+                Some(votes) => option.votes = votes,
+                None => overflow = true,
+            },
+        )?;
+        if overflow {
+            return Err(ContractError::WeightOverflow {});
+        }
+    }
+    SATELLITE_TALLIES.save(deps.storage, key, &tally)?;
+    POLL.save(deps.storage, poll_id, &poll)?;
+    Ok(())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_packet_ack(
+    _deps: DepsMut,
+    _env: Env,
+    _msg: IbcPacketAckMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    Ok(IbcBasicResponse::new())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_packet_timeout(
+    _deps: DepsMut,
+    _env: Env,
+    _msg: IbcPacketTimeoutMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    Ok(IbcBasicResponse::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contract::{execute, instantiate, query};
+    use crate::msg::{
+        ChainTalliesResponse, ExecuteMsg, InstantiateMsg, NewPoll, QueryMsg, TallyAtHeightResponse,
+    };
+    use crate::state::WeightDecay;
+    use cosmwasm_std::from_binary;
+    use cosmwasm_std::testing::{
+        mock_dependencies, mock_env, mock_ibc_channel_open_try, mock_ibc_packet_recv, mock_info,
+    };
+
+    #[test]
+    fn test_ibc_final_result() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+
+        let msg = mock_ibc_channel_open_try("channel-0", IbcOrder::Ordered, IBC_VERSION);
+        let err = ibc_channel_open(deps.as_mut(), env.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::OnlyUnorderedChannel {}));
+        let msg = mock_ibc_channel_open_try("channel-0", IbcOrder::Unordered, "ics20-1");
+        let err = ibc_channel_open(deps.as_mut(), env.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidIbcVersion { .. }));
+        let msg = mock_ibc_channel_open_try("channel-0", IbcOrder::Unordered, IBC_VERSION);
+        ibc_channel_open(deps.as_mut(), env.clone(), msg).unwrap();
+
+        let msg = InstantiateMsg {
+            admin: Some("admin".to_string()),
+            ..Default::default()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+        let new_poll = NewPoll {
+            poll_id: "1".to_string(),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            ..Default::default()
+        };
+        let msg = ExecuteMsg::CreatePoll(new_poll);
+        execute(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+        let msg = ExecuteMsg::Vote {
+            poll_id: "1".to_string(),
+            vote: 0,
+            memo: None,
+            proof: None,
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("voter1", &[]), msg).unwrap();
+
+        let packet = PollPacket::FinalResult {
+            poll_id: "1".to_string(),
+        };
+        let msg = mock_ibc_packet_recv("channel-0", &packet).unwrap();
+        let res = ibc_packet_receive(deps.as_mut(), env.clone(), msg).unwrap();
+        let ack: PollAck = from_binary(&res.acknowledgement).unwrap();
+        assert_eq!(ack, PollAck::Result(FinalResultResponse { result: None }));
+
+        let msg = ExecuteMsg::ClosePoll {
+            poll_id: "1".to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+        let msg = ExecuteMsg::FinalizePoll {
+            poll_id: "1".to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+
+        let msg = mock_ibc_packet_recv("channel-0", &packet).unwrap();
+        let res = ibc_packet_receive(deps.as_mut(), env.clone(), msg).unwrap();
+        match from_binary(&res.acknowledgement).unwrap() {
+            PollAck::Result(FinalResultResponse {
+                result: Some(result),
+            }) => {
+                assert_eq!(result.winner, Some(0))
+            }
+            ack => panic!("unexpected ack {:?}", ack),
+        }
+
+        // malformed packets are acknowledged with an error
+        let msg = mock_ibc_packet_recv("channel-0", &"garbage").unwrap();
+        let res = ibc_packet_receive(deps.as_mut(), env, msg).unwrap();
+        let ack: PollAck = from_binary(&res.acknowledgement).unwrap();
+        assert!(matches!(ack, PollAck::Error(_)));
+    }
+
+    #[test]
+    fn test_ibc_submit_tally() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            InstantiateMsg::default(),
+        )
+        .unwrap();
+        let new_poll = NewPoll {
+            poll_id: "1".to_string(),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            satellite_channels: vec!["channel-1".to_string()],
+            ..Default::default()
+        };
+        let msg = ExecuteMsg::CreatePoll(new_poll);
+        execute(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+
+        let mut submit = |channel_id: &str, sequence, tallies: Vec<(u8, u64)>| {
+            let packet = PollPacket::SubmitTally {
+                poll_id: "1".to_string(),
+                sequence,
+                tallies,
+                voters: 3,
+            };
+            let msg = mock_ibc_packet_recv(channel_id, &packet).unwrap();
+            let res = ibc_packet_receive(deps.as_mut(), env.clone(), msg).unwrap();
+            from_binary::<PollAck>(&res.acknowledgement).unwrap()
+        };
+        assert!(matches!(
+            submit("channel-2", 1, vec![(0, 5)]),
+            PollAck::Error(_)
+        ));
+        assert_eq!(
+            submit("channel-1", 1, vec![(0, 2), (1, 1)]),
+            PollAck::TallyAccepted {}
+        );
+        // a later push replaces the earlier one rather than adding to it
+        assert_eq!(
+            submit("channel-1", 2, vec![(0, 2), (1, 3)]),
+            PollAck::TallyAccepted {}
+        );
+        assert!(matches!(
+            submit("channel-1", 2, vec![(0, 9)]),
+            PollAck::Error(_)
+        ));
+
+        let poll = POLL.load(&deps.storage, "1".to_string()).unwrap();
+        let votes: Vec<u64> = poll.options.iter().map(|option| option.votes).collect();
+        assert_eq!(votes, vec![2, 3]);
+        let msg = QueryMsg::ChainTallies {
+            poll_id: "1".to_string(),
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg.into()).unwrap();
+        let resp: ChainTalliesResponse = from_binary(&resp).unwrap();
+        assert_eq!(resp.tallies.len(), 1);
+        assert_eq!(resp.tallies[0].0, "channel-1");
+        assert_eq!(resp.tallies[0].1.sequence, 2);
+
+        // the pushed votes show up in the tally history too
+        let msg = QueryMsg::TallyAtHeight {
+            poll_id: "1".to_string(),
+            height: env.block.height + 1,
+        };
+        let resp = query(deps.as_ref(), env, msg.into()).unwrap();
+        let resp: TallyAtHeightResponse = from_binary(&resp).unwrap();
+        assert_eq!(resp.tallies, vec![(0, 2), (1, 3)]);
+    }
+
+    #[test]
+    fn test_satellite_tally_survives_settling() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            InstantiateMsg::default(),
+        )
+        .unwrap();
+        let new_poll = NewPoll {
+            poll_id: "1".to_string(),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            decay: Some(WeightDecay::Linear { period: 3600 }),
+            satellite_channels: vec!["channel-1".to_string()],
+            ..Default::default()
+        };
+        let msg = ExecuteMsg::CreatePoll(new_poll);
+        execute(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+
+        let submit = |deps: DepsMut, sequence, tallies: Vec<(u8, u64)>| {
+            let packet = PollPacket::SubmitTally {
+                poll_id: "1".to_string(),
+                sequence,
+                tallies,
+                voters: 3,
+            };
+            let msg = mock_ibc_packet_recv("channel-1", &packet).unwrap();
+            let res = ibc_packet_receive(deps, mock_env(), msg).unwrap();
+            from_binary::<PollAck>(&res.acknowledgement).unwrap()
+        };
+        assert_eq!(
+            submit(deps.as_mut(), 1, vec![(0, 5)]),
+            PollAck::TallyAccepted {}
+        );
+
+        // closing rebuilds the decaying tallies from local ballots
+        let msg = ExecuteMsg::ClosePoll {
+            poll_id: "1".to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+        let poll = POLL.load(&deps.storage, "1".to_string()).unwrap();
+        assert_eq!(poll.options[0].votes, 5);
+
+        // pushes stop counting once the poll is closed
+        assert!(matches!(
+            submit(deps.as_mut(), 2, vec![(0, 2)]),
+            PollAck::Error(_)
+        ));
+        let poll = POLL.load(&deps.storage, "1".to_string()).unwrap();
+        assert_eq!(poll.options[0].votes, 5);
+
+        // reopening rebuilds the tallies too, at full weight
+        let msg = ExecuteMsg::ReopenPoll {
+            poll_id: "1".to_string(),
+            new_end: env.block.time.plus_seconds(600),
+            reason: "closed by mistake".to_string(),
+        };
+        execute(deps.as_mut(), env, mock_info("admin", &[]), msg).unwrap();
+        let poll = POLL.load(&deps.storage, "1".to_string()).unwrap();
+        assert_eq!(poll.options[0].votes, 5);
+        assert_eq!(
+            submit(deps.as_mut(), 2, vec![(0, 2)]),
+            PollAck::TallyAccepted {}
+        );
+        let poll = POLL.load(&deps.storage, "1".to_string()).unwrap();
+        assert_eq!(poll.options[0].votes, 2);
+        assert_eq!(
+            submit(deps.as_mut(), 3, vec![(0, 1)]),
+            PollAck::TallyAccepted {}
+        );
+        let poll = POLL.load(&deps.storage, "1".to_string()).unwrap();
+        assert_eq!(poll.options[0].votes, 1);
+    }
+}
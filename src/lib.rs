@@ -1,6 +1,16 @@
+#[cfg(any(test, feature = "library"))]
+pub mod api;
 pub mod contract;
 mod error;
+pub mod ibc;
+mod migrations;
 pub mod msg;
+pub mod querier;
 pub mod state;
+#[cfg(any(test, feature = "library"))]
+pub mod strategy;
+mod tally;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 
 pub use crate::error::ContractError;
@@ -0,0 +1,915 @@
+//! Storage upgrades run by `migrate`, oldest first.
+//!
+//! Every step reads the maps it rewrites whole, inside the migrating
+//! transaction, so its gas grows linearly with what is stored: a write or
+//! two per poll for 0.4.0, 0.5.0, 0.10.0 and 0.11.0, per stake for 0.7.0,
+//! and up to one per ballot for the others. A deployment too large to go
+//! through every step in one block's gas limit has to be migrated one
+//! release at a time.
+
+use std::collections::{BTreeMap, HashMap};
+
+use cosmwasm_std::{Addr, Binary, Order, StdError, StdResult, Storage, Timestamp, Uint128};
+use cw_storage_plus::{Item, Map};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::error::ContractError;
+use crate::state::{
+    voter_stats, Ballot, Poll, PollOption, PollResult, PollStatus, RewardDistribution,
+    SuggestionStatus, TieBreakPolicy, TieResolution, VoteMode, WeightFormula, BALLOT, CONFIG,
+    CREATOR_POLLS, OPTION_VOTERS, POLL, POLL_ENDS, POLL_SALTS, POLL_VOTERS, RAFFLE_COUNTS,
+    RAFFLE_ENTRIES, RAFFLE_SLOTS, RESULTS, STAKES, STAKE_TOTALS, SUGGESTIONS,
+};
+
+/// An upgrade step: the version whose layout it reads, and the function
+/// rewriting it to the next version's.
+type Step = (&'static str, fn(&mut dyn Storage) -> StdResult<()>);
+
+/// Every step in version order; the last produces the current layout.
+const STEPS: &[Step] = &[
+    ("0.1.0", option_ids),
+    ("0.2.0", option_voters),
+    ("0.3.0", poll_voters),
+    ("0.4.0", poll_ends),
+    ("0.5.0", creator_polls),
+    ("0.6.0", raffle_entries),
+    ("0.7.0", stake_totals),
+    ("0.8.0", ballot_weights),
+    ("0.9.0", refunded_deposits),
+    ("0.10.0", poll_salts),
+    ("0.11.0", polls_created),
+];
+
+/// Brings storage written by version `from` up to `current`, running each
+/// step from `from` onwards. Returns the versions upgraded from.
+pub fn upgrade(
+    storage: &mut dyn Storage,
+    from: &str,
+    current: &str,
+) -> Result<Vec<&'static str>, ContractError> {
+    if from == current {
+        return Ok(vec![]);
+    }
+    let start = STEPS
+        .iter()
+        .position(|(version, _)| *version == from)
+        .ok_or_else(|| ContractError::UnsupportedVersion {
+            version: from.to_string(),
+        })?;
+    let mut applied = vec![];
+    for (version, step) in &STEPS[start..] {
+        step(storage)?;
+        applied.push(*version);
+    }
+    Ok(applied)
+}
+
+/// Layouts written by 0.1.0, which stored options as `(label, votes)`
+/// and referred to them by label. The first 0.1.0 release stored only a
+/// poll's admin, question and options and a ballot's option; later 0.1.0
+/// builds added the rest, so every other field falls back to a default.
+mod v0_1 {
+    use super::*;
+
+    #[derive(Serialize, Deserialize)]
+    pub struct Config {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub admin: Option<Addr>,
+        #[serde(default)]
+        pub admins: Vec<Addr>,
+        #[serde(default)]
+        pub moderators: Vec<Addr>,
+        #[serde(default)]
+        pub min_voting_period: u64,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct Poll {
+        pub admin: Addr,
+        pub question: String,
+        pub options: Vec<(String, u64)>,
+        #[serde(default = "open")]
+        pub status: PollStatus,
+        #[serde(default)]
+        pub start_time: Timestamp,
+        #[serde(default)]
+        pub end_time: Option<Timestamp>,
+        #[serde(default)]
+        pub mode: VoteMode,
+        #[serde(default)]
+        pub reward_distribution: RewardDistribution,
+        #[serde(default)]
+        pub snapshot_height: u64,
+        #[serde(default)]
+        pub tie_break: TieBreakPolicy,
+        /// Counted from the poll's ballots when missing.
+        #[serde(default)]
+        pub voter_count: Option<u64>,
+        #[serde(default)]
+        pub electorate: Option<u64>,
+        #[serde(default)]
+        pub cancel_reason: Option<String>,
+    }
+
+    fn open() -> PollStatus {
+        PollStatus::Open
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct Ballot {
+        pub option: String,
+        /// Every ballot counted once before weighted modes.
+        #[serde(default = "one")]
+        pub weight: u64,
+        #[serde(default)]
+        pub deposit: Uint128,
+        #[serde(default)]
+        pub memo: Option<String>,
+        #[serde(default)]
+        pub allocations: Vec<(String, u64)>,
+    }
+
+    fn one() -> u64 {
+        1
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct TieResolution {
+        pub tied: Vec<String>,
+        pub policy: TieBreakPolicy,
+        pub resolved: bool,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct PollResult {
+        pub winner: Option<String>,
+        pub tallies: Vec<(String, u64)>,
+        pub total: u64,
+        pub finalized_at: Timestamp,
+        #[serde(default)]
+        pub tie: Option<TieResolution>,
+    }
+
+    pub const CONFIG: Item<Config> = Item::new("config");
+    pub const POLL: Map<String, Poll> = Map::new("polls");
+    pub const BALLOT: Map<(Addr, String), Ballot> = Map::new("ballots");
+    pub const RESULTS: Map<String, PollResult> = Map::new("results");
+}
+
+fn to_options(options: Vec<(String, u64)>) -> Vec<PollOption> {
+    options
+        .into_iter()
+        .enumerate()
+        .map(|(id, (label, votes))| PollOption {
+            id: id as u8,
+            label,
+            votes,
+            description: None,
+            image_uri: None,
+        })
+        .collect()
+}
+
+/// Rewrites polls, ballots and results to refer to options by id, and a
+/// single-admin config to the admin list.
+pub fn option_ids(storage: &mut dyn Storage) -> StdResult<()> {
+    if CONFIG.load(storage).is_err() {
+        if let Some(old) = v0_1::CONFIG.may_load(storage)? {
+            let mut admins = old.admins;
+            admins.extend(old.admin);
+            let config = v0_1::Config {
+                admin: None,
+                admins,
+                moderators: old.moderators,
+                min_voting_period: old.min_voting_period,
+            };
+            v0_1::CONFIG.save(storage, &config)?;
+        }
+    }
+
+    let ballots = v0_1::BALLOT
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    let mut ballot_counts: HashMap<String, u64> = HashMap::new();
+    for ((_, poll_id), _) in &ballots {
+        *ballot_counts.entry(poll_id.clone()).or_default() += 1;
+    }
+    let polls = v0_1::POLL
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    let mut ids: HashMap<String, HashMap<String, u8>> = HashMap::new();
+    for (poll_id, old) in polls {
+        let options = to_options(old.options);
+        ids.insert(
+            poll_id.clone(),
+            options
+                .iter()
+                .map(|option| (option.label.clone(), option.id))
+                .collect(),
+        );
+        let poll = Poll {
+            admin: old.admin,
+            question: old.question,
+            options,
+            status: old.status,
+            start_time: old.start_time,
+            end_time: old.end_time,
+            mode: old.mode,
+            reward_distribution: old.reward_distribution,
+            snapshot_height: old.snapshot_height,
+            tie_break: old.tie_break,
+            voter_count: old
+                .voter_count
+                .unwrap_or_else(|| ballot_counts.get(&poll_id).copied().unwrap_or_default()),
+            electorate: old.electorate,
+            eligible_weight: None,
+            cancel_reason: old.cancel_reason,
+            min_balance: None,
+            merkle_root: None,
+            decay: None,
+            category: None,
+            creation_deposit: None,
+            cw20_creation_deposit: None,
+            flags: 0,
+            closed_at: None,
+            content_hash: None,
+            max_voters: None,
+            min_voters: None,
+            quorum: None,
+            pseudonymous: false,
+            default_locale: None,
+            translations: BTreeMap::new(),
+            voters_only_results: false,
+            results_after_quorum: false,
+            revote_window: None,
+            open_options: false,
+            satellite_channels: vec![],
+            weight_fn: WeightFormula::Linear,
+            max_weight_per_voter: None,
+            raffle: false,
+            win_threshold: None,
+            attributes: vec![],
+            display_order: vec![],
+            shuffle_options: false,
+            supersedes: None,
+            superseded_by: None,
+            option_count: 0,
+            pending_suggestions: 0,
+            ballot_weight: 0,
+            pruned_ballots: 0,
+        };
+        POLL.save(storage, poll_id, &poll)?;
+    }
+    let id_of = |poll_id: &str, label: &str| -> StdResult<u8> {
+        ids.get(poll_id)
+            .and_then(|labels| labels.get(label))
+            .copied()
+            .ok_or_else(|| StdError::not_found(format!("option {label} of poll {poll_id}")))
+    };
+
+    for ((voter, poll_id), old) in ballots {
+        let ballot = Ballot {
+            option: id_of(&poll_id, &old.option)?,
+            weight: old.weight,
+            deposit: old.deposit,
+            memo: old.memo,
+            allocations: old
+                .allocations
+                .iter()
+                .map(|(label, weight)| Ok((id_of(&poll_id, label)?, *weight)))
+                .collect::<StdResult<_>>()?,
+            voted_at: Timestamp::default(),
+            ranking: vec![],
+            raw_weight: None,
+        };
+        BALLOT.save(storage, (voter, poll_id), &ballot)?;
+    }
+
+    let results = v0_1::RESULTS
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (poll_id, old) in results {
+        let result = PollResult {
+            winner: old
+                .winner
+                .map(|label| id_of(&poll_id, &label))
+                .transpose()?,
+            tallies: to_options(old.tallies),
+            total: old.total,
+            finalized_at: old.finalized_at,
+            tie: old
+                .tie
+                .map(|tie| -> StdResult<_> {
+                    Ok(TieResolution {
+                        tied: tie
+                            .tied
+                            .iter()
+                            .map(|label| id_of(&poll_id, label))
+                            .collect::<StdResult<_>>()?,
+                        policy: tie.policy,
+                        resolved: tie.resolved,
+                    })
+                })
+                .transpose()?,
+            quorum_reached: None,
+            threshold: None,
+            eligible_weight: None,
+            min_voters_reached: None,
+            rounds: vec![],
+            pairwise: None,
+            randomness: None,
+            raffle_winner: None,
+        };
+        RESULTS.save(storage, poll_id, &result)?;
+    }
+    Ok(())
+}
+
+/// Indexes every stored ballot by option for `VotersForOption`.
+pub fn option_voters(storage: &mut dyn Storage) -> StdResult<()> {
+    let ballots = BALLOT
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for ((voter, poll_id), ballot) in ballots {
+        for (id, weight) in ballot
+            .shares()
+            .into_iter()
+            .filter(|(_, weight)| *weight > 0)
+        {
+            OPTION_VOTERS.save(storage, (poll_id.clone(), id, voter.clone()), &weight)?;
+        }
+    }
+    Ok(())
+}
+
+/// Indexes every stored ballot by poll for listing a poll's voters.
+pub fn poll_voters(storage: &mut dyn Storage) -> StdResult<()> {
+    let keys = BALLOT
+        .keys(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (voter, poll_id) in keys {
+        POLL_VOTERS.save(storage, (poll_id, voter), &())?;
+    }
+    Ok(())
+}
+
+/// Indexes every poll with a deadline by its end time for `FinalizeExpired`.
+pub fn poll_ends(storage: &mut dyn Storage) -> StdResult<()> {
+    let polls = POLL
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (poll_id, poll) in polls {
+        if let Some(end) = poll.end_time {
+            POLL_ENDS.save(storage, (end.seconds(), poll_id), &())?;
+        }
+    }
+    Ok(())
+}
+
+/// Indexes every poll under its creator and counts its pending
+/// suggestions for `CreatorDashboard`.
+pub fn creator_polls(storage: &mut dyn Storage) -> StdResult<()> {
+    let polls = POLL
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (poll_id, mut poll) in polls {
+        poll.pending_suggestions = SUGGESTIONS
+            .prefix(poll_id.clone())
+            .range(storage, None, None, Order::Ascending)
+            .filter(|item| {
+                item.as_ref().map_or(true, |(_, suggestion)| {
+                    suggestion.status == SuggestionStatus::Pending
+                })
+            })
+            .count() as u32;
+        POLL.save(storage, poll_id.clone(), &poll)?;
+        CREATOR_POLLS.save(storage, (poll.admin, poll_id), &())?;
+    }
+    Ok(())
+}
+
+/// Numbers the entrants of every raffle poll by option so a draw loads
+/// only the entry it picks.
+pub fn raffle_entries(storage: &mut dyn Storage) -> StdResult<()> {
+    let poll_ids = POLL
+        .range(storage, None, None, Order::Ascending)
+        .filter_map(|item| match item {
+            Ok((poll_id, poll)) => poll.raffle.then_some(Ok(poll_id)),
+            Err(err) => Some(Err(err)),
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    for poll_id in poll_ids {
+        let voters = POLL_VOTERS
+            .prefix(poll_id.clone())
+            .keys(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        let mut counts = BTreeMap::<u8, u64>::new();
+        for voter in voters {
+            let ballot = BALLOT.load(storage, (voter.clone(), poll_id.clone()))?;
+            for id in ballot.raffle_options() {
+                let count = counts.entry(id).or_default();
+                RAFFLE_ENTRIES.save(storage, (poll_id.clone(), id, *count), &voter)?;
+                RAFFLE_SLOTS.save(storage, (poll_id.clone(), id, voter.clone()), count)?;
+                *count += 1;
+            }
+        }
+        for (id, count) in counts {
+            RAFFLE_COUNTS.save(storage, (poll_id.clone(), id), &count)?;
+        }
+    }
+    Ok(())
+}
+
+/// Sums every token's stakes into `STAKE_TOTALS`. Heights before the
+/// migration read back the total at migration.
+pub fn stake_totals(storage: &mut dyn Storage) -> StdResult<()> {
+    let stakes = STAKES
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    let mut totals = BTreeMap::<Addr, Uint128>::new();
+    for ((token, _), stake) in stakes {
+        *totals.entry(token).or_default() += stake;
+    }
+    for (token, total) in totals {
+        STAKE_TOTALS.save(storage, token, &total, 0)?;
+    }
+    Ok(())
+}
+
+/// Sums each poll's ballot weights into `Poll::ballot_weight`, which
+/// splits its reward pool.
+pub fn ballot_weights(storage: &mut dyn Storage) -> StdResult<()> {
+    let ballots = BALLOT
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    let mut weights = BTreeMap::<String, u64>::new();
+    for ((_, poll_id), ballot) in ballots {
+        *weights.entry(poll_id).or_default() += ballot.weight;
+    }
+    for (poll_id, weight) in weights {
+        if let Some(mut poll) = POLL.may_load(storage, poll_id.clone())? {
+            poll.ballot_weight = weight;
+            POLL.save(storage, poll_id, &poll)?;
+        }
+    }
+    Ok(())
+}
+
+/// Clears the deposits of ballots in polls that stopped taking votes, which
+/// were refunded when they did; the rest are now claimed by their voters.
+pub fn refunded_deposits(storage: &mut dyn Storage) -> StdResult<()> {
+    let ballots = BALLOT
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    let mut refunded = HashMap::<String, bool>::new();
+    for ((voter, poll_id), mut ballot) in ballots {
+        if ballot.deposit.is_zero() {
+            continue;
+        }
+        let done = match refunded.get(&poll_id) {
+            Some(done) => *done,
+            None => {
+                let status = POLL.load(storage, poll_id.clone())?.status;
+                let done = !matches!(status, PollStatus::Open | PollStatus::Frozen);
+                refunded.insert(poll_id.clone(), done);
+                done
+            }
+        };
+        if done {
+            ballot.deposit = Uint128::zero();
+            BALLOT.save(storage, (voter, poll_id), &ballot)?;
+        }
+    }
+    Ok(())
+}
+
+/// Stores the salt pseudonymous polls used to derive from their id and
+/// creation block, so the hashes of their ballots still match.
+pub fn poll_salts(storage: &mut dyn Storage) -> StdResult<()> {
+    let polls = POLL
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (poll_id, poll) in polls.into_iter().filter(|(_, poll)| poll.pseudonymous) {
+        let salt = Sha256::new()
+            .chain_update(&poll_id)
+            .chain_update(poll.start_time.nanos().to_be_bytes())
+            .chain_update(poll.snapshot_height.to_be_bytes())
+            .finalize();
+        POLL_SALTS.save(storage, poll_id, &Binary::from(salt.to_vec()))?;
+    }
+    Ok(())
+}
+
+/// Counts each address's `CREATOR_POLLS` entries into its
+/// `VoterStats::polls_created`.
+pub fn polls_created(storage: &mut dyn Storage) -> StdResult<()> {
+    let keys = CREATOR_POLLS
+        .keys(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    let mut counts = BTreeMap::<Addr, u64>::new();
+    for (creator, _) in keys {
+        *counts.entry(creator).or_default() += 1;
+    }
+    for (creator, count) in counts {
+        voter_stats().update(storage, creator, |stats| -> StdResult<_> {
+            let mut stats = stats.unwrap_or_default();
+            stats.polls_created = count;
+            Ok(stats)
+        })?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::MockStorage;
+
+    #[test]
+    fn test_option_ids() {
+        let mut storage = MockStorage::new();
+        let poll = v0_1::Poll {
+            admin: Addr::unchecked("creator"),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![("Yes".to_string(), 1), ("No".to_string(), 2)],
+            status: PollStatus::Finalized,
+            start_time: Timestamp::from_seconds(0),
+            end_time: None,
+            mode: VoteMode::Standard,
+            reward_distribution: RewardDistribution::ProRata,
+            snapshot_height: 1,
+            tie_break: TieBreakPolicy::None,
+            voter_count: Some(3),
+            electorate: None,
+            cancel_reason: None,
+        };
+        v0_1::POLL
+            .save(&mut storage, "1".to_string(), &poll)
+            .unwrap();
+        let ballot = v0_1::Ballot {
+            option: "No".to_string(),
+            weight: 1,
+            deposit: Uint128::zero(),
+            memo: None,
+            allocations: vec![],
+        };
+        v0_1::BALLOT
+            .save(
+                &mut storage,
+                (Addr::unchecked("voter"), "1".to_string()),
+                &ballot,
+            )
+            .unwrap();
+        let result = v0_1::PollResult {
+            winner: Some("No".to_string()),
+            tallies: poll.options.clone(),
+            total: 3,
+            finalized_at: Timestamp::from_seconds(10),
+            tie: None,
+        };
+        v0_1::RESULTS
+            .save(&mut storage, "1".to_string(), &result)
+            .unwrap();
+
+        option_ids(&mut storage).unwrap();
+
+        let poll = POLL.load(&storage, "1".to_string()).unwrap();
+        assert_eq!(
+            poll.options,
+            vec![
+                PollOption {
+                    id: 0,
+                    label: "Yes".to_string(),
+                    votes: 1,
+                    description: None,
+                    image_uri: None,
+                },
+                PollOption {
+                    id: 1,
+                    label: "No".to_string(),
+                    votes: 2,
+                    description: None,
+                    image_uri: None,
+                },
+            ]
+        );
+        let ballot = BALLOT
+            .load(&storage, (Addr::unchecked("voter"), "1".to_string()))
+            .unwrap();
+        assert_eq!(ballot.option, 1);
+        let result = RESULTS.load(&storage, "1".to_string()).unwrap();
+        assert_eq!(result.winner, Some(1));
+        assert_eq!(result.tallies, poll.options);
+    }
+
+    fn save_ballot(storage: &mut dyn Storage, voter: &str, ballot: Ballot) {
+        BALLOT
+            .save(storage, (Addr::unchecked(voter), "1".to_string()), &ballot)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_option_voters() {
+        let mut storage = MockStorage::new();
+        let ballot = Ballot {
+            option: 1,
+            weight: 3,
+            deposit: Uint128::zero(),
+            memo: None,
+            allocations: vec![],
+            voted_at: Timestamp::default(),
+            ranking: vec![],
+            raw_weight: None,
+        };
+        save_ballot(&mut storage, "voter1", ballot.clone());
+        let split = Ballot {
+            allocations: vec![(0, 2), (1, 0)],
+            ..ballot
+        };
+        save_ballot(&mut storage, "voter2", split);
+
+        option_voters(&mut storage).unwrap();
+
+        let indexed = OPTION_VOTERS
+            .range(&storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()
+            .unwrap();
+        assert_eq!(
+            indexed,
+            vec![
+                (("1".to_string(), 0, Addr::unchecked("voter2")), 2),
+                (("1".to_string(), 1, Addr::unchecked("voter1")), 3),
+            ]
+        );
+    }
+
+    /// The layouts the first 0.1.0 release wrote.
+    #[derive(Serialize, Deserialize)]
+    struct BaselineConfig {
+        admin: Addr,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct BaselinePoll {
+        admin: Addr,
+        question: String,
+        options: Vec<(String, u64)>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct BaselineBallot {
+        option: String,
+    }
+
+    #[test]
+    fn test_upgrade() {
+        let mut storage = MockStorage::new();
+        Item::new("config")
+            .save(
+                &mut storage,
+                &BaselineConfig {
+                    admin: Addr::unchecked("admin"),
+                },
+            )
+            .unwrap();
+        let poll = BaselinePoll {
+            admin: Addr::unchecked("creator"),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![("Yes".to_string(), 0), ("No".to_string(), 1)],
+        };
+        Map::new("polls")
+            .save(&mut storage, "1".to_string(), &poll)
+            .unwrap();
+        let ballots: Map<(Addr, String), BaselineBallot> = Map::new("ballots");
+        let ballot = BaselineBallot {
+            option: "No".to_string(),
+        };
+        ballots
+            .save(
+                &mut storage,
+                (Addr::unchecked("voter"), "1".to_string()),
+                &ballot,
+            )
+            .unwrap();
+
+        assert_eq!(
+            upgrade(&mut storage, "0.12.0", "0.12.0").unwrap(),
+            Vec::<&str>::new()
+        );
+        let err = upgrade(&mut storage, "0.0.9", "0.12.0").unwrap_err();
+        assert!(matches!(err, ContractError::UnsupportedVersion { .. }));
+
+        // 0.1.0 storage goes through every hop
+        let applied = upgrade(&mut storage, "0.1.0", "0.12.0").unwrap();
+        assert_eq!(
+            applied,
+            vec![
+                "0.1.0", "0.2.0", "0.3.0", "0.4.0", "0.5.0", "0.6.0", "0.7.0", "0.8.0", "0.9.0",
+                "0.10.0", "0.11.0"
+            ]
+        );
+        let config = CONFIG.load(&storage).unwrap();
+        assert_eq!(config.admins, vec![Addr::unchecked("admin")]);
+        let migrated = POLL.load(&storage, "1".to_string()).unwrap();
+        assert_eq!(migrated.status, PollStatus::Open);
+        assert_eq!(migrated.voter_count, 1);
+        assert_eq!(migrated.ballot_weight, 1);
+        let migrated = BALLOT
+            .load(&storage, (Addr::unchecked("voter"), "1".to_string()))
+            .unwrap();
+        assert_eq!((migrated.option, migrated.weight), (1, 1));
+        assert!(OPTION_VOTERS.has(&storage, ("1".to_string(), 1, Addr::unchecked("voter"))));
+        assert!(POLL_VOTERS.has(&storage, ("1".to_string(), Addr::unchecked("voter"))));
+        assert!(CREATOR_POLLS.has(&storage, (Addr::unchecked("creator"), "1".to_string())));
+        let stats = voter_stats()
+            .load(&storage, Addr::unchecked("creator"))
+            .unwrap();
+        assert_eq!(stats.polls_created, 1);
+
+        // a ballot for a label its poll lacks is an error, not the first option
+        let mut storage = MockStorage::new();
+        let poll = BaselinePoll {
+            options: vec![("Yes".to_string(), 0)],
+            ..poll
+        };
+        Map::new("polls")
+            .save(&mut storage, "1".to_string(), &poll)
+            .unwrap();
+        ballots
+            .save(
+                &mut storage,
+                (Addr::unchecked("voter"), "1".to_string()),
+                &ballot,
+            )
+            .unwrap();
+        upgrade(&mut storage, "0.1.0", "0.12.0").unwrap_err();
+    }
+
+    /// Saves polls as 0.1.0 wrote them and brings them to the current
+    /// layout, for steps that read fields added since.
+    fn save_polls(storage: &mut dyn Storage, poll_ids: &[&str]) {
+        for poll_id in poll_ids {
+            let poll = v0_1::Poll {
+                admin: Addr::unchecked("creator"),
+                question: "Should We Have a Meeting Today".to_string(),
+                options: vec![("Yes".to_string(), 0), ("No".to_string(), 0)],
+                status: PollStatus::Open,
+                start_time: Timestamp::from_seconds(0),
+                end_time: None,
+                mode: VoteMode::Standard,
+                reward_distribution: RewardDistribution::ProRata,
+                snapshot_height: 1,
+                tie_break: TieBreakPolicy::None,
+                voter_count: Some(0),
+                electorate: None,
+                cancel_reason: None,
+            };
+            v0_1::POLL
+                .save(storage, poll_id.to_string(), &poll)
+                .unwrap();
+        }
+        option_ids(storage).unwrap();
+    }
+
+    fn update_poll(storage: &mut dyn Storage, poll_id: &str, update: impl FnOnce(&mut Poll)) {
+        let mut poll = POLL.load(storage, poll_id.to_string()).unwrap();
+        update(&mut poll);
+        POLL.save(storage, poll_id.to_string(), &poll).unwrap();
+    }
+
+    fn ballot(option: u8, deposit: u128) -> Ballot {
+        Ballot {
+            option,
+            weight: 1,
+            deposit: Uint128::new(deposit),
+            memo: None,
+            allocations: vec![],
+            voted_at: Timestamp::default(),
+            ranking: vec![],
+            raw_weight: None,
+        }
+    }
+
+    #[test]
+    fn test_poll_ends() {
+        let mut storage = MockStorage::new();
+        save_polls(&mut storage, &["1", "2"]);
+        update_poll(&mut storage, "1", |poll| {
+            poll.end_time = Some(Timestamp::from_seconds(100))
+        });
+
+        poll_ends(&mut storage).unwrap();
+
+        let indexed = POLL_ENDS
+            .keys(&storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()
+            .unwrap();
+        assert_eq!(indexed, vec![(100, "1".to_string())]);
+    }
+
+    #[test]
+    fn test_raffle_entries() {
+        let mut storage = MockStorage::new();
+        save_polls(&mut storage, &["1", "2"]);
+        update_poll(&mut storage, "1", |poll| poll.raffle = true);
+        for (voter, poll_id, option) in [
+            ("voter1", "1", 0),
+            ("voter2", "1", 1),
+            ("voter3", "1", 0),
+            ("voter1", "2", 0),
+        ] {
+            let voter = Addr::unchecked(voter);
+            BALLOT
+                .save(
+                    &mut storage,
+                    (voter.clone(), poll_id.to_string()),
+                    &ballot(option, 0),
+                )
+                .unwrap();
+            POLL_VOTERS
+                .save(&mut storage, (poll_id.to_string(), voter), &())
+                .unwrap();
+        }
+
+        raffle_entries(&mut storage).unwrap();
+
+        let entries = RAFFLE_ENTRIES
+            .range(&storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()
+            .unwrap();
+        assert_eq!(
+            entries,
+            vec![
+                (("1".to_string(), 0, 0), Addr::unchecked("voter1")),
+                (("1".to_string(), 0, 1), Addr::unchecked("voter3")),
+                (("1".to_string(), 1, 0), Addr::unchecked("voter2")),
+            ]
+        );
+        let slot = RAFFLE_SLOTS
+            .load(&storage, ("1".to_string(), 0, Addr::unchecked("voter3")))
+            .unwrap();
+        assert_eq!(slot, 1);
+        let counts = RAFFLE_COUNTS
+            .range(&storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()
+            .unwrap();
+        assert_eq!(
+            counts,
+            vec![(("1".to_string(), 0), 2), (("1".to_string(), 1), 1)]
+        );
+    }
+
+    #[test]
+    fn test_refunded_deposits() {
+        let mut storage = MockStorage::new();
+        save_polls(&mut storage, &["1", "2"]);
+        update_poll(&mut storage, "1", |poll| poll.status = PollStatus::Closed);
+        for poll_id in ["1", "2"] {
+            BALLOT
+                .save(
+                    &mut storage,
+                    (Addr::unchecked("voter"), poll_id.to_string()),
+                    &ballot(0, 50),
+                )
+                .unwrap();
+        }
+
+        refunded_deposits(&mut storage).unwrap();
+
+        // refunded when the poll closed; still held for the open one
+        let deposits: Vec<_> = ["1", "2"]
+            .into_iter()
+            .map(|poll_id| {
+                let key = (Addr::unchecked("voter"), poll_id.to_string());
+                BALLOT.load(&storage, key).unwrap().deposit
+            })
+            .collect();
+        assert_eq!(deposits, vec![Uint128::zero(), Uint128::new(50)]);
+    }
+
+    #[test]
+    fn test_stake_totals() {
+        let mut storage = MockStorage::new();
+        let token = Addr::unchecked("token");
+        for (staker, amount) in [("a", 5u128), ("b", 7)] {
+            STAKES
+                .save(
+                    &mut storage,
+                    (token.clone(), Addr::unchecked(staker)),
+                    &Uint128::new(amount),
+                    10,
+                )
+                .unwrap();
+        }
+        stake_totals(&mut storage).unwrap();
+        assert_eq!(
+            STAKE_TOTALS.load(&storage, token.clone()).unwrap(),
+            Uint128::new(12)
+        );
+        assert_eq!(
+            STAKE_TOTALS.may_load_at_height(&storage, token, 1).unwrap(),
+            Some(Uint128::new(12))
+        );
+    }
+}
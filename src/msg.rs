@@ -1,55 +1,1275 @@
-use crate::state::{Ballot, Poll};
+use std::collections::BTreeMap;
+
+use crate::state::{
+    AdminAction, Ballot, ChangeRecord, Comment, Config, LocalizedText, Poll, PollOption,
+    PollResult, PollStatus, Quorum, RevoteWindow, RewardDistribution, RewardPool, SatelliteTally,
+    Suggestion, TieBreakPolicy, VoteMode, WeightDecay, WeightFormula,
+};
+use cosmwasm_schema::QueryResponses;
+use cosmwasm_std::{Addr, Binary, Coin, CosmosMsg, Decimal, HexBinary, Timestamp, Uint128};
+use cw2::ContractVersion;
+use cw20::{Cw20Coin, Cw20CoinVerified, Cw20ReceiveMsg};
+use cw_utils::Expiration;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct InstantiateMsg {
     pub admin: Option<String>,
+    pub min_voting_period: Option<u64>,
+    /// Zero sets no limit, as in `UpdateConfig`.
+    pub max_voting_period: Option<u64>,
+    pub max_delegation_depth: Option<u64>,
+    pub creation_deposit: Option<Coin>,
+    pub cw20_creation_deposit: Option<Cw20Coin>,
+    pub voting_token: Option<String>,
+    pub treasury: Option<String>,
+    pub flag_threshold: Option<u64>,
+    pub veto_period: Option<u64>,
+    pub notify_contract: Option<String>,
+    pub sponsors: Option<Vec<String>>,
+    pub required_sponsors: Option<u64>,
+    /// Nois proxy contract that delivers randomness for tie-breaks and raffles.
+    pub randomness_proxy: Option<String>,
+    /// Non-transferable cw721 collection that mints voters a receipt.
+    pub receipt_nft: Option<String>,
+    /// Lets only admins and `creators` create polls.
+    #[serde(default)]
+    pub restricted_creation: bool,
+    pub creators: Option<Vec<String>>,
+    /// Polls created with the contract, administered by `admin` and exempt
+    /// from the creation deposit.
+    pub initial_polls: Option<Vec<NewPoll>>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
+#[allow(clippy::large_enum_variant)]
 pub enum ExecuteMsg {
-    CreatePoll {
-        poll_id: String,
-        question: String,
-        options: Vec<String>,
+    CreatePoll(NewPoll),
+    /// Creates `new_poll_id` with the question, options and settings of an
+    /// existing poll but none of its votes. Only the source poll's creator
+    /// or a contract admin may clone it.
+    ClonePoll {
+        source_poll_id: String,
+        new_poll_id: String,
     },
+    /// `vote` is the id of the chosen option.
     Vote {
         poll_id: String,
-        vote: String,
+        vote: u8,
+        memo: Option<String>,
+        /// Required in polls with a Merkle allowlist.
+        proof: Option<MerkleProof>,
+    },
+    /// Votes for the option at position `option_index` in the poll's option
+    /// list, for clients that render options in order rather than by id.
+    /// Positions follow the poll's display order; shuffled polls are refused.
+    VoteByIndex {
+        poll_id: String,
+        option_index: u32,
+        memo: Option<String>,
+        proof: Option<MerkleProof>,
+    },
+    /// Orders options by preference in a ranked poll, most preferred first.
+    /// Options left out are never counted for the voter.
+    RankVotes {
+        poll_id: String,
+        ranking: Vec<u8>,
+        memo: Option<String>,
+        proof: Option<MerkleProof>,
+    },
+    /// Splits the voter's budget across options in a cumulative poll.
+    AllocateVotes {
+        poll_id: String,
+        allocations: Vec<(u8, Uint128)>,
+        memo: Option<String>,
+        /// Required in polls with a Merkle allowlist or snapshot budget.
+        proof: Option<MerkleProof>,
+    },
+    Comment {
+        poll_id: String,
+        body: String,
+        reply_to: Option<u64>,
+    },
+    /// Proposes a new option for a poll with open options.
+    SuggestOption {
+        poll_id: String,
+        option: String,
+    },
+    /// Poll admin only: adds a pending suggestion to the options, or drops it.
+    ReviewSuggestion {
+        poll_id: String,
+        suggestion_id: u64,
+        approve: bool,
+    },
+    ClosePoll {
+        poll_id: String,
+    },
+    /// Suspends voting without ending the poll, e.g. while investigating
+    /// manipulation.
+    FreezePoll {
+        poll_id: String,
+    },
+    UnfreezePoll {
+        poll_id: String,
+    },
+    /// Opens a closed poll that has not been finalized again until `new_end`,
+    /// keeping its ballots. `new_end` must leave the minimum voting period
+    /// and stay within the maximum one from the poll's start. Contract
+    /// admins only.
+    ReopenPoll {
+        poll_id: String,
+        new_end: Timestamp,
+        reason: String,
+    },
+    CancelPoll {
+        poll_id: String,
+        reason: String,
+    },
+    /// Reports a poll as spam. Enough distinct flags, or a single flag from a
+    /// moderator, cancel it and slash its creation deposit.
+    FlagPoll {
+        poll_id: String,
+    },
+    /// Blocks a closed poll from being finalized during the veto period.
+    Veto {
+        poll_id: String,
+        reason: String,
+    },
+    /// Seconds a draft poll; it opens once enough sponsors have. A poll
+    /// opening with less than the minimum voting period left has its end
+    /// moved to keep the duration it was created with.
+    SponsorPoll {
+        poll_id: String,
+    },
+    ExtendDeadline {
+        poll_id: String,
+        new_end: Timestamp,
+    },
+    /// Sets the order queries list a poll's options in, to counter position
+    /// bias. Options missing from `order` follow it in creation order;
+    /// `shuffle` instead reshuffles them every block. Poll admin only.
+    SetDisplayOrder {
+        poll_id: String,
+        order: Vec<u8>,
+        shuffle: bool,
+    },
+    ArchivePoll {
+        poll_id: String,
+    },
+    FinalizePoll {
+        poll_id: String,
+    },
+    /// Closes the open polls among the next `limit` indexed end times that
+    /// have passed, picking up where the last crank stopped. Anyone may call
+    /// it.
+    Crank {
+        limit: Option<u32>,
+    },
+    /// Goes through up to `limit` indexed end times whose veto period has
+    /// passed, earliest first, and finalizes those polls. Anyone may call
+    /// it; polls that fail to finalize are reported as `failed`, and those
+    /// that may need randomness as `skipped`, both left to `FinalizePoll`.
+    FinalizeExpired {
+        limit: Option<u32>,
+    },
+    /// Deletes up to `limit` ballots of an archived poll and reports how
+    /// many remain, so large polls can be cleaned up over several calls.
+    /// Deposits left unclaimed are returned to their voters. Anyone may
+    /// call it.
+    PruneBallots {
+        poll_id: String,
+        limit: Option<u32>,
+    },
+    ResolveTie {
+        poll_id: String,
+        option: u8,
+    },
+    /// Randomness requested at finalization, sent by the randomness proxy.
+    NoisReceive {
+        callback: NoisCallback,
+    },
+    /// Closes a poll whose outcome can no longer change.
+    TryEarlyClose {
+        poll_id: String,
+    },
+    UpdateConfig {
+        min_voting_period: Option<u64>,
+        /// Zero lifts the limit.
+        max_voting_period: Option<u64>,
+        max_delegation_depth: Option<u64>,
+        veto_period: Option<u64>,
+        restricted_creation: Option<bool>,
+    },
+    /// Lets `to` vote with the sender's weight in standard polls the sender
+    /// skips. Delegations chain: `to` may pass the combined weight on.
+    Delegate {
+        to: String,
+    },
+    Undelegate {},
+    /// Withdraws the sender's lock from an open conviction poll, dropping its
+    /// ballot and accumulated conviction.
+    Unlock {
+        poll_id: String,
+    },
+    /// Returns the sender's escrowed cw20 deposit once the poll has stopped
+    /// taking votes.
+    ClaimDeposit {
+        poll_id: String,
+    },
+    /// Binds a secp256k1 public key to the sender so a relayer can submit
+    /// ballots signed with it. `signature` is the key's signature over the
+    /// sha256 hash of the JSON-encoded `KeyRegistration`.
+    RegisterVotingKey {
+        pubkey: Binary,
+        signature: Binary,
+    },
+    /// Casts ballots signed off-chain; the relayer pays the fees.
+    SubmitSignedVotes {
+        votes: Vec<SignedVote>,
+    },
+    AddAdmin {
+        address: String,
+    },
+    RemoveAdmin {
+        address: String,
+    },
+    AddModerator {
+        address: String,
+    },
+    RemoveModerator {
+        address: String,
+    },
+    /// Lets `address` close, cancel and moderate polls in `category`.
+    AddCategoryAdmin {
+        category: String,
+        address: String,
+    },
+    RemoveCategoryAdmin {
+        category: String,
+        address: String,
+    },
+    BanVoter {
+        address: String,
+    },
+    UnbanVoter {
+        address: String,
+    },
+    /// Lets `address` create polls while creation is restricted.
+    AddCreator {
+        address: String,
+    },
+    RemoveCreator {
+        address: String,
+    },
+    Receive(Cw20ReceiveMsg),
+    /// Turns import mode on or off. It can only be turned on while this
+    /// contract has no polls.
+    SetImportMode {
+        enabled: bool,
+    },
+    /// Writes a batch of polls, their records and ballots exported from
+    /// another deployment. Admin only, and only in import mode; a batch's
+    /// records and ballots may belong to polls imported by an earlier one.
+    /// The voter count and ballot weight of a poll that isn't archived are
+    /// rebuilt from its imported ballots.
+    ImportPolls {
+        polls: Vec<PollImport>,
+        ballots: Vec<BallotExport>,
+        #[serde(default)]
+        records: Vec<RecordExport>,
+    },
+    /// Adds the attached native coin to the poll's reward pool.
+    FundRewards {
+        poll_id: String,
+    },
+    /// Pays out the sender's share of a finalized poll's reward pool.
+    ClaimReward {
+        poll_id: String,
+    },
+    Unstake {
+        token: String,
+        amount: Uint128,
     },
 }
 
+/// Sent to the configured `notify_contract` after each ballot is recorded.
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
+pub enum NotifyMsg {
+    VoteCast {
+        poll_id: String,
+        voter: String,
+        /// `(option id, weight)` pairs the ballot counts towards, left out
+        /// while the poll hides its results.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        shares: Option<Vec<(u8, u64)>>,
+        memo: Option<String>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct NewPoll {
+    pub poll_id: String,
+    pub question: String,
+    /// At most 255, as option ids are `u8`. Polls with more than 10 keep
+    /// them apart from the poll and list them with the `Options` query.
+    pub options: Vec<String>,
+    /// Description and image for each of `options`, in the same order.
+    pub option_metadata: Option<Vec<OptionMetadata>>,
+    pub end_time: Option<Timestamp>,
+    pub mode: Option<VoteMode>,
+    pub reward_distribution: Option<RewardDistribution>,
+    pub tie_break: Option<TieBreakPolicy>,
+    /// Restricts voting to these addresses.
+    pub allowed_voters: Option<Vec<String>>,
+    /// Fixed weights for these addresses, taking precedence over their token
+    /// or one-vote weight, e.g. for council members voting alongside holders.
+    pub weight_overrides: Option<Vec<(String, u64)>>,
+    /// Native denom or cw20 contract address, and the balance a voter must hold.
+    pub min_balance: Option<(String, Uint128)>,
+    /// Restricts voting to the `(address, weight)` pairs under this hex-encoded
+    /// Merkle root; voters prove their entry with each `Vote`.
+    pub merkle_root: Option<String>,
+    /// Sum of the weights in `merkle_root`'s tree, which percentage quorums
+    /// are counted against.
+    #[serde(default)]
+    pub merkle_total_weight: Option<Uint128>,
+    /// Decays each ballot's weight from when it was cast until the poll closes,
+    /// so voters have to re-confirm late.
+    pub decay: Option<WeightDecay>,
+    pub category: Option<String>,
+    /// Hash of the full proposal document, e.g. the SHA-256 of a file on IPFS.
+    pub content_hash: Option<HexBinary>,
+    /// Rejects ballots from new voters once this many have voted. Ranked,
+    /// conviction and decaying polls are recounted from every ballot at
+    /// close, so they take 1000 voters at most, which is also their default.
+    pub max_voters: Option<u32>,
+    /// Distinct addresses that have to vote, however much weight they hold,
+    /// for the poll to have a winner.
+    #[serde(default)]
+    pub min_voters: Option<u32>,
+    /// Without it being met the poll finalizes with no winner.
+    pub quorum: Option<Quorum>,
+    /// Stores ballots under salted hashes of voter addresses. Not available
+    /// with escrowed deposits or rewards, which need to pay voters back.
+    #[serde(default)]
+    pub pseudonymous: bool,
+    /// Language of `question` and `options`; required with `translations`.
+    pub default_locale: Option<String>,
+    pub translations: Option<BTreeMap<String, LocalizedText>>,
+    /// Shows tallies and ballots only to addresses that voted until the poll
+    /// closes. Queries cannot authenticate `viewer`, so this keeps results out
+    /// of casual view rather than secret.
+    #[serde(default)]
+    pub voters_only_results: bool,
+    /// Hides tallies and ballots from everyone until turnout meets `quorum`,
+    /// then shows them while voting continues. Needs a quorum.
+    #[serde(default)]
+    pub results_after_quorum: bool,
+    #[serde(default)]
+    pub revote_window: Option<RevoteWindow>,
+    #[serde(default)]
+    pub open_options: bool,
+    /// IBC channels whose satellite tallies are added to this poll's.
+    #[serde(default)]
+    pub satellite_channels: Vec<String>,
+    /// Applied to token-weighted votes before tallying. Defaults to linear.
+    pub weight_fn: Option<WeightFormula>,
+    /// Caps the weight any single ballot counts with.
+    pub max_weight_per_voter: Option<Uint128>,
+    /// Draws a random voter of the winning option at finalization. Needs a
+    /// randomness proxy.
+    #[serde(default)]
+    pub raffle: bool,
+    /// Share of the votes, above zero and at most one, the leading option
+    /// needs; below it the poll finalizes with no winner.
+    pub win_threshold: Option<Decimal>,
+    /// Integration-specific metadata such as a forum post id, returned as
+    /// given. Keys must be unique.
+    #[serde(default)]
+    pub attributes: Vec<(String, String)>,
+    /// Earlier poll of the same admin this one replaces. It is closed if
+    /// still open and links back to this one.
+    #[serde(default)]
+    pub supersedes: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct OptionMetadata {
+    pub description: Option<String>,
+    pub image_uri: Option<String>,
+}
+
+/// Proof that `(voter, weight)` is a leaf under a poll's Merkle root. Leaves
+/// are `sha256(address + weight)` and each pair is hashed in sorted order.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct MerkleProof {
+    pub weight: Uint128,
+    /// Hex-encoded sibling hashes from the leaf up.
+    pub proof: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct SignedVote {
+    /// A key registered with `RegisterVotingKey`.
+    pub pubkey: Binary,
+    pub poll_id: String,
+    pub option: u8,
+    /// Must be greater than the voter's last used nonce.
+    pub nonce: u64,
+    /// secp256k1 signature over the sha256 hash of the JSON-encoded `VotePayload`.
+    pub signature: Binary,
+}
+
+/// What a voter signs for a `SignedVote`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct VotePayload {
+    pub chain_id: String,
+    pub contract: String,
+    pub poll_id: String,
+    pub option: u8,
+    pub nonce: u64,
+}
+
+/// What a key signs to prove it belongs to the address registering it.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct KeyRegistration {
+    pub chain_id: String,
+    pub contract: String,
+    pub voter: String,
+}
+
+/// Shows that the querier owns an address: a voting key registered to it
+/// and that key's signature over the sha256 hash of the JSON-encoded
+/// `BallotLookup`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct OwnerProof {
+    pub pubkey: Binary,
+    pub signature: Binary,
+}
+
+/// What an `OwnerProof` signs to look up the voter's pseudonymous ballot.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct BallotLookup {
+    pub chain_id: String,
+    pub contract: String,
+    pub poll_id: String,
+    pub voter: String,
+}
+
+/// Messages embedded in a cw20 `Send` to this contract.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+#[allow(clippy::large_enum_variant)]
+pub enum ReceiveMsg {
+    Vote {
+        poll_id: String,
+        option: u8,
+        memo: Option<String>,
+    },
+    FundRewards {
+        poll_id: String,
+    },
+    Stake {},
+    /// Creates a poll paying the configured cw20 creation deposit.
+    CreatePoll(NewPoll),
+    /// Locks the tokens behind `option` in a conviction poll.
+    Lock {
+        poll_id: String,
+        option: u8,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, QueryResponses)]
+#[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
+    #[returns(ConfigResponse)]
+    Config {},
+    #[returns(AllPollResponse)]
     AllPoll {},
-    Poll { poll_id: String },
-    Vote { poll_id: String, address: String },
+    /// Polls without their options, for clients listing many at once.
+    /// `status` keeps only polls currently in that status.
+    #[returns(PollSummariesResponse)]
+    PollSummaries {
+        start_after: Option<String>,
+        limit: Option<u32>,
+        status: Option<PollStatus>,
+    },
+    /// Polls carrying the attribute `key` with exactly `value`.
+    #[returns(PollSummariesResponse)]
+    PollsByAttribute {
+        key: String,
+        value: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Returns the question and labels translated to `locale` when the poll
+    /// has that translation, and in its default language otherwise.
+    ///
+    /// `viewer` unlocks the tallies of a voters-only poll if they voted in it.
+    #[returns(PollResponse)]
+    Poll {
+        poll_id: String,
+        locale: Option<String>,
+        viewer: Option<String>,
+    },
+    /// Checks a document hash against the one anchored at creation.
+    #[returns(VerifyContentResponse)]
+    VerifyContent { poll_id: String, hash: HexBinary },
+    /// In a pseudonymous poll `address` is the voter hash `ListVotes`
+    /// shows, unless `proof` shows the querier owns the address. Hidden
+    /// results fail it like `ListVotes`, with the proven owner as `viewer`.
+    #[returns(VoteResponse)]
+    Vote {
+        poll_id: String,
+        address: String,
+        #[serde(default)]
+        proof: Option<OwnerProof>,
+        #[serde(default)]
+        viewer: Option<String>,
+    },
+    /// The voter's ballot with every option it backs, labelled, in any mode.
+    /// `address`, `proof` and `viewer` work as in `Vote`.
+    #[returns(BallotResponse)]
+    Ballot {
+        poll_id: String,
+        address: String,
+        #[serde(default)]
+        proof: Option<OwnerProof>,
+        #[serde(default)]
+        viewer: Option<String>,
+    },
+    /// Everything a poll creator's frontend needs, a page of their polls
+    /// at a time.
+    #[returns(CreatorDashboardResponse)]
+    CreatorDashboard {
+        address: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Fails for a voters-only poll until it closes unless `viewer` voted.
+    #[returns(ListVotesResponse)]
+    ListVotes {
+        poll_id: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+        viewer: Option<String>,
+    },
+    /// Voters whose ballot counts towards `option`, with the weight it gives it.
+    #[returns(VotersForOptionResponse)]
+    VotersForOption {
+        poll_id: String,
+        option: u8,
+        start_after: Option<String>,
+        limit: Option<u32>,
+        viewer: Option<String>,
+    },
+    /// Every ballot in every poll, ordered by `(voter, poll_id)`; resume from
+    /// the last pair returned.
+    #[returns(ExportBallotsResponse)]
+    ExportBallots {
+        start_after: Option<(String, String)>,
+        limit: Option<u32>,
+    },
+    /// Contract state in a versioned shape for re-importing elsewhere, one
+    /// section at a time, exactly as stored. `start_after` is a poll id, a
+    /// voter for sections keyed by voter, or a channel for satellite tallies.
+    /// Queries cannot check who is asking, so this reveals nothing beyond raw
+    /// storage; tallies and ballots a poll keeps to its voters stay hidden.
+    #[returns(ExportStateResponse)]
+    ExportState {
+        section: ExportSection,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    #[returns(CommentsResponse)]
+    Comments {
+        poll_id: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// A page of the poll's options, for polls too large to list them inline.
+    #[returns(OptionsResponse)]
+    Options {
+        poll_id: String,
+        start_after: Option<u8>,
+        limit: Option<u32>,
+        viewer: Option<String>,
+    },
+    #[returns(SuggestionsResponse)]
+    Suggestions {
+        poll_id: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Tallies each satellite chain contributed to the poll.
+    #[returns(ChainTalliesResponse)]
+    ChainTallies { poll_id: String },
+    /// Votes each option had at the start of block `height`. Fails for a
+    /// voters-only poll until it closes.
+    #[returns(TallyAtHeightResponse)]
+    TallyAtHeight { poll_id: String, height: u64 },
+    #[returns(PollHistoryResponse)]
+    PollHistory {
+        poll_id: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Privileged actions, oldest first.
+    #[returns(AdminLogResponse)]
+    AdminLog {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    #[returns(FinalResultResponse)]
+    FinalResult { poll_id: String },
+    #[returns(ResolvedWeightResponse)]
+    ResolvedWeight { address: String, poll_id: String },
+    /// Addresses with a fixed weight in the poll.
+    #[returns(WeightOverridesResponse)]
+    WeightOverrides {
+        poll_id: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    #[returns(TurnoutStatsResponse)]
+    TurnoutStats {},
+    #[returns(TopVotersResponse)]
+    TopVoters { limit: Option<u32> },
+    /// Participation history of a single address.
+    #[returns(VoterInfoResponse)]
+    VoterInfo { address: String },
+    #[returns(CategoryAdminsResponse)]
+    CategoryAdmins {
+        category: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    #[returns(BannedVotersResponse)]
+    BannedVoters {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Addresses allowed to create polls while creation is restricted.
+    #[returns(CreatorsResponse)]
+    Creators {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    #[returns(RewardPoolResponse)]
+    RewardPool { poll_id: String },
+    #[returns(ClaimableRewardResponse)]
+    ClaimableReward { poll_id: String, address: String },
+    #[returns(StakeResponse)]
+    Stake {
+        token: String,
+        address: String,
+        height: Option<u64>,
+    },
+}
+
+/// Queries from the cw3 spec, exposing each poll as a proposal so generic DAO
+/// tooling can read it.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, QueryResponses)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw3QueryMsg {
+    #[returns(Cw3ProposalResponse)]
+    Proposal { proposal_id: u64 },
+    #[returns(Cw3ProposalListResponse)]
+    ListProposals {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    #[returns(Cw3VoteResponse)]
+    Vote { proposal_id: u64, voter: String },
+    #[returns(Cw3VoteListResponse)]
+    ListVotes {
+        proposal_id: u64,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// With `proposal_id`, the weight `address` votes with in that poll;
+    /// without, the weight of a plain vote.
+    #[returns(Cw3VoterResponse)]
+    Voter {
+        address: String,
+        proposal_id: Option<u64>,
+    },
+}
+
+/// Queries DAO DAO expects from a voting module. Voting power is the stake
+/// of the configured `voting_token`; the DAO is the first contract admin.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, QueryResponses)]
+#[serde(rename_all = "snake_case")]
+pub enum DaoQueryMsg {
+    #[returns(VotingPowerAtHeightResponse)]
+    VotingPowerAtHeight {
+        address: String,
+        height: Option<u64>,
+    },
+    #[returns(TotalPowerAtHeightResponse)]
+    TotalPowerAtHeight { height: Option<u64> },
+    #[returns(Addr)]
+    Dao {},
+    #[returns(InfoResponse)]
+    Info {},
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+pub struct VotingPowerAtHeightResponse {
+    pub power: Uint128,
+    pub height: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+pub struct TotalPowerAtHeightResponse {
+    pub power: Uint128,
+    pub height: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+pub struct InfoResponse {
+    pub info: ContractVersion,
+}
+
+/// Everything the `query` entry point accepts. `Vote` and `ListVotes` exist in
+/// both sets, and the field names decide which one is meant.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(untagged)]
+pub enum ContractQueryMsg {
+    Poll(QueryMsg),
+    Cw3(Cw3QueryMsg),
+    Dao(DaoQueryMsg),
+}
+
+impl From<QueryMsg> for ContractQueryMsg {
+    fn from(msg: QueryMsg) -> Self {
+        ContractQueryMsg::Poll(msg)
+    }
+}
+
+impl From<Cw3QueryMsg> for ContractQueryMsg {
+    fn from(msg: Cw3QueryMsg) -> Self {
+        ContractQueryMsg::Cw3(msg)
+    }
+}
+
+impl From<DaoQueryMsg> for ContractQueryMsg {
+    fn from(msg: DaoQueryMsg) -> Self {
+        ContractQueryMsg::Dao(msg)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ConfigResponse {
+    pub config: Config,
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct AllPollResponse {
-    pub polls: Vec<Poll>,
+    pub polls: Vec<PollEntry>,
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
 #[serde(rename_all = "snake_case")]
+pub struct PollSummariesResponse {
+    pub polls: Vec<PollSummary>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct PollSummary {
+    pub id: String,
+    pub question: String,
+    pub status: PollStatus,
+    pub end_time: Option<Timestamp>,
+    /// Number of addresses that have voted.
+    pub total_votes: u64,
+}
 
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct PollEntry {
+    pub id: String,
+    pub poll: Poll,
+    #[serde(default)]
+    pub results_hidden: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
 pub struct PollResponse {
     pub poll: Option<Poll>,
+    /// Option votes are zeroed because the viewer has not voted in this
+    /// voters-only poll.
+    #[serde(default)]
+    pub results_hidden: bool,
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
 #[serde(rename_all = "snake_case")]
+pub struct VerifyContentResponse {
+    /// False as well when the poll has no content hash.
+    pub matches: bool,
+}
 
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
 pub struct VoteResponse {
     pub vote: Option<Ballot>,
 }
 
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct CreatorDashboardResponse {
+    /// Drafts and polls open or frozen.
+    pub open_polls: Vec<PollSummary>,
+    /// Finalized polls in this page, newest first.
+    pub finalized_polls: Vec<FinalizedPoll>,
+    /// Creation deposits held until their poll closes.
+    pub deposits: Vec<HeldDeposit>,
+    pub pending_actions: Vec<PendingAction>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct FinalizedPoll {
+    pub id: String,
+    pub question: String,
+    pub winner: Option<u8>,
+    pub total: u64,
+    pub finalized_at: Timestamp,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct HeldDeposit {
+    pub poll_id: String,
+    pub native: Option<Coin>,
+    pub cw20: Option<Cw20CoinVerified>,
+}
+
+/// Something a poll is waiting on its creator for.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PendingAction {
+    /// Suggested options to approve or reject.
+    ReviewSuggestions { poll_id: String, count: u64 },
+    /// Closed and past its veto period, ready for `FinalizePoll`.
+    Finalize { poll_id: String },
+    /// A tie left to the admin, settled with `ResolveTie`.
+    ResolveTie { poll_id: String, tied: Vec<u8> },
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct BallotResponse {
+    pub ballot: Option<BallotDetail>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct BallotDetail {
+    pub weight: u64,
+    pub deposit: Uint128,
+    pub voted_at: Timestamp,
+    pub memo: Option<String>,
+    /// Weight the ballot adds to each option: one entry, or a cumulative
+    /// ballot's split.
+    pub shares: Vec<BallotShare>,
+    /// A ranked ballot's full preference order, most preferred first.
+    pub ranking: Vec<BallotShare>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct BallotShare {
+    pub option: u8,
+    pub label: String,
+    /// Zero for ranked preferences after the first, which only count once
+    /// earlier ones are eliminated.
+    pub weight: u64,
+}
+
+/// Data set on every vote response: the options whose tally moved and their
+/// new counts, so mirrors can follow revotes without reloading the poll.
+/// Both lists stay empty while the poll hides its results.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct TallyDelta {
+    pub poll_id: String,
+    pub decremented: Vec<OptionTally>,
+    pub incremented: Vec<OptionTally>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct OptionTally {
+    pub option: u8,
+    pub votes: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct VoteEntry {
+    pub voter: Addr,
+    pub ballot: Ballot,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ListVotesResponse {
+    pub votes: Vec<VoteEntry>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct OptionVoter {
+    pub voter: Addr,
+    pub weight: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct VotersForOptionResponse {
+    pub voters: Vec<OptionVoter>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportSection {
+    Config {},
+    /// Polls with their options' tallies.
+    Polls {},
+    Results {},
+    /// Ballots of one poll, by voter.
+    Ballots {
+        poll_id: String,
+    },
+    AllowedVoters {
+        poll_id: String,
+    },
+    WeightOverrides {
+        poll_id: String,
+    },
+    LockedSince {
+        poll_id: String,
+    },
+    /// Reward pools of every poll, by poll id.
+    RewardPools {},
+    RewardClaims {
+        poll_id: String,
+    },
+    SatelliteTallies {
+        poll_id: String,
+    },
+}
+
+/// Shape version of `ExportStateResponse`, bumped when it changes.
+pub const EXPORT_FORMAT: u32 = 2;
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+pub struct ExportStateResponse {
+    pub format: u32,
+    pub contract_version: String,
+    pub entries: Vec<ExportEntry>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+#[allow(clippy::large_enum_variant)]
+pub enum ExportEntry {
+    Config(Config),
+    Poll { poll_id: String, poll: Poll },
+    Result { poll_id: String, result: PollResult },
+    Ballot { voter: Addr, ballot: Ballot },
+    Record { poll_id: String, record: PollRecord },
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct BallotExport {
+    pub voter: Addr,
+    pub poll_id: String,
+    pub ballot: Ballot,
+}
+
+/// An entry of one of the maps kept beside a poll. Reward pools and claims
+/// are only imported, so the contract must be funded to pay them.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PollRecord {
+    AllowedVoter {
+        voter: Addr,
+    },
+    WeightOverride {
+        voter: Addr,
+        weight: u64,
+    },
+    /// Keyed like the voter's ballot.
+    LockedSince {
+        voter: Addr,
+        since: Timestamp,
+    },
+    RewardPool {
+        pool: RewardPool,
+    },
+    /// Keyed like the voter's ballot.
+    RewardClaim {
+        voter: Addr,
+        amount: Uint128,
+    },
+    SatelliteTally {
+        channel: String,
+        tally: SatelliteTally,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct RecordExport {
+    pub poll_id: String,
+    pub record: PollRecord,
+}
+
+/// A poll to import, with its result if it was finalized.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct PollImport {
+    pub poll_id: String,
+    pub poll: Poll,
+    pub result: Option<PollResult>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ExportBallotsResponse {
+    pub ballots: Vec<BallotExport>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct CommentsResponse {
+    pub comments: Vec<Comment>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+pub struct TallyAtHeightResponse {
+    pub height: u64,
+    /// `(option id, votes)` for every option of the poll.
+    pub tallies: Vec<(u8, u64)>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+pub struct ChainTalliesResponse {
+    /// Channel ids and their latest tallies.
+    pub tallies: Vec<(String, SatelliteTally)>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+pub struct OptionsResponse {
+    pub options: Vec<PollOption>,
+    /// Whether vote counts were zeroed because the viewer may not see them.
+    pub results_hidden: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+pub struct SuggestionsResponse {
+    pub suggestions: Vec<Suggestion>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct PollHistoryResponse {
+    pub history: Vec<ChangeRecord>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+pub struct AdminLogResponse {
+    pub actions: Vec<AdminAction>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct FinalResultResponse {
+    pub result: Option<PollResult>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct WeightOverridesResponse {
+    pub overrides: Vec<(Addr, u64)>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ResolvedWeightResponse {
+    /// Ballot weight: one for the voter plus one per entry in `delegators`.
+    pub weight: u64,
+    /// Non-voting addresses whose weight reached this voter through delegation.
+    pub delegators: Vec<Addr>,
+    /// Set when the address did not vote and a delegate carries its weight.
+    pub represented_by: Option<Addr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct TurnoutStatsResponse {
+    pub finalized_polls: u64,
+    /// Mean number of distinct voters per finalized poll.
+    pub average_turnout: Decimal,
+    pub unique_voters: u64,
+    /// Finalized polls bucketed by voter count: 0, 1-9, 10-99, 100-999 and 1000+.
+    pub voter_histogram: Vec<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct TopVoter {
+    pub address: Addr,
+    pub votes_cast: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct TopVotersResponse {
+    pub voters: Vec<TopVoter>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct VoterInfoResponse {
+    pub first_vote: Option<Timestamp>,
+    pub votes_cast: u64,
+    /// Polls created with the address as their admin.
+    pub polls_created: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct CategoryAdminsResponse {
+    pub admins: Vec<Addr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct BannedVotersResponse {
+    pub voters: Vec<Addr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct CreatorsResponse {
+    pub creators: Vec<Addr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct RewardPoolResponse {
+    pub pool: Option<RewardPool>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ClaimableRewardResponse {
+    pub amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct StakeResponse {
+    pub amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw3Status {
+    Pending,
+    Open,
+    Rejected,
+    Passed,
+    Executed,
+}
+
+/// cw3 only knows yes/no/abstain/veto. In polls with options labelled
+/// "Yes" and "No", ignoring case, ballots for those and for an option
+/// labelled "Veto" map by label; every other ballot, split and ranked ones
+/// included, reads as `Abstain`.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw3Vote {
+    Yes,
+    No,
+    Abstain,
+    Veto,
+}
+
+/// Polls are decided by plurality, so any single vote can carry one.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw3ThresholdResponse {
+    AbsoluteCount { weight: u64, total_weight: u64 },
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct Cw3ProposalResponse {
+    pub id: u64,
+    pub title: String,
+    pub description: String,
+    pub msgs: Vec<CosmosMsg>,
+    pub status: Cw3Status,
+    pub expires: Expiration,
+    pub threshold: Cw3ThresholdResponse,
+    pub proposer: Addr,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct Cw3ProposalListResponse {
+    pub proposals: Vec<Cw3ProposalResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct Cw3VoteInfo {
+    pub proposal_id: u64,
+    pub voter: String,
+    pub vote: Cw3Vote,
+    pub weight: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct Cw3VoteResponse {
+    pub vote: Option<Cw3VoteInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct Cw3VoteListResponse {
+    pub votes: Vec<Cw3VoteInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct Cw3VoterResponse {
+    pub weight: Option<u64>,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct CustomResponse {
@@ -58,4 +1278,113 @@ pub struct CustomResponse {
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
-pub enum MigrateMsg {}
+pub struct MigrateMsg {}
+
+/// Messages only the chain itself can send, e.g. through a governance proposal.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SudoMsg {
+    /// Replaces every contract admin with `admin`, for recovering control
+    /// when the admin keys are lost or compromised.
+    UpdateAdmin { admin: String },
+}
+
+/// Mint message of a cw721-base collection carrying `ReceiptMetadata`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReceiptMintMsg {
+    Mint {
+        token_id: String,
+        owner: String,
+        token_uri: Option<String>,
+        extension: ReceiptMetadata,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ReceiptMetadata {
+    pub poll_id: String,
+    /// Options the ballot counted towards when it was first cast, empty if
+    /// the poll was hiding its results.
+    pub options: Vec<u8>,
+}
+
+/// Request understood by the Nois proxy; `job_id` comes back in the callback.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum NoisProxyMsg {
+    GetNextRandomness { job_id: String },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct NoisCallback {
+    /// Id of the poll the randomness was requested for.
+    pub job_id: String,
+    pub published: Timestamp,
+    pub randomness: HexBinary,
+}
+
+/// Query a vesting contract answers for polls in `VoteMode::Vesting`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum VestingQueryMsg {
+    Allocation { address: String },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AllocationResponse {
+    pub vested: Uint128,
+    pub unvested: Uint128,
+}
+
+/// Query a cw20-stake contract answers for polls in `VoteMode::Staking`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum StakingQueryMsg {
+    StakedBalanceAtHeight {
+        address: String,
+        height: Option<u64>,
+    },
+    TotalStakedAtHeight {
+        height: Option<u64>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StakedBalanceAtHeightResponse {
+    pub balance: Uint128,
+    pub height: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TotalStakedAtHeightResponse {
+    pub total: Uint128,
+    pub height: u64,
+}
+
+/// Packets a counterparty contract can send over a poll results channel.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PollPacket {
+    FinalResult {
+        poll_id: String,
+    },
+    /// Pushed by a satellite contract with the votes cast on its chain so
+    /// far, to be counted in the home poll. Refused once the poll has ended.
+    SubmitTally {
+        poll_id: String,
+        sequence: u64,
+        tallies: Vec<(u8, u64)>,
+        voters: u64,
+    },
+}
+
+/// Acknowledgement written for every `PollPacket`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+#[allow(clippy::large_enum_variant)]
+pub enum PollAck {
+    Result(FinalResultResponse),
+    TallyAccepted {},
+    Error(String),
+}
@@ -0,0 +1,116 @@
+//! Typed queries for contracts that read polls from a deployed instance.
+
+use cosmwasm_std::{Addr, QuerierWrapper, StdResult};
+use serde::de::DeserializeOwned;
+
+use crate::msg::{
+    ConfigResponse, ContractQueryMsg, FinalResultResponse, PollResponse, QueryMsg, VoteResponse,
+};
+use crate::state::{Ballot, Config, Poll, PollResult};
+
+pub struct PollQuerier<'a> {
+    querier: &'a QuerierWrapper<'a>,
+    contract: Addr,
+}
+
+impl<'a> PollQuerier<'a> {
+    pub fn new(querier: &'a QuerierWrapper<'a>, contract: Addr) -> Self {
+        PollQuerier { querier, contract }
+    }
+
+    fn query<T: DeserializeOwned>(&self, msg: QueryMsg) -> StdResult<T> {
+        self.querier
+            .query_wasm_smart(self.contract.clone(), &ContractQueryMsg::from(msg))
+    }
+
+    pub fn config(&self) -> StdResult<Config> {
+        self.query::<ConfigResponse>(QueryMsg::Config {})
+            .map(|resp| resp.config)
+    }
+
+    pub fn poll(&self, poll_id: impl Into<String>) -> StdResult<Option<Poll>> {
+        let msg = QueryMsg::Poll {
+            poll_id: poll_id.into(),
+            locale: None,
+            viewer: None,
+        };
+        self.query::<PollResponse>(msg).map(|resp| resp.poll)
+    }
+
+    /// The finalized outcome, or `None` while the poll is still running.
+    pub fn result(&self, poll_id: impl Into<String>) -> StdResult<Option<PollResult>> {
+        let msg = QueryMsg::FinalResult {
+            poll_id: poll_id.into(),
+        };
+        self.query::<FinalResultResponse>(msg)
+            .map(|resp| resp.result)
+    }
+
+    pub fn vote(
+        &self,
+        poll_id: impl Into<String>,
+        voter: impl Into<String>,
+    ) -> StdResult<Option<Ballot>> {
+        let msg = QueryMsg::Vote {
+            poll_id: poll_id.into(),
+            address: voter.into(),
+            proof: None,
+            viewer: None,
+        };
+        self.query::<VoteResponse>(msg).map(|resp| resp.vote)
+    }
+
+    pub fn has_voted(
+        &self,
+        poll_id: impl Into<String>,
+        voter: impl Into<String>,
+    ) -> StdResult<bool> {
+        self.vote(poll_id, voter).map(|vote| vote.is_some())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::msg::{InstantiateMsg, NewPoll};
+    use crate::testing::PollContract;
+    use cw_multi_test::App;
+
+    #[test]
+    fn test_poll_querier() {
+        let mut app = App::default();
+        let msg = InstantiateMsg {
+            admin: Some("admin".to_string()),
+            ..Default::default()
+        };
+        let contract = PollContract::instantiate(&mut app, "admin", &msg).unwrap();
+        let new_poll = NewPoll {
+            poll_id: "1".to_string(),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            ..Default::default()
+        };
+        contract.create_poll(&mut app, "admin", new_poll).unwrap();
+        contract.vote(&mut app, "voter1", "1", 1).unwrap();
+
+        let wrapper = app.wrap();
+        let querier = PollQuerier::new(&wrapper, contract.addr().clone());
+        assert_eq!(
+            querier.config().unwrap().admins,
+            vec![Addr::unchecked("admin")]
+        );
+        assert_eq!(querier.poll("1").unwrap().unwrap().voter_count, 1);
+        assert_eq!(querier.poll("2").unwrap(), None);
+        assert!(querier.has_voted("1", "voter1").unwrap());
+        assert!(!querier.has_voted("1", "voter2").unwrap());
+        assert_eq!(querier.vote("1", "voter1").unwrap().unwrap().option, 1);
+        assert_eq!(querier.result("1").unwrap(), None);
+
+        contract.close_poll(&mut app, "admin", "1").unwrap();
+        contract.finalize_poll(&mut app, "admin", "1").unwrap();
+        let wrapper = app.wrap();
+        let querier = PollQuerier::new(&wrapper, contract.addr().clone());
+        let result = querier.result("1").unwrap().unwrap();
+        assert_eq!(result.winner, Some(1));
+    }
+}
@@ -1,26 +1,923 @@
+use std::collections::BTreeMap;
+
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::Addr;
-use cw_storage_plus::{Item, Map};
+use cosmwasm_std::{Addr, Binary, BlockInfo, Coin, Decimal, HexBinary, Isqrt, Timestamp, Uint128};
+use cw20::Cw20CoinVerified;
+use cw_storage_plus::{Index, IndexList, IndexedMap, Item, Map, MultiIndex, SnapshotMap, Strategy};
+use sha2::{Digest, Sha256};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
-    pub admin: Addr,
+    pub admins: Vec<Addr>,
+    pub moderators: Vec<Addr>,
+    /// Seconds a poll must stay open before it can be closed.
+    pub min_voting_period: u64,
+    /// Longest a poll may run, in seconds. When set, every poll needs an end
+    /// time within it.
+    #[serde(default)]
+    pub max_voting_period: Option<u64>,
+    /// Longest delegation chain whose weight still reaches a voter.
+    #[serde(default)]
+    pub max_delegation_depth: u64,
+    /// Native funds a creator must attach to `CreatePoll`, returned when the
+    /// poll closes unless it is cancelled as spam.
+    #[serde(default)]
+    pub creation_deposit: Option<Coin>,
+    /// Receives slashed creation deposits; they are burned when unset.
+    #[serde(default)]
+    pub treasury: Option<Addr>,
+    /// Distinct flaggers needed to cancel a poll as spam; zero leaves it to moderators.
+    #[serde(default)]
+    pub flag_threshold: u64,
+    /// Seconds after a poll closes during which an admin may veto it before
+    /// it can be finalized.
+    #[serde(default)]
+    pub veto_period: u64,
+    /// Contract sent a `NotifyMsg::VoteCast` after every ballot; failures
+    /// are ignored so a broken receiver cannot block voting.
+    #[serde(default)]
+    pub notify_contract: Option<Addr>,
+    /// Addresses that may second draft polls.
+    #[serde(default)]
+    pub sponsors: Vec<Addr>,
+    /// Sponsors a new poll needs before voting opens; zero opens it at once.
+    #[serde(default)]
+    pub required_sponsors: u64,
+    /// cw20 tokens a creator may send with `ReceiveMsg::CreatePoll` instead of
+    /// the native deposit, held and returned or slashed the same way.
+    #[serde(default)]
+    pub cw20_creation_deposit: Option<Cw20CoinVerified>,
+    /// cw20 whose stakes here are the voting power reported to DAO DAO.
+    #[serde(default)]
+    pub voting_token: Option<Addr>,
+    /// Nois proxy asked for randomness when a poll needs a random draw.
+    #[serde(default)]
+    pub randomness_proxy: Option<Addr>,
+    /// cw721 contract minting a receipt to each voter's first ballot in a
+    /// poll; failed mints are ignored like notifications.
+    #[serde(default)]
+    pub receipt_nft: Option<Addr>,
+    /// Only contract admins and addresses in `CREATORS` may create polls.
+    #[serde(default)]
+    pub restricted_creation: bool,
+    /// Set while an admin imports another deployment's polls in batches;
+    /// no polls can be created until it is cleared.
+    #[serde(default)]
+    pub import_mode: bool,
+}
+
+impl Poll {
+    /// `max_weight_per_voter` as a ballot weight.
+    pub fn weight_cap(&self) -> Option<u64> {
+        self.max_weight_per_voter
+            .map(|cap| u64::try_from(cap.u128()).unwrap_or(u64::MAX))
+    }
+
+    pub fn is_expired(&self, block: &BlockInfo) -> bool {
+        self.end_time.is_some_and(|end| block.time >= end)
+    }
+
+    pub fn option_mut(&mut self, id: u8) -> Option<&mut PollOption> {
+        self.options.iter_mut().find(|option| option.id == id)
+    }
+
+    /// Reflects an expiry nobody has acted on yet: an open poll past its end
+    /// time reads as closed at that time. Queries apply this so clients get
+    /// one status field; stored polls only change through transitions.
+    pub fn catch_up(&mut self, block: &BlockInfo) {
+        if self.status == PollStatus::Open && self.is_expired(block) {
+            self.status = PollStatus::Closed;
+            self.closed_at = self.end_time;
+        }
+    }
+
+    pub fn hides_results(&self) -> bool {
+        (self.voters_only_results || self.awaits_quorum())
+            && matches!(
+                self.status,
+                PollStatus::Draft | PollStatus::Open | PollStatus::Frozen
+            )
+    }
+
+    /// Whether results are held back because turnout has not met the quorum.
+    pub fn awaits_quorum(&self) -> bool {
+        let turnout = self.options.iter().map(|option| option.votes).sum();
+        self.results_after_quorum
+            && !self
+                .quorum
+                .as_ref()
+                .is_some_and(|quorum| quorum.is_met(turnout, self.total_eligible()))
+    }
+
+    /// Weight that could be cast in the poll, which percentage quorums are a
+    /// share of. Polls stored before it was recorded fall back to the size
+    /// of their allowlist.
+    pub fn total_eligible(&self) -> Option<u64> {
+        self.eligible_weight.or(self.electorate)
+    }
+
+    /// Swaps in the question and labels translated to `locale`, if any.
+    pub fn localize(&mut self, locale: &str) {
+        if let Some(text) = self.translations.get(locale) {
+            self.question = text.question.clone();
+            for (option, label) in self.options.iter_mut().zip(&text.options) {
+                option.label = label.clone();
+            }
+        }
+    }
+
+    /// `BALLOT` key for the voter: their address, or in a pseudonymous poll
+    /// the hex `sha256(address || salt)` with the poll's `POLL_SALTS` entry.
+    pub fn ballot_key(&self, poll_id: &str, voter: &Addr, salt: &[u8]) -> (Addr, String) {
+        if !self.pseudonymous {
+            return (voter.clone(), poll_id.to_string());
+        }
+        let hash = Sha256::new()
+            .chain_update(voter.as_bytes())
+            .chain_update(salt)
+            .finalize();
+        (Addr::unchecked(hex::encode(hash)), poll_id.to_string())
+    }
+}
+
+impl Config {
+    pub fn is_admin(&self, addr: &Addr) -> bool {
+        self.admins.contains(addr)
+    }
+
+    pub fn is_moderator(&self, addr: &Addr) -> bool {
+        self.moderators.contains(addr)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PollStatus {
+    /// Waiting for sponsors before voting opens.
+    Draft,
+    Open,
+    /// Voting suspended by a moderator until the poll is unfrozen.
+    Frozen,
+    Closed,
+    Cancelled,
+    Finalized,
+    Archived,
+    Vetoed,
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
 pub struct Poll {
     pub admin: Addr,
     pub question: String,
-    pub options: Vec<(String, u64)>,
+    pub options: Vec<PollOption>,
+    pub status: PollStatus,
+    pub start_time: Timestamp,
+    /// Votes are rejected from this time on; `None` leaves the poll open until closed.
+    pub end_time: Option<Timestamp>,
+    pub mode: VoteMode,
+    pub reward_distribution: RewardDistribution,
+    /// Block height at creation; snapshot-weighted votes use stakes as of this height.
+    pub snapshot_height: u64,
+    pub tie_break: TieBreakPolicy,
+    /// Number of distinct addresses that have voted.
+    pub voter_count: u64,
+    /// Weight the allowlist can cast when voting is restricted to one: each
+    /// listed address counts its weight override, or 1 without one.
+    pub electorate: Option<u64>,
+    /// Total weight eligible to vote, taken at creation from the allowlist,
+    /// the Merkle tree's declared total or the staked supply.
+    #[serde(default)]
+    pub eligible_weight: Option<u64>,
+    /// Why the poll was cancelled or vetoed.
+    pub cancel_reason: Option<String>,
+    /// Voters must hold at least this much of a native denom or cw20 contract.
+    #[serde(default)]
+    pub min_balance: Option<(String, Uint128)>,
+    /// Hex-encoded sha256 Merkle root of eligible `(address, weight)` pairs.
+    #[serde(default)]
+    pub merkle_root: Option<String>,
+    /// Shrinks ballot weight with the time between casting it and the close.
+    #[serde(default)]
+    pub decay: Option<WeightDecay>,
+    /// Category admins of this category may moderate the poll.
+    #[serde(default)]
+    pub category: Option<String>,
+    /// Creation deposit still held for the creator.
+    #[serde(default)]
+    pub creation_deposit: Option<Coin>,
+    /// cw20 creation deposit still held for the creator.
+    #[serde(default)]
+    pub cw20_creation_deposit: Option<Cw20CoinVerified>,
+    /// Distinct addresses that flagged the poll as spam.
+    #[serde(default)]
+    pub flags: u64,
+    /// When voting stopped: the close, or the deadline if that came first.
+    #[serde(default)]
+    pub closed_at: Option<Timestamp>,
+    /// Hash of the full proposal document kept off-chain; fixed at creation.
+    #[serde(default)]
+    pub content_hash: Option<HexBinary>,
+    /// Distinct voters accepted before the poll is full.
+    #[serde(default)]
+    pub max_voters: Option<u32>,
+    /// Distinct voters the poll needs for its winner to stand, whatever
+    /// their weight.
+    #[serde(default)]
+    pub min_voters: Option<u32>,
+    /// Participation the poll needs for its winner to stand.
+    #[serde(default)]
+    pub quorum: Option<Quorum>,
+    /// Ballots are stored and listed under a salted hash of the voter.
+    #[serde(default)]
+    pub pseudonymous: bool,
+    /// Language of `question` and the option labels.
+    #[serde(default)]
+    pub default_locale: Option<String>,
+    #[serde(default)]
+    pub translations: BTreeMap<String, LocalizedText>,
+    /// Tallies and ballots are only shown to voters until the poll closes.
+    #[serde(default)]
+    pub voters_only_results: bool,
+    /// Tallies and ballots stay hidden from everyone until turnout meets the
+    /// quorum or the poll closes.
+    #[serde(default)]
+    pub results_after_quorum: bool,
+    /// Limits when voters may change their ballot; `None` allows it while open.
+    #[serde(default)]
+    pub revote_window: Option<RevoteWindow>,
+    /// Voters may suggest options, which become votable once approved.
+    #[serde(default)]
+    pub open_options: bool,
+    /// IBC channels to satellite contracts whose tallies count toward this poll.
+    #[serde(default)]
+    pub satellite_channels: Vec<String>,
+    /// Turns token amounts into ballot weight.
+    #[serde(default)]
+    pub weight_fn: WeightFormula,
+    /// Most weight any one ballot counts with; the rest is recorded but not tallied.
+    #[serde(default)]
+    pub max_weight_per_voter: Option<Uint128>,
+    /// Draws one voter of the winning option at random once it is finalized.
+    #[serde(default)]
+    pub raffle: bool,
+    /// Share of the votes the leading option needs to win, e.g. 60% for a
+    /// supermajority.
+    #[serde(default)]
+    pub win_threshold: Option<Decimal>,
+    /// Key-value pairs integrations attach to the poll, kept as given.
+    #[serde(default)]
+    pub attributes: Vec<(String, String)>,
+    /// Option ids in the order queries list them; unlisted options follow
+    /// in creation order.
+    #[serde(default)]
+    pub display_order: Vec<u8>,
+    /// Queries list the options in an order shuffled anew every block.
+    #[serde(default)]
+    pub shuffle_options: bool,
+    /// Earlier poll this one re-runs or amends.
+    #[serde(default)]
+    pub supersedes: Option<String>,
+    /// Later poll that replaced this one.
+    #[serde(default)]
+    pub superseded_by: Option<String>,
+    /// Number of options kept in `OPTIONS` instead of `options`, which is
+    /// then empty. Zero for polls small enough to hold them inline.
+    #[serde(default)]
+    pub option_count: u32,
+    /// Suggested options still awaiting review.
+    #[serde(default)]
+    pub pending_suggestions: u32,
+    /// Sum of `weight` over the poll's ballots.
+    #[serde(default)]
+    pub ballot_weight: u64,
+    /// Ballots `PruneBallots` has deleted; `voter_count` keeps counting them.
+    #[serde(default)]
+    pub pruned_ballots: u64,
+}
+
+/// A poll's question and option labels in one language; `options` follow
+/// the poll's option order.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct LocalizedText {
+    pub question: String,
+    pub options: Vec<String>,
+}
+
+/// When a voter may still switch their ballot to other options.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RevoteWindow {
+    /// For `seconds` after the voter's first vote.
+    AfterFirstVote { seconds: u64 },
+    /// Until `seconds` before the poll's end time.
+    BeforeClose { seconds: u64 },
+}
+
+impl RevoteWindow {
+    /// Whether a ballot first cast at `first_vote` may still change at `now`.
+    pub fn is_open(
+        &self,
+        first_vote: Timestamp,
+        end_time: Option<Timestamp>,
+        now: Timestamp,
+    ) -> bool {
+        match *self {
+            RevoteWindow::AfterFirstVote { seconds } => now < first_vote.plus_seconds(seconds),
+            RevoteWindow::BeforeClose { seconds } => {
+                end_time.is_none_or(|end| now.plus_seconds(seconds) < end)
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum WeightDecay {
+    /// Weight falls linearly to zero over `period` seconds.
+    Linear { period: u64 },
+    /// Weight halves every `half_life` seconds.
+    HalfLife { half_life: u64 },
+}
+
+impl WeightDecay {
+    /// What remains of `weight` after `elapsed` seconds.
+    pub fn apply(&self, weight: u64, elapsed: u64) -> u64 {
+        match *self {
+            WeightDecay::Linear { period } => {
+                let remaining = period.saturating_sub(elapsed);
+                (weight as u128 * remaining as u128 / period as u128) as u64
+            }
+            WeightDecay::HalfLife { half_life } => {
+                let halvings = elapsed / half_life;
+                if halvings >= 64 {
+                    return 0;
+                }
+                // interpolate linearly within the current half-life
+                let start = weight >> halvings;
+                let into = elapsed % half_life;
+                start - (start as u128 * into as u128 / (2 * half_life as u128)) as u64
+            }
+        }
+    }
+}
+
+/// How a voter's token amount becomes ballot weight, for dampening whales.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum WeightFormula {
+    #[default]
+    Linear,
+    /// Integer square root of the amount.
+    Sqrt,
+    /// Bit length of the amount, i.e. `floor(log2(amount)) + 1`.
+    Log,
+    /// The amount, up to `cap`.
+    CappedLinear { cap: Uint128 },
+}
+
+impl WeightFormula {
+    pub fn apply(&self, amount: Uint128) -> Uint128 {
+        match *self {
+            WeightFormula::Linear => amount,
+            WeightFormula::Sqrt => amount.isqrt(),
+            WeightFormula::Log => Uint128::from(128 - amount.u128().leading_zeros()),
+            WeightFormula::CappedLinear { cap } => amount.min(cap),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Quorum {
+    /// Share of the poll's total eligible weight that has to be cast; needs
+    /// an allowlist, a Merkle total weight or a linear snapshot or staking poll.
+    Percentage(Decimal),
+    /// Total weight that has to be cast, e.g. tokens in a deposit or snapshot poll.
+    AbsoluteWeight(Uint128),
+    /// `(turnout, threshold)` steps in ascending turnout, both as shares of
+    /// the total eligible weight: the winner needs the threshold of the highest step
+    /// reached, and turnout below the first step fails the quorum. Lets low
+    /// turnout demand a supermajority.
+    Dynamic { curve: Vec<(Decimal, Decimal)> },
+}
+
+impl Quorum {
+    pub fn is_met(&self, total: u64, eligible: Option<u64>) -> bool {
+        match self {
+            Quorum::Percentage(share) => {
+                let required = Uint128::from(eligible.unwrap_or_default()) * *share;
+                Uint128::from(total) >= required
+            }
+            Quorum::AbsoluteWeight(required) => Uint128::from(total) >= *required,
+            Quorum::Dynamic { .. } => self.threshold(total, eligible).is_some(),
+        }
+    }
+
+    /// Share of the votes the winner needs at this turnout, if the quorum
+    /// scales with it.
+    pub fn threshold(&self, total: u64, eligible: Option<u64>) -> Option<Decimal> {
+        let Quorum::Dynamic { curve } = self else {
+            return None;
+        };
+        let turnout = Decimal::from_ratio(total, eligible.unwrap_or_default().max(1));
+        curve
+            .iter()
+            .take_while(|(step, _)| turnout >= *step)
+            .last()
+            .map(|(_, threshold)| *threshold)
+    }
+}
+
+/// Ballots refer to options by `id`, so a label can change without
+/// invalidating votes.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PollOption {
+    pub id: u8,
+    pub label: String,
+    pub votes: u64,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub image_uri: Option<String>,
+}
+
+/// How a tie between the leading options is resolved at finalization.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TieBreakPolicy {
+    /// A tie has no winner.
+    #[default]
+    None,
+    /// The tied option listed first wins.
+    FirstListed,
+    /// A winner is drawn from the tied options once randomness is delivered.
+    Random,
+    /// The poll admin or a contract admin picks among the tied options.
+    AdminDecides,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum VoteMode {
+    /// One address, one vote.
+    #[default]
+    Standard,
+    /// Voters escrow cw20 `token` until the poll closes; the deposit is the vote weight.
+    Cw20Deposit { token: Addr },
+    /// Voters attach native `denom` which is burned; the burned amount is the vote weight.
+    Burn { denom: String },
+    /// Weight is the voter's stake of cw20 `token` at the poll's snapshot height.
+    Snapshot { token: Addr },
+    /// Voters split `budget` across options with `AllocateVotes`.
+    Cumulative { budget: VoteBudget },
+    /// Voters lock cw20 `token`; a lock's weight grows linearly from its amount
+    /// to `max_multiplier` times the amount over `ramp_seconds`.
+    Conviction {
+        token: Addr,
+        max_multiplier: u64,
+        ramp_seconds: u64,
+    },
+    /// Weight is the voter's whole grant in vesting `contract`, vested or
+    /// not, as reported by its `VestingQueryMsg::Allocation` query.
+    Vesting { contract: Addr },
+    /// Weight is the voter's stake in cw20-stake `contract` at the poll's
+    /// snapshot height, so tokens bought or staked after creation do not count.
+    Staking { contract: Addr },
+    /// Voters order the options with `RankVotes`; the winner is counted by
+    /// `method` when the poll is finalized.
+    Ranked {
+        #[serde(default)]
+        method: RankedMethod,
+    },
+}
+
+/// How ranked ballots are counted.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RankedMethod {
+    #[default]
+    InstantRunoff,
+    /// Pairwise comparison, falling back to Schulze when preferences cycle.
+    Condorcet,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum VoteBudget {
+    /// Every voter gets the same number of points.
+    Points(Uint128),
+    /// The voter's stake of cw20 `token` at the poll's snapshot height.
+    Stake { token: Addr },
+    /// The voter's balance in the off-chain token snapshot committed to by
+    /// the poll's Merkle root, proven with each allocation.
+    Snapshot {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RewardDistribution {
+    /// Split by ballot weight.
+    #[default]
+    ProRata,
+    /// Split evenly per voter.
+    Equal,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RewardAsset {
+    Native(String),
+    Cw20(Addr),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RewardPool {
+    pub asset: RewardAsset,
+    pub amount: Uint128,
+    /// Set once the poll ends; voters then claim their share.
+    pub distributed: bool,
+    /// Units the pool is split into when the poll is finalized: its ballot
+    /// weight, or its voter count for an equal split.
+    #[serde(default)]
+    pub shares: u64,
+    /// Shares claimed so far; the last claim also takes the rounding dust.
+    #[serde(default)]
+    pub claimed_shares: u64,
+    #[serde(default)]
+    pub claimed: Uint128,
+}
+
+/// Outcome of a poll, frozen when it is finalized.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PollResult {
+    /// Id of the winning option; `None` when nobody voted or the top options tied.
+    pub winner: Option<u8>,
+    pub tallies: Vec<PollOption>,
+    pub total: u64,
+    pub finalized_at: Timestamp,
+    pub tie: Option<TieResolution>,
+    /// Whether turnout met the poll's quorum; `None` when it had none.
+    #[serde(default)]
+    pub quorum_reached: Option<bool>,
+    /// Share of the votes the winner needed, the higher of the poll's win
+    /// threshold and a dynamic quorum's.
+    #[serde(default)]
+    pub threshold: Option<Decimal>,
+    /// Total weight that could have been cast; `total` is the weight that was.
+    #[serde(default)]
+    pub eligible_weight: Option<u64>,
+    /// Whether enough distinct addresses voted; `None` when the poll set no
+    /// minimum.
+    #[serde(default)]
+    pub min_voters_reached: Option<bool>,
+    /// Instant-runoff count of a ranked poll; `tallies` holds first preferences.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rounds: Vec<RunoffRound>,
+    /// Head-to-head count of a Condorcet poll.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pairwise: Option<Pairwise>,
+    /// Beacon delivered by the randomness proxy for this poll.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub randomness: Option<HexBinary>,
+    /// Voter of the winning option drawn in a raffle poll.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raffle_winner: Option<Addr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Pairwise {
+    /// `(a, b, weight)`: the weight of ballots ranking `a` above `b`.
+    pub preferences: Vec<(u8, u8, u64)>,
+    /// The option beating every other head to head; `None` when preferences
+    /// cycle and the winner was found by Schulze.
+    pub condorcet_winner: Option<u8>,
+}
+
+/// One round of an instant-runoff count.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct RunoffRound {
+    /// Weight behind each continuing option this round.
+    pub tallies: Vec<(u8, u64)>,
+    /// Option knocked out this round; `None` in the deciding round.
+    pub eliminated: Option<u8>,
+    /// Weight the eliminated option's ballots moved to each option.
+    pub transfers: Vec<(u8, u64)>,
+    /// Weight of ballots with no continuing option left.
+    pub exhausted: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TieResolution {
+    pub tied: Vec<u8>,
+    pub policy: TieBreakPolicy,
+    /// False while an admin decision or randomness is outstanding.
+    pub resolved: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PollChange {
+    Created,
+    DeadlineExtended {
+        old_end: Timestamp,
+        new_end: Timestamp,
+    },
+    StatusChanged {
+        from: PollStatus,
+        to: PollStatus,
+    },
+    /// An approved suggestion was added as a new option.
+    OptionAdded {
+        id: u8,
+        label: String,
+    },
+}
+
+/// An entry in a poll's append-only audit trail.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ChangeRecord {
+    pub id: u64,
+    pub actor: Addr,
+    pub height: u64,
+    pub time: Timestamp,
+    pub change: PollChange,
+}
+
+/// A privileged action, kept in `ADMIN_LOG` for accountability.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AdminAction {
+    pub id: u64,
+    /// Who acted; the contract itself for actions taken through sudo.
+    pub actor: Addr,
+    pub action: String,
+    /// The poll, address or `category/address` acted on; empty for config.
+    pub target: String,
+    pub height: u64,
+    pub time: Timestamp,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Comment {
+    pub id: u64,
+    pub author: Addr,
+    pub body: String,
+    /// The comment this one replies to, if any.
+    pub reply_to: Option<u64>,
+    pub created_at: Timestamp,
+}
+
+/// The latest tally a satellite contract pushed for a poll. Each push
+/// replaces the previous one from the same channel.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct SatelliteTally {
+    /// Increases with every push; older pushes are rejected.
+    pub sequence: u64,
+    /// Votes cast on the satellite's chain for each option id.
+    pub tallies: Vec<(u8, u64)>,
+    pub voters: u64,
+}
+
+/// An option proposed by a voter in a poll with open options.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Suggestion {
+    pub id: u64,
+    pub proposer: Addr,
+    pub option: String,
+    pub status: SuggestionStatus,
+    pub created_at: Timestamp,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SuggestionStatus {
+    Pending,
+    /// Added to the poll as option `option_id`.
+    Approved {
+        option_id: u8,
+    },
+    Rejected,
+}
+
+/// Contract-wide participation statistics, maintained incrementally.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct Stats {
+    pub unique_voters: u64,
+    pub finalized_polls: u64,
+    /// Sum of distinct voters across finalized polls.
+    pub finalized_voters: u64,
+    /// Finalized polls bucketed by voter count: 0, 1-9, 10-99, 100-999 and 1000+.
+    pub voter_histogram: [u64; 5],
+}
+
+impl Stats {
+    pub fn record_finalized(&mut self, voters: u64) {
+        self.finalized_polls += 1;
+        self.finalized_voters += voters;
+        let bucket = match voters {
+            0 => 0,
+            1..=9 => 1,
+            10..=99 => 2,
+            100..=999 => 3,
+            _ => 4,
+        };
+        self.voter_histogram[bucket] += 1;
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct VoterStats {
+    /// Number of polls the address has voted in.
+    pub votes_cast: u64,
+    /// Block time of the address's first ballot; unset for addresses that
+    /// had voted before it was recorded.
+    #[serde(default)]
+    pub first_vote: Option<Timestamp>,
+    /// Polls created with the address as their admin.
+    #[serde(default)]
+    pub polls_created: u64,
+}
+
+pub struct VoterStatsIndexes<'a> {
+    pub votes_cast: MultiIndex<'a, u64, VoterStats, Addr>,
+}
+
+impl<'a> IndexList<VoterStats> for VoterStatsIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<VoterStats>> + '_> {
+        let v: Vec<&dyn Index<VoterStats>> = vec![&self.votes_cast];
+        Box::new(v.into_iter())
+    }
+}
+
+/// Per-address participation, indexed by `votes_cast` for leaderboards.
+pub fn voter_stats<'a>() -> IndexedMap<'a, Addr, VoterStats, VoterStatsIndexes<'a>> {
+    let indexes = VoterStatsIndexes {
+        votes_cast: MultiIndex::new(
+            |_pk, stats| stats.votes_cast,
+            "voter_stats",
+            "voter_stats__votes_cast",
+        ),
+    };
+    IndexedMap::new("voter_stats", indexes)
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, JsonSchema)]
 pub struct Ballot {
-    pub option: String,
+    pub option: u8,
+    pub weight: u64,
+    /// Escrowed cw20 tokens, claimable by the voter once the poll closes.
+    pub deposit: Uint128,
+    /// Short public rationale attached by the voter.
+    pub memo: Option<String>,
+    /// Per-option split of `weight` for cumulative polls, which ignore `option`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allocations: Vec<(u8, u64)>,
+    /// When the ballot was last cast or changed.
+    #[serde(default)]
+    pub voted_at: Timestamp,
+    /// Preference order in ranked polls, most preferred first; `option` is
+    /// the first choice.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ranking: Vec<u8>,
+    /// The weight before the poll's per-voter cap, when the cap lowered it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw_weight: Option<u64>,
+}
+
+impl Ballot {
+    /// The weight before any cap.
+    pub fn uncapped_weight(&self) -> u64 {
+        self.raw_weight.unwrap_or(self.weight)
+    }
+
+    /// Sets the uncapped weight, clamping the counted `weight` to `cap`.
+    pub fn set_weight(&mut self, raw: u64, cap: Option<u64>) {
+        self.weight = cap.map_or(raw, |cap| raw.min(cap));
+        self.raw_weight = Some(raw).filter(|raw| *raw != self.weight);
+    }
+
+    /// The weight this ballot adds to each option id.
+    pub fn shares(&self) -> Vec<(u8, u64)> {
+        if self.allocations.is_empty() {
+            vec![(self.option, self.weight)]
+        } else {
+            self.allocations.clone()
+        }
+    }
+
+    /// Options this ballot enters a raffle for: its whole ranking, or each
+    /// option it gives weight to.
+    pub fn raffle_options(&self) -> Vec<u8> {
+        if !self.ranking.is_empty() {
+            return self.ranking.clone();
+        }
+        self.shares()
+            .into_iter()
+            .filter(|(_, weight)| *weight > 0)
+            .map(|(id, _)| id)
+            .collect()
+    }
 }
 
 pub const CONFIG: Item<Config> = Item::new("config");
 pub const POLL: Map<String, Poll> = Map::new("polls");
 pub const BALLOT: Map<(Addr, String), Ballot> = Map::new("ballots");
+/// Voters holding a ballot in each poll, keyed as in `BALLOT`, so a poll's
+/// ballots can be listed without scanning every poll's.
+pub const POLL_VOTERS: Map<(String, Addr), ()> = Map::new("poll_voters");
+/// Salt each pseudonymous poll hashes its voters with. No query returns it,
+/// so a hash can't be recomputed from a guessed address.
+pub const POLL_SALTS: Map<String, Binary> = Map::new("poll_salts");
+/// Chained into every new salt, so a salt doesn't follow from its creating
+/// transaction alone.
+pub const SALT_SEED: Item<Binary> = Item::new("salt_seed");
+pub const RESULTS: Map<String, PollResult> = Map::new("results");
+/// Polls by end time in seconds, so overdue polls are found without scanning
+/// every poll. Entries outlive deadline changes; readers check them against
+/// the poll's current end time.
+pub const POLL_ENDS: Map<(u64, String), ()> = Map::new("poll_ends");
+/// Last `POLL_ENDS` entry `Crank` went past. The closed polls it leaves
+/// indexed for `FinalizeExpired` are behind it, so they aren't scanned again.
+pub const CRANK_CURSOR: Item<(u64, String)> = Item::new("crank_cursor");
+/// Polls by the address that created them.
+pub const CREATOR_POLLS: Map<(Addr, String), ()> = Map::new("creator_polls");
+/// Polls by attribute key and value.
+pub const POLL_ATTRIBUTES: Map<(String, String, String), ()> = Map::new("poll_attributes");
+/// Options of polls with too many to store inline, by poll id and option id.
+/// Option ids are `u8` here as in ballots, tallies and messages, so a poll
+/// holds at most 255 options: enough for the large elections paging is for,
+/// without widening every stored ballot.
+pub const OPTIONS: Map<(String, u8), PollOption> = Map::new("options");
+pub const COMMENTS: Map<(String, u64), Comment> = Map::new("comments");
+pub const SUGGESTIONS: Map<(String, u64), Suggestion> = Map::new("suggestions");
+/// Keyed by poll id and the channel the tally arrived on.
+pub const SATELLITE_TALLIES: Map<(String, String), SatelliteTally> = Map::new("satellite_tallies");
+pub const POLL_HISTORY: Map<(String, u64), ChangeRecord> = Map::new("poll_history");
+/// Append-only log of privileged actions, by id.
+pub const ADMIN_LOG: Map<u64, AdminAction> = Map::new("admin_log");
+pub const ALLOWED_VOTERS: Map<(String, Addr), ()> = Map::new("allowed_voters");
+/// Fixed ballot weights set by the poll creator, used in place of the
+/// voter's token or one-vote weight.
+pub const WEIGHT_OVERRIDES: Map<(String, Addr), u64> = Map::new("weight_overrides");
+pub const BANNED: Map<Addr, ()> = Map::new("banned");
+/// Addresses allowed to create polls while creation is restricted.
+pub const CREATORS: Map<Addr, ()> = Map::new("creators");
+pub const FLAGS: Map<(String, Addr), ()> = Map::new("flags");
+pub const SPONSORSHIPS: Map<(String, Addr), ()> = Map::new("sponsorships");
+/// Addresses that may moderate polls in a category.
+pub const CATEGORY_ADMINS: Map<(String, Addr), ()> = Map::new("category_admins");
+pub const SEEN_VOTERS: Map<Addr, ()> = Map::new("seen_voters");
+pub const STATS: Item<Stats> = Item::new("stats");
+/// Numeric cw3 proposal ids, assigned to polls in creation order.
+pub const PROPOSAL_SEQ: Item<u64> = Item::new("proposal_seq");
+pub const PROPOSALS: Map<u64, String> = Map::new("proposals");
+/// Standing delegations: delegator -> delegate.
+pub const DELEGATIONS: Map<Addr, Addr> = Map::new("delegations");
+/// Reverse of `DELEGATIONS`, keyed (delegate, delegator).
+pub const DELEGATORS: Map<(Addr, Addr), ()> = Map::new("delegators");
+/// Voting keys registered for signed ballots, by public key.
+pub const VOTING_KEYS: Map<&[u8], Addr> = Map::new("voting_keys");
+/// Last nonce used in a signed ballot, by voter.
+pub const VOTE_NONCES: Map<Addr, u64> = Map::new("vote_nonces");
+/// When each conviction lock started, by poll and voter. Top-ups move the
+/// start forward in proportion to the amount added.
+pub const LOCKED_SINCE: Map<(String, Addr), Timestamp> = Map::new("locked_since");
+/// The voter whose ballot carries a non-voting delegator's weight in a poll.
+pub const REPRESENTED: Map<(String, Addr), Addr> = Map::new("represented");
+/// Weight each voter's ballot gives an option, keyed by `(poll_id, option, voter)`
+/// with the voter as in `BALLOT`.
+pub const OPTION_VOTERS: Map<(String, u8, Addr), u64> = Map::new("option_voters");
+/// A raffle poll's entrants per option, numbered `0..RAFFLE_COUNTS` so a draw
+/// loads a single entry.
+pub const RAFFLE_ENTRIES: Map<(String, u8, u64), Addr> = Map::new("raffle_entries");
+/// Each entrant's number in `RAFFLE_ENTRIES`.
+pub const RAFFLE_SLOTS: Map<(String, u8, Addr), u64> = Map::new("raffle_slots");
+/// Number of raffle entrants per option.
+pub const RAFFLE_COUNTS: Map<(String, u8), u64> = Map::new("raffle_counts");
+/// cw20 stakes keyed by (token, staker), snapshotted so polls can weigh votes at creation height.
+pub const STAKES: SnapshotMap<(Addr, Addr), Uint128> = SnapshotMap::new(
+    "stakes",
+    "stakes__checkpoints",
+    "stakes__changelog",
+    Strategy::EveryBlock,
+);
+/// Sum of `STAKES` per token, snapshotted alongside it.
+pub const STAKE_TOTALS: SnapshotMap<Addr, Uint128> = SnapshotMap::new(
+    "stake_totals",
+    "stake_totals__checkpoints",
+    "stake_totals__changelog",
+    Strategy::EveryBlock,
+);
+/// Votes for each option keyed by `(poll_id, option)`, checkpointed every
+/// block so past tallies can be read back by height.
+pub const TALLIES: SnapshotMap<(String, u8), u64> = SnapshotMap::new(
+    "tallies",
+    "tallies__checkpoints",
+    "tallies__changelog",
+    Strategy::EveryBlock,
+);
+pub const REWARD_POOLS: Map<String, RewardPool> = Map::new("reward_pools");
+/// Reward owed to a voter outside their ballot, such as a share set aside
+/// when the ballot was pruned. Zero once the voter has claimed.
+pub const REWARD_CLAIMS: Map<(String, Addr), Uint128> = Map::new("reward_claims");
@@ -0,0 +1,151 @@
+//! Interchangeable ways of counting a poll's ballots, for contracts that
+//! embed polling and want a tally this contract does not offer.
+//!
+//! A ballot is the list of options it names, most preferred first, and its
+//! weight. Plain ballots name one option; [`crate::api::custom_tally`] reads
+//! them from storage in that shape.
+
+use crate::tally;
+
+/// Winner of a count and the score each option reached, in option order.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Tally {
+    /// `None` when nobody voted or the top options tied.
+    pub winner: Option<u8>,
+    pub scores: Vec<(u8, u64)>,
+}
+
+pub trait TallyStrategy {
+    fn tally(&self, options: &[u8], ballots: &[(Vec<u8>, u64)]) -> Tally;
+}
+
+/// Each ballot's weight goes to its first preference.
+pub struct Plurality;
+
+/// Each ballot's weight goes to every option it names.
+pub struct Approval;
+
+/// Each ballot gives `points[i]` times its weight to its `i`th preference;
+/// `[2, 1, 0]` is a Borda count over three options.
+pub struct Weighted {
+    pub points: Vec<u64>,
+}
+
+/// Instant-runoff count; scores are the deciding round's tallies.
+pub struct InstantRunoff;
+
+/// Condorcet count with a Schulze fallback; an option's score is its
+/// weakest head-to-head support.
+pub struct Condorcet;
+
+/// The option scoring highest, unless it scored nothing or shares the lead.
+fn leader(scores: &[(u8, u64)]) -> Option<u8> {
+    let top = scores.iter().map(|(_, score)| *score).max()?;
+    let mut leaders = scores.iter().filter(|(_, score)| *score == top);
+    match (leaders.next(), leaders.next()) {
+        (Some((id, _)), None) if top > 0 => Some(*id),
+        _ => None,
+    }
+}
+
+fn positional(options: &[u8], ballots: &[(Vec<u8>, u64)], points: &[u64]) -> Tally {
+    let mut scores: Vec<(u8, u64)> = options.iter().map(|id| (*id, 0)).collect();
+    for (ranking, weight) in ballots {
+        for (id, points) in ranking.iter().zip(points) {
+            if let Some(score) = scores.iter_mut().find(|(option, _)| option == id) {
+                score.1 += weight * points;
+            }
+        }
+    }
+    Tally {
+        winner: leader(&scores),
+        scores,
+    }
+}
+
+impl TallyStrategy for Plurality {
+    fn tally(&self, options: &[u8], ballots: &[(Vec<u8>, u64)]) -> Tally {
+        positional(options, ballots, &[1])
+    }
+}
+
+impl TallyStrategy for Approval {
+    fn tally(&self, options: &[u8], ballots: &[(Vec<u8>, u64)]) -> Tally {
+        positional(options, ballots, &vec![1; options.len()])
+    }
+}
+
+impl TallyStrategy for Weighted {
+    fn tally(&self, options: &[u8], ballots: &[(Vec<u8>, u64)]) -> Tally {
+        positional(options, ballots, &self.points)
+    }
+}
+
+impl TallyStrategy for InstantRunoff {
+    fn tally(&self, options: &[u8], ballots: &[(Vec<u8>, u64)]) -> Tally {
+        let (winner, rounds) = tally::instant_runoff(options, ballots);
+        let last = rounds.last().map(|round| round.tallies.as_slice());
+        let scores = options
+            .iter()
+            .map(|id| {
+                let votes = last
+                    .and_then(|tallies| tallies.iter().find(|(option, _)| option == id))
+                    .map_or(0, |(_, votes)| *votes);
+                (*id, votes)
+            })
+            .collect();
+        Tally { winner, scores }
+    }
+}
+
+impl TallyStrategy for Condorcet {
+    fn tally(&self, options: &[u8], ballots: &[(Vec<u8>, u64)]) -> Tally {
+        let (winner, pairwise) = tally::condorcet(options, ballots);
+        let scores = options
+            .iter()
+            .map(|id| {
+                let support = pairwise
+                    .preferences
+                    .iter()
+                    .filter(|(a, _, _)| a == id)
+                    .map(|(_, _, weight)| *weight)
+                    .min()
+                    .unwrap_or_default();
+                (*id, support)
+            })
+            .collect();
+        Tally { winner, scores }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strategies() {
+        let options = [0, 1, 2];
+        let ballots = vec![(vec![0, 1], 4), (vec![1, 2], 3), (vec![2, 1], 2)];
+        let count = |strategy: &dyn TallyStrategy| strategy.tally(&options, &ballots);
+
+        assert_eq!(
+            count(&Plurality),
+            Tally {
+                winner: Some(0),
+                scores: vec![(0, 4), (1, 3), (2, 2)],
+            }
+        );
+        assert_eq!(count(&Approval).scores, vec![(0, 4), (1, 9), (2, 5)]);
+        assert_eq!(count(&Approval).winner, Some(1));
+        let borda = Weighted {
+            points: vec![2, 1, 0],
+        };
+        assert_eq!(count(&borda).scores, vec![(0, 8), (1, 12), (2, 7)]);
+        assert_eq!(count(&InstantRunoff).winner, Some(1));
+        assert_eq!(count(&Condorcet).winner, Some(1));
+
+        // a shared lead has no winner
+        let tied = vec![(vec![0], 1), (vec![1], 1)];
+        assert_eq!(Plurality.tally(&[0, 1], &tied).winner, None);
+    }
+}
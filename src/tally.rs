@@ -0,0 +1,207 @@
+//! Tallies over ranked ballots, computed when a ranked poll is finalized.
+
+use crate::state::{Pairwise, RunoffRound};
+
+/// The ballot's most preferred option still in `continuing`.
+fn choice(ranking: &[u8], continuing: &[u8]) -> Option<u8> {
+    ranking.iter().copied().find(|id| continuing.contains(id))
+}
+
+/// Instant-runoff count. Each round the option with the fewest votes is
+/// eliminated and its ballots move to their next continuing preference, until
+/// one option holds a majority of the ballots still in play. Ties for last
+/// place eliminate the option listed latest in `options`.
+///
+/// `ballots` are preference lists, most preferred first, with their weight.
+pub fn instant_runoff(
+    options: &[u8],
+    ballots: &[(Vec<u8>, u64)],
+) -> (Option<u8>, Vec<RunoffRound>) {
+    let mut continuing = options.to_vec();
+    let mut rounds = vec![];
+    loop {
+        let mut tallies: Vec<(u8, u64)> = continuing.iter().map(|id| (*id, 0)).collect();
+        let mut exhausted = 0;
+        for (ranking, weight) in ballots {
+            match choice(ranking, &continuing) {
+                Some(id) => {
+                    if let Some(tally) = tallies.iter_mut().find(|(option, _)| *option == id) {
+                        tally.1 += weight;
+                    }
+                }
+                None => exhausted += weight,
+            }
+        }
+
+        let active: u64 = tallies.iter().map(|(_, votes)| votes).sum();
+        let Some(leader) =
+            tallies
+                .iter()
+                .copied()
+                .reduce(|best, next| if next.1 > best.1 { next } else { best })
+        else {
+            return (None, rounds);
+        };
+        if leader.1 * 2 > active || continuing.len() == 1 || active == 0 {
+            let winner = Some(leader.0).filter(|_| leader.1 > 0);
+            rounds.push(RunoffRound {
+                tallies,
+                eliminated: None,
+                transfers: vec![],
+                exhausted,
+            });
+            return (winner, rounds);
+        }
+
+        let (eliminated, _) = tallies
+            .iter()
+            .copied()
+            .reduce(|worst, next| if next.1 <= worst.1 { next } else { worst })
+            .unwrap_or(leader);
+        let mut transfers: Vec<(u8, u64)> = vec![];
+        let before = continuing.clone();
+        continuing.retain(|id| *id != eliminated);
+        for (ranking, weight) in ballots {
+            if choice(ranking, &before) != Some(eliminated) {
+                continue;
+            }
+            if let Some(next) = choice(ranking, &continuing) {
+                match transfers.iter_mut().find(|(id, _)| *id == next) {
+                    Some(transfer) => transfer.1 += weight,
+                    None => transfers.push((next, *weight)),
+                }
+            }
+        }
+        transfers.sort_unstable();
+        rounds.push(RunoffRound {
+            tallies,
+            eliminated: Some(eliminated),
+            transfers,
+            exhausted,
+        });
+    }
+}
+
+/// Whether the ballot ranks `a` above `b`. Ranked options are preferred
+/// to unranked ones; two unranked options are not compared.
+fn prefers(ranking: &[u8], a: u8, b: u8) -> bool {
+    match (
+        ranking.iter().position(|id| *id == a),
+        ranking.iter().position(|id| *id == b),
+    ) {
+        (Some(a), Some(b)) => a < b,
+        (Some(_), None) => true,
+        _ => false,
+    }
+}
+
+/// Condorcet count. The winner beats every other option head to head; when
+/// preferences cycle the Schulze method picks it instead, with ties between
+/// Schulze winners going to the option listed first.
+pub fn condorcet(options: &[u8], ballots: &[(Vec<u8>, u64)]) -> (Option<u8>, Pairwise) {
+    let n = options.len();
+    let mut d = vec![vec![0u64; n]; n];
+    for (ranking, weight) in ballots {
+        for i in 0..n {
+            for j in 0..n {
+                if i != j && prefers(ranking, options[i], options[j]) {
+                    d[i][j] += weight;
+                }
+            }
+        }
+    }
+
+    let beats_all = |i: usize| (0..n).all(|j| i == j || d[i][j] > d[j][i]);
+    let condorcet_winner = (0..n).find(|i| beats_all(*i)).map(|i| options[i]);
+
+    // strongest path strengths between each pair
+    let mut p = vec![vec![0u64; n]; n];
+    for i in 0..n {
+        for j in 0..n {
+            if i != j && d[i][j] > d[j][i] {
+                p[i][j] = d[i][j];
+            }
+        }
+    }
+    for k in 0..n {
+        for i in 0..n {
+            for j in 0..n {
+                if i != j && i != k && j != k {
+                    p[i][j] = p[i][j].max(p[i][k].min(p[k][j]));
+                }
+            }
+        }
+    }
+    let voted = d.iter().flatten().any(|weight| *weight > 0);
+    let winner = condorcet_winner.or_else(|| {
+        (0..n)
+            .find(|i| voted && (0..n).all(|j| p[*i][j] >= p[j][*i]))
+            .map(|i| options[i])
+    });
+
+    let preferences = (0..n)
+        .flat_map(|i| (0..n).map(move |j| (i, j)))
+        .filter(|(i, j)| i != j)
+        .map(|(i, j)| (options[i], options[j], d[i][j]))
+        .collect();
+    let pairwise = Pairwise {
+        preferences,
+        condorcet_winner,
+    };
+    (winner, pairwise)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_instant_runoff() {
+        let ballots = vec![
+            (vec![0, 1], 4),
+            (vec![1, 0], 3),
+            (vec![2, 1], 2),
+            (vec![2], 1),
+        ];
+        let (winner, rounds) = instant_runoff(&[0, 1, 2], &ballots);
+        assert_eq!(winner, Some(1));
+        assert_eq!(
+            rounds,
+            vec![
+                RunoffRound {
+                    tallies: vec![(0, 4), (1, 3), (2, 3)],
+                    eliminated: Some(2),
+                    transfers: vec![(1, 2)],
+                    exhausted: 0,
+                },
+                RunoffRound {
+                    tallies: vec![(0, 4), (1, 5)],
+                    eliminated: None,
+                    transfers: vec![],
+                    exhausted: 1,
+                },
+            ]
+        );
+
+        assert_eq!(instant_runoff(&[0, 1], &[]).0, None);
+    }
+
+    #[test]
+    fn test_condorcet() {
+        // 1 beats both 0 and 2 head to head despite fewer first preferences
+        let ballots = vec![(vec![0, 1, 2], 3), (vec![2, 1, 0], 3), (vec![1, 0], 2)];
+        let (winner, pairwise) = condorcet(&[0, 1, 2], &ballots);
+        assert_eq!(winner, Some(1));
+        assert_eq!(pairwise.condorcet_winner, Some(1));
+        assert!(pairwise.preferences.contains(&(1, 0, 5)));
+        assert!(pairwise.preferences.contains(&(0, 1, 3)));
+
+        // a rock-paper-scissors cycle falls back to Schulze
+        let ballots = vec![(vec![0, 1, 2], 5), (vec![1, 2, 0], 4), (vec![2, 0, 1], 3)];
+        let (winner, pairwise) = condorcet(&[0, 1, 2], &ballots);
+        assert_eq!(pairwise.condorcet_winner, None);
+        assert_eq!(winner, Some(0));
+
+        assert_eq!(condorcet(&[0, 1], &[]).0, None);
+    }
+}
@@ -0,0 +1,196 @@
+//! cw-multi-test helpers for contracts that compose with this one.
+//!
+//! Enable the `testing` feature in `[dev-dependencies]` to use them.
+
+use anyhow::Result as AnyResult;
+use cosmwasm_std::{Addr, Coin, Empty, StdResult};
+use cw_multi_test::{App, AppResponse, Contract, ContractWrapper, Executor};
+use serde::de::DeserializeOwned;
+
+use crate::contract::{execute, instantiate, query, reply};
+use crate::msg::{
+    ContractQueryMsg, ExecuteMsg, FinalResultResponse, InstantiateMsg, NewPoll, PollResponse,
+    QueryMsg,
+};
+use crate::state::{Poll, PollResult};
+
+pub fn poll_contract() -> Box<dyn Contract<Empty>> {
+    Box::new(ContractWrapper::new(execute, instantiate, query).with_reply(reply))
+}
+
+/// Moves the chain forward, producing one block every five seconds.
+pub fn advance_time(app: &mut App, seconds: u64) {
+    app.update_block(|block| {
+        block.time = block.time.plus_seconds(seconds);
+        block.height += seconds / 5;
+    });
+}
+
+/// A deployed poll contract inside a multi-test `App`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PollContract(pub Addr);
+
+impl PollContract {
+    /// Stores the code and instantiates it with `sender` as the admin.
+    pub fn instantiate(app: &mut App, sender: &str, msg: &InstantiateMsg) -> AnyResult<Self> {
+        let code_id = app.store_code(poll_contract());
+        let addr = app.instantiate_contract(
+            code_id,
+            Addr::unchecked(sender),
+            msg,
+            &[],
+            "poll",
+            Some(sender.to_string()),
+        )?;
+        Ok(PollContract(addr))
+    }
+
+    pub fn addr(&self) -> &Addr {
+        &self.0
+    }
+
+    pub fn execute(
+        &self,
+        app: &mut App,
+        sender: &str,
+        msg: &ExecuteMsg,
+        funds: &[Coin],
+    ) -> AnyResult<AppResponse> {
+        app.execute_contract(Addr::unchecked(sender), self.0.clone(), msg, funds)
+    }
+
+    pub fn create_poll(
+        &self,
+        app: &mut App,
+        sender: &str,
+        poll: NewPoll,
+    ) -> AnyResult<AppResponse> {
+        self.execute(app, sender, &ExecuteMsg::CreatePoll(poll), &[])
+    }
+
+    pub fn vote(
+        &self,
+        app: &mut App,
+        sender: &str,
+        poll_id: &str,
+        option: u8,
+    ) -> AnyResult<AppResponse> {
+        let msg = ExecuteMsg::Vote {
+            poll_id: poll_id.to_string(),
+            vote: option,
+            memo: None,
+            proof: None,
+        };
+        self.execute(app, sender, &msg, &[])
+    }
+
+    pub fn close_poll(&self, app: &mut App, sender: &str, poll_id: &str) -> AnyResult<AppResponse> {
+        let msg = ExecuteMsg::ClosePoll {
+            poll_id: poll_id.to_string(),
+        };
+        self.execute(app, sender, &msg, &[])
+    }
+
+    pub fn finalize_poll(
+        &self,
+        app: &mut App,
+        sender: &str,
+        poll_id: &str,
+    ) -> AnyResult<AppResponse> {
+        let msg = ExecuteMsg::FinalizePoll {
+            poll_id: poll_id.to_string(),
+        };
+        self.execute(app, sender, &msg, &[])
+    }
+
+    pub fn query<T: DeserializeOwned>(&self, app: &App, msg: QueryMsg) -> StdResult<T> {
+        app.wrap()
+            .query_wasm_smart(self.0.clone(), &ContractQueryMsg::from(msg))
+    }
+
+    pub fn poll(&self, app: &App, poll_id: &str) -> StdResult<Option<Poll>> {
+        let msg = QueryMsg::Poll {
+            poll_id: poll_id.to_string(),
+            locale: None,
+            viewer: None,
+        };
+        self.query::<PollResponse>(app, msg).map(|resp| resp.poll)
+    }
+
+    pub fn final_result(&self, app: &App, poll_id: &str) -> StdResult<Option<PollResult>> {
+        let msg = QueryMsg::FinalResult {
+            poll_id: poll_id.to_string(),
+        };
+        self.query::<FinalResultResponse>(app, msg)
+            .map(|resp| resp.result)
+    }
+
+    /// Panics unless the poll has been finalized with the option labelled
+    /// `winner` as its outcome.
+    pub fn assert_winner(&self, app: &App, poll_id: &str, winner: Option<&str>) {
+        let result = self
+            .final_result(app, poll_id)
+            .unwrap()
+            .unwrap_or_else(|| panic!("poll {} is not finalized", poll_id));
+        let label = result
+            .winner
+            .and_then(|id| result.tallies.iter().find(|option| option.id == id))
+            .map(|option| option.label.as_str());
+        assert_eq!(label, winner);
+    }
+
+    /// Panics unless each option's tally matches `tallies`.
+    pub fn assert_tallies(&self, app: &App, poll_id: &str, tallies: &[(&str, u64)]) {
+        let poll = self
+            .poll(app, poll_id)
+            .unwrap()
+            .unwrap_or_else(|| panic!("poll {} not found", poll_id));
+        let actual: Vec<(&str, u64)> = poll
+            .options
+            .iter()
+            .map(|option| (option.label.as_str(), option.votes))
+            .collect();
+        assert_eq!(actual, tallies);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ContractError;
+
+    #[test]
+    fn test_poll_contract_harness() {
+        let mut app = App::default();
+        let msg = InstantiateMsg {
+            admin: Some("admin".to_string()),
+            min_voting_period: Some(60),
+            ..Default::default()
+        };
+        let contract = PollContract::instantiate(&mut app, "admin", &msg).unwrap();
+
+        let end_time = app.block_info().time.plus_seconds(3600);
+        let new_poll = NewPoll {
+            poll_id: "1".to_string(),
+            question: "Should We Have a Meeting Today".to_string(),
+            options: vec![String::from("Yes"), String::from("No")],
+            end_time: Some(end_time),
+            ..Default::default()
+        };
+        contract.create_poll(&mut app, "creator", new_poll).unwrap();
+        contract.vote(&mut app, "voter1", "1", 0).unwrap();
+        contract.vote(&mut app, "voter2", "1", 0).unwrap();
+        contract.vote(&mut app, "voter3", "1", 1).unwrap();
+        contract.assert_tallies(&app, "1", &[("Yes", 2), ("No", 1)]);
+
+        advance_time(&mut app, 3600);
+        let err = contract.vote(&mut app, "voter4", "1", 1).unwrap_err();
+        assert!(matches!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::PollExpired {}
+        ));
+
+        contract.finalize_poll(&mut app, "anyone", "1").unwrap();
+        contract.assert_winner(&app, "1", Some("Yes"));
+    }
+}